@@ -0,0 +1,67 @@
+use std::fmt;
+use std::ops::Range;
+
+use pest::error::{InputLocation, LineColLocation};
+use pest::Span;
+
+use crate::Rule;
+
+/// An assembly error and the source text it is about. `assemble` reports
+/// these through its `eyre::Report`; get one back with
+/// `report.downcast_ref::<SourceError>()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceError {
+    pub message: String,
+    /// 1-based line of the start of `span`
+    pub line: usize,
+    /// 1-based column, in characters, of the start of `span`
+    pub column: usize,
+    /// Byte offsets into the source
+    pub span: Range<usize>,
+}
+
+impl SourceError {
+    pub(crate) fn at(span: Span<'_>, message: impl Into<String>) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        SourceError {
+            message: message.into(),
+            line,
+            column,
+            span: span.start()..span.end(),
+        }
+    }
+
+    /// Give `error` the location `span`, unless it already has a closer one
+    pub(crate) fn locate(span: Span<'_>, error: eyre::Report) -> eyre::Report {
+        if error.is::<SourceError>() {
+            return error;
+        }
+        SourceError::at(span, error.to_string()).into()
+    }
+}
+
+impl From<pest::error::Error<Rule>> for SourceError {
+    fn from(error: pest::error::Error<Rule>) -> Self {
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+        };
+        let span = match error.location {
+            InputLocation::Pos(pos) => pos..pos,
+            InputLocation::Span((start, end)) => start..end,
+        };
+        SourceError {
+            message: error.variant.message().into_owned(),
+            line,
+            column,
+            span,
+        }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for SourceError {}
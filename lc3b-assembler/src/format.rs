@@ -0,0 +1,121 @@
+use pest::iterators::Pair;
+use pest::Parser;
+
+use crate::{LC3BAsmParser, Rule, SourceError};
+
+/// Column instructions and directives start at, after any label
+const CODE_COLUMN: usize = 8;
+/// Column trailing comments start at, unless the code runs past it
+const COMMENT_COLUMN: usize = 32;
+
+/// Reformat a program in one consistent layout: labels at the left
+/// margin, instructions and directives at column 8 with upper-case
+/// mnemonics (condition suffixes lower case, as in `BRnz`) and registers,
+/// operands separated by `, `, and trailing comments lined up at column
+/// 32. Blank lines and comment-only lines are kept, the latter at the
+/// margin or, if they were indented, at column 8. Label names, literals
+/// and strings are left as written. The result assembles to the same
+/// words.
+pub fn format(program: &str) -> Result<String, SourceError> {
+    let parsed = LC3BAsmParser::parse(Rule::program, program)?
+        .next()
+        .unwrap();
+    let mut lines = Vec::new();
+    for line in parsed.into_inner() {
+        if line.as_rule() != Rule::line {
+            continue;
+        }
+        let indented = line.as_str().starts_with([' ', '\t']);
+        let content = line.into_inner().next();
+        lines.push(content.map_or(String::new(), |c| format_line(c, indented)));
+    }
+    // A final newline parses as an empty last line, so joining keeps it
+    Ok(lines.join("\n"))
+}
+
+fn format_line(line: Pair<Rule>, indented: bool) -> String {
+    if line.as_rule() == Rule::comment_line {
+        let mut out = String::new();
+        if indented {
+            pad(&mut out, CODE_COLUMN);
+        }
+        out.push_str(line.as_str().trim_end());
+        return out;
+    }
+    let mut label = String::new();
+    let mut code = String::new();
+    let mut comment = None;
+    for part in line.into_inner() {
+        match part.as_rule() {
+            Rule::label => {
+                let name = part.into_inner().next().map_or("", |id| id.as_str());
+                label = format!("{}:", name);
+            }
+            Rule::instruction => code = format_instruction(part),
+            Rule::directive => code = format_directive(part),
+            Rule::comment => comment = Some(part.as_str().trim_end()),
+            _ => {}
+        }
+    }
+    let mut out = label;
+    if !code.is_empty() {
+        pad(&mut out, CODE_COLUMN);
+        out.push_str(&code);
+    }
+    if let Some(comment) = comment {
+        if !out.is_empty() {
+            pad(&mut out, COMMENT_COLUMN);
+        }
+        out.push_str("; ");
+        out.push_str(comment);
+    }
+    out.trim_end().to_string()
+}
+
+/// Pad `out` with spaces to `column`, or with one space if already past it
+fn pad(out: &mut String, column: usize) {
+    let width = out.chars().count();
+    let spaces = if width < column { column - width } else { 1 };
+    out.extend(std::iter::repeat_n(' ', spaces));
+}
+
+fn format_instruction(instruction: Pair<Rule>) -> String {
+    let mut parts = instruction.into_inner();
+    let opcode = parts.next().map_or("", |op| op.as_str()).to_uppercase();
+    let mnemonic = match opcode.strip_prefix("BR") {
+        Some(suffix) => format!("BR{}", suffix.to_lowercase()),
+        None => opcode,
+    };
+    let operands: Vec<String> = parts
+        .next()
+        .map(|operands| {
+            operands
+                .into_inner()
+                .map(|op| match op.as_rule() {
+                    Rule::register => op.as_str().to_uppercase(),
+                    _ => op.as_str().to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if operands.is_empty() {
+        mnemonic
+    } else {
+        format!("{} {}", mnemonic, operands.join(", "))
+    }
+}
+
+fn format_directive(directive: Pair<Rule>) -> String {
+    let Some(directive) = directive.into_inner().next() else {
+        return String::new();
+    };
+    let text = directive.as_str();
+    let name_len = text.find([' ', '\t']).unwrap_or(text.len());
+    let (name, operand) = text.split_at(name_len);
+    let operand = operand.trim();
+    if operand.is_empty() {
+        name.to_uppercase()
+    } else {
+        format!("{} {}", name.to_uppercase(), operand)
+    }
+}
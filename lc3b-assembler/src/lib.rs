@@ -1,13 +1,25 @@
 #![forbid(unsafe_code)]
 
-use std::{collections::HashMap, str::FromStr};
+use std::str::FromStr;
 
-use lc3b_isa::{AddInstruction, AndInstruction, Bit, Condition, Immediate4, Immediate5, Instruction, PCOffset6, PCOffset9, PCOffset11, Register, TrapVect8, XorInstruction};
+use lc3b_isa::{Condition, Instruction, PCOffset9, PCOffset11, Register};
 use pest::{
     iterators::{Pair, Pairs},
     Parser,
 };
 
+mod error;
+pub use error::SourceError;
+
+mod format;
+pub use format::format;
+
+mod source_map;
+pub use source_map::SourceMap;
+
+mod symbols;
+pub use symbols::SymbolTable;
+
 #[derive(pest_derive::Parser)]
 #[grammar = "lc3b_asm.pest"]
 struct LC3BAsmParser {}
@@ -25,11 +37,47 @@ pub struct AssembledProgram {
     pub origin: u16,
     /// Raw 16-bit words (instructions and data)
     pub words: Vec<u16>,
+    /// Every label and the address it marks
+    pub symbols: SymbolTable,
+    /// The source line of every instruction
+    pub source_map: SourceMap,
+}
+
+impl AssembledProgram {
+    /// Each contiguous block of words as (load address, words). A program
+    /// currently has one segment starting at `origin`; loaders should go
+    /// through this so programs with several .ORIG blocks load correctly
+    /// once the assembler produces them.
+    pub fn segments(&self) -> impl Iterator<Item = (u16, &[u16])> {
+        std::iter::once((self.origin, self.words.as_slice()))
+    }
+
+    /// One row per word: address, encoding, and for instructions the
+    /// source line number and text, e.g.
+    /// `x3000  x1265     3  ADD R1, R1, #5`. `source` is the text this
+    /// program was assembled from.
+    pub fn listing(&self, source: &str) -> String {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut out = String::new();
+        for (origin, words) in self.segments() {
+            for (offset, &word) in words.iter().enumerate() {
+                let addr = origin.wrapping_add(offset as u16);
+                out.push_str(&format!("x{:04X}  x{:04X}", addr, word));
+                if let Some(line) = self.source_map.line_of(addr) {
+                    let text = lines.get(line - 1).map_or("", |text| text.trim());
+                    out.push_str(&format!("  {:>4}  {}", line, text));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
 }
 
 /// Two-pass assembler that supports labels and directives
 struct Assembler {
-    symbols: HashMap<String, u16>,
+    symbols: SymbolTable,
+    source_map: SourceMap,
     origin: u16,
     current_address: u16,
 }
@@ -37,7 +85,8 @@ struct Assembler {
 impl Assembler {
     fn new() -> Self {
         Assembler {
-            symbols: HashMap::new(),
+            symbols: SymbolTable::new(),
+            source_map: SourceMap::new(),
             origin: 0x3000, // Default origin
             current_address: 0x3000,
         }
@@ -45,7 +94,8 @@ impl Assembler {
 
     /// Pass 1: Build symbol table by collecting all label addresses and processing directives
     fn pass1(&mut self, program: &str) -> eyre::Result<()> {
-        let parsed = LC3BAsmParser::parse(Rule::program, program)?
+        let parsed = LC3BAsmParser::parse(Rule::program, program)
+            .map_err(SourceError::from)?
             .next()
             .unwrap();
 
@@ -54,7 +104,9 @@ impl Assembler {
                 for inner in pair.into_inner() {
                     match inner.as_rule() {
                         Rule::directive_line => {
-                            self.pass1_directive_line(inner)?;
+                            let span = inner.as_span();
+                            self.pass1_directive_line(inner)
+                                .map_err(|e| SourceError::locate(span, e))?;
                         }
                         Rule::label_only_line => {
                             for part in inner.into_inner() {
@@ -128,7 +180,8 @@ impl Assembler {
 
     /// Pass 2: Generate words, resolving label references
     fn pass2(&mut self, program: &str) -> eyre::Result<Vec<u16>> {
-        let parsed = LC3BAsmParser::parse(Rule::program, program)?
+        let parsed = LC3BAsmParser::parse(Rule::program, program)
+            .map_err(SourceError::from)?
             .next()
             .unwrap();
 
@@ -140,7 +193,10 @@ impl Assembler {
                 for inner in pair.into_inner() {
                     match inner.as_rule() {
                         Rule::directive_line => {
-                            let directive_words = self.pass2_directive_line(inner)?;
+                            let span = inner.as_span();
+                            let directive_words = self
+                                .pass2_directive_line(inner)
+                                .map_err(|e| SourceError::locate(span, e))?;
                             if directive_words.is_none() {
                                 // .END directive - stop processing
                                 return Ok(words);
@@ -148,11 +204,16 @@ impl Assembler {
                             words.extend(directive_words.unwrap());
                         }
                         Rule::instruction_line => {
+                            let (line, _) = inner.as_span().start_pos().line_col();
                             for part in inner.into_inner() {
                                 if part.as_rule() == Rule::instruction {
-                                    let inst = self.instruction_from_pair(part)?;
+                                    let span = part.as_span();
+                                    let inst = self
+                                        .instruction_from_pair(part)
+                                        .map_err(|e| SourceError::locate(span, e))?;
                                     let word: u16 = (&inst).into();
                                     words.push(word);
+                                    self.source_map.insert(self.current_address, line);
                                     self.current_address += 1;
                                 }
                             }
@@ -189,9 +250,7 @@ impl Assembler {
                         }
                         Rule::blkw_directive => {
                             let count = self.parse_directive_number(&directive)?;
-                            for _ in 0..count {
-                                words.push(0);
-                            }
+                            words.resize(words.len() + count as usize, 0);
                             self.current_address += count;
                         }
                         Rule::stringz_directive => {
@@ -213,8 +272,9 @@ impl Assembler {
 
     fn add_label(&mut self, pair: &Pair<Rule>) -> eyre::Result<()> {
         let label_name = self.extract_label_name(pair);
-        if self.symbols.contains_key(&label_name) {
-            return Err(eyre::eyre!("Duplicate label: {}", label_name));
+        if self.symbols.address_of(&label_name).is_some() {
+            let message = format!("Duplicate label: {}", label_name);
+            return Err(SourceError::at(pair.as_span(), message).into());
         }
         self.symbols.insert(label_name, self.current_address);
         Ok(())
@@ -266,10 +326,10 @@ impl Assembler {
                 Rule::identifier => {
                     // Label reference
                     let label_name = inner.as_str();
-                    let addr = self.symbols.get(label_name).ok_or_else(|| {
-                        eyre::eyre!("Undefined label: {}", label_name)
+                    let addr = self.symbols.address_of(label_name).ok_or_else(|| {
+                        SourceError::at(inner.as_span(), format!("Undefined label: {}", label_name))
                     })?;
-                    return Ok(*addr);
+                    return Ok(addr);
                 }
                 _ => {}
             }
@@ -302,11 +362,11 @@ impl Assembler {
             }
             Rule::identifier => {
                 let label_name = operand.as_str();
-                let target_addr = self.symbols.get(label_name).ok_or_else(|| {
-                    eyre::eyre!("Undefined label: {}", label_name)
+                let target_addr = self.symbols.address_of(label_name).ok_or_else(|| {
+                    SourceError::at(operand.as_span(), format!("Undefined label: {}", label_name))
                 })?;
                 // PC-relative offset: target - (current + 1)
-                let offset = (*target_addr as i32) - (self.current_address as i32 + 1);
+                let offset = (target_addr as i32) - (self.current_address as i32 + 1);
                 Ok(offset as i16)
             }
             _ => Err(eyre::eyre!("Expected literal or label, got {:?}", operand.as_rule())),
@@ -323,13 +383,14 @@ impl Assembler {
         let opcode_str = opcode.as_str();
 
         // Check for BR variants first
-        if let Some(condition) = parse_br_condition(opcode_str) {
+        let opcode_upper = opcode_str.to_uppercase();
+        if let Some(condition) = opcode_upper.strip_prefix("BR").and_then(Condition::from_suffix) {
             let mut operands = inner.next().unwrap().into_inner();
             let offset_arg = operands.next().unwrap();
             let offset_value = self.resolve_label_or_offset(&offset_arg)?;
             
             // Check range for PCOffset9
-            if offset_value < -256 || offset_value > 255 {
+            if !(-256..=255).contains(&offset_value) {
                 return Err(eyre::eyre!(
                     "Branch offset {} out of range (-256 to 255)",
                     offset_value
@@ -341,72 +402,24 @@ impl Assembler {
         }
 
         let instruction = match opcode_str.to_uppercase().as_str() {
-            "ADD" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let arg_one = operands.next().unwrap().as_str();
-                let dst_reg = Register::from_str(arg_one)?;
-
-                let arg_two = operands.next().unwrap().as_str();
-                let src_reg = Register::from_str(arg_two)?;
-
-                let arg_three = operands.next().unwrap();
-                let inner: AddInstruction = match arg_three.as_rule() {
-                    Rule::literal | Rule::hex_literal => {
-                        let imm5 = Immediate5::from_str(arg_three.as_str())?;
-                        AddInstruction::AddImm(dst_reg, src_reg, imm5)
-                    }
-                    Rule::register => {
-                        let src2_reg = Register::from_str(arg_three.as_str())?;
-                        AddInstruction::AddReg(dst_reg, src_reg, src2_reg)
-                    }
-                    _ => return Err(eyre::eyre!("unhandled `{:?}`", arg_three)),
-                };
-                Instruction::AddInstruction(inner)
-            }
-            "AND" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let arg_one = operands.next().unwrap().as_str();
-                let dst_reg = Register::from_str(arg_one)?;
-
-                let arg_two = operands.next().unwrap().as_str();
-                let src_reg = Register::from_str(arg_two)?;
-
-                let arg_three = operands.next().unwrap();
-                let inner: AndInstruction = match arg_three.as_rule() {
-                    Rule::literal | Rule::hex_literal => {
-                        let imm5 = Immediate5::from_str(arg_three.as_str())?;
-                        AndInstruction::AndImm(dst_reg, src_reg, imm5)
-                    }
-                    Rule::register => {
-                        let src2_reg = Register::from_str(arg_three.as_str())?;
-                        AndInstruction::AndReg(dst_reg, src_reg, src2_reg)
-                    }
-                    _ => return Err(eyre::eyre!("unhandled `{:?}`", arg_three)),
-                };
-                Instruction::AndInstruction(inner)
-            }
-            "NOT" => {
-                // NOT is XOR with immediate -1 (0x1F sign-extended = 0xFFFF)
-                let mut operands = inner.next().unwrap().into_inner();
-                let arg_one = operands.next().unwrap().as_str();
-                let dst_reg = Register::from_str(arg_one)?;
-
-                let arg_two = operands.next().unwrap().as_str();
-                let src_reg = Register::from_str(arg_two)?;
-
-                // NOT DR, SR is encoded as XOR DR, SR, #-1
-                let imm5 = Immediate5::from_signed(-1)?;
-                Instruction::XorInstruction(XorInstruction::XorImm(dst_reg, src_reg, imm5))
-            }
             "JSR" => {
                 let mut operands = inner.next().unwrap().into_inner();
                 let offset_arg = operands.next().unwrap();
-                let offset_value = self.resolve_label_or_offset(&offset_arg)?;
-                
-                // JSR uses PCOffset11, and the offset is left-shifted by 1 in hardware
-                // So we need to divide by 2 to get the actual offset stored
+                let mut offset_value = self.resolve_label_or_offset(&offset_arg)?;
+
+                // JSR uses LSHF(SEXT(PCoffset11), 1) in hardware, so a label's
+                // distance is halved; a numeric operand is the field itself
+                if offset_arg.as_rule() == Rule::identifier {
+                    if offset_value % 2 != 0 {
+                        return Err(eyre::eyre!(
+                            "JSR target must be word-aligned (offset {} is not even)",
+                            offset_value
+                        ));
+                    }
+                    offset_value /= 2;
+                }
                 // Range check: -1024 to 1023 (11-bit signed)
-                if offset_value < -1024 || offset_value > 1023 {
+                if !(-1024..=1023).contains(&offset_value) {
                     return Err(eyre::eyre!(
                         "JSR offset {} out of range (-1024 to 1023)",
                         offset_value
@@ -416,36 +429,6 @@ impl Assembler {
                 let offset = PCOffset11::new(offset_value);
                 Instruction::Jsr(offset)
             }
-            "JSRR" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let arg_one = operands.next().unwrap().as_str();
-                let base_reg = Register::from_str(arg_one)?;
-
-                Instruction::Jsrr(base_reg)
-            }
-            "TRAP" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let arg = operands.next().unwrap();
-                let vector = match arg.as_rule() {
-                    Rule::hex_literal => {
-                        let value = self.parse_hex_literal(&arg)?;
-                        if value > 0xFF {
-                            return Err(eyre::eyre!("TRAP vector {} out of range (0x00-0xFF)", value));
-                        }
-                        value as u8
-                    }
-                    Rule::literal => {
-                        let s = arg.as_str().strip_prefix('#').unwrap_or(arg.as_str());
-                        let value: u16 = s.parse().map_err(|e| eyre::eyre!("Invalid number '{}': {}", s, e))?;
-                        if value > 0xFF {
-                            return Err(eyre::eyre!("TRAP vector {} out of range (0x00-0xFF)", value));
-                        }
-                        value as u8
-                    }
-                    _ => return Err(eyre::eyre!("Expected trap vector, got {:?}", arg.as_rule())),
-                };
-                Instruction::Trap(TrapVect8::new(vector))
-            }
             "LEA" => {
                 let mut operands = inner.next().unwrap().into_inner();
                 let arg_one = operands.next().unwrap().as_str();
@@ -465,7 +448,7 @@ impl Assembler {
                 let stored_offset = offset_value / 2;
 
                 // Check range for PCOffset9
-                if stored_offset < -256 || stored_offset > 255 {
+                if !(-256..=255).contains(&stored_offset) {
                     return Err(eyre::eyre!(
                         "LEA offset {} out of range (-256 to 255)",
                         stored_offset
@@ -475,150 +458,22 @@ impl Assembler {
                 let offset = PCOffset9::new(stored_offset);
                 Instruction::Lea(dst_reg, offset)
             }
-            "JMP" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let arg_one = operands.next().unwrap().as_str();
-                let base_reg = Register::from_str(arg_one)?;
-                Instruction::Jmp(base_reg)
+            // Everything else takes only registers and literals
+            _ => {
+                let operands: Vec<&str> = inner
+                    .next()
+                    .map(|operands| operands.into_inner().map(|op| op.as_str()).collect())
+                    .unwrap_or_default();
+                Instruction::from_parts(opcode_str, &operands)?
             }
-            "RET" => Instruction::Ret,
-            "STW" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let sr = Register::from_str(operands.next().unwrap().as_str())?;
-                let base = Register::from_str(operands.next().unwrap().as_str())?;
-                let offset_arg = operands.next().unwrap();
-                let offset_value: i8 = match offset_arg.as_rule() {
-                    Rule::literal => {
-                        let s = offset_arg.as_str().strip_prefix('#').unwrap_or(offset_arg.as_str());
-                        s.parse()?
-                    }
-                    Rule::hex_literal => {
-                        let value = self.parse_hex_literal(&offset_arg)?;
-                        value as i8
-                    }
-                    _ => return Err(eyre::eyre!("Expected offset, got {:?}", offset_arg.as_rule())),
-                };
-                let offset = PCOffset6::new(offset_value)?;
-                Instruction::Stw(sr, base, offset)
-            }
-            "LDW" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let dr = Register::from_str(operands.next().unwrap().as_str())?;
-                let base = Register::from_str(operands.next().unwrap().as_str())?;
-                let offset_arg = operands.next().unwrap();
-                let offset_value: i8 = match offset_arg.as_rule() {
-                    Rule::literal => {
-                        let s = offset_arg.as_str().strip_prefix('#').unwrap_or(offset_arg.as_str());
-                        s.parse()?
-                    }
-                    Rule::hex_literal => {
-                        let value = self.parse_hex_literal(&offset_arg)?;
-                        value as i8
-                    }
-                    _ => return Err(eyre::eyre!("Expected offset, got {:?}", offset_arg.as_rule())),
-                };
-                let offset = PCOffset6::new(offset_value)?;
-                Instruction::Ldr(dr, base, offset)  // LDW uses same encoding as LDR
-            }
-            // Shift instructions
-            "LSHF" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let dr = Register::from_str(operands.next().unwrap().as_str())?;
-                let sr = Register::from_str(operands.next().unwrap().as_str())?;
-                let amount_arg = operands.next().unwrap();
-                let amount_value: u8 = match amount_arg.as_rule() {
-                    Rule::literal => {
-                        let s = amount_arg.as_str().strip_prefix('#').unwrap_or(amount_arg.as_str());
-                        s.parse()?
-                    }
-                    Rule::hex_literal => {
-                        let value = self.parse_hex_literal(&amount_arg)?;
-                        value as u8
-                    }
-                    _ => return Err(eyre::eyre!("Expected shift amount, got {:?}", amount_arg.as_rule())),
-                };
-                let amount = Immediate4::new(amount_value)?;
-                // LSHF: D=0, A=0
-                Instruction::Shf(dr, sr, Bit::new(false), Bit::new(false), amount)
-            }
-            "RSHFL" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let dr = Register::from_str(operands.next().unwrap().as_str())?;
-                let sr = Register::from_str(operands.next().unwrap().as_str())?;
-                let amount_arg = operands.next().unwrap();
-                let amount_value: u8 = match amount_arg.as_rule() {
-                    Rule::literal => {
-                        let s = amount_arg.as_str().strip_prefix('#').unwrap_or(amount_arg.as_str());
-                        s.parse()?
-                    }
-                    Rule::hex_literal => {
-                        let value = self.parse_hex_literal(&amount_arg)?;
-                        value as u8
-                    }
-                    _ => return Err(eyre::eyre!("Expected shift amount, got {:?}", amount_arg.as_rule())),
-                };
-                let amount = Immediate4::new(amount_value)?;
-                // RSHFL: D=1, A=0 (right shift logical)
-                Instruction::Shf(dr, sr, Bit::new(true), Bit::new(false), amount)
-            }
-            "RSHFA" => {
-                let mut operands = inner.next().unwrap().into_inner();
-                let dr = Register::from_str(operands.next().unwrap().as_str())?;
-                let sr = Register::from_str(operands.next().unwrap().as_str())?;
-                let amount_arg = operands.next().unwrap();
-                let amount_value: u8 = match amount_arg.as_rule() {
-                    Rule::literal => {
-                        let s = amount_arg.as_str().strip_prefix('#').unwrap_or(amount_arg.as_str());
-                        s.parse()?
-                    }
-                    Rule::hex_literal => {
-                        let value = self.parse_hex_literal(&amount_arg)?;
-                        value as u8
-                    }
-                    _ => return Err(eyre::eyre!("Expected shift amount, got {:?}", amount_arg.as_rule())),
-                };
-                let amount = Immediate4::new(amount_value)?;
-                // RSHFA: D=1, A=1 (right shift arithmetic)
-                Instruction::Shf(dr, sr, Bit::new(true), Bit::new(true), amount)
-            }
-            // Trap aliases
-            "GETC" => Instruction::Trap(TrapVect8::new(0x20)),
-            "OUT" => Instruction::Trap(TrapVect8::new(0x21)),
-            "PUTS" => Instruction::Trap(TrapVect8::new(0x22)),
-            "IN" => Instruction::Trap(TrapVect8::new(0x23)),
-            "PUTSP" => Instruction::Trap(TrapVect8::new(0x24)),
-            "HALT" => Instruction::Trap(TrapVect8::new(0x25)),
-            other => return Err(eyre::eyre!("unhandled opcode {:#?}", other)),
         };
 
         Ok(instruction)
     }
 }
 
-fn parse_br_condition(opcode: &str) -> Option<Condition> {
-    let opcode_upper = opcode.to_uppercase();
-    if !opcode_upper.starts_with("BR") {
-        return None;
-    }
-
-    let suffix = &opcode_upper[2..];
-
-    if suffix.is_empty() {
-        return Some(Condition { n: true, z: true, p: true });
-    }
-
-    let n = suffix.contains('N');
-    let z = suffix.contains('Z');
-    let p = suffix.contains('P');
-
-    if !n && !z && !p {
-        return None;
-    }
-
-    Some(Condition { n, z, p })
-}
-
-/// Assemble a program and return the origin address and raw words
+/// Assemble a program and return the origin address and raw words.
+/// Errors wrap a `SourceError` locating the offending text.
 pub fn assemble(program: &str) -> eyre::Result<AssembledProgram> {
     let mut assembler = Assembler::new();
     assembler.pass1(program)?;
@@ -626,6 +481,8 @@ pub fn assemble(program: &str) -> eyre::Result<AssembledProgram> {
     Ok(AssembledProgram {
         origin: assembler.origin,
         words,
+        symbols: assembler.symbols,
+        source_map: assembler.source_map,
     })
 }
 
@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+/// Which source line each instruction came from. Data emitted by
+/// directives (.FILL, .BLKW, .STRINGZ) is not mapped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// 1-based line number of the instruction at each address
+    lines: BTreeMap<u16, usize>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, addr: u16, line: usize) {
+        self.lines.insert(addr, line);
+    }
+
+    /// The 1-based source line of the instruction at `addr`
+    pub fn line_of(&self, addr: u16) -> Option<usize> {
+        self.lines.get(&addr).copied()
+    }
+
+    /// The address of the first instruction assembled from `line`
+    pub fn address_of_line(&self, line: usize) -> Option<u16> {
+        self.lines
+            .iter()
+            .find(|&(_, &l)| l == line)
+            .map(|(&addr, _)| addr)
+    }
+
+    /// Instruction addresses and their lines, by address
+    pub fn iter(&self) -> impl Iterator<Item = (u16, usize)> + '_ {
+        self.lines.iter().map(|(&addr, &line)| (addr, line))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+/// Label addresses from an assembled program
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    by_name: BTreeMap<String, u16>,
+    /// Labels at each address, in the order they were defined
+    by_address: BTreeMap<u16, Vec<String>>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or move a label. Returns its previous address, if any.
+    pub fn insert(&mut self, name: impl Into<String>, addr: u16) -> Option<u16> {
+        let name = name.into();
+        let previous = self.by_name.insert(name.clone(), addr);
+        if let Some(old) = previous {
+            if let Some(names) = self.by_address.get_mut(&old) {
+                names.retain(|n| *n != name);
+                if names.is_empty() {
+                    self.by_address.remove(&old);
+                }
+            }
+        }
+        self.by_address.entry(addr).or_default().push(name);
+        previous
+    }
+
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.by_name.get(name).copied()
+    }
+
+    /// The first label defined at exactly `addr`
+    pub fn symbol_at(&self, addr: u16) -> Option<&str> {
+        self.by_address
+            .get(&addr)
+            .and_then(|names| names.first())
+            .map(String::as_str)
+    }
+
+    /// The closest label at or below `addr` and the distance past it, for
+    /// rendering addresses as `LOOP+3`
+    pub fn nearest(&self, addr: u16) -> Option<(&str, u16)> {
+        let (&base, names) = self.by_address.range(..=addr).next_back()?;
+        Some((names.first()?.as_str(), addr - base))
+    }
+
+    /// Labels and addresses, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.by_name.iter().map(|(name, &addr)| (name.as_str(), addr))
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+impl FromIterator<(String, u16)> for SymbolTable {
+    fn from_iter<T: IntoIterator<Item = (String, u16)>>(iter: T) -> Self {
+        let mut table = SymbolTable::new();
+        for (name, addr) in iter {
+            table.insert(name, addr);
+        }
+        table
+    }
+}
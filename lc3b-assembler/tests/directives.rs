@@ -12,6 +12,9 @@ fn test_orig_directive() {
     let assembled = assemble(test_asm).unwrap();
     assert_eq!(assembled.origin, 0x4000);
     assert_eq!(assembled.words.len(), 1);
+
+    let segments: Vec<(u16, &[u16])> = assembled.segments().collect();
+    assert_eq!(segments, vec![(0x4000, assembled.words.as_slice())]);
 }
 
 #[test]
@@ -0,0 +1,50 @@
+//! Tests for the formatter and listing
+
+use lc3b_assembler::{assemble, format};
+
+const MESSY: &str = "\
+; Count down
+.orig x3000
+  add r0,r0,#3
+loop: add r0, r0, #-1 ; next
+   brp loop
+    ; done
+  halt
+msg:   .stringz \"a  b\"
+";
+
+#[test]
+fn test_format_layout() {
+    let expected = "\
+; Count down
+        .ORIG x3000
+        ADD R0, R0, #3
+loop:   ADD R0, R0, #-1         ; next
+        BRp loop
+        ; done
+        HALT
+msg:    .STRINGZ \"a  b\"
+";
+    assert_eq!(format(MESSY).unwrap(), expected);
+    // Formatting is stable and keeps the program
+    assert_eq!(format(expected).unwrap(), expected);
+    assert_eq!(assemble(expected).unwrap().words, assemble(MESSY).unwrap().words);
+}
+
+#[test]
+fn test_format_reports_syntax_errors() {
+    let error = format("ADD R0, R0, #1\n  ADD R0,, R1\n").unwrap_err();
+    assert_eq!(error.line, 2);
+}
+
+#[test]
+fn test_listing() {
+    let program = assemble(MESSY).unwrap();
+    let listing = program.listing(MESSY);
+    let rows: Vec<&str> = listing.lines().collect();
+    assert_eq!(rows[0], "x3000  x1023     3  add r0,r0,#3");
+    assert_eq!(rows[2], "x3002  x03FE     5  brp loop");
+    // .STRINGZ data has no source line
+    assert_eq!(rows[4], "x3004  x0061");
+    assert_eq!(rows.len(), 4 + 5);
+}
@@ -13,24 +13,26 @@ fn test_jsr() {
     let asm = r#"
         JSR QUEUE
         ADD R0, R0, #0
+        ADD R0, R0, #0
 QUEUE:  ADD R1, R1, #1
 "#;
     let instructions = parse_to_program(asm).unwrap();
 
-    assert_eq!(instructions.len(), 3);
-    // JSR is at address 0, QUEUE is at address 2
-    // offset = 2 - (0 + 1) = 1
+    assert_eq!(instructions.len(), 4);
+    // JSR is at address 0, QUEUE is at address 3
+    // offset = 3 - (0 + 1) = 2, stored halved since JSR doubles it
     // Expected: Jsr(PCOffset11::new(1))
 }
 
 #[test]
 fn test_jsr_encoding() {
-    // JSR with offset 1 should encode as:
+    // JSR to a label two words past the next instruction should encode as:
     // 0100 1 00000000001
     // opcode=0100, mode=1, PCoffset11=1
     let asm = r#"
         JSR QUEUE
         ADD R0, R0, #0
+        ADD R0, R0, #0
 QUEUE:  ADD R1, R1, #1
 "#;
     let instructions = parse_to_program(asm).unwrap();
@@ -39,6 +41,43 @@ QUEUE:  ADD R1, R1, #1
     assert_eq!(encoded, 0b0100_1_00000000001);
 }
 
+#[test]
+fn test_jsr_odd_distance() {
+    // JSR doubles its offset, so it cannot reach a label an odd number of
+    // words past the next instruction
+    let asm = r#"
+        JSR QUEUE
+        ADD R0, R0, #0
+QUEUE:  ADD R1, R1, #1
+"#;
+    let err = parse_to_program(asm).unwrap_err();
+    assert!(err.to_string().contains("word-aligned"), "{}", err);
+}
+
+#[test]
+fn test_jsr_backward() {
+    // A label behind the JSR: offset = 0 - (3 + 1) = -4, stored as -2
+    let asm = r#"
+QUEUE:  ADD R1, R1, #1
+        ADD R0, R0, #0
+        ADD R0, R0, #0
+        JSR QUEUE
+"#;
+    let instructions = parse_to_program(asm).unwrap();
+    let encoded: u16 = u16::from(&instructions[3]);
+
+    assert_eq!(encoded, 0b0100_1_11111111110);
+}
+
+#[test]
+fn test_jsr_numeric_offset() {
+    // A numeric operand is the PCoffset11 field itself, not a distance
+    let instructions = parse_to_program("JSR #1").unwrap();
+    let encoded: u16 = u16::from(&instructions[0]);
+
+    assert_eq!(encoded, 0b0100_1_00000000001);
+}
+
 #[test]
 fn test_jsrr() {
     // JSRR R3 ; Jump to address in R3
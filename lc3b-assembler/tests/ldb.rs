@@ -5,11 +5,7 @@
 
 use lc3b_assembler::parse_to_program;
 
-// Note: LDB is not yet implemented in the assembler, so these tests are marked as ignored
-// until support is added.
-
 #[test]
-#[ignore = "LDB instruction not yet implemented in assembler"]
 fn test_ldb() {
     // LDB R4, R2, #10 ; R4 <- SEXT(mem[R2 + 10])
     let asm = "LDB R4, R2, #10";
@@ -20,7 +16,6 @@ fn test_ldb() {
 }
 
 #[test]
-#[ignore = "LDB instruction not yet implemented in assembler"]
 fn test_ldb_encoding() {
     // LDB R4, R2, #10 should encode as:
     // 0010 100 010 001010
@@ -33,7 +28,6 @@ fn test_ldb_encoding() {
 }
 
 #[test]
-#[ignore = "LDB instruction not yet implemented in assembler"]
 fn test_ldb_negative_offset() {
     // LDB with negative offset
     let asm = "LDB R4, R2, #-5";
@@ -5,11 +5,7 @@
 
 use lc3b_assembler::parse_to_program;
 
-// Note: RTI is not yet implemented in the assembler, so these tests are marked as ignored
-// until support is added.
-
 #[test]
-#[ignore = "RTI instruction not yet implemented in assembler"]
 fn test_rti() {
     // RTI ; Return from interrupt
     let asm = "RTI";
@@ -20,7 +16,6 @@ fn test_rti() {
 }
 
 #[test]
-#[ignore = "RTI instruction not yet implemented in assembler"]
 fn test_rti_encoding() {
     // RTI should encode as:
     // 1000 000000000000
@@ -5,11 +5,7 @@
 
 use lc3b_assembler::parse_to_program;
 
-// Note: STB is not yet implemented in the assembler, so these tests are marked as ignored
-// until support is added.
-
 #[test]
-#[ignore = "STB instruction not yet implemented in assembler"]
 fn test_stb() {
     // STB R4, R2, #10 ; mem[R2 + 10] <- R4[7:0]
     let asm = "STB R4, R2, #10";
@@ -20,7 +16,6 @@ fn test_stb() {
 }
 
 #[test]
-#[ignore = "STB instruction not yet implemented in assembler"]
 fn test_stb_encoding() {
     // STB R4, R2, #10 should encode as:
     // 0011 100 010 001010
@@ -0,0 +1,76 @@
+//! Tests for the symbol table and source locations returned by the assembler
+
+use lc3b_assembler::{assemble, SourceError, SymbolTable};
+
+#[test]
+fn test_labels_are_exported() {
+    let test_asm = r#"
+.ORIG x3000
+START:  ADD R0, R0, #1
+LOOP:
+AGAIN:  BRnzp LOOP
+DATA:   .FILL x1234
+"#;
+
+    let assembled = assemble(test_asm).unwrap();
+    let symbols = &assembled.symbols;
+    assert_eq!(symbols.len(), 4);
+    assert_eq!(symbols.address_of("START"), Some(0x3000));
+    assert_eq!(symbols.address_of("DATA"), Some(0x3002));
+    assert_eq!(symbols.address_of("MISSING"), None);
+
+    // The first label defined at an address wins
+    assert_eq!(symbols.symbol_at(0x3001), Some("LOOP"));
+    assert_eq!(symbols.nearest(0x3005), Some(("DATA", 3)));
+    assert_eq!(symbols.nearest(0x2FFF), None);
+}
+
+#[test]
+fn test_moving_a_label() {
+    let mut symbols = SymbolTable::new();
+    assert_eq!(symbols.insert("A", 0x10), None);
+    assert_eq!(symbols.insert("A", 0x20), Some(0x10));
+    assert_eq!(symbols.symbol_at(0x10), None);
+    assert_eq!(symbols.symbol_at(0x20), Some("A"));
+    assert_eq!(symbols.iter().collect::<Vec<_>>(), vec![("A", 0x20)]);
+}
+
+#[test]
+fn test_source_map_covers_instructions() {
+    let test_asm = r#".ORIG x3000
+START:  ADD R0, R0, #1
+
+        BRnzp START
+DATA:   .FILL x1234
+        HALT
+"#;
+
+    let assembled = assemble(test_asm).unwrap();
+    let map = &assembled.source_map;
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.line_of(0x3000), Some(2));
+    assert_eq!(map.line_of(0x3001), Some(4));
+    assert_eq!(map.line_of(0x3002), None);
+    assert_eq!(map.line_of(0x3003), Some(6));
+    assert_eq!(map.address_of_line(6), Some(0x3003));
+}
+
+#[test]
+fn test_errors_carry_source_locations() {
+    let undefined = "ADD R0, R0, #1\n  BRz MISSING\n";
+    let report = assemble(undefined).unwrap_err();
+    let error = report.downcast_ref::<SourceError>().unwrap();
+    assert_eq!(error.message, "Undefined label: MISSING");
+    assert_eq!((error.line, error.column), (2, 7));
+    assert_eq!(&undefined[error.span.clone()], "MISSING");
+
+    let syntax = "ADD R0, R0, #1\nFROB R1\n";
+    let report = assemble(syntax).unwrap_err();
+    let error = report.downcast_ref::<SourceError>().unwrap();
+    assert_eq!(error.line, 2);
+
+    let duplicate = "A: ADD R0, R0, #1\nA: ADD R0, R0, #1\n";
+    let report = assemble(duplicate).unwrap_err();
+    let error = report.downcast_ref::<SourceError>().unwrap();
+    assert_eq!((error.line, error.column), (2, 1));
+}
@@ -8,11 +8,7 @@
 use lc3b_assembler::parse_to_program;
 use lc3b_isa::{Immediate5, Instruction, Register, XorInstruction};
 
-// Note: XOR is not yet implemented in the assembler grammar, so these tests are marked as ignored
-// until XOR support is added to the grammar.
-
 #[test]
-#[ignore = "XOR instruction not yet implemented in assembler grammar"]
 fn test_xor_register_mode() {
     // XOR R3, R1, R2 ; R3 <- R1 XOR R2
     let asm = "XOR R3, R1, R2";
@@ -23,7 +19,6 @@ fn test_xor_register_mode() {
 }
 
 #[test]
-#[ignore = "XOR instruction not yet implemented in assembler grammar"]
 fn test_xor_immediate_mode() {
     // XOR R3, R1, #12 ; R3 <- R1 with bits [3], [2] complemented
     let asm = "XOR R3, R1, #12";
@@ -10,10 +10,27 @@ pub struct Program {
 #[derive(Debug, Clone, PartialEq)]
 pub enum TopLevelItem {
     Include(String),
+    StructDefinition(StructDefinition),
     Function(Function),
     GlobalDeclaration(Declaration),
 }
 
+/// A struct definition: `struct name { ... };`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDefinition {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+/// A struct member
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub ty: Type,
+    pub name: String,
+    /// Element count, if the member is an array
+    pub array_len: Option<usize>,
+}
+
 /// A function definition
 #[derive(Debug, Clone, PartialEq)]
 pub struct Function {
@@ -21,6 +38,8 @@ pub struct Function {
     pub name: String,
     pub parameters: Vec<Parameter>,
     pub body: Block,
+    /// 1-based source line the definition starts on
+    pub line: usize,
 }
 
 /// A function parameter
@@ -39,12 +58,16 @@ pub enum Type {
     Short { unsigned: bool },
     Char,
     Pointer(Box<Type>),
+    /// `struct name`
+    Struct(String),
 }
 
 /// A block of statements
 #[derive(Debug, Clone, PartialEq)]
 pub struct Block {
     pub items: Vec<BlockItem>,
+    /// 1-based source line each item starts on
+    pub lines: Vec<usize>,
 }
 
 /// Items that can appear in a block
@@ -65,6 +88,8 @@ pub struct Declaration {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declarator {
     pub name: String,
+    /// Element count, if this declares an array
+    pub array_len: Option<usize>,
     pub initializer: Option<Initializer>,
 }
 
@@ -73,6 +98,8 @@ pub struct Declarator {
 pub enum Initializer {
     Expression(Expression),
     String(String),
+    /// `{a, b, c}`, for arrays
+    List(Vec<Expression>),
 }
 
 /// Statements
@@ -89,6 +116,11 @@ pub enum Statement {
         condition: Expression,
         body: Box<Statement>,
     },
+    /// `do body while (condition);`: the body runs at least once
+    DoWhile {
+        body: Box<Statement>,
+        condition: Expression,
+    },
     For {
         init: Option<ForInit>,
         condition: Option<Expression>,
@@ -96,6 +128,10 @@ pub enum Statement {
         body: Box<Statement>,
     },
     Return(Option<Expression>),
+    /// Leave the innermost loop
+    Break,
+    /// Go on to the innermost loop's next iteration
+    Continue,
     Empty,
 }
 
@@ -128,12 +164,18 @@ pub enum Expression {
         op: UnaryOp,
         operand: Box<Expression>,
     },
-    /// Assignment
+    /// Assignment to a variable, array element or `*pointer`
     Assignment {
         op: AssignOp,
-        target: String,
+        target: Box<Expression>,
         value: Box<Expression>,
     },
+    /// `condition ? then_value : else_value`
+    Conditional {
+        condition: Box<Expression>,
+        then_value: Box<Expression>,
+        else_value: Box<Expression>,
+    },
     /// Function call
     Call {
         function: String,
@@ -144,6 +186,12 @@ pub enum Expression {
         array: Box<Expression>,
         index: Box<Expression>,
     },
+    /// Struct member access, `object.field`; `p->field` is built as
+    /// `(*p).field`
+    Member {
+        object: Box<Expression>,
+        field: String,
+    },
     /// Post-increment
     PostIncrement(String),
     /// Post-decrement
@@ -9,15 +9,12 @@ pub fn build_ast(pairs: Pairs<Rule>) -> Result<Program, String> {
     let mut items = Vec::new();
 
     for pair in pairs {
-        match pair.as_rule() {
-            Rule::program => {
-                for inner in pair.into_inner() {
-                    if let Some(item) = build_top_level_item(inner)? {
-                        items.push(item);
-                    }
+        if pair.as_rule() == Rule::program {
+            for inner in pair.into_inner() {
+                if let Some(item) = build_top_level_item(inner)? {
+                    items.push(item);
                 }
             }
-            _ => {}
         }
     }
 
@@ -34,6 +31,10 @@ fn build_top_level_item(pair: Pair<Rule>) -> Result<Option<TopLevelItem>, String
             let path = pair.into_inner().next().unwrap().as_str().to_string();
             Ok(Some(TopLevelItem::Include(path)))
         }
+        Rule::struct_definition => {
+            let def = build_struct_definition(pair)?;
+            Ok(Some(TopLevelItem::StructDefinition(def)))
+        }
         Rule::function_definition => {
             let func = build_function(pair)?;
             Ok(Some(TopLevelItem::Function(func)))
@@ -48,13 +49,17 @@ fn build_top_level_item(pair: Pair<Rule>) -> Result<Option<TopLevelItem>, String
 }
 
 fn build_function(pair: Pair<Rule>) -> Result<Function, String> {
+    let line = pair.line_col().0;
     let mut inner = pair.into_inner();
 
     let return_type = build_return_type(inner.next().unwrap())?;
     let name = inner.next().unwrap().as_str().to_string();
 
     let mut parameters = Vec::new();
-    let mut body = Block { items: Vec::new() };
+    let mut body = Block {
+        items: Vec::new(),
+        lines: Vec::new(),
+    };
 
     for part in inner {
         match part.as_rule() {
@@ -73,9 +78,41 @@ fn build_function(pair: Pair<Rule>) -> Result<Function, String> {
         name,
         parameters,
         body,
+        line,
     })
 }
 
+fn build_struct_definition(pair: Pair<Rule>) -> Result<StructDefinition, String> {
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap().as_str().to_string();
+    let mut fields: Vec<Field> = Vec::new();
+    for field in inner {
+        let mut parts = field.into_inner();
+        let ty = build_type_from_rule(parts.next().unwrap())?;
+        let field_name = parts.next().unwrap().as_str().to_string();
+        if fields.iter().any(|f| f.name == field_name) {
+            return Err(format!("Duplicate member '{}' in struct '{}'", field_name, name));
+        }
+        let array_len = match parts.next() {
+            Some(len) => {
+                let len = len
+                    .into_inner()
+                    .next()
+                    .map(|len| parse_integer_literal(len.as_str()))
+                    .transpose()?;
+                Some(array_len(&field_name, len, None)?)
+            }
+            None => None,
+        };
+        fields.push(Field {
+            ty,
+            name: field_name,
+            array_len,
+        });
+    }
+    Ok(StructDefinition { name, fields })
+}
+
 fn build_return_type(pair: Pair<Rule>) -> Result<Type, String> {
     let inner = pair.into_inner().next().unwrap();
     build_type_from_rule(inner)
@@ -92,6 +129,10 @@ fn build_type_from_rule(pair: Pair<Rule>) -> Result<Type, String> {
             Ok(Type::Short { unsigned })
         }
         Rule::char_type => Ok(Type::Char),
+        Rule::struct_type => {
+            let name = pair.into_inner().next().unwrap().as_str().to_string();
+            Ok(Type::Struct(name))
+        }
         Rule::pointer_type => {
             let inner = pair.into_inner().next().unwrap();
             let base = build_type_from_rule(inner)?;
@@ -124,13 +165,15 @@ fn build_parameter_list(pair: Pair<Rule>) -> Result<Vec<Parameter>, String> {
 
 fn build_block(pair: Pair<Rule>) -> Result<Block, String> {
     let mut items = Vec::new();
+    let mut lines = Vec::new();
     for inner in pair.into_inner() {
         if inner.as_rule() == Rule::block_item {
+            lines.push(inner.line_col().0);
             let item = build_block_item(inner)?;
             items.push(item);
         }
     }
-    Ok(Block { items })
+    Ok(Block { items, lines })
 }
 
 fn build_block_item(pair: Pair<Rule>) -> Result<BlockItem, String> {
@@ -176,12 +219,57 @@ fn build_init_declarator_list(pair: Pair<Rule>) -> Result<Vec<Declarator>, Strin
 fn build_init_declarator(pair: Pair<Rule>) -> Result<Declarator, String> {
     let mut inner = pair.into_inner();
     let name = inner.next().unwrap().as_str().to_string();
-    let initializer = if let Some(init_pair) = inner.next() {
-        Some(build_initializer(init_pair)?)
-    } else {
-        None
+    let mut array = None;
+    let mut initializer = None;
+    for part in inner {
+        match part.as_rule() {
+            Rule::array_declarator => {
+                let len = part
+                    .into_inner()
+                    .next()
+                    .map(|len| parse_integer_literal(len.as_str()))
+                    .transpose()?;
+                array = Some(len);
+            }
+            _ => initializer = Some(build_initializer(part)?),
+        }
+    }
+    let array_len = match array {
+        None => None,
+        Some(len) => Some(array_len(&name, len, initializer.as_ref())?),
     };
-    Ok(Declarator { name, initializer })
+    Ok(Declarator {
+        name,
+        array_len,
+        initializer,
+    })
+}
+
+/// The length of array `name`, declared as `len` (if given) and
+/// initialized with `initializer`
+fn array_len(
+    name: &str,
+    len: Option<i32>,
+    initializer: Option<&Initializer>,
+) -> Result<usize, String> {
+    let needed = match initializer {
+        None => 0,
+        Some(Initializer::List(values)) => values.len(),
+        // The terminating NUL too
+        Some(Initializer::String(s)) => s.chars().count() + 1,
+        Some(Initializer::Expression(_)) => {
+            return Err(format!("Array '{}' needs a {{...}} initializer", name));
+        }
+    };
+    match len {
+        Some(len) if len <= 0 => Err(format!("Array '{}' must have a positive length", name)),
+        Some(len) if (len as usize) < needed => {
+            Err(format!("Too many initializers for array '{}'", name))
+        }
+        Some(len) => Ok(len as usize),
+        None if needed == 0 => Err(format!("Array '{}' needs a length", name)),
+        None => Ok(needed),
+    }
 }
 
 fn build_initializer(pair: Pair<Rule>) -> Result<Initializer, String> {
@@ -191,6 +279,10 @@ fn build_initializer(pair: Pair<Rule>) -> Result<Initializer, String> {
             let s = extract_string_content(&inner);
             Ok(Initializer::String(s))
         }
+        Rule::initializer_list => {
+            let values = inner.into_inner().map(build_expression).collect::<Result<_, _>>()?;
+            Ok(Initializer::List(values))
+        }
         _ => {
             // Check if the expression is just a string literal
             let expr = build_expression(inner)?;
@@ -217,8 +309,11 @@ fn build_statement(pair: Pair<Rule>) -> Result<Statement, String> {
         }
         Rule::if_statement => build_if_statement(inner),
         Rule::while_statement => build_while_statement(inner),
+        Rule::do_while_statement => build_do_while_statement(inner),
         Rule::for_statement => build_for_statement(inner),
         Rule::return_statement => build_return_statement(inner),
+        Rule::break_statement => Ok(Statement::Break),
+        Rule::continue_statement => Ok(Statement::Continue),
         Rule::empty_statement => Ok(Statement::Empty),
         _ => Err(format!("Unexpected statement: {:?}", inner.as_rule())),
     }
@@ -245,6 +340,14 @@ fn build_while_statement(pair: Pair<Rule>) -> Result<Statement, String> {
     Ok(Statement::While { condition, body })
 }
 
+fn build_do_while_statement(pair: Pair<Rule>) -> Result<Statement, String> {
+    let mut inner = pair.into_inner();
+    let body = Box::new(build_statement(inner.next().unwrap())?);
+    let condition = build_expression(inner.next().unwrap())?;
+
+    Ok(Statement::DoWhile { body, condition })
+}
+
 fn build_for_statement(pair: Pair<Rule>) -> Result<Statement, String> {
     let mut init = None;
     let mut condition = None;
@@ -307,7 +410,7 @@ fn build_return_statement(pair: Pair<Rule>) -> Result<Statement, String> {
 
 fn build_expression(pair: Pair<Rule>) -> Result<Expression, String> {
     match pair.as_rule() {
-        Rule::expression | Rule::assignment_expression | Rule::conditional_expression => {
+        Rule::expression | Rule::assignment_expression => {
             // Check if this is an assignment
             let mut inner = pair.clone().into_inner().peekable();
             
@@ -318,7 +421,7 @@ fn build_expression(pair: Pair<Rule>) -> Result<Expression, String> {
             }
             let first = first.unwrap();
             
-            if first.as_rule() == Rule::identifier {
+            if first.as_rule() == Rule::lvalue {
                 if let Some(second) = inner.next() {
                     if second.as_rule() == Rule::assignment_operator {
                         let op = match second.as_str() {
@@ -333,7 +436,7 @@ fn build_expression(pair: Pair<Rule>) -> Result<Expression, String> {
                         let value = build_expression(inner.next().unwrap())?;
                         return Ok(Expression::Assignment {
                             op,
-                            target: first.as_str().to_string(),
+                            target: Box::new(build_lvalue(first)?),
                             value: Box::new(value),
                         });
                     }
@@ -343,6 +446,18 @@ fn build_expression(pair: Pair<Rule>) -> Result<Expression, String> {
             // Not an assignment, recurse into first child
             build_expression(pair.into_inner().next().unwrap())
         }
+        Rule::conditional_expression => {
+            let mut inner = pair.into_inner();
+            let condition = build_expression(inner.next().unwrap())?;
+            match (inner.next(), inner.next()) {
+                (Some(then_value), Some(else_value)) => Ok(Expression::Conditional {
+                    condition: Box::new(condition),
+                    then_value: Box::new(build_expression(then_value)?),
+                    else_value: Box::new(build_expression(else_value)?),
+                }),
+                _ => Ok(condition),
+            }
+        }
         Rule::logical_or_expression => build_binary_expression(pair, &[("||", BinaryOp::LogicalOr)]),
         Rule::logical_and_expression => build_binary_expression(pair, &[("&&", BinaryOp::LogicalAnd)]),
         Rule::bitwise_or_expression => build_binary_expression(pair, &[("|", BinaryOp::BitOr)]),
@@ -388,6 +503,17 @@ fn build_expression(pair: Pair<Rule>) -> Result<Expression, String> {
     }
 }
 
+fn build_lvalue(pair: Pair<Rule>) -> Result<Expression, String> {
+    let first = pair.into_inner().next().unwrap();
+    if first.as_rule() == Rule::unary_expression {
+        return Ok(Expression::Unary {
+            op: UnaryOp::Deref,
+            operand: Box::new(build_expression(first)?),
+        });
+    }
+    build_expression(first)
+}
+
 fn build_binary_expression(pair: Pair<Rule>, ops: &[(&str, BinaryOp)]) -> Result<Expression, String> {
     let mut inner = pair.into_inner();
     let mut left = build_expression(inner.next().unwrap())?;
@@ -512,6 +638,21 @@ fn build_postfix_expression(pair: Pair<Rule>) -> Result<Expression, String> {
                                 index: Box::new(index_expr),
                             };
                         }
+                        Rule::member_access => {
+                            let mut parts = suffix_inner.into_inner();
+                            let arrow = parts.next().unwrap().as_str() == "->";
+                            let field = parts.next().unwrap().as_str().to_string();
+                            if arrow {
+                                result = Expression::Unary {
+                                    op: UnaryOp::Deref,
+                                    operand: Box::new(result),
+                                };
+                            }
+                            result = Expression::Member {
+                                object: Box::new(result),
+                                field,
+                            };
+                        }
                         _ => {
                             return Err(format!("Unexpected postfix suffix: {:?}", suffix_inner.as_rule()));
                         }
@@ -752,4 +893,151 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_literals_keep_spaces() {
+        let ast = parse_and_build(r#"int main() { char c = ' '; char *s = "  a b "; }"#).unwrap();
+        let TopLevelItem::Function(f) = &ast.items[0] else {
+            panic!("Expected function");
+        };
+        let initializer = |index: usize| match &f.body.items[index] {
+            BlockItem::Declaration(d) => d.declarators[0].initializer.clone(),
+            _ => panic!("Expected declaration"),
+        };
+        assert_eq!(
+            initializer(0),
+            Some(Initializer::Expression(Expression::CharLiteral(' ')))
+        );
+        assert_eq!(initializer(1), Some(Initializer::String("  a b ".to_string())));
+    }
+
+    #[test]
+    fn test_array_declarations() {
+        let ast = parse_and_build("int a[4] = {1, 2}; int b[] = {3, 4, 5}; int c[2];").unwrap();
+        let declarator = |index: usize| match &ast.items[index] {
+            TopLevelItem::GlobalDeclaration(d) => d.declarators[0].clone(),
+            _ => panic!("Expected declaration"),
+        };
+        assert_eq!(declarator(0).array_len, Some(4));
+        assert_eq!(declarator(1).array_len, Some(3));
+        assert_eq!(declarator(2).array_len, Some(2));
+        assert_eq!(declarator(2).initializer, None);
+
+        assert!(parse_and_build("int a[2] = {1, 2, 3};").is_err());
+        assert!(parse_and_build("int a[];").is_err());
+        assert!(parse_and_build("int a[0];").is_err());
+
+        let ast = parse_and_build("int main() { a[i + 1] += 2; }").unwrap();
+        let TopLevelItem::Function(f) = &ast.items[0] else {
+            panic!("Expected function");
+        };
+        let BlockItem::Statement(Statement::Expression(Expression::Assignment { target, .. })) =
+            &f.body.items[0]
+        else {
+            panic!("Expected assignment");
+        };
+        assert!(matches!(**target, Expression::Subscript { .. }));
+    }
+
+    #[test]
+    fn test_pointer_assignment() {
+        let ast = parse_and_build("int main() { *p = 1; *(p + 1) -= 2; x = *p == 1; }").unwrap();
+        let TopLevelItem::Function(f) = &ast.items[0] else {
+            panic!("Expected function");
+        };
+        let target = |index: usize| match &f.body.items[index] {
+            BlockItem::Statement(Statement::Expression(Expression::Assignment {
+                target, ..
+            })) => (**target).clone(),
+            _ => panic!("Expected assignment"),
+        };
+        let deref = |operand: Expression| Expression::Unary {
+            op: UnaryOp::Deref,
+            operand: Box::new(operand),
+        };
+        assert_eq!(target(0), deref(Expression::Identifier("p".to_string())));
+        let Expression::Unary { op: UnaryOp::Deref, operand } = target(1) else {
+            panic!("Expected dereference");
+        };
+        assert!(matches!(*operand, Expression::Binary { op: BinaryOp::Add, .. }));
+        assert_eq!(target(2), Expression::Identifier("x".to_string()));
+    }
+
+    #[test]
+    fn test_struct_definition() {
+        let source = "struct node { int value; struct node *next; int pad[2]; };
+            int main() { p->next->value = s.value; }";
+        let ast = parse_and_build(source).unwrap();
+        let TopLevelItem::StructDefinition(def) = &ast.items[0] else {
+            panic!("Expected struct definition");
+        };
+        assert_eq!(def.name, "node");
+        assert_eq!(def.fields.len(), 3);
+        assert_eq!(
+            def.fields[1].ty,
+            Type::Pointer(Box::new(Type::Struct("node".to_string())))
+        );
+        assert_eq!(def.fields[2].array_len, Some(2));
+
+        let TopLevelItem::Function(f) = &ast.items[1] else {
+            panic!("Expected function");
+        };
+        let BlockItem::Statement(Statement::Expression(Expression::Assignment {
+            target, value, ..
+        })) = &f.body.items[0]
+        else {
+            panic!("Expected assignment");
+        };
+        let member = |object: Expression, field: &str| Expression::Member {
+            object: Box::new(object),
+            field: field.to_string(),
+        };
+        let deref = |operand: Expression| Expression::Unary {
+            op: UnaryOp::Deref,
+            operand: Box::new(operand),
+        };
+        let p = Expression::Identifier("p".to_string());
+        assert_eq!(**target, member(deref(member(deref(p), "next")), "value"));
+        assert_eq!(**value, member(Expression::Identifier("s".to_string()), "value"));
+
+        assert!(parse_and_build("struct s { int a; int a; };").is_err());
+    }
+
+    #[test]
+    fn test_do_while() {
+        let ast = parse_and_build("int main() { do x++; while (x < 3); }").unwrap();
+        let TopLevelItem::Function(f) = &ast.items[0] else {
+            panic!("Expected function");
+        };
+        let BlockItem::Statement(Statement::DoWhile { body, condition }) = &f.body.items[0] else {
+            panic!("Expected do-while");
+        };
+        assert_eq!(
+            **body,
+            Statement::Expression(Expression::PostIncrement("x".to_string()))
+        );
+        assert!(matches!(condition, Expression::Binary { op: BinaryOp::Less, .. }));
+        assert!(parse_and_build("int do;").is_err());
+    }
+
+    #[test]
+    fn test_conditional() {
+        let ast = parse_and_build("int main() { x = a || b ? 1 : c ? 2 : 3; }").unwrap();
+        let TopLevelItem::Function(f) = &ast.items[0] else {
+            panic!("Expected function");
+        };
+        let BlockItem::Statement(Statement::Expression(Expression::Assignment { value, .. })) =
+            &f.body.items[0]
+        else {
+            panic!("Expected assignment");
+        };
+        // Lower precedence than ||, and right-associative
+        let Expression::Conditional { condition, then_value, else_value } = &**value else {
+            panic!("Expected conditional");
+        };
+        assert!(matches!(**condition, Expression::Binary { op: BinaryOp::LogicalOr, .. }));
+        assert_eq!(**then_value, Expression::IntLiteral(1));
+        assert!(matches!(**else_value, Expression::Conditional { .. }));
+    }
 }
+
@@ -0,0 +1,311 @@
+//! The assembly program under construction
+//!
+//! Code generation appends lines here rather than text: instructions are
+//! `lc3b_isa` instructions, or BR, LEA and JSR naming a label, and data
+//! is directives. The peephole optimizer reworks the instructions, and
+//! only then is the program rendered as text or assembled straight to
+//! words. Padding for alignment is worked out at that point, once the
+//! final addresses are known.
+
+use crate::debug_info::LINE_MARKER;
+use crate::CompileError;
+use lc3b_isa::{Condition, Instruction, PCOffset9, ParseInstructionError, Register};
+use std::collections::HashMap;
+use std::fmt;
+
+pub(crate) const R0: Register = Register::Register0;
+pub(crate) const R1: Register = Register::Register1;
+pub(crate) const R5: Register = Register::Register5;
+pub(crate) const R6: Register = Register::Register6;
+pub(crate) const R7: Register = Register::Register7;
+
+/// Branch conditions, on the value the condition codes were set from
+pub(crate) const ZERO: Condition = Condition { n: false, z: true, p: false };
+pub(crate) const NONZERO: Condition = Condition { n: true, z: false, p: true };
+pub(crate) const NEGATIVE: Condition = Condition { n: true, z: false, p: false };
+pub(crate) const NOT_NEGATIVE: Condition = Condition { n: false, z: true, p: true };
+pub(crate) const POSITIVE: Condition = Condition { n: false, z: false, p: true };
+pub(crate) const NOT_POSITIVE: Condition = Condition { n: true, z: true, p: false };
+
+/// One line of the program
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Line {
+    /// `.ORIG`, where the program is loaded
+    Origin(u16),
+    /// `.END`
+    End,
+    Blank,
+    /// `label:`
+    Label(String),
+    /// `; text`
+    Comment(String),
+    /// A `LINE_MARKER` for a C source line
+    Marker(usize),
+    Instruction(Op),
+    Data(Directive),
+    /// A padding word if need be, so what follows is at an even address
+    Align,
+}
+
+/// An instruction, with any label it refers to left to resolve
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Op {
+    Fixed(Instruction),
+    /// `BR` to a label
+    Branch(Condition, String),
+    /// `LEA` of a label's address
+    Lea(Register, String),
+    /// `JSR` to a label
+    Jsr(String),
+}
+
+impl Op {
+    /// Parse `text` like `ADD R0, R0, #1`, or a BR or JSR to a label like
+    /// `BRz label`
+    pub fn parse(text: &str) -> Result<Self, ParseInstructionError> {
+        let text = text.trim();
+        let (mnemonic, operand) = text.split_once(' ').unwrap_or((text, ""));
+        let operand = operand.trim();
+        let is_label = operand.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+        let condition = mnemonic.strip_prefix("BR").and_then(Condition::from_suffix);
+        match condition {
+            Some(condition) if is_label => Ok(Op::Branch(condition, operand.to_string())),
+            _ if mnemonic == "JSR" && is_label => Ok(Op::Jsr(operand.to_string())),
+            _ => text.parse().map(Op::Fixed),
+        }
+    }
+
+    /// LEA and JSR double their offsets, so they reach the labels they
+    /// are used with, all at even addresses, only from an odd address
+    fn needs_odd_address(&self) -> bool {
+        matches!(self, Op::Lea(..) | Op::Jsr(_))
+    }
+
+    /// The instruction, with its label `target` words past the next one
+    fn resolve(
+        &self,
+        target: impl Fn(&str) -> Result<i32, CompileError>,
+    ) -> Result<Instruction, CompileError> {
+        let halved = |label: &str| {
+            let offset = target(label)?;
+            if offset % 2 != 0 {
+                return Err(CompileError::new(format!("'{}' is not word-aligned", label)));
+            }
+            field(offset / 2)
+        };
+        let instruction = match self {
+            Op::Fixed(instruction) => *instruction,
+            Op::Branch(condition, label) => {
+                let Condition { n, z, p } = *condition;
+                Instruction::br(n, z, p, field(target(label)?)?)?
+            }
+            Op::Lea(register, label) => Instruction::lea(*register, halved(label)?)?,
+            Op::Jsr(label) => Instruction::jsr(halved(label)?)?,
+        };
+        Ok(instruction)
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Fixed(instruction) => write!(f, "{}", instruction),
+            Op::Branch(condition, label) if *condition == Condition::ALWAYS => {
+                write!(f, "BR {}", label)
+            }
+            Op::Branch(condition, label) => write!(f, "BR{} {}", condition.suffix(), label),
+            Op::Lea(register, label) => write!(f, "LEA {}, {}", register, label),
+            Op::Jsr(label) => write!(f, "JSR {}", label),
+        }
+    }
+}
+
+/// A data directive
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Directive {
+    /// `.FILL #value`
+    Fill(i32),
+    /// `.FILL xNNNN`
+    Word(u16),
+    /// `.BLKW #count`: that many zeros
+    Block(usize),
+    /// `.STRINGZ`: the string escaped, then a zero. The assembler takes
+    /// the text between the quotes as is, a word per character.
+    String(String),
+}
+
+impl Directive {
+    fn encode(&self, words: &mut Vec<u16>) -> Result<(), CompileError> {
+        match self {
+            Directive::Fill(value) => {
+                let value: i16 = field(*value)?;
+                words.push(value as u16);
+            }
+            Directive::Word(value) => words.push(*value),
+            Directive::Block(count) => words.resize(words.len() + count, 0),
+            Directive::String(value) => {
+                words.extend(escape_string(value).chars().map(|c| c as u16));
+                words.push(0);
+            }
+        }
+        Ok(())
+    }
+
+    /// How many words of memory it takes
+    pub fn words(&self) -> usize {
+        match self {
+            Directive::Fill(_) | Directive::Word(_) => 1,
+            Directive::Block(count) => *count,
+            Directive::String(value) => escape_string(value).len() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Directive::Fill(value) => write!(f, ".FILL #{}", value),
+            Directive::Word(value) => write!(f, ".FILL x{:04X}", value),
+            Directive::Block(count) => write!(f, ".BLKW #{}", count),
+            Directive::String(value) => write!(f, ".STRINGZ \"{}\"", escape_string(value)),
+        }
+    }
+}
+
+fn escape_string(s: &str) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        match c {
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_ascii_graphic() || c == ' ' => result.push(c),
+            c => result.push_str(&format!("\\x{:02X}", c as u8)),
+        }
+    }
+    result
+}
+
+/// `ADD to, from, #0`: a copy, which sets the condition codes from it
+pub(crate) fn copy(to: Register, from: Register) -> Instruction {
+    Instruction::add_imm(to, from, 0).expect("0 fits any immediate")
+}
+
+/// `ADD reg, reg, #0`, which only sets the condition codes from `reg`
+pub(crate) fn test(reg: Register) -> Instruction {
+    copy(reg, reg)
+}
+
+/// `AND reg, reg, #0`
+pub(crate) fn clear(reg: Register) -> Instruction {
+    Instruction::and_imm(reg, reg, 0).expect("0 fits any immediate")
+}
+
+/// `value` as the type of an instruction's operand. The instruction
+/// checks the range of the operand's field more closely.
+pub(crate) fn field<T, V>(value: V) -> Result<T, CompileError>
+where
+    T: TryFrom<V>,
+    V: Copy + fmt::Display,
+{
+    T::try_from(value)
+        .map_err(|_| CompileError::new(format!("operand value {} out of range", value)))
+}
+
+/// `BRnzp #0`, which pads the program with a word that does nothing
+fn padding() -> Instruction {
+    Instruction::Br(Condition::ALWAYS, PCOffset9::new(0))
+}
+
+/// The address of each line, and whether a padding word goes before it
+fn layout(lines: &[Line]) -> Vec<(usize, bool)> {
+    let mut address = 0;
+    let mut placed = Vec::with_capacity(lines.len());
+    for line in lines {
+        if let Line::Origin(origin) = line {
+            address = usize::from(*origin);
+        }
+        let padded = match line {
+            Line::Instruction(op) => op.needs_odd_address() && address % 2 == 0,
+            Line::Align => address % 2 == 1,
+            _ => false,
+        };
+        if padded {
+            address += 1;
+        }
+        placed.push((address, padded));
+        address += match line {
+            Line::Instruction(_) => 1,
+            Line::Data(directive) => directive.words(),
+            _ => 0,
+        };
+    }
+    placed
+}
+
+/// The program as assembly text, padded where alignment needs it
+pub(crate) fn render(lines: &[Line]) -> String {
+    let mut out = String::new();
+    for (line, &(_, padded)) in lines.iter().zip(&layout(lines)) {
+        let text = match line {
+            Line::Origin(origin) => format!(".ORIG x{:04X}", origin),
+            Line::End => ".END".to_string(),
+            Line::Blank => String::new(),
+            Line::Label(label) => format!("{}:", label),
+            Line::Comment(text) => format!("; {}", text),
+            Line::Marker(line) => format!("{}{}", LINE_MARKER, line),
+            Line::Instruction(op) => {
+                if padded {
+                    out.push_str(&format!("    {}\n", padding()));
+                }
+                format!("    {}", op)
+            }
+            Line::Data(directive) => format!("    {}", directive),
+            Line::Align if padded => "    .FILL x0000  ; padding for alignment".to_string(),
+            Line::Align => continue,
+        };
+        out.push_str(&text);
+        out.push('\n');
+    }
+    out
+}
+
+/// The program as words from its origin, as assembling the rendered text
+/// would give
+pub(crate) fn assemble(lines: &[Line]) -> Result<(u16, Vec<u16>), CompileError> {
+    let placed = layout(lines);
+    let mut labels = HashMap::new();
+    for (line, &(address, _)) in lines.iter().zip(&placed) {
+        if let Line::Label(label) = line {
+            labels.insert(label.as_str(), address);
+        }
+    }
+
+    let mut origin = None;
+    let mut words = Vec::new();
+    for (line, &(address, padded)) in lines.iter().zip(&placed) {
+        match line {
+            Line::Origin(address) => {
+                origin.get_or_insert(*address);
+            }
+            Line::End => break,
+            Line::Instruction(op) => {
+                if padded {
+                    words.push(u16::from(&padding()));
+                }
+                let target = |label: &str| match labels.get(label) {
+                    Some(&target) => Ok(target as i32 - (address as i32 + 1)),
+                    None => Err(CompileError::new(format!("undefined label '{}'", label))),
+                };
+                words.push(u16::from(&op.resolve(target)?));
+            }
+            Line::Data(directive) => directive.encode(&mut words)?,
+            Line::Align if padded => words.push(0),
+            _ => {}
+        }
+    }
+    let origin = origin.ok_or_else(|| CompileError::new("the program has no origin"))?;
+    Ok((origin, words))
+}
@@ -1,8 +1,19 @@
-//! Code generation: AST to LC-3B assembly text
+//! Code generation: AST to LC-3B assembly, as the lines of `asm`
 
+use crate::asm::{
+    self, field, Directive, Line, Op, NEGATIVE, NONZERO, NOT_NEGATIVE, NOT_POSITIVE, POSITIVE,
+    R0, R1, R5, R6, R7, ZERO,
+};
+use crate::fold;
 use crate::headers::get_header;
+use crate::layout::Layouts;
+use crate::peephole;
+use crate::regalloc::{self, Allocation};
+use crate::runtime::Routine;
 use lc3b_c_ast::*;
-use std::collections::HashMap;
+use lc3b_c_grammar::SourceLocation;
+use lc3b_isa::{Condition, Instruction, OperandError, Register};
+use std::collections::{BTreeSet, HashMap};
 
 /// Compilation options
 #[derive(Debug, Clone)]
@@ -11,6 +22,18 @@ pub struct CompileOptions {
     pub origin: u16,
     /// Include comments showing original C code
     pub emit_comments: bool,
+    /// Mark where each function and statement's code starts with its C
+    /// source line, for `line_markers`
+    pub debug_info: bool,
+    /// How many leading arguments are passed in R0, R1 and R2 rather than
+    /// on the stack, at most 3 (default: 0)
+    pub register_args: usize,
+    /// How hard to optimize: 0 compiles the program as written, 1 first
+    /// folds constant expressions, drops branches that can't run and
+    /// propagates constants through locals, then multiplies and divides
+    /// by constants with shifts and adds where it can, and 2 also
+    /// rewrites wasteful runs of the generated instructions (default: 0)
+    pub opt_level: u8,
 }
 
 impl Default for CompileOptions {
@@ -18,6 +41,9 @@ impl Default for CompileOptions {
         Self {
             origin: 0x3000,
             emit_comments: true,
+            debug_info: true,
+            register_args: 0,
+            opt_level: 0,
         }
     }
 }
@@ -26,6 +52,33 @@ impl Default for CompileOptions {
 #[derive(Debug, Clone)]
 pub struct CompileError {
     pub message: String,
+    /// Known for syntax errors in the program itself; later errors are
+    /// found on the syntax tree, which keeps no positions
+    pub location: Option<SourceLocation>,
+}
+
+impl CompileError {
+    pub fn new(message: impl Into<String>) -> Self {
+        CompileError {
+            message: message.into(),
+            location: None,
+        }
+    }
+}
+
+impl From<Box<lc3b_c_grammar::Error>> for CompileError {
+    fn from(error: Box<lc3b_c_grammar::Error>) -> Self {
+        CompileError {
+            message: error.to_string(),
+            location: Some(SourceLocation::from(&*error)),
+        }
+    }
+}
+
+impl From<OperandError> for CompileError {
+    fn from(error: OperandError) -> Self {
+        CompileError::new(error.to_string())
+    }
 }
 
 impl std::fmt::Display for CompileError {
@@ -36,22 +89,58 @@ impl std::fmt::Display for CompileError {
 
 impl std::error::Error for CompileError {}
 
+/// A program compiled straight to machine words
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledWords {
+    /// Where the program is loaded, `CompileOptions::origin`
+    pub origin: u16,
+    /// The words from `origin` on, as assembling the text `compile` gives
+    /// would produce
+    pub words: Vec<u16>,
+}
+
 /// Compile C source to LC-3B assembly text
 pub fn compile(source: &str, options: &CompileOptions) -> Result<String, CompileError> {
+    Ok(asm::render(&compile_lines(source, options)?))
+}
+
+/// Compile C source to machine words, without going through assembly text
+pub fn compile_to_words(
+    source: &str,
+    options: &CompileOptions,
+) -> Result<CompiledWords, CompileError> {
+    let (origin, words) = asm::assemble(&compile_lines(source, options)?)?;
+    Ok(CompiledWords { origin, words })
+}
+
+fn compile_lines(source: &str, options: &CompileOptions) -> Result<Vec<Line>, CompileError> {
+    if options.register_args > 3 {
+        return Err(CompileError::new(format!(
+            "at most 3 arguments can be passed in registers, not {}",
+            options.register_args
+        )));
+    }
+
     // First pass: parse the source to find includes
     let pairs = lc3b_c_grammar::parse(source)
-        .map_err(|e| CompileError { message: e.to_string() })?;
+        .map_err(CompileError::from)?;
     
     let ast = lc3b_c_ast::build_ast(pairs)
-        .map_err(|e| CompileError { message: e })?;
+        .map_err(CompileError::new)?;
     
     // Expand includes by parsing header contents and merging
-    let expanded_ast = expand_includes(&ast)?;
+    let mut expanded_ast = expand_includes(&ast)?;
+    if options.opt_level > 0 {
+        fold::fold_program(&mut expanded_ast);
+    }
     
     let mut compiler = Compiler::new(options.clone());
     compiler.compile_program(&expanded_ast)?;
-    
-    Ok(compiler.output)
+    if options.opt_level > 1 {
+        peephole::optimize(&mut compiler.lines);
+    }
+
+    Ok(compiler.lines)
 }
 
 /// Expand #include directives by parsing and merging header contents
@@ -62,20 +151,15 @@ fn expand_includes(program: &Program) -> Result<Program, CompileError> {
         match item {
             TopLevelItem::Include(path) => {
                 // Look up the header
-                let header_source = get_header(path).ok_or_else(|| CompileError {
-                    message: format!("Unknown header file: <{}>", path),
-                })?;
+                let header_source = get_header(path)
+                    .ok_or_else(|| CompileError::new(format!("Unknown header file: <{}>", path)))?;
                 
                 // Parse the header
                 let pairs = lc3b_c_grammar::parse(header_source)
-                    .map_err(|e| CompileError { 
-                        message: format!("Error parsing <{}>: {}", path, e) 
-                    })?;
+                    .map_err(|e| CompileError::new(format!("Error parsing <{}>: {}", path, e)))?;
                 
                 let header_ast = lc3b_c_ast::build_ast(pairs)
-                    .map_err(|e| CompileError { 
-                        message: format!("Error in <{}>: {}", path, e) 
-                    })?;
+                    .map_err(|e| CompileError::new(format!("Error in <{}>: {}", path, e)))?;
                 
                 // Add all items from the header (except nested includes for now)
                 for header_item in header_ast.items {
@@ -112,17 +196,26 @@ struct InlineableFunction {
 /// Compiler state
 struct Compiler {
     options: CompileOptions,
-    output: String,
+    lines: Vec<Line>,
     /// Current label counter for generating unique labels
     label_counter: u32,
     /// Variable storage: maps variable name to location (register or stack)
     locals: HashMap<String, VarLocation>,
-    /// Current stack offset for next local variable (when using stack allocation)
-    local_offset: i16,
-    /// Next available register for allocation (R1-R4)
-    next_reg: u8,
-    /// Whether current function uses register allocation
-    use_registers: bool,
+    /// Local arrays and structs: maps name to the offset of the first word
+    /// from R5
+    arrays: HashMap<String, i16>,
+    /// Types of locals, arrays as pointers to their first element
+    local_types: HashMap<String, Type>,
+    /// Types of globals, arrays as pointers to their first element
+    global_types: HashMap<String, Type>,
+    /// Return types of functions
+    function_types: HashMap<String, Type>,
+    /// Struct definitions
+    layouts: Layouts,
+    /// Words of the stack frame below R5 allocated to locals
+    frame_words: i16,
+    /// Registers given to the current function's locals
+    allocation: Allocation,
     /// Global variables and string literals
     data_section: Vec<DataItem>,
     /// Current function name (for generating labels)
@@ -133,10 +226,21 @@ struct Compiler {
     defined_globals: std::collections::HashSet<String>,
     /// Set of globals initialized with string literals (these point directly to the string, not a pointer)
     string_globals: std::collections::HashSet<String>,
-    /// Count of words emitted (for alignment)
-    word_count: usize,
+    /// Set of global arrays and structs (the name is the address of the
+    /// first word)
+    global_arrays: std::collections::HashSet<String>,
     /// Functions that can be inlined (maps name to inline info)
     inlineable_functions: HashMap<String, InlineableFunction>,
+    /// Runtime routines the program calls, emitted after its functions
+    runtime: BTreeSet<Routine>,
+    /// Enclosing loops, innermost last
+    loops: Vec<LoopLabels>,
+}
+
+/// Where `break` and `continue` go in a loop
+struct LoopLabels {
+    break_label: String,
+    continue_label: String,
 }
 
 enum DataItem {
@@ -144,17 +248,6 @@ enum DataItem {
     Word { label: String, value: i32 },
 }
 
-/// Analyze a function to determine if it's "simple" enough for register allocation
-fn is_simple_function(func: &Function) -> bool {
-    let mut local_count = 0;
-    let mut has_calls = false;
-    
-    count_locals_and_calls(&func.body, &mut local_count, &mut has_calls);
-    
-    // Simple if: at most 4 locals AND no function calls (except trap)
-    local_count <= 4 && !has_calls
-}
-
 /// Check if a function is just a single trap() call and return the trap vector if so
 fn get_trap_only_function(func: &Function) -> Option<u8> {
     // Must have exactly one statement in the body
@@ -178,127 +271,109 @@ fn get_trap_only_function(func: &Function) -> Option<u8> {
     }
 }
 
-fn count_locals_and_calls(block: &Block, local_count: &mut usize, has_calls: &mut bool) {
-    for item in &block.items {
-        match item {
-            BlockItem::Declaration(decl) => {
-                *local_count += decl.declarators.len();
-            }
-            BlockItem::Statement(stmt) => {
-                check_statement_for_calls(stmt, local_count, has_calls);
-            }
-        }
-    }
-}
-
-fn check_statement_for_calls(stmt: &Statement, local_count: &mut usize, has_calls: &mut bool) {
-    match stmt {
-        Statement::Expression(expr) => {
-            check_expression_for_calls(expr, has_calls);
-        }
-        Statement::Compound(block) => {
-            count_locals_and_calls(block, local_count, has_calls);
-        }
-        Statement::If { condition, then_branch, else_branch } => {
-            check_expression_for_calls(condition, has_calls);
-            check_statement_for_calls(then_branch, local_count, has_calls);
-            if let Some(else_stmt) = else_branch {
-                check_statement_for_calls(else_stmt, local_count, has_calls);
-            }
-        }
-        Statement::While { condition, body } => {
-            check_expression_for_calls(condition, has_calls);
-            check_statement_for_calls(body, local_count, has_calls);
-        }
-        Statement::For { init, condition, update, body } => {
-            if let Some(ForInit::Declaration(decl)) = init {
-                *local_count += decl.declarators.len();
-            }
-            if let Some(ForInit::Expression(expr)) = init {
-                check_expression_for_calls(expr, has_calls);
-            }
-            if let Some(cond) = condition {
-                check_expression_for_calls(cond, has_calls);
-            }
-            if let Some(upd) = update {
-                check_expression_for_calls(upd, has_calls);
-            }
-            check_statement_for_calls(body, local_count, has_calls);
-        }
-        Statement::Return(Some(expr)) => {
-            check_expression_for_calls(expr, has_calls);
-        }
-        _ => {}
-    }
-}
-
-fn check_expression_for_calls(expr: &Expression, has_calls: &mut bool) {
-    match expr {
-        Expression::Call { function, arguments } => {
-            // trap() is an intrinsic, doesn't count as a real call
-            if function != "trap" {
-                *has_calls = true;
-            }
-            for arg in arguments {
-                check_expression_for_calls(arg, has_calls);
-            }
-        }
-        Expression::Binary { left, right, .. } => {
-            check_expression_for_calls(left, has_calls);
-            check_expression_for_calls(right, has_calls);
-        }
-        Expression::Unary { operand, .. } => {
-            check_expression_for_calls(operand, has_calls);
-        }
-        Expression::Assignment { value, .. } => {
-            check_expression_for_calls(value, has_calls);
-        }
-        Expression::Subscript { array, index } => {
-            check_expression_for_calls(array, has_calls);
-            check_expression_for_calls(index, has_calls);
-        }
-        _ => {}
-    }
-}
-
 impl Compiler {
     fn new(options: CompileOptions) -> Self {
         Self {
             options,
-            output: String::new(),
+            lines: Vec::new(),
             label_counter: 0,
             locals: HashMap::new(),
-            local_offset: 0,
-            next_reg: 1, // Start with R1 (R0 is for return values/temps)
-            use_registers: false,
+            arrays: HashMap::new(),
+            local_types: HashMap::new(),
+            global_types: HashMap::new(),
+            function_types: HashMap::new(),
+            layouts: Layouts::default(),
+            frame_words: 0,
+            allocation: Allocation::default(),
             data_section: Vec::new(),
             current_function: String::new(),
             defined_functions: std::collections::HashSet::new(),
             defined_globals: std::collections::HashSet::new(),
             string_globals: std::collections::HashSet::new(),
-            word_count: 0,
+            global_arrays: std::collections::HashSet::new(),
             inlineable_functions: HashMap::new(),
+            runtime: BTreeSet::new(),
+            loops: Vec::new(),
         }
     }
 
-    fn emit(&mut self, line: &str) {
-        self.output.push_str(line);
-        self.output.push('\n');
+    fn emit(&mut self, line: Line) {
+        self.lines.push(line);
     }
 
     fn emit_comment(&mut self, comment: &str) {
         if self.options.emit_comments {
-            self.emit(&format!("; {}", comment));
+            self.emit(Line::Comment(comment.to_string()));
+        }
+    }
+
+    fn emit_line_marker(&mut self, line: usize) {
+        if self.options.debug_info {
+            self.emit(Line::Marker(line));
         }
     }
 
     fn emit_label(&mut self, label: &str) {
-        self.emit(&format!("{}:", label));
+        self.emit(Line::Label(label.to_string()));
+    }
+
+    fn emit_instruction(&mut self, instruction: Instruction) {
+        self.emit(Line::Instruction(Op::Fixed(instruction)));
+    }
+
+    fn emit_branch(&mut self, condition: Condition, label: &str) {
+        self.emit(Line::Instruction(Op::Branch(condition, label.to_string())));
+    }
+
+    /// LEA doubles its offset, so it reaches only labels an even number of
+    /// words past the next instruction. Data labels are all at even
+    /// addresses (see `emit_data`), so the LEA is put at an odd one, after
+    /// a no-op if need be, when the program is laid out.
+    fn emit_lea(&mut self, reg: u8, label: &str) {
+        self.emit(Line::Instruction(Op::Lea(Register::from_index(reg), label.to_string())));
+    }
+
+    /// JSR doubles its offset like LEA does. Functions and runtime routines
+    /// start at even addresses (see `align`), so the JSR goes at an odd one.
+    fn emit_jsr(&mut self, label: &str) {
+        self.emit(Line::Instruction(Op::Jsr(label.to_string())));
+    }
+
+    /// Pad with a word, if need be, so what comes next is at an even address
+    fn align(&mut self) {
+        self.emit(Line::Align);
+    }
+
+    /// Rd = Rs + `value`, in as many ADDs as the 5-bit immediate needs
+    fn emit_add_immediate(&mut self, dst: u8, src: u8, value: i32) -> Result<(), CompileError> {
+        let dst = Register::from_index(dst);
+        let mut from = Register::from_index(src);
+        let mut rest = value;
+        loop {
+            let step = rest.clamp(-16, 15);
+            if step != 0 || from != dst {
+                self.emit_instruction(Instruction::add_imm(dst, from, field(step)?)?);
+            }
+            from = dst;
+            rest -= step;
+            if rest == 0 {
+                break;
+            }
+        }
+        Ok(())
     }
 
-    fn emit_instruction(&mut self, instr: &str) {
-        self.emit(&format!("    {}", instr));
-        self.word_count += 1;
+    /// Emit labelled data directives, padded to an even length so the
+    /// next label is at an even address too
+    fn emit_data(&mut self, label: &str, directives: Vec<Directive>) {
+        self.emit_label(label);
+        let words: usize = directives.iter().map(Directive::words).sum();
+        for directive in directives {
+            self.emit(Line::Data(directive));
+        }
+        if !words.is_multiple_of(2) {
+            self.emit(Line::Data(Directive::Word(0)));
+        }
     }
 
     fn new_label(&mut self, prefix: &str) -> String {
@@ -311,8 +386,12 @@ impl Compiler {
         // First pass: collect all defined functions, globals, and detect inlineable functions
         for item in &program.items {
             match item {
+                TopLevelItem::StructDefinition(def) => {
+                    self.layouts.define(def)?;
+                }
                 TopLevelItem::Function(f) => {
                     self.defined_functions.insert(f.name.clone());
+                    self.function_types.insert(f.name.clone(), f.return_type.clone());
                     
                     // Check if this function is just a trap wrapper
                     if let Some(trap_vector) = get_trap_only_function(f) {
@@ -325,10 +404,15 @@ impl Compiler {
                 TopLevelItem::GlobalDeclaration(d) => {
                     for declarator in &d.declarators {
                         self.defined_globals.insert(declarator.name.clone());
+                        let ty = declared_type(d, declarator);
+                        self.global_types.insert(declarator.name.clone(), ty);
                         // Track globals initialized with string literals
                         if let Some(Initializer::String(_)) = &declarator.initializer {
                             self.string_globals.insert(declarator.name.clone());
                         }
+                        if declarator.array_len.is_some() || matches!(d.ty, Type::Struct(_)) {
+                            self.global_arrays.insert(declarator.name.clone());
+                        }
                     }
                 }
                 TopLevelItem::Include(_) => {}
@@ -336,8 +420,8 @@ impl Compiler {
         }
         
         // Emit origin
-        self.emit(&format!(".ORIG x{:04X}", self.options.origin));
-        self.emit("");
+        self.emit(Line::Origin(self.options.origin));
+        self.emit(Line::Blank);
 
         // Find main function and other functions
         let mut main_func = None;
@@ -349,6 +433,7 @@ impl Compiler {
                 TopLevelItem::Include(_) => {
                     // Includes should already be expanded; skip if any remain
                 }
+                TopLevelItem::StructDefinition(_) => {}
                 TopLevelItem::Function(f) if f.name == "main" => {
                     main_func = Some(f);
                 }
@@ -372,20 +457,23 @@ impl Compiler {
             if self.inlineable_functions.contains_key(&func.name) {
                 continue;
             }
-            self.emit("");
+            self.emit(Line::Blank);
             self.compile_function(func)?;
         }
 
+        // Emit the runtime routines used
+        for routine in std::mem::take(&mut self.runtime) {
+            self.emit(Line::Blank);
+            self.emit_routine(routine);
+        }
+
         // Emit data section
         if !self.data_section.is_empty() || !globals.is_empty() {
-            self.emit("");
+            self.emit(Line::Blank);
             self.emit_comment("Data section");
             
             // Ensure data section starts at even word boundary for LEA alignment
-            if self.word_count % 2 != 0 {
-                self.emit("    .FILL x0000  ; padding for alignment");
-                self.word_count += 1;
-            }
+            self.align();
             
             for global in globals {
                 self.compile_global_declaration(global)?;
@@ -396,46 +484,85 @@ impl Compiler {
             for item in data_items {
                 match item {
                     DataItem::String { label, value } => {
-                        self.emit_label(&label);
-                        self.emit(&format!("    .STRINGZ \"{}\"", escape_string(&value)));
+                        self.emit_data(&label, vec![Directive::String(value)]);
                     }
                     DataItem::Word { label, value } => {
-                        self.emit_label(&label);
-                        if value < 0 {
-                            self.emit(&format!("    .FILL #{}", value));
+                        let directive = if value < 0 {
+                            Directive::Fill(value)
                         } else {
-                            self.emit(&format!("    .FILL x{:04X}", value as u16));
-                        }
+                            Directive::Word(value as u16)
+                        };
+                        self.emit_data(&label, vec![directive]);
                     }
                 }
             }
         }
 
-        self.emit("");
-        self.emit(".END");
+        self.emit(Line::Blank);
+        self.emit(Line::End);
 
         Ok(())
     }
 
+    fn emit_routine(&mut self, routine: Routine) {
+        self.align();
+        for line in routine.source().lines().map(str::trim) {
+            if let Some(label) = line.strip_suffix(':') {
+                self.emit_label(label);
+            } else if let Some(comment) = line.strip_prefix(';') {
+                self.emit_comment(comment.trim_start());
+            } else if !line.is_empty() {
+                let op = Op::parse(line).expect("runtime routines are valid assembly");
+                self.emit(Line::Instruction(op));
+            }
+        }
+    }
+
+    /// Call `routine` on R0 and R7, leaving its result in R0 (and a
+    /// remainder in R7). Routines take their second operand in R1, so a
+    /// local there is saved around the call.
+    fn call_routine(&mut self, routine: Routine) -> Result<(), CompileError> {
+        self.runtime.insert(routine);
+        let save = self.allocation.uses(1);
+        if save {
+            self.emit_instruction(Instruction::add_imm(R6, R6, -1)?);
+            self.emit_instruction(Instruction::stw(R1, R6, 0)?);
+        }
+        self.emit_instruction(asm::copy(R1, R7));
+        self.emit_jsr(routine.label());
+        if routine == Routine::DivMod {
+            self.emit_instruction(asm::copy(R7, R1));
+        }
+        if save {
+            self.emit_instruction(Instruction::ldr(R1, R6, 0)?);
+            self.emit_instruction(Instruction::add_imm(R6, R6, 1)?);
+        }
+        Ok(())
+    }
+
     fn compile_main(&mut self, func: &Function) -> Result<(), CompileError> {
         self.current_function = "main".to_string();
         self.emit_comment("int main()");
         self.emit_label("main");
+        self.emit_line_marker(func.line);
 
         // Reset locals for this function
         self.locals.clear();
-        self.local_offset = -1; // First local at offset -1 from FP
-        self.next_reg = 1; // R1-R4 available for locals
-        
-        // Check if we can use register allocation
-        self.use_registers = is_simple_function(func);
-        
-        if self.use_registers {
-            self.emit_comment("Using register allocation for locals");
-        } else {
+        self.arrays.clear();
+        self.local_types.clear();
+        self.frame_words = 0;
+        self.allocate_registers(func);
+
+        // The stack grows down from the I/O page; R6 = xFFFF << 9 = xFE00
+        self.emit_comment("Set up the stack");
+        self.emit_instruction(asm::clear(R6));
+        self.emit_instruction(Instruction::add_imm(R6, R6, -1)?);
+        self.emit_instruction(Instruction::lshf(R6, R6, 9)?);
+
+        if self.allocation.needs_frame() {
             // main() is the entry point - no stack frame setup needed
             // Just set R5 = R6 so local variable addressing works
-            self.emit_instruction("ADD R5, R6, #0");  // R5 = SP (frame pointer for locals)
+            self.emit_instruction(asm::copy(R5, R6));  // R5 = SP (frame pointer for locals)
         }
 
         // Compile function body
@@ -443,7 +570,7 @@ impl Compiler {
 
         // End of main - halt the machine
         self.emit_label("main_exit");
-        self.emit_instruction("HALT");
+        self.emit_instruction(Instruction::trap(0x25));
 
         Ok(())
     }
@@ -460,32 +587,65 @@ impl Compiler {
                 .collect::<Vec<_>>()
                 .join(", ")
         ));
+        self.align();
         self.emit_label(&func.name);
+        self.emit_line_marker(func.line);
 
         // Reset locals
         self.locals.clear();
-        self.local_offset = -1;
-        self.next_reg = 1;
-        
-        // For non-main functions, we always need stack frame for R7 (return address)
-        // But we can still use registers for locals if it's simple
-        self.use_registers = is_simple_function(func) && func.parameters.is_empty();
+        self.arrays.clear();
+        self.local_types.clear();
+        self.frame_words = 0;
+        self.allocate_registers(func);
 
-        // Set up stack frame
+        // For non-main functions, we always need stack frame for R7 (return address)
+        // Set up stack frame: the return address at R5, the caller's R5 one
+        // slot (two words) above it
         self.emit_comment("Set up stack frame");
-        self.emit_instruction("ADD R6, R6, #-2");
-        self.emit_instruction("STW R7, R6, #0");
-        self.emit_instruction("STW R5, R6, #1");
-        self.emit_instruction("ADD R5, R6, #0");
-
-        if self.use_registers {
-            self.emit_comment("Using register allocation for locals");
+        self.emit_instruction(Instruction::add_imm(R6, R6, -4)?);
+        self.emit_instruction(Instruction::stw(R7, R6, 0)?);
+        self.emit_instruction(Instruction::stw(R5, R6, 1)?);
+        self.emit_instruction(asm::copy(R5, R6));
+
+        // Parameters passed in registers go to their own registers, or are
+        // stored below R5 like locals, before anything can overwrite them
+        let in_registers = func.parameters.len().min(self.options.register_args);
+        let (in_registers, on_stack) = func.parameters.split_at(in_registers);
+        let mut moves = Vec::new();
+        for (i, param) in in_registers.iter().enumerate() {
+            let location = match self.allocation.register(&param.name) {
+                Some(reg) => {
+                    moves.push((i as u8, reg));
+                    VarLocation::Register(reg)
+                }
+                None => {
+                    let offset = self.allocate_slot()?;
+                    let reg = Register::from_index(i as u8);
+                    self.emit_instruction(Instruction::stw(reg, R5, field(offset)?)?);
+                    VarLocation::Stack(offset)
+                }
+            };
+            self.locals.insert(param.name.clone(), location);
+            self.local_types.insert(param.name.clone(), param.ty.clone());
         }
+        self.emit_moves(moves);
 
         // Map parameters to positive offsets from frame pointer
-        // Parameters are pushed right-to-left by caller, so first param is at FP+2
-        for (i, param) in func.parameters.iter().enumerate() {
-            self.locals.insert(param.name.clone(), VarLocation::Stack(i as i16 + 2));
+        // Parameters are pushed right-to-left by caller, one slot each, so
+        // the first is in the slot above the saved R5. Those given
+        // registers are loaded into them.
+        for (i, param) in on_stack.iter().enumerate() {
+            let offset = i as i16 + 2;
+            let location = match self.allocation.register(&param.name) {
+                Some(reg) => {
+                    let dr = Register::from_index(reg);
+                    self.emit_instruction(Instruction::ldr(dr, R5, field(offset)?)?);
+                    VarLocation::Register(reg)
+                }
+                None => VarLocation::Stack(offset),
+            };
+            self.locals.insert(param.name.clone(), location);
+            self.local_types.insert(param.name.clone(), param.ty.clone());
         }
 
         // Compile body
@@ -495,17 +655,58 @@ impl Compiler {
         let exit_label = format!("{}_exit", func.name);
         self.emit_label(&exit_label);
         self.emit_comment("Function epilogue");
-        self.emit_instruction("ADD R6, R5, #0");  // SP = FP
-        self.emit_instruction("LDW R5, R6, #1");  // Restore old FP
-        self.emit_instruction("LDW R7, R6, #0");  // Restore return address
-        self.emit_instruction("ADD R6, R6, #2");  // Pop frame
-        self.emit_instruction("RET");
+        self.emit_instruction(asm::copy(R6, R5));  // SP = FP
+        self.emit_instruction(Instruction::ldr(R5, R6, 1)?);  // Restore old FP
+        self.emit_instruction(Instruction::ldr(R7, R6, 0)?);  // Restore return address
+        self.emit_instruction(Instruction::add_imm(R6, R6, 4)?);  // Pop frame
+        self.emit_instruction(Instruction::ret());
 
         Ok(())
     }
 
+    /// Decide where `func`'s locals live before compiling it
+    fn allocate_registers(&mut self, func: &Function) {
+        let inlined = &self.inlineable_functions;
+        self.allocation =
+            regalloc::allocate(func, |name| name != "trap" && !inlined.contains_key(name));
+        let assignments: Vec<String> = self
+            .allocation
+            .assignments()
+            .iter()
+            .map(|(name, reg)| format!("{} in R{}", name, reg))
+            .collect();
+        for comment in assignments {
+            self.emit_comment(&comment);
+        }
+    }
+
+    /// Copy registers to registers, `(from, to)`, as if all at once: a move
+    /// waits until its destination has been read, and a cycle is broken by
+    /// copying one source to R7.
+    fn emit_moves(&mut self, mut moves: Vec<(u8, u8)>) {
+        moves.retain(|&(from, to)| from != to);
+        while !moves.is_empty() {
+            let ready = moves.iter().position(|&(_, to)| moves.iter().all(|&(from, _)| from != to));
+            match ready {
+                Some(i) => {
+                    let (from, to) = moves.remove(i);
+                    let (from, to) = (Register::from_index(from), Register::from_index(to));
+                    self.emit_instruction(asm::copy(to, from));
+                }
+                None => {
+                    let from = moves[0].0;
+                    self.emit_instruction(asm::copy(R7, Register::from_index(from)));
+                    moves[0].0 = 7;
+                }
+            }
+        }
+    }
+
     fn compile_block(&mut self, block: &Block) -> Result<(), CompileError> {
-        for item in &block.items {
+        for (i, item) in block.items.iter().enumerate() {
+            if let Some(&line) = block.lines.get(i) {
+                self.emit_line_marker(line);
+            }
             match item {
                 BlockItem::Declaration(decl) => {
                     self.compile_declaration(decl)?;
@@ -520,21 +721,20 @@ impl Compiler {
 
     fn compile_declaration(&mut self, decl: &Declaration) -> Result<(), CompileError> {
         for declarator in &decl.declarators {
+            self.local_types.insert(declarator.name.clone(), declared_type(decl, declarator));
+            if let Some(words) = self.object_words(&decl.ty, declarator)? {
+                self.compile_array_declaration(&decl.ty, declarator, words)?;
+                continue;
+            }
+
             // Decide where to allocate this variable
-            let location = if self.use_registers && self.next_reg <= 4 {
-                // Allocate to a register
-                let reg = self.next_reg;
-                self.next_reg += 1;
-                VarLocation::Register(reg)
-            } else {
-                // Allocate on stack
-                self.emit_instruction("ADD R6, R6, #-1"); // Push space for variable
-                let loc = VarLocation::Stack(self.local_offset);
-                self.local_offset -= 1;
-                loc
+            let location = match self.allocation.register(&declarator.name) {
+                Some(reg) => VarLocation::Register(reg),
+                None => VarLocation::Stack(self.allocate_slot()?),
             };
             
             // Record variable location
+            self.arrays.remove(&declarator.name);
             self.locals.insert(declarator.name.clone(), location);
             
             if let Some(init) = &declarator.initializer {
@@ -543,15 +743,6 @@ impl Compiler {
                     Initializer::Expression(expr) => {
                         // Evaluate expression into R0
                         self.compile_expression(expr)?;
-                        // Store R0 at variable location
-                        match location {
-                            VarLocation::Register(reg) => {
-                                self.emit_instruction(&format!("ADD R{}, R0, #0", reg));
-                            }
-                            VarLocation::Stack(offset) => {
-                                self.emit_instruction(&format!("STW R0, R5, #{}", offset));
-                            }
-                        }
                     }
                     Initializer::String(s) => {
                         // Create string in data section and store pointer
@@ -560,51 +751,183 @@ impl Compiler {
                             label: label.clone(),
                             value: s.clone(),
                         });
-                        self.emit_instruction(&format!("LEA R0, {}", label));
-                        match location {
-                            VarLocation::Register(reg) => {
-                                self.emit_instruction(&format!("ADD R{}, R0, #0", reg));
-                            }
-                            VarLocation::Stack(offset) => {
-                                self.emit_instruction(&format!("STW R0, R5, #{}", offset));
-                            }
-                        }
+                        self.emit_lea(0, &label);
+                    }
+                    Initializer::List(_) => {
+                        return Err(CompileError::new(format!(
+                            "'{}' is not an array but has a {{...}} initializer",
+                            declarator.name
+                        )));
+                    }
+                }
+                // Store R0 at variable location
+                match location {
+                    VarLocation::Register(reg) => {
+                        self.emit_instruction(asm::copy(Register::from_index(reg), R0));
+                    }
+                    VarLocation::Stack(offset) => {
+                        self.emit_instruction(Instruction::stw(R0, R5, field(offset)?)?);
                     }
                 }
             } else {
                 self.emit_comment(&format!("{} {} (uninitialized)", type_to_string(&decl.ty), declarator.name));
                 // For register-allocated uninitialized vars, we could zero them
                 if let VarLocation::Register(reg) = location {
-                    self.emit_instruction(&format!("AND R{}, R{}, #0", reg, reg));
+                    self.emit_instruction(asm::clear(Register::from_index(reg)));
                 }
             }
         }
         Ok(())
     }
 
+    /// Make room on the stack for a scalar. LDW/STW double their offset,
+    /// so the slot is an even number of words below R5; its offset is in
+    /// slots.
+    fn allocate_slot(&mut self) -> Result<i16, CompileError> {
+        let depth = (self.frame_words + 2) & !1;
+        self.emit_add_immediate(6, 6, (self.frame_words - depth) as i32)?;
+        self.frame_words = depth;
+        Ok(-depth / 2)
+    }
+
+    /// The words an array or struct declared by `declarator` takes, or
+    /// `None` if it declares a scalar
+    fn object_words(
+        &self,
+        ty: &Type,
+        declarator: &Declarator,
+    ) -> Result<Option<usize>, CompileError> {
+        if declarator.array_len.is_none() && !matches!(ty, Type::Struct(_)) {
+            return Ok(None);
+        }
+        let words = self.layouts.size_of(ty)? * declarator.array_len.unwrap_or(1);
+        Ok(Some(words))
+    }
+
+    /// Allocate a local array or struct of `len` words on the stack and
+    /// store its initializer, a word at a time (the rest zeroed)
+    fn compile_array_declaration(
+        &mut self,
+        ty: &Type,
+        declarator: &Declarator,
+        len: usize,
+    ) -> Result<(), CompileError> {
+        let len = i16::try_from(len)
+            .ok()
+            .filter(|&len| len <= 0x4000)
+            .ok_or_else(|| CompileError::new(format!("array '{}' is too big", declarator.name)))?;
+        self.emit_comment(&format!(
+            "{} {} ({} words)",
+            type_to_string(ty),
+            declarator.name,
+            len
+        ));
+        self.emit_add_immediate(6, 6, -(len as i32))?;
+        self.frame_words += len;
+        let base = -self.frame_words;
+        self.locals.remove(&declarator.name);
+        self.arrays.insert(declarator.name.clone(), base);
+
+        let values: Vec<Expression> = match &declarator.initializer {
+            None => return Ok(()),
+            Some(Initializer::List(values)) => values.clone(),
+            Some(Initializer::String(s)) => {
+                s.chars().map(Expression::CharLiteral).collect()
+            }
+            Some(Initializer::Expression(_)) => {
+                return Err(CompileError::new(format!(
+                    "'{}' needs a {{...}} initializer",
+                    declarator.name
+                )));
+            }
+        };
+        if values.len() > len as usize {
+            return Err(CompileError::new(format!(
+                "too many initializers for '{}'",
+                declarator.name
+            )));
+        }
+        for i in 0..len {
+            match values.get(i as usize) {
+                Some(value) => self.compile_expression(value)?,
+                None => self.emit_instruction(asm::clear(R0)),
+            }
+            self.emit_add_immediate(7, 5, (base + i) as i32)?;
+            self.emit_instruction(Instruction::stw(R0, R7, 0)?);
+        }
+        Ok(())
+    }
+
     fn compile_global_declaration(&mut self, decl: &Declaration) -> Result<(), CompileError> {
         for declarator in &decl.declarators {
-            self.emit_label(&declarator.name);
-            if let Some(init) = &declarator.initializer {
-                match init {
-                    Initializer::Expression(Expression::IntLiteral(n)) => {
-                        self.emit(&format!("    .FILL #{}", n));
-                    }
-                    Initializer::String(s) => {
-                        self.emit(&format!("    .STRINGZ \"{}\"", escape_string(s)));
-                    }
-                    _ => {
-                        // Default to 0 for complex expressions
-                        self.emit("    .FILL #0");
-                    }
+            if let Some(words) = self.object_words(&decl.ty, declarator)? {
+                self.compile_global_array(declarator, words)?;
+                continue;
+            }
+            match &declarator.initializer {
+                Some(Initializer::String(s)) => {
+                    self.emit_data(&declarator.name, vec![Directive::String(s.clone())]);
+                }
+                Some(Initializer::Expression(expr)) => {
+                    // Default to 0 for complex expressions
+                    let value = constant_value(expr).unwrap_or(0);
+                    self.emit_data(&declarator.name, vec![Directive::Fill(value)]);
+                }
+                Some(Initializer::List(_)) => {
+                    return Err(CompileError::new(format!(
+                        "'{}' is not an array but has a {{...}} initializer",
+                        declarator.name
+                    )));
+                }
+                None => {
+                    self.emit_data(&declarator.name, vec![Directive::Fill(0)]);
                 }
-            } else {
-                self.emit("    .FILL #0");
             }
         }
         Ok(())
     }
 
+    fn compile_global_array(
+        &mut self,
+        declarator: &Declarator,
+        len: usize,
+    ) -> Result<(), CompileError> {
+        let values = match &declarator.initializer {
+            None => Vec::new(),
+            Some(Initializer::List(values)) => values
+                .iter()
+                .map(|value| {
+                    constant_value(value).ok_or_else(|| {
+                        CompileError::new(format!(
+                            "initializers of global '{}' must be constants",
+                            declarator.name
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(Initializer::String(s)) => s.chars().map(|c| c as i32).collect(),
+            Some(Initializer::Expression(_)) => {
+                return Err(CompileError::new(format!(
+                    "'{}' needs a {{...}} initializer",
+                    declarator.name
+                )));
+            }
+        };
+        if values.len() > len {
+            return Err(CompileError::new(format!(
+                "too many initializers for '{}'",
+                declarator.name
+            )));
+        }
+        let rest = len - values.len();
+        let mut directives: Vec<Directive> = values.into_iter().map(Directive::Fill).collect();
+        if rest > 0 {
+            directives.push(Directive::Block(rest));
+        }
+        self.emit_data(&declarator.name, directives);
+        Ok(())
+    }
+
     fn compile_statement(&mut self, stmt: &Statement) -> Result<(), CompileError> {
         match stmt {
             Statement::Compound(block) => {
@@ -619,12 +942,26 @@ impl Compiler {
             Statement::While { condition, body } => {
                 self.compile_while(condition, body)?;
             }
+            Statement::DoWhile { body, condition } => {
+                self.compile_do_while(body, condition)?;
+            }
             Statement::For { init, condition, update, body } => {
                 self.compile_for(init, condition, update, body)?;
             }
             Statement::Return(expr) => {
                 self.compile_return(expr.as_ref())?;
             }
+            Statement::Break => {
+                let label = self.loops.last().map(|l| l.break_label.clone());
+                let label = label.ok_or_else(|| CompileError::new("'break' outside a loop"))?;
+                self.emit_branch(Condition::ALWAYS, &label);
+            }
+            Statement::Continue => {
+                let label = self.loops.last().map(|l| l.continue_label.clone());
+                let label =
+                    label.ok_or_else(|| CompileError::new("'continue' outside a loop"))?;
+                self.emit_branch(Condition::ALWAYS, &label);
+            }
             Statement::Empty => {}
         }
         Ok(())
@@ -643,14 +980,14 @@ impl Compiler {
         self.compile_expression(condition)?;
         
         // Branch to else if R0 == 0
-        self.emit_instruction("ADD R0, R0, #0"); // Set condition codes
-        self.emit_instruction(&format!("BRz {}", if else_branch.is_some() { &else_label } else { &end_label }));
+        self.emit_instruction(asm::test(R0)); // Set condition codes
+        self.emit_branch(ZERO, if else_branch.is_some() { &else_label } else { &end_label });
 
         // Then branch
         self.compile_statement(then_branch)?;
 
         if let Some(else_stmt) = else_branch {
-            self.emit_instruction(&format!("BR {}", end_label)); // Skip else
+            self.emit_branch(Condition::ALWAYS, &end_label); // Skip else
             self.emit_label(&else_label);
             self.emit_comment("else");
             self.compile_statement(else_stmt)?;
@@ -660,6 +997,28 @@ impl Compiler {
         Ok(())
     }
 
+    /// `condition ? then_value : else_value`, evaluating only the value
+    /// chosen
+    fn compile_conditional(
+        &mut self,
+        condition: &Expression,
+        then_value: &Expression,
+        else_value: &Expression,
+    ) -> Result<(), CompileError> {
+        let else_label = self.new_label("condelse");
+        let end_label = self.new_label("condend");
+
+        self.compile_expression(condition)?;
+        self.emit_instruction(asm::test(R0));
+        self.emit_branch(ZERO, &else_label);
+        self.compile_expression(then_value)?;
+        self.emit_branch(Condition::ALWAYS, &end_label);
+        self.emit_label(&else_label);
+        self.compile_expression(else_value)?;
+        self.emit_label(&end_label);
+        Ok(())
+    }
+
     fn compile_while(&mut self, condition: &Expression, body: &Statement) -> Result<(), CompileError> {
         let loop_label = self.new_label("while");
         let end_label = self.new_label("endwhile");
@@ -668,17 +1027,39 @@ impl Compiler {
         self.emit_comment("while (...)");
         self.compile_expression(condition)?;
         
-        self.emit_instruction("ADD R0, R0, #0");
-        self.emit_instruction(&format!("BRz {}", end_label));
+        self.emit_instruction(asm::test(R0));
+        self.emit_branch(ZERO, &end_label);
 
-        self.compile_statement(body)?;
+        self.compile_loop_body(body, &end_label, &loop_label)?;
         
-        self.emit_instruction(&format!("BR {}", loop_label));
+        self.emit_branch(Condition::ALWAYS, &loop_label);
         self.emit_label(&end_label);
         
         Ok(())
     }
 
+    fn compile_do_while(
+        &mut self,
+        body: &Statement,
+        condition: &Expression,
+    ) -> Result<(), CompileError> {
+        let loop_label = self.new_label("do");
+        let condition_label = self.new_label("docond");
+        let end_label = self.new_label("enddo");
+
+        self.emit_label(&loop_label);
+        self.compile_loop_body(body, &end_label, &condition_label)?;
+
+        self.emit_label(&condition_label);
+        self.emit_comment("do ... while (...)");
+        self.compile_expression(condition)?;
+        self.emit_instruction(asm::test(R0));
+        self.emit_branch(NONZERO, &loop_label);
+        self.emit_label(&end_label);
+
+        Ok(())
+    }
+
     fn compile_for(
         &mut self,
         init: &Option<ForInit>,
@@ -688,6 +1069,7 @@ impl Compiler {
     ) -> Result<(), CompileError> {
         let loop_label = self.new_label("for");
         let end_label = self.new_label("endfor");
+        let update_label = self.new_label("forupdate");
 
         // Init
         if let Some(init) = init {
@@ -707,25 +1089,43 @@ impl Compiler {
         if let Some(cond) = condition {
             self.emit_comment("for condition");
             self.compile_expression(cond)?;
-            self.emit_instruction("ADD R0, R0, #0");
-            self.emit_instruction(&format!("BRz {}", end_label));
+            self.emit_instruction(asm::test(R0));
+            self.emit_branch(ZERO, &end_label);
         }
 
         // Body
-        self.compile_statement(body)?;
+        self.compile_loop_body(body, &end_label, &update_label)?;
 
         // Update
+        self.emit_label(&update_label);
         if let Some(upd) = update {
             self.emit_comment("for update");
             self.compile_expression(upd)?;
         }
 
-        self.emit_instruction(&format!("BR {}", loop_label));
+        self.emit_branch(Condition::ALWAYS, &loop_label);
         self.emit_label(&end_label);
 
         Ok(())
     }
 
+    /// Compile a loop body in which `break` goes to `break_label` and
+    /// `continue` to `continue_label`
+    fn compile_loop_body(
+        &mut self,
+        body: &Statement,
+        break_label: &str,
+        continue_label: &str,
+    ) -> Result<(), CompileError> {
+        self.loops.push(LoopLabels {
+            break_label: break_label.to_string(),
+            continue_label: continue_label.to_string(),
+        });
+        let result = self.compile_statement(body);
+        self.loops.pop();
+        result
+    }
+
     fn compile_return(&mut self, expr: Option<&Expression>) -> Result<(), CompileError> {
         self.emit_comment("return");
         
@@ -736,9 +1136,9 @@ impl Compiler {
 
         // Jump to function epilogue
         if self.current_function == "main" {
-            self.emit_instruction("BR main_exit");
+            self.emit_branch(Condition::ALWAYS, "main_exit");
         } else {
-            self.emit_instruction(&format!("BR {}_exit", self.current_function));
+            self.emit_branch(Condition::ALWAYS, &format!("{}_exit", self.current_function));
         }
 
         Ok(())
@@ -759,30 +1159,31 @@ impl Compiler {
                     label: label.clone(),
                     value: s.clone(),
                 });
-                self.emit_instruction(&format!("LEA R0, {}", label));
+                self.emit_lea(0, &label);
             }
             Expression::Identifier(name) => {
-                if let Some(&location) = self.locals.get(name) {
+                if let Some(&offset) = self.arrays.get(name) {
+                    // An array evaluates to the address of its first element
+                    self.emit_add_immediate(0, 5, offset as i32)?;
+                } else if let Some(&location) = self.locals.get(name) {
                     match location {
                         VarLocation::Register(reg) => {
-                            self.emit_instruction(&format!("ADD R0, R{}, #0", reg));
+                            self.emit_instruction(asm::copy(R0, Register::from_index(reg)));
                         }
                         VarLocation::Stack(offset) => {
-                            self.emit_instruction(&format!("LDW R0, R5, #{}", offset));
+                            self.emit_instruction(Instruction::ldr(R0, R5, field(offset)?)?);
                         }
                     }
                 } else if self.defined_globals.contains(name) {
                     // Global variable
-                    self.emit_instruction(&format!("LEA R0, {}", name));
+                    self.emit_lea(0, name);
                     // String-initialized globals point directly to the string data,
                     // so we don't need to dereference - LEA gives us the address directly
-                    if !self.string_globals.contains(name) {
-                        self.emit_instruction("LDW R0, R0, #0");
+                    if !self.string_globals.contains(name) && !self.global_arrays.contains(name) {
+                        self.emit_instruction(Instruction::ldr(R0, R0, 0)?);
                     }
                 } else {
-                    return Err(CompileError {
-                        message: format!("undefined variable '{}'", name),
-                    });
+                    return Err(CompileError::new(format!("undefined variable '{}'", name)));
                 }
             }
             Expression::Binary { op, left, right } => {
@@ -794,8 +1195,11 @@ impl Compiler {
             Expression::Assignment { op, target, value } => {
                 self.compile_assignment(*op, target, value)?;
             }
+            Expression::Conditional { condition, then_value, else_value } => {
+                self.compile_conditional(condition, then_value, else_value)?;
+            }
             Expression::Call { function, arguments } => {
-                self.compile_call(function, arguments)?;
+                self.compile_call(expr, function, arguments)?;
             }
             Expression::PostIncrement(name) => {
                 self.compile_post_inc_dec(name, true)?;
@@ -809,26 +1213,23 @@ impl Compiler {
             Expression::PreDecrement(name) => {
                 self.compile_pre_inc_dec(name, false)?;
             }
-            Expression::Subscript { array, index } => {
+            Expression::Subscript { .. } | Expression::Member { .. } => {
                 // array[index] = *(array + index)
-                self.compile_expression(array)?;
-                self.emit_instruction("ADD R1, R0, #0"); // R1 = array base
-                self.compile_expression(index)?;
-                // LC-3B uses word addressing, so multiply index by 2
-                self.emit_instruction("ADD R0, R0, R0"); // R0 = index * 2
-                self.emit_instruction("ADD R0, R1, R0"); // R0 = base + offset
-                self.emit_instruction("LDW R0, R0, #0"); // R0 = *R0
+                self.compile_address(expr)?;
+                if !self.is_object(expr) {
+                    self.emit_instruction(Instruction::ldr(R0, R0, 0)?);
+                }
             }
         }
         Ok(())
     }
 
     fn load_immediate(&mut self, value: i32) -> Result<(), CompileError> {
-        if value >= -16 && value <= 15 {
+        if (-16..=15).contains(&value) {
             // Can use AND to zero, then ADD immediate
-            self.emit_instruction("AND R0, R0, #0");
+            self.emit_instruction(asm::clear(R0));
             if value != 0 {
-                self.emit_instruction(&format!("ADD R0, R0, #{}", value));
+                self.emit_instruction(Instruction::add_imm(R0, R0, field(value)?)?);
             }
         } else {
             // Need to load from memory
@@ -837,8 +1238,8 @@ impl Compiler {
                 label: label.clone(),
                 value,
             });
-            self.emit_instruction(&format!("LEA R0, {}", label));
-            self.emit_instruction("LDW R0, R0, #0");
+            self.emit_lea(0, &label);
+            self.emit_instruction(Instruction::ldr(R0, R0, 0)?);
         }
         Ok(())
     }
@@ -849,171 +1250,164 @@ impl Compiler {
         left: &Expression,
         right: &Expression,
     ) -> Result<(), CompileError> {
-        // Evaluate left into R0, push it, evaluate right into R0, pop left into R1
+        if self.options.opt_level > 0 {
+            let constant = |e: &Expression| constant_value(e).map(|n| n as i16);
+            match (op, constant(left), constant(right)) {
+                (BinaryOp::Mul, _, Some(factor)) if shift_add_factor(factor).is_some() => {
+                    return self.compile_multiply_by(left, factor);
+                }
+                (BinaryOp::Mul, Some(factor), _) if shift_add_factor(factor).is_some() => {
+                    return self.compile_multiply_by(right, factor);
+                }
+                (BinaryOp::Div, _, Some(divisor))
+                    if divisor.unsigned_abs().is_power_of_two() =>
+                {
+                    return self.compile_divide_by(left, divisor);
+                }
+                _ => {}
+            }
+        }
+
+        // Multiplying by a power of two is a left shift
+        if op == BinaryOp::Mul {
+            let shift = |e: &Expression| match e {
+                Expression::IntLiteral(n) if *n > 0 && (*n as u16).is_power_of_two() => {
+                    Some((*n as u16).trailing_zeros())
+                }
+                _ => None,
+            };
+            let shifted = match (shift(left), shift(right)) {
+                (_, Some(amount)) => Some((left, amount)),
+                (Some(amount), _) => Some((right, amount)),
+                _ => None,
+            };
+            if let Some((value, amount)) = shifted {
+                self.compile_expression(value)?;
+                if amount > 0 {
+                    self.emit_instruction(Instruction::lshf(R0, R0, field(amount)?)?);
+                }
+                return Ok(());
+            }
+        }
+
+        if matches!(op, BinaryOp::LogicalAnd | BinaryOp::LogicalOr) {
+            return self.compile_logical_op(op, left, right);
+        }
+
+        // Evaluate left into R0, push it, evaluate right into R0, pop left into R0
+        // with right in R7
         self.compile_expression(left)?;
-        self.emit_instruction("ADD R6, R6, #-1"); // Push
-        self.emit_instruction("STW R0, R6, #0");
+        self.emit_instruction(Instruction::add_imm(R6, R6, -1)?); // Push
+        self.emit_instruction(Instruction::stw(R0, R6, 0)?);
         
         self.compile_expression(right)?;
-        self.emit_instruction("ADD R1, R0, #0"); // R1 = right
-        self.emit_instruction("LDW R0, R6, #0"); // R0 = left
-        self.emit_instruction("ADD R6, R6, #1"); // Pop
+        self.emit_instruction(asm::copy(R7, R0)); // R7 = right
+        self.emit_instruction(Instruction::ldr(R0, R6, 0)?); // R0 = left
+        self.emit_instruction(Instruction::add_imm(R6, R6, 1)?); // Pop
 
         match op {
             BinaryOp::Add => {
-                self.emit_instruction("ADD R0, R0, R1");
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
             }
             BinaryOp::Sub => {
-                // R0 = R0 - R1 = R0 + (~R1 + 1)
-                self.emit_instruction("NOT R1, R1");
-                self.emit_instruction("ADD R1, R1, #1");
-                self.emit_instruction("ADD R0, R0, R1");
+                // R0 = R0 - R7 = R0 + (~R7 + 1)
+                self.emit_instruction(Instruction::not(R7, R7));
+                self.emit_instruction(Instruction::add_imm(R7, R7, 1)?);
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
             }
             BinaryOp::BitAnd => {
-                self.emit_instruction("AND R0, R0, R1");
+                self.emit_instruction(Instruction::and_reg(R0, R0, R7));
             }
             BinaryOp::BitOr => {
-                // R0 | R1 = ~(~R0 & ~R1)
-                self.emit_instruction("NOT R0, R0");
-                self.emit_instruction("NOT R1, R1");
-                self.emit_instruction("AND R0, R0, R1");
-                self.emit_instruction("NOT R0, R0");
+                // R0 | R7 = ~(~R0 & ~R7)
+                self.emit_instruction(Instruction::not(R0, R0));
+                self.emit_instruction(Instruction::not(R7, R7));
+                self.emit_instruction(Instruction::and_reg(R0, R0, R7));
+                self.emit_instruction(Instruction::not(R0, R0));
             }
             BinaryOp::BitXor => {
-                // R0 ^ R1 = (R0 & ~R1) | (~R0 & R1)
-                self.emit_instruction("ADD R2, R0, #0"); // R2 = R0
-                self.emit_instruction("NOT R3, R1");     // R3 = ~R1
-                self.emit_instruction("AND R2, R2, R3"); // R2 = R0 & ~R1
-                self.emit_instruction("NOT R0, R0");     // R0 = ~R0
-                self.emit_instruction("AND R0, R0, R1"); // R0 = ~R0 & R1
-                // OR the results
-                self.emit_instruction("NOT R0, R0");
-                self.emit_instruction("NOT R2, R2");
-                self.emit_instruction("AND R0, R0, R2");
-                self.emit_instruction("NOT R0, R0");
+                self.emit_instruction(Instruction::xor_reg(R0, R0, R7));
             }
             BinaryOp::Equal | BinaryOp::NotEqual => {
-                // Compare: R0 - R1, check if zero
-                self.emit_instruction("NOT R1, R1");
-                self.emit_instruction("ADD R1, R1, #1");
-                self.emit_instruction("ADD R0, R0, R1");
+                // Compare: R0 - R7, check if zero
+                self.emit_instruction(Instruction::not(R7, R7));
+                self.emit_instruction(Instruction::add_imm(R7, R7, 1)?);
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
                 
                 let true_label = self.new_label("true");
                 let end_label = self.new_label("cmp_end");
                 
                 if op == BinaryOp::Equal {
-                    self.emit_instruction(&format!("BRz {}", true_label));
+                    self.emit_branch(ZERO, &true_label);
                 } else {
-                    self.emit_instruction(&format!("BRnp {}", true_label));
+                    self.emit_branch(NONZERO, &true_label);
                 }
                 
-                self.emit_instruction("AND R0, R0, #0"); // false = 0
-                self.emit_instruction(&format!("BR {}", end_label));
+                self.emit_instruction(asm::clear(R0)); // false = 0
+                self.emit_branch(Condition::ALWAYS, &end_label);
                 self.emit_label(&true_label);
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction("ADD R0, R0, #1"); // true = 1
+                self.emit_instruction(asm::clear(R0));
+                self.emit_instruction(Instruction::add_imm(R0, R0, 1)?); // true = 1
                 self.emit_label(&end_label);
             }
             BinaryOp::Less | BinaryOp::GreaterEqual => {
-                // R0 < R1: check if R0 - R1 < 0
-                self.emit_instruction("NOT R1, R1");
-                self.emit_instruction("ADD R1, R1, #1");
-                self.emit_instruction("ADD R0, R0, R1");
+                // R0 < R7: check if R0 - R7 < 0
+                self.emit_instruction(Instruction::not(R7, R7));
+                self.emit_instruction(Instruction::add_imm(R7, R7, 1)?);
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
                 
                 let true_label = self.new_label("true");
                 let end_label = self.new_label("cmp_end");
                 
                 if op == BinaryOp::Less {
-                    self.emit_instruction(&format!("BRn {}", true_label));
+                    self.emit_branch(NEGATIVE, &true_label);
                 } else {
-                    self.emit_instruction(&format!("BRzp {}", true_label));
+                    self.emit_branch(NOT_NEGATIVE, &true_label);
                 }
                 
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction(&format!("BR {}", end_label));
+                self.emit_instruction(asm::clear(R0));
+                self.emit_branch(Condition::ALWAYS, &end_label);
                 self.emit_label(&true_label);
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction("ADD R0, R0, #1");
+                self.emit_instruction(asm::clear(R0));
+                self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
                 self.emit_label(&end_label);
             }
             BinaryOp::Greater | BinaryOp::LessEqual => {
-                // R0 > R1: check if R0 - R1 > 0
-                self.emit_instruction("NOT R1, R1");
-                self.emit_instruction("ADD R1, R1, #1");
-                self.emit_instruction("ADD R0, R0, R1");
+                // R0 > R7: check if R0 - R7 > 0
+                self.emit_instruction(Instruction::not(R7, R7));
+                self.emit_instruction(Instruction::add_imm(R7, R7, 1)?);
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
                 
                 let true_label = self.new_label("true");
                 let end_label = self.new_label("cmp_end");
                 
                 if op == BinaryOp::Greater {
-                    self.emit_instruction(&format!("BRp {}", true_label));
+                    self.emit_branch(POSITIVE, &true_label);
                 } else {
-                    self.emit_instruction(&format!("BRnz {}", true_label));
+                    self.emit_branch(NOT_POSITIVE, &true_label);
                 }
                 
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction(&format!("BR {}", end_label));
-                self.emit_label(&true_label);
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction("ADD R0, R0, #1");
-                self.emit_label(&end_label);
-            }
-            BinaryOp::LogicalAnd => {
-                let false_label = self.new_label("and_false");
-                let end_label = self.new_label("and_end");
-                
-                // Left is already evaluated, check if false
-                self.emit_instruction("ADD R0, R0, #0");
-                self.emit_instruction(&format!("BRz {}", false_label));
-                
-                // Evaluate right
-                self.compile_expression(right)?;
-                self.emit_instruction("ADD R0, R0, #0");
-                self.emit_instruction(&format!("BRz {}", false_label));
-                
-                // Both true
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction("ADD R0, R0, #1");
-                self.emit_instruction(&format!("BR {}", end_label));
-                
-                self.emit_label(&false_label);
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_label(&end_label);
-            }
-            BinaryOp::LogicalOr => {
-                let true_label = self.new_label("or_true");
-                let end_label = self.new_label("or_end");
-                
-                self.emit_instruction("ADD R0, R0, #0");
-                self.emit_instruction(&format!("BRnp {}", true_label));
-                
-                // Evaluate right
-                self.compile_expression(right)?;
-                self.emit_instruction("ADD R0, R0, #0");
-                self.emit_instruction(&format!("BRnp {}", true_label));
-                
-                // Both false
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction(&format!("BR {}", end_label));
-                
+                self.emit_instruction(asm::clear(R0));
+                self.emit_branch(Condition::ALWAYS, &end_label);
                 self.emit_label(&true_label);
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction("ADD R0, R0, #1");
+                self.emit_instruction(asm::clear(R0));
+                self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
                 self.emit_label(&end_label);
             }
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr => unreachable!(),
             BinaryOp::ShiftLeft => {
-                // Shift left by adding to itself R1 times
+                // Shift left by adding to itself R7 times
                 // This is a loop-based implementation
                 let loop_label = self.new_label("shl_loop");
                 let end_label = self.new_label("shl_end");
                 
-                // R0 = value, R1 = count
+                // R0 = value, R7 = count
                 self.emit_label(&loop_label);
-                self.emit_instruction("ADD R1, R1, #0");
-                self.emit_instruction(&format!("BRz {}", end_label));
-                self.emit_instruction("ADD R0, R0, R0"); // R0 *= 2
-                self.emit_instruction("ADD R1, R1, #-1");
-                self.emit_instruction(&format!("BR {}", loop_label));
+                self.emit_instruction(asm::test(R7));
+                self.emit_branch(ZERO, &end_label);
+                self.emit_instruction(Instruction::add_reg(R0, R0, R0)); // R0 *= 2
+                self.emit_instruction(Instruction::add_imm(R7, R7, -1)?);
+                self.emit_branch(Condition::ALWAYS, &loop_label);
                 self.emit_label(&end_label);
             }
             BinaryOp::ShiftRight => {
@@ -1028,69 +1422,182 @@ impl Compiler {
                 // Actually LC-3B RSHFL shifts by amount in imm4
                 // For variable shift, we need a loop
                 self.emit_label(&loop_label);
-                self.emit_instruction("ADD R1, R1, #0");
-                self.emit_instruction(&format!("BRz {}", end_label));
-                self.emit_instruction("RSHFL R0, R0, #1");
-                self.emit_instruction("ADD R1, R1, #-1");
-                self.emit_instruction(&format!("BR {}", loop_label));
+                self.emit_instruction(asm::test(R7));
+                self.emit_branch(ZERO, &end_label);
+                self.emit_instruction(Instruction::rshfl(R0, R0, 1)?);
+                self.emit_instruction(Instruction::add_imm(R7, R7, -1)?);
+                self.emit_branch(Condition::ALWAYS, &loop_label);
                 self.emit_label(&end_label);
             }
-            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
-                self.emit_comment(&format!("TODO: {:?} requires subroutine", op));
-                // Would need multiplication/division subroutines
+            BinaryOp::Mul => {
+                self.call_routine(Routine::Multiply)?;
+            }
+            BinaryOp::Div => {
+                self.call_routine(Routine::DivMod)?;
+            }
+            BinaryOp::Mod => {
+                self.call_routine(Routine::DivMod)?;
+                self.emit_instruction(asm::copy(R0, R7));
             }
         }
         Ok(())
     }
 
-    fn compile_unary_op(&mut self, op: UnaryOp, operand: &Expression) -> Result<(), CompileError> {
-        self.compile_expression(operand)?;
-        
-        match op {
-            UnaryOp::Negate => {
-                self.emit_instruction("NOT R0, R0");
-                self.emit_instruction("ADD R0, R0, #1");
-            }
-            UnaryOp::BitNot => {
-                self.emit_instruction("NOT R0, R0");
-            }
-            UnaryOp::LogicalNot => {
-                let true_label = self.new_label("not_true");
-                let end_label = self.new_label("not_end");
-                
-                self.emit_instruction("ADD R0, R0, #0");
-                self.emit_instruction(&format!("BRz {}", true_label));
-                self.emit_instruction("AND R0, R0, #0"); // was non-zero, return 0
-                self.emit_instruction(&format!("BR {}", end_label));
-                self.emit_label(&true_label);
-                self.emit_instruction("AND R0, R0, #0");
-                self.emit_instruction("ADD R0, R0, #1"); // was zero, return 1
-                self.emit_label(&end_label);
-            }
-            UnaryOp::Deref => {
-                self.emit_instruction("LDW R0, R0, #0");
-            }
-            UnaryOp::AddressOf => {
-                // For now, only works with identifiers (handled elsewhere)
-                self.emit_comment("Address-of (requires identifier operand)");
+    /// R0 = `value` * `factor` without the runtime multiply: R7 keeps the
+    /// value while R0 is shifted left and has it added back for each set
+    /// bit of the factor, from the top one down
+    fn compile_multiply_by(&mut self, value: &Expression, factor: i16) -> Result<(), CompileError> {
+        let (bits, negate) = shift_add_factor(factor).expect("factor checked by the caller");
+        self.compile_expression(value)?;
+        if bits == 0 {
+            // The value is still evaluated, for its side effects
+            self.emit_instruction(asm::clear(R0));
+            return Ok(());
+        }
+        if bits.count_ones() > 1 {
+            self.emit_instruction(asm::copy(R7, R0));
+        }
+        let mut shift = 0;
+        for bit in (0..15 - bits.leading_zeros()).rev() {
+            shift += 1;
+            if bits & (1 << bit) != 0 {
+                self.emit_instruction(Instruction::lshf(R0, R0, field(shift)?)?);
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
+                shift = 0;
             }
         }
+        if shift > 0 {
+            self.emit_instruction(Instruction::lshf(R0, R0, field(shift)?)?);
+        }
+        if negate {
+            self.emit_instruction(Instruction::not(R0, R0));
+            self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
+        }
         Ok(())
     }
 
-    fn compile_assignment(
+    /// R0 = `dividend` / `divisor`, a power of two or its negation,
+    /// without the runtime divide. RSHFA rounds down, so for the quotient
+    /// to round toward zero a negative dividend first has the divisor's
+    /// magnitude less one added, which is its sign bits shifted right.
+    fn compile_divide_by(
         &mut self,
-        op: AssignOp,
-        target: &str,
-        value: &Expression,
+        dividend: &Expression,
+        divisor: i16,
     ) -> Result<(), CompileError> {
-        let target_location = self.locals.get(target).copied();
+        let shift = divisor.unsigned_abs().trailing_zeros();
+        self.compile_expression(dividend)?;
+        if shift > 0 {
+            self.emit_instruction(Instruction::rshfa(R7, R0, 15)?);
+            self.emit_instruction(Instruction::rshfl(R7, R7, field(16 - shift)?)?);
+            self.emit_instruction(Instruction::add_reg(R0, R0, R7));
+            self.emit_instruction(Instruction::rshfa(R0, R0, field(shift)?)?);
+        }
+        if divisor < 0 {
+            self.emit_instruction(Instruction::not(R0, R0));
+            self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
+        }
+        Ok(())
+    }
+
+    /// `&&` and `||`: R0 = 1 or 0. The right operand is evaluated only if
+    /// the left one doesn't settle the result.
+    fn compile_logical_op(
+        &mut self,
+        op: BinaryOp,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<(), CompileError> {
+        // `&&` is settled by a false (zero) operand, `||` by a true one
+        let (prefix, settles) = match op {
+            BinaryOp::LogicalAnd => ("and", ZERO),
+            _ => ("or", NONZERO),
+        };
+        let settled_label = self.new_label(&format!("{}_settled", prefix));
+        let end_label = self.new_label(&format!("{}_end", prefix));
+
+        self.compile_expression(left)?;
+        self.emit_instruction(asm::test(R0));
+        self.emit_branch(settles, &settled_label);
+
+        // The result is the right operand's truth value
+        self.compile_expression(right)?;
+        self.emit_instruction(asm::test(R0));
+        self.emit_branch(ZERO, &end_label);
+        self.emit_instruction(asm::clear(R0));
+        self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
+        self.emit_branch(Condition::ALWAYS, &end_label);
+
+        // The left operand's truth value: 0 for `&&`, 1 for `||`
+        self.emit_label(&settled_label);
+        self.emit_instruction(asm::clear(R0));
+        if op == BinaryOp::LogicalOr {
+            self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
+        }
+        self.emit_label(&end_label);
+        Ok(())
+    }
+
+    fn compile_unary_op(&mut self, op: UnaryOp, operand: &Expression) -> Result<(), CompileError> {
+        if op == UnaryOp::AddressOf {
+            return self.compile_address(operand);
+        }
+        self.compile_expression(operand)?;
+        
+        match op {
+            UnaryOp::Negate => {
+                self.emit_instruction(Instruction::not(R0, R0));
+                self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
+            }
+            UnaryOp::BitNot => {
+                self.emit_instruction(Instruction::not(R0, R0));
+            }
+            UnaryOp::LogicalNot => {
+                let true_label = self.new_label("not_true");
+                let end_label = self.new_label("not_end");
+                
+                self.emit_instruction(asm::test(R0));
+                self.emit_branch(ZERO, &true_label);
+                self.emit_instruction(asm::clear(R0)); // was non-zero, return 0
+                self.emit_branch(Condition::ALWAYS, &end_label);
+                self.emit_label(&true_label);
+                self.emit_instruction(asm::clear(R0));
+                self.emit_instruction(Instruction::add_imm(R0, R0, 1)?); // was zero, return 1
+                self.emit_label(&end_label);
+            }
+            UnaryOp::Deref => {
+                // A struct is used by its address
+                let pointee = match self.type_of(operand) {
+                    Type::Pointer(ty) => *ty,
+                    _ => Type::Int,
+                };
+                if !matches!(pointee, Type::Struct(_)) {
+                    self.emit_instruction(Instruction::ldr(R0, R0, 0)?);
+                }
+            }
+            UnaryOp::AddressOf => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn compile_assignment(
+        &mut self,
+        op: AssignOp,
+        target: &Expression,
+        value: &Expression,
+    ) -> Result<(), CompileError> {
+        if self.is_object(target) {
+            return Err(CompileError::new("cannot assign to an array or struct as a whole"));
+        }
+        let target = match target {
+            Expression::Identifier(name) => name.as_str(),
+            _ => return self.compile_indirect_assignment(op, target, value),
+        };
+        let target_location = self.locals.get(target).copied();
         
         // Validate that the target variable exists
         if target_location.is_none() && !self.defined_globals.contains(target) {
-            return Err(CompileError {
-                message: format!("undefined variable '{}'", target),
-            });
+            return Err(CompileError::new(format!("undefined variable '{}'", target)));
         }
         
         match op {
@@ -1102,104 +1609,264 @@ impl Compiler {
                 // Load current value
                 match target_location {
                     Some(VarLocation::Register(reg)) => {
-                        self.emit_instruction(&format!("ADD R0, R{}, #0", reg));
+                        self.emit_instruction(asm::copy(R0, Register::from_index(reg)));
                     }
                     Some(VarLocation::Stack(offset)) => {
-                        self.emit_instruction(&format!("LDW R0, R5, #{}", offset));
+                        self.emit_instruction(Instruction::ldr(R0, R5, field(offset)?)?);
                     }
                     None => {
-                        self.emit_instruction(&format!("LEA R0, {}", target));
-                        self.emit_instruction("LDW R0, R0, #0");
+                        self.emit_lea(0, target);
+                        self.emit_instruction(Instruction::ldr(R0, R0, 0)?);
                     }
                 }
                 
                 // Push current value
-                self.emit_instruction("ADD R6, R6, #-1");
-                self.emit_instruction("STW R0, R6, #0");
+                self.emit_instruction(Instruction::add_imm(R6, R6, -1)?);
+                self.emit_instruction(Instruction::stw(R0, R6, 0)?);
                 
                 // Evaluate RHS
                 self.compile_expression(value)?;
-                self.emit_instruction("ADD R1, R0, #0"); // R1 = new value
+                self.emit_instruction(asm::copy(R7, R0)); // R7 = new value
                 
                 // Pop original value
-                self.emit_instruction("LDW R0, R6, #0");
-                self.emit_instruction("ADD R6, R6, #1");
+                self.emit_instruction(Instruction::ldr(R0, R6, 0)?);
+                self.emit_instruction(Instruction::add_imm(R6, R6, 1)?);
                 
-                // Apply operation
-                match op {
-                    AssignOp::AddAssign => {
-                        self.emit_instruction("ADD R0, R0, R1");
-                    }
-                    AssignOp::SubAssign => {
-                        self.emit_instruction("NOT R1, R1");
-                        self.emit_instruction("ADD R1, R1, #1");
-                        self.emit_instruction("ADD R0, R0, R1");
-                    }
-                    AssignOp::AndAssign => {
-                        self.emit_instruction("AND R0, R0, R1");
-                    }
-                    AssignOp::OrAssign => {
-                        self.emit_instruction("NOT R0, R0");
-                        self.emit_instruction("NOT R1, R1");
-                        self.emit_instruction("AND R0, R0, R1");
-                        self.emit_instruction("NOT R0, R0");
-                    }
-                    AssignOp::XorAssign => {
-                        self.emit_instruction("ADD R2, R0, #0");
-                        self.emit_instruction("NOT R3, R1");
-                        self.emit_instruction("AND R2, R2, R3");
-                        self.emit_instruction("NOT R0, R0");
-                        self.emit_instruction("AND R0, R0, R1");
-                        self.emit_instruction("NOT R0, R0");
-                        self.emit_instruction("NOT R2, R2");
-                        self.emit_instruction("AND R0, R0, R2");
-                        self.emit_instruction("NOT R0, R0");
-                    }
-                    _ => {}
-                }
+                self.apply_assign_op(op)?;
             }
         }
 
         // Store result
         match target_location {
             Some(VarLocation::Register(reg)) => {
-                self.emit_instruction(&format!("ADD R{}, R0, #0", reg));
+                self.emit_instruction(asm::copy(Register::from_index(reg), R0));
             }
             Some(VarLocation::Stack(offset)) => {
-                self.emit_instruction(&format!("STW R0, R5, #{}", offset));
+                self.emit_instruction(Instruction::stw(R0, R5, field(offset)?)?);
             }
             None => {
                 // Global variable - need to use a temp register for address
-                self.emit_instruction("ADD R1, R0, #0"); // Save value
-                self.emit_instruction(&format!("LEA R0, {}", target));
-                self.emit_instruction("STW R1, R0, #0");
-                self.emit_instruction("ADD R0, R1, #0"); // Restore R0
+                self.emit_lea(7, target);
+                self.emit_instruction(Instruction::stw(R0, R7, 0)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Assign through an address: `a[i] = x`, `*p = x` and the like
+    fn compile_indirect_assignment(
+        &mut self,
+        op: AssignOp,
+        target: &Expression,
+        value: &Expression,
+    ) -> Result<(), CompileError> {
+        self.compile_address(target)?;
+        self.emit_instruction(Instruction::add_imm(R6, R6, -1)?);
+        self.emit_instruction(Instruction::stw(R0, R6, 0)?);
+        if op == AssignOp::Assign {
+            self.compile_expression(value)?;
+        } else {
+            // Push the current value, evaluate RHS, then combine
+            self.emit_instruction(Instruction::ldr(R0, R0, 0)?);
+            self.emit_instruction(Instruction::add_imm(R6, R6, -1)?);
+            self.emit_instruction(Instruction::stw(R0, R6, 0)?);
+            self.compile_expression(value)?;
+            self.emit_instruction(asm::copy(R7, R0));
+            self.emit_instruction(Instruction::ldr(R0, R6, 0)?);
+            self.emit_instruction(Instruction::add_imm(R6, R6, 1)?);
+            self.apply_assign_op(op)?;
+        }
+        self.emit_instruction(Instruction::ldr(R7, R6, 0)?);
+        self.emit_instruction(Instruction::add_imm(R6, R6, 1)?);
+        self.emit_instruction(Instruction::stw(R0, R7, 0)?);
+        Ok(())
+    }
+
+    /// R0 = the address of a variable, `array[index]` or `*pointer`
+    fn compile_address(&mut self, target: &Expression) -> Result<(), CompileError> {
+        match target {
+            Expression::Identifier(name) => {
+                if let Some(&offset) = self.arrays.get(name) {
+                    self.emit_add_immediate(0, 5, offset as i32)?;
+                } else if let Some(&location) = self.locals.get(name) {
+                    match location {
+                        VarLocation::Stack(offset) => {
+                            // LDW/STW offsets count pairs of words
+                            self.emit_add_immediate(0, 5, 2 * offset as i32)?;
+                        }
+                        VarLocation::Register(_) => {
+                            return Err(CompileError::new(format!(
+                                "cannot take the address of register variable '{}'",
+                                name
+                            )));
+                        }
+                    }
+                } else if self.defined_globals.contains(name) {
+                    self.emit_lea(0, name);
+                } else {
+                    return Err(CompileError::new(format!("undefined variable '{}'", name)));
+                }
+                Ok(())
             }
+            Expression::Unary { op: UnaryOp::Deref, operand } => self.compile_expression(operand),
+            Expression::Subscript { array, index } => {
+                let stride = match self.type_of(array) {
+                    Type::Pointer(element) => self.layouts.size_of(&element)?,
+                    _ => 1,
+                };
+                self.compile_expression(array)?;
+                self.emit_instruction(Instruction::add_imm(R6, R6, -1)?);
+                self.emit_instruction(Instruction::stw(R0, R6, 0)?);
+                self.compile_expression(index)?;
+                self.scale(stride)?;
+                self.emit_instruction(Instruction::ldr(R7, R6, 0)?);
+                self.emit_instruction(Instruction::add_imm(R6, R6, 1)?);
+                self.emit_instruction(Instruction::add_reg(R0, R7, R0));
+                Ok(())
+            }
+            Expression::Member { object, field } => {
+                let offset = self.layouts.field(&self.type_of(object), field)?.offset;
+                self.compile_address(object)?;
+                self.emit_add_immediate(0, 0, offset as i32)?;
+                Ok(())
+            }
+            _ => Err(CompileError::new("expression is not an lvalue")),
         }
+    }
 
+    /// R0 = R0 * `size`, for indexing elements of `size` words
+    fn scale(&mut self, size: usize) -> Result<(), CompileError> {
+        if size == 1 {
+            return Ok(());
+        }
+        if size.is_power_of_two() {
+            let shift = size.trailing_zeros();
+            self.emit_instruction(Instruction::lshf(R0, R0, field(shift)?)?);
+        } else {
+            self.emit_instruction(asm::copy(R7, R0));
+            self.load_immediate(size as i32)?;
+            self.call_routine(Routine::Multiply)?;
+        }
         Ok(())
     }
 
-    fn compile_call(&mut self, function: &str, arguments: &[Expression]) -> Result<(), CompileError> {
+    /// The type of `expr`, as far as the compiler tracks types: arrays are
+    /// pointers to their first element, and anything untyped is an int
+    fn type_of(&self, expr: &Expression) -> Type {
+        let pointee = |ty: Type| match ty {
+            Type::Pointer(ty) => *ty,
+            _ => Type::Int,
+        };
+        match expr {
+            Expression::Identifier(name) => self
+                .local_types
+                .get(name)
+                .or_else(|| self.global_types.get(name))
+                .cloned()
+                .unwrap_or(Type::Int),
+            Expression::StringLiteral(_) => Type::Pointer(Box::new(Type::Char)),
+            Expression::Unary { op: UnaryOp::Deref, operand } => pointee(self.type_of(operand)),
+            Expression::Unary { op: UnaryOp::AddressOf, operand } => {
+                Type::Pointer(Box::new(self.type_of(operand)))
+            }
+            Expression::Subscript { array, .. } => pointee(self.type_of(array)),
+            Expression::Member { object, field } => {
+                match self.layouts.field(&self.type_of(object), field) {
+                    Ok(field) if field.array_len.is_some() => {
+                        Type::Pointer(Box::new(field.ty.clone()))
+                    }
+                    Ok(field) => field.ty.clone(),
+                    Err(_) => Type::Int,
+                }
+            }
+            Expression::Assignment { target, .. } => self.type_of(target),
+            Expression::Conditional { then_value, .. } => self.type_of(then_value),
+            Expression::Call { function, .. } => {
+                self.function_types.get(function).cloned().unwrap_or(Type::Int)
+            }
+            _ => Type::Int,
+        }
+    }
+
+    /// Whether `expr` is an array or struct, which is used by its address
+    /// rather than loaded
+    fn is_object(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Identifier(name) => {
+                if self.locals.contains_key(name) {
+                    false
+                } else {
+                    self.arrays.contains_key(name) || self.global_arrays.contains(name)
+                }
+            }
+            Expression::Member { object, field } => {
+                match self.layouts.field(&self.type_of(object), field) {
+                    Ok(field) => field.array_len.is_some() || matches!(field.ty, Type::Struct(_)),
+                    Err(_) => false,
+                }
+            }
+            Expression::Subscript { .. } | Expression::Unary { op: UnaryOp::Deref, .. } => {
+                matches!(self.type_of(expr), Type::Struct(_))
+            }
+            _ => false,
+        }
+    }
+
+    /// R0 = R0 <op> R7 for a compound assignment
+    fn apply_assign_op(&mut self, op: AssignOp) -> Result<(), CompileError> {
+        match op {
+            AssignOp::AddAssign => {
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
+            }
+            AssignOp::SubAssign => {
+                self.emit_instruction(Instruction::not(R7, R7));
+                self.emit_instruction(Instruction::add_imm(R7, R7, 1)?);
+                self.emit_instruction(Instruction::add_reg(R0, R0, R7));
+            }
+            AssignOp::AndAssign => {
+                self.emit_instruction(Instruction::and_reg(R0, R0, R7));
+            }
+            AssignOp::OrAssign => {
+                self.emit_instruction(Instruction::not(R0, R0));
+                self.emit_instruction(Instruction::not(R7, R7));
+                self.emit_instruction(Instruction::and_reg(R0, R0, R7));
+                self.emit_instruction(Instruction::not(R0, R0));
+            }
+            AssignOp::XorAssign => {
+                self.emit_instruction(Instruction::xor_reg(R0, R0, R7));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn compile_call(
+        &mut self,
+        call: &Expression,
+        function: &str,
+        arguments: &[Expression],
+    ) -> Result<(), CompileError> {
         // Check for trap() intrinsic - trap(vector) emits TRAP instruction
         if function == "trap" {
             if arguments.len() != 1 {
-                return Err(CompileError { message: "trap() takes exactly 1 argument".to_string() });
+                return Err(CompileError::new("trap() takes exactly 1 argument"));
             }
             // Argument should be a literal trap vector
             if let Expression::IntLiteral(vector) = &arguments[0] {
-                self.emit_instruction(&format!("TRAP x{:02X}", vector));
+                self.emit_instruction(Instruction::trap(field(*vector)?));
             } else {
-                return Err(CompileError { message: "trap() argument must be a constant".to_string() });
+                return Err(CompileError::new("trap() argument must be a constant"));
             }
             return Ok(());
         }
 
         // Validate that the function is defined
         if !self.defined_functions.contains(function) {
-            return Err(CompileError { 
-                message: format!("undefined function '{}' (did you forget to #include a header?)", function) 
-            });
+            return Err(CompileError::new(format!(
+                "undefined function '{}' (did you forget to #include a header?)",
+                function
+            )));
         }
 
         // Check if this function can be inlined (simple trap wrapper)
@@ -1213,26 +1880,62 @@ impl Compiler {
             }
             
             // Emit the trap directly
-            self.emit_instruction(&format!("TRAP x{:02X}", inline_info.trap_vector));
+            self.emit_instruction(Instruction::trap(inline_info.trap_vector));
             return Ok(());
         }
 
         // Regular function call
         self.emit_comment(&format!("Call {}()", function));
-        
-        // Push arguments right-to-left
-        for arg in arguments.iter().rev() {
+
+        // The callee may use any register but R5 and R6, so save the
+        // locals still needed after the call, a slot each
+        let saved = self.allocation.saved_across(call).to_vec();
+        if !saved.is_empty() {
+            self.emit_add_immediate(6, 6, -2 * saved.len() as i32)?;
+            for (slot, &reg) in saved.iter().enumerate() {
+                let reg = Register::from_index(reg);
+                self.emit_instruction(Instruction::stw(reg, R6, field(slot)?)?);
+            }
+        }
+
+        let in_registers = arguments.len().min(self.options.register_args);
+        let (in_registers, on_stack) = arguments.split_at(in_registers);
+
+        // Push arguments right-to-left, each in a slot of its own so the
+        // callee reaches it with an LDW offset
+        for arg in on_stack.iter().rev() {
             self.compile_expression(arg)?;
-            self.emit_instruction("ADD R6, R6, #-1");
-            self.emit_instruction("STW R0, R6, #0");
+            self.emit_instruction(Instruction::add_imm(R6, R6, -2)?);
+            self.emit_instruction(Instruction::stw(R0, R6, 0)?);
+        }
+
+        // Evaluating one register argument may clobber the others, so all
+        // but the first wait on the stack until it is in R0
+        for arg in in_registers.iter().skip(1).rev() {
+            self.compile_expression(arg)?;
+            self.emit_instruction(Instruction::add_imm(R6, R6, -1)?);
+            self.emit_instruction(Instruction::stw(R0, R6, 0)?);
+        }
+        if let Some(first) = in_registers.first() {
+            self.compile_expression(first)?;
+        }
+        for reg in 1..in_registers.len() as u8 {
+            self.emit_instruction(Instruction::ldr(Register::from_index(reg), R6, 0)?);
+            self.emit_instruction(Instruction::add_imm(R6, R6, 1)?);
         }
 
         // Call function
-        self.emit_instruction(&format!("JSR {}", function));
+        self.emit_jsr(function);
 
         // Pop arguments
-        if !arguments.is_empty() {
-            self.emit_instruction(&format!("ADD R6, R6, #{}", arguments.len()));
+        self.emit_add_immediate(6, 6, 2 * on_stack.len() as i32)?;
+
+        if !saved.is_empty() {
+            for (slot, &reg) in saved.iter().enumerate() {
+                let reg = Register::from_index(reg);
+                self.emit_instruction(Instruction::ldr(reg, R6, field(slot)?)?);
+            }
+            self.emit_add_immediate(6, 6, 2 * saved.len() as i32)?;
         }
 
         // Return value is in R0
@@ -1244,22 +1947,20 @@ impl Compiler {
         
         // Validate that the variable exists
         if location.is_none() && !self.defined_globals.contains(name) {
-            return Err(CompileError {
-                message: format!("undefined variable '{}'", name),
-            });
+            return Err(CompileError::new(format!("undefined variable '{}'", name)));
         }
         
         // Load current value into R0 (this is the return value)
         match location {
             Some(VarLocation::Register(reg)) => {
-                self.emit_instruction(&format!("ADD R0, R{}, #0", reg));
+                self.emit_instruction(asm::copy(R0, Register::from_index(reg)));
             }
             Some(VarLocation::Stack(offset)) => {
-                self.emit_instruction(&format!("LDW R0, R5, #{}", offset));
+                self.emit_instruction(Instruction::ldr(R0, R5, field(offset)?)?);
             }
             None => {
-                self.emit_instruction(&format!("LEA R1, {}", name));
-                self.emit_instruction("LDW R0, R1, #0");
+                self.emit_lea(7, name);
+                self.emit_instruction(Instruction::ldr(R0, R7, 0)?);
             }
         }
 
@@ -1267,37 +1968,24 @@ impl Compiler {
         match location {
             Some(VarLocation::Register(reg)) => {
                 // Increment/decrement the register directly
-                if increment {
-                    self.emit_instruction(&format!("ADD R{}, R{}, #1", reg, reg));
-                } else {
-                    self.emit_instruction(&format!("ADD R{}, R{}, #-1", reg, reg));
-                }
+                let reg = Register::from_index(reg);
+                let step = if increment { 1 } else { -1 };
+                self.emit_instruction(Instruction::add_imm(reg, reg, step)?);
                 // R0 still has original value
             }
             Some(VarLocation::Stack(offset)) => {
-                // Save original value
-                self.emit_instruction("ADD R1, R0, #0");
-                // Increment/decrement
-                if increment {
-                    self.emit_instruction("ADD R1, R1, #1");
-                } else {
-                    self.emit_instruction("ADD R1, R1, #-1");
-                }
+                // Increment/decrement a copy, keeping the original value
+                let step = if increment { 1 } else { -1 };
+                self.emit_instruction(Instruction::add_imm(R7, R0, field(step)?)?);
                 // Store new value
-                self.emit_instruction(&format!("STW R1, R5, #{}", offset));
+                self.emit_instruction(Instruction::stw(R7, R5, field(offset)?)?);
             }
             None => {
-                // Global variable
-                self.emit_instruction("ADD R1, R0, #0");
-                if increment {
-                    self.emit_instruction("ADD R1, R1, #1");
-                } else {
-                    self.emit_instruction("ADD R1, R1, #-1");
-                }
-                self.emit_instruction("ADD R2, R0, #0"); // Save return value
-                self.emit_instruction(&format!("LEA R0, {}", name));
-                self.emit_instruction("STW R1, R0, #0");
-                self.emit_instruction("ADD R0, R2, #0"); // Restore return value
+                // Global variable, its address still in R7
+                let step = if increment { 1 } else { -1 };
+                self.emit_instruction(Instruction::add_imm(R0, R0, field(step)?)?);
+                self.emit_instruction(Instruction::stw(R0, R7, 0)?);
+                self.emit_instruction(Instruction::add_imm(R0, R0, field(-step)?)?);
             }
         }
 
@@ -1310,45 +1998,40 @@ impl Compiler {
         
         // Validate that the variable exists
         if location.is_none() && !self.defined_globals.contains(name) {
-            return Err(CompileError {
-                message: format!("undefined variable '{}'", name),
-            });
+            return Err(CompileError::new(format!("undefined variable '{}'", name)));
         }
         
         match location {
             Some(VarLocation::Register(reg)) => {
                 // Increment/decrement the register directly
-                if increment {
-                    self.emit_instruction(&format!("ADD R{}, R{}, #1", reg, reg));
-                } else {
-                    self.emit_instruction(&format!("ADD R{}, R{}, #-1", reg, reg));
-                }
+                let reg = Register::from_index(reg);
+                let step = if increment { 1 } else { -1 };
+                self.emit_instruction(Instruction::add_imm(reg, reg, step)?);
                 // Copy to R0 for return value
-                self.emit_instruction(&format!("ADD R0, R{}, #0", reg));
+                self.emit_instruction(asm::copy(R0, reg));
             }
             Some(VarLocation::Stack(offset)) => {
                 // Load current value
-                self.emit_instruction(&format!("LDW R0, R5, #{}", offset));
+                self.emit_instruction(Instruction::ldr(R0, R5, field(offset)?)?);
                 // Increment/decrement
                 if increment {
-                    self.emit_instruction("ADD R0, R0, #1");
+                    self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
                 } else {
-                    self.emit_instruction("ADD R0, R0, #-1");
+                    self.emit_instruction(Instruction::add_imm(R0, R0, -1)?);
                 }
                 // Store new value
-                self.emit_instruction(&format!("STW R0, R5, #{}", offset));
+                self.emit_instruction(Instruction::stw(R0, R5, field(offset)?)?);
             }
             None => {
                 // Global variable
-                self.emit_instruction(&format!("LEA R1, {}", name));
-                self.emit_instruction("LDW R0, R1, #0");
+                self.emit_lea(7, name);
+                self.emit_instruction(Instruction::ldr(R0, R7, 0)?);
                 if increment {
-                    self.emit_instruction("ADD R0, R0, #1");
+                    self.emit_instruction(Instruction::add_imm(R0, R0, 1)?);
                 } else {
-                    self.emit_instruction("ADD R0, R0, #-1");
+                    self.emit_instruction(Instruction::add_imm(R0, R0, -1)?);
                 }
-                self.emit_instruction(&format!("LEA R1, {}", name));
-                self.emit_instruction("STW R0, R1, #0");
+                self.emit_instruction(Instruction::stw(R0, R7, 0)?);
             }
         }
 
@@ -1357,6 +2040,41 @@ impl Compiler {
     }
 }
 
+/// The type `declarator` gives its variable, arrays as pointers to their
+/// first element
+fn declared_type(decl: &Declaration, declarator: &Declarator) -> Type {
+    match declarator.array_len {
+        Some(_) => Type::Pointer(Box::new(decl.ty.clone())),
+        None => decl.ty.clone(),
+    }
+}
+
+/// The value of a constant initializer, if it is one
+fn constant_value(expr: &Expression) -> Option<i32> {
+    match expr {
+        Expression::IntLiteral(n) => Some(*n),
+        Expression::CharLiteral(c) => Some(*c as i32),
+        Expression::Unary { op: UnaryOp::Negate, operand } => constant_value(operand).map(|n| -n),
+        _ => None,
+    }
+}
+
+/// How to multiply by `factor` with shifts and adds: the bits to add a
+/// shifted copy of the value for, and whether to negate the sum, which
+/// is shorter for factors like -3. None if that takes more adds than
+/// calling the runtime multiply is worth.
+fn shift_add_factor(factor: i16) -> Option<(u16, bool)> {
+    const MAX_ADDS: u32 = 4;
+    let bits = factor as u16;
+    let negated = factor.wrapping_neg() as u16;
+    let (bits, negate) = if negated.count_ones() + 1 < bits.count_ones() {
+        (negated, true)
+    } else {
+        (bits, false)
+    };
+    (bits.count_ones() <= MAX_ADDS + 1).then_some((bits, negate))
+}
+
 fn type_to_string(ty: &Type) -> &'static str {
     match ty {
         Type::Void => "void",
@@ -1366,25 +2084,10 @@ fn type_to_string(ty: &Type) -> &'static str {
         Type::Short { unsigned: false } => "short",
         Type::Char => "char",
         Type::Pointer(_) => "ptr",
+        Type::Struct(_) => "struct",
     }
 }
 
-fn escape_string(s: &str) -> String {
-    let mut result = String::new();
-    for c in s.chars() {
-        match c {
-            '\n' => result.push_str("\\n"),
-            '\r' => result.push_str("\\r"),
-            '\t' => result.push_str("\\t"),
-            '"' => result.push_str("\\\""),
-            '\\' => result.push_str("\\\\"),
-            c if c.is_ascii_graphic() || c == ' ' => result.push(c),
-            c => result.push_str(&format!("\\x{:02X}", c as u8)),
-        }
-    }
-    result
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1422,7 +2125,7 @@ mod tests {
         let result = compile(source, &CompileOptions::default()).unwrap();
         println!("{}", result);
         // Should have ADD instruction for a + b
-        assert!(result.contains("ADD R0, R0, R1"));
+        assert!(result.contains("ADD R0, R0, R7"));
     }
 
     #[test]
@@ -1460,6 +2163,63 @@ mod tests {
         assert!(result.contains("RET"));
     }
 
+    #[test]
+    fn test_calling_convention() {
+        let source = r#"
+            int add(int a, int b) {
+                return a + b;
+            }
+            int main() {
+                return add(1, 2);
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        println!("{}", result);
+        // Each argument gets a two-word slot, popped after the call
+        assert_eq!(result.matches("ADD R6, R6, #-2").count(), 2);
+        assert!(result.contains("JSR add\n    ADD R6, R6, #4"));
+        // The frame record is two slots, with the parameters above it
+        assert!(result.contains("ADD R6, R6, #-4\n    STW R7, R6, #0\n    STW R5, R6, #1"));
+        // Both parameters are loaded into registers
+        assert!(result.contains("LDW R4, R5, #2"));
+        assert!(result.contains("LDW R3, R5, #3"));
+        assert!(lc3b_assembler::assemble(&result).is_ok());
+    }
+
+    #[test]
+    fn test_register_args() {
+        let source = r#"
+            int add(int a, int b, int c) {
+                return a + b + c;
+            }
+            int main() {
+                return add(1, 2, 3);
+            }
+        "#;
+        let options = CompileOptions {
+            register_args: 2,
+            ..CompileOptions::default()
+        };
+        let result = compile(source, &options).unwrap();
+        println!("{}", result);
+        // Only `c` is pushed; `b` waits on the stack while `a` is evaluated
+        let (main, add) = result.split_at(result.find("add:").unwrap());
+        assert_eq!(main.matches("ADD R6, R6, #-2").count(), 1);
+        assert!(main.contains("LDW R1, R6, #0"));
+        assert!(main.contains("JSR add\n    ADD R6, R6, #2"));
+        // The callee moves `a` and `b` to their registers and loads `c`
+        // from above its frame
+        assert!(add.contains("ADD R4, R0, #0"));
+        assert!(add.contains("ADD R3, R1, #0"));
+        assert!(add.contains("LDW R2, R5, #2"));
+
+        let options = CompileOptions {
+            register_args: 4,
+            ..CompileOptions::default()
+        };
+        assert!(compile(source, &options).is_err());
+    }
+
     #[test]
     fn test_string_literal() {
         let source = r#"
@@ -1499,13 +2259,11 @@ mod tests {
         // (that would be double-dereferencing)
         let lines: Vec<&str> = result.lines().collect();
         for (i, line) in lines.iter().enumerate() {
-            if line.contains("LEA R0, hello") {
-                if i + 1 < lines.len() {
-                    assert!(
-                        !lines[i + 1].contains("LDW R0, R0, #0"),
-                        "Should not dereference string global pointer"
-                    );
-                }
+            if line.contains("LEA R0, hello") && i + 1 < lines.len() {
+                assert!(
+                    !lines[i + 1].contains("LDW R0, R0, #0"),
+                    "Should not dereference string global pointer"
+                );
             }
         }
     }
@@ -1542,8 +2300,8 @@ mod tests {
         println!("{}", result);
         // puts is a simple trap wrapper, so it should be inlined
         assert!(result.contains("puts() [inlined]"));
-        // Should emit TRAP x22 directly (no JSR)
-        assert!(result.contains("TRAP x22"));
+        // Should emit TRAP x22, PUTS, directly (no JSR)
+        assert!(result.contains("    PUTS"));
         // Should NOT have the puts function defined (it's inlined)
         assert!(!result.contains("puts:"));
     }
@@ -1558,7 +2316,8 @@ mod tests {
         "#;
         let result = compile(source, &CompileOptions::default()).unwrap();
         println!("{}", result);
-        assert!(result.contains("TRAP x25"));
+        // TRAP x25 is HALT, as is the end of main()
+        assert_eq!(result.matches("    HALT").count(), 2);
     }
 
     #[test]
@@ -1574,17 +2333,18 @@ mod tests {
         let result = compile(source, &CompileOptions::default()).unwrap();
         println!("{}", result);
         // Should use register allocation (no STW/LDW for locals)
-        assert!(result.contains("Using register allocation"));
-        // Variables should be in R1 and R2
-        assert!(result.contains("ADD R1, R0, #0")); // a = 5 -> R1
-        assert!(result.contains("ADD R2, R0, #0")); // b = 10 -> R2
+        assert!(result.contains("; a in R4"));
+        assert!(result.contains("; b in R3"));
+        assert!(result.contains("ADD R4, R0, #0")); // a = 5 -> R4
+        assert!(result.contains("ADD R3, R0, #0")); // b = 10 -> R3
         // Should NOT have frame pointer setup for main with register alloc
         assert!(!result.contains("ADD R5, R6, #0"));
     }
 
     #[test]
     fn test_register_allocation_for_loop() {
-        // For loop with 2 locals (sum, i), no calls -> should use registers
+        // For loop with 2 locals (sum, i) -> should use registers, the loop
+        // counter (used most) first
         let source = r#"
             int main() {
                 int sum = 0;
@@ -1596,28 +2356,75 @@ mod tests {
         "#;
         let result = compile(source, &CompileOptions::default()).unwrap();
         println!("{}", result);
-        assert!(result.contains("Using register allocation"));
+        assert!(result.contains("; i in R4"));
         // i++ should be a simple register increment
-        assert!(result.contains("ADD R2, R2, #1")); // i++
+        assert!(result.contains("ADD R4, R4, #1")); // i++
     }
 
     #[test]
-    fn test_stack_allocation_with_calls() {
-        // Function with calls -> should use stack
+    fn test_registers_saved_across_calls() {
+        // x is needed after the call, y is not
         let source = r#"
             void helper() {}
             int main() {
                 int x = 5;
+                int y = 6;
+                x = x + y;
                 helper();
                 return x;
             }
         "#;
         let result = compile(source, &CompileOptions::default()).unwrap();
         println!("{}", result);
-        // main has a call, so should NOT use register allocation
-        assert!(!result.contains("; Using register allocation for locals\nmain"));
-        // Should use stack for x
-        assert!(result.contains("STW R0, R5"));
+        assert!(result.contains("; x in R4"));
+        assert!(result.contains("; y in R3"));
+        assert!(result.contains("ADD R6, R6, #-2\n    STW R4, R6, #0"));
+        assert!(result.contains("JSR helper\n    LDW R4, R6, #0\n    ADD R6, R6, #2"));
+        assert!(!result.contains("STW R3, R6"));
+    }
+
+    #[test]
+    fn test_spilling() {
+        // Five variables live at once don't fit in R1-R4; the one used least
+        // stays on the stack
+        let source = r#"
+            int main() {
+                int a = 1;
+                int b = 2;
+                int c = 3;
+                int d = 4;
+                int e = 5;
+                int i;
+                for (i = 0; i < 3; i++) {
+                    a = a + b + c + d;
+                }
+                return a + b + c + d + e;
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        println!("{}", result);
+        assert!(!result.contains("; e in R"));
+        assert!(result.contains("STW R0, R5, #-1"));
+        assert!(result.contains("ADD R5, R6, #0"));
+    }
+
+    #[test]
+    fn test_jsr_targets_are_even() {
+        // JSR doubles its offset, so every call has to land an even
+        // distance away
+        let source = r#"
+            int one() { return 1; }
+            int two() { return 2; }
+            int main() {
+                return one() + two() * 3;
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        let program = lc3b_assembler::assemble(&result)
+            .unwrap_or_else(|e| panic!("{}\n\n{}", e, result));
+        for label in ["one", "two", "rt_mul"] {
+            assert_eq!(program.symbols.address_of(label).unwrap() % 2, 0, "{}", label);
+        }
     }
 
     #[test]
@@ -1683,4 +2490,281 @@ int main() {
         }
         assert!(assembled.is_ok());
     }
+
+    #[test]
+    fn test_lea_reaches_data_wherever_it_lands() {
+        // LEA reaches only labels an even distance away, so each load has
+        // to assemble whether it lands at an odd or an even address
+        for padding in 0..4 {
+            let statements = "g = g + 1;\n".repeat(padding);
+            let source = format!(
+                r#"#include <lc3b-io.h>
+int g = 3;
+char *s = "abc";
+int main() {{
+    {}puts(s);
+    puts("x");
+    return g + 1000;
+}}
+"#,
+                statements
+            );
+            let asm = compile(&source, &CompileOptions::default()).unwrap();
+            if let Err(e) = lc3b_assembler::assemble(&asm) {
+                panic!("Assembly failed: {}\n\nGenerated assembly:\n{}", e, asm);
+            }
+        }
+    }
+
+    #[test]
+    fn test_multiply_divide_use_runtime() {
+        let source = r#"
+            int main() {
+                int a = 7;
+                int b = a * 3;
+                return (b / 2) + (b % 2) + (a * 8);
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        println!("{}", result);
+        assert!(result.contains("JSR rt_mul"));
+        assert!(result.contains("JSR rt_divmod"));
+        // Each routine is emitted once however often it is called
+        assert_eq!(result.matches("rt_divmod:").count(), 1);
+        // Powers of two are shifts
+        assert!(result.contains("LSHF R0, R0, #3"));
+        assert!(lc3b_assembler::assemble(&result).is_ok());
+    }
+
+    #[test]
+    fn test_syntax_error_location() {
+        let source = "int main() {\n    return 0\n}\n";
+        let error = compile(source, &CompileOptions::default()).unwrap_err();
+        let location = error.location.unwrap();
+        assert_eq!(location.line, 3);
+
+        let source = "int main() {\n    return missing;\n}\n";
+        let error = compile(source, &CompileOptions::default()).unwrap_err();
+        assert!(error.location.is_none());
+    }
+
+    #[test]
+    fn test_line_markers() {
+        let source = "int main() {\n    int x = 1;\n\n    x = x + 2;\n    return x;\n}\n";
+        let asm = compile(source, &CompileOptions::default()).unwrap();
+        let lines: Vec<usize> = crate::line_markers(&asm).iter().map(|&(_, l)| l).collect();
+        assert_eq!(lines, vec![1, 2, 4, 5]);
+
+        let options = CompileOptions {
+            debug_info: false,
+            ..CompileOptions::default()
+        };
+        let asm = compile(source, &options).unwrap();
+        assert!(crate::line_markers(&asm).is_empty());
+    }
+
+    #[test]
+    fn test_arrays() {
+        let source = r#"
+            int table[5] = {1, -2, 'a'};
+            int main() {
+                int a[3] = {7};
+                a[2] = table[1];
+                return a[0];
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        println!("{}", result);
+        assert!(result.contains(".FILL #-2"));
+        assert!(result.contains(".FILL #97"));
+        assert!(result.contains(".BLKW #2"));
+        // The local array takes three words of the frame
+        assert!(result.contains("ADD R6, R6, #-3"));
+        assert!(result.contains("STW R0, R7, #0"));
+        assert!(lc3b_assembler::assemble(&result).is_ok());
+
+        let error = compile("int main() { int a[2]; a = 1; }", &CompileOptions::default());
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_address_of() {
+        let source = r#"
+            int g;
+            int main() {
+                int x = 1;
+                int *p = &x;
+                *p = 2;
+                p = &g;
+                return x;
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        println!("{}", result);
+        // x is in the frame rather than a register, so it has an address
+        assert!(result.contains("ADD R0, R5, #-2"));
+        assert!(result.contains("LEA R0, g"));
+        assert!(lc3b_assembler::assemble(&result).is_ok());
+
+        let error = compile("int main() { int *p = &1; }", &CompileOptions::default());
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_structs() {
+        let source = r#"
+            struct pair { int a; int b; };
+            struct pair pairs[3];
+            struct pair one = {1, 2};
+            int main() {
+                struct pair p = {5};
+                pairs[2].b = p.a;
+                return one.b;
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        println!("{}", result);
+        assert!(result.contains("pairs:"));
+        assert!(result.contains(".BLKW #6"));
+        // Elements of two words are indexed with a shift
+        assert!(result.contains("LSHF R0, R0, #1"));
+        assert!(lc3b_assembler::assemble(&result).is_ok());
+
+        let options = CompileOptions::default();
+        let missing = "struct pair { int a; }; int main() { struct pair p; return p.c; }";
+        assert!(compile(missing, &options).is_err());
+        let undefined = "int main() { struct nope n; return 0; }";
+        assert!(compile(undefined, &options).is_err());
+        let whole = "struct pair { int a; }; struct pair p; struct pair q; int main() { p = q; }";
+        assert!(compile(whole, &options).is_err());
+    }
+
+    #[test]
+    fn test_break_continue() {
+        let source = r#"
+            int main() {
+                int i;
+                for (i = 0; i < 3; i++) {
+                    while (i) {
+                        break;
+                    }
+                    continue;
+                }
+                return i;
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        println!("{}", result);
+        // break leaves the while, continue goes to the for's update
+        assert!(result.contains("BR endwhile_"));
+        assert!(result.contains("BR forupdate_"));
+
+        let options = CompileOptions::default();
+        assert!(compile("int main() { break; }", &options).is_err());
+        assert!(compile("int main() { if (1) { continue; } }", &options).is_err());
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let source = r#"
+            int f() {
+                return 1;
+            }
+            int main() {
+                int x = 2 + 3 * 5;
+                int y = x * 1 + 0;
+                if (y - 17) {
+                    f();
+                }
+                return y >> 1;
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        assert!(result.contains("JSR rt_mul"));
+        assert!(result.contains("JSR f"));
+
+        let options = CompileOptions {
+            opt_level: 1,
+            ..CompileOptions::default()
+        };
+        let result = compile(source, &options).unwrap();
+        println!("{}", result);
+        // Everything is known, so the branch and the multiply are gone
+        // and main returns 8 outright
+        assert!(!result.contains("rt_mul"));
+        assert!(!result.contains("JSR f"));
+        assert!(!result.contains("shr_loop"));
+        assert!(result.contains("AND R0, R0, #0\n    ADD R0, R0, #8"));
+    }
+
+    #[test]
+    fn test_strength_reduction() {
+        let source = r#"
+            int f(int x) {
+                return x * 10 + x / 8 - x * 1000 + x / 3;
+            }
+            int main() {
+                return f(5);
+            }
+        "#;
+        let options = CompileOptions {
+            opt_level: 1,
+            ..CompileOptions::default()
+        };
+        let result = compile(source, &options).unwrap();
+        println!("{}", result);
+        // x * 10 is (x << 2 + x) << 1
+        assert!(result.contains(
+            "ADD R7, R0, #0\n    LSHF R0, R0, #2\n    ADD R0, R0, R7\n    LSHF R0, R0, #1\n"
+        ));
+        assert!(result.contains("RSHFL R7, R7, #13\n    ADD R0, R0, R7\n    RSHFA R0, R0, #3"));
+        // 1000 has too many bits set, and 3 isn't a power of two
+        assert!(result.contains("JSR rt_mul"));
+        assert!(result.contains("JSR rt_divmod"));
+    }
+
+    #[test]
+    fn test_peephole() {
+        let source = r#"
+            int f(int a, int b) {
+                return a + b - 1;
+            }
+            int main() {
+                return f(2, 3);
+            }
+        "#;
+        let result = compile(source, &CompileOptions::default()).unwrap();
+        let f = &result[result.find("f:").unwrap()..];
+        assert!(f.contains("STW R0, R6, #0"));
+        assert!(f.contains("BR f_exit"));
+
+        let options = CompileOptions {
+            opt_level: 2,
+            ..CompileOptions::default()
+        };
+        let result = compile(source, &options).unwrap();
+        println!("{}", result);
+        // The right operands are evaluated straight into R7, with nothing
+        // pushed, and the return falls through to the epilogue
+        let f = &result[result.find("f:").unwrap()..];
+        assert!(!f.contains("STW R0, R6, #0"));
+        assert!(f.contains("ADD R7, R3, #0\n    ADD R0, R0, R7"));
+        assert!(f.contains("AND R7, R7, #0\n    ADD R7, R7, #1\n"));
+        assert!(!f.contains("BR f_exit"));
+    }
+
+    #[test]
+    fn test_operand_out_of_range() {
+        // Locals past the 32nd slot below R5 are out of LDW/STW's reach
+        let names: Vec<String> = (0..40).map(|i| format!("a{}", i)).collect();
+        let source = format!(
+            "int main() {{ int {}; {} = 1; return {}; }}",
+            names.join(", "),
+            names.join(" = "),
+            names.join(" + ")
+        );
+        let error = compile(&source, &CompileOptions::default()).unwrap_err();
+        assert!(error.message.contains("out of range"), "{}", error);
+        assert!(compile_to_words(&source, &CompileOptions::default()).is_err());
+    }
 }
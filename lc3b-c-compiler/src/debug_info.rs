@@ -0,0 +1,19 @@
+//! Line markers tying generated assembly back to the C source
+
+/// Comment the compiler emits before the code for each function and
+/// statement, followed by its 1-based C source line
+pub(crate) const LINE_MARKER: &str = ";#line ";
+
+/// The C line markers in compiled `assembly`, as `(assembly line, C line)`
+/// pairs in order, both 1-based. The code for a C line is everything
+/// between its marker and the next one.
+pub fn line_markers(assembly: &str) -> Vec<(usize, usize)> {
+    assembly
+        .lines()
+        .enumerate()
+        .filter_map(|(index, text)| {
+            let line = text.trim_start().strip_prefix(LINE_MARKER)?;
+            Some((index + 1, line.trim().parse().ok()?))
+        })
+        .collect()
+}
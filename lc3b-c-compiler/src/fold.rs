@@ -0,0 +1,498 @@
+//! Constant folding on the syntax tree, for `CompileOptions::opt_level` 1
+//!
+//! Operations on constants are evaluated the way the generated code would
+//! evaluate them on 16-bit words, so folding never changes what a program
+//! computes: arithmetic wraps, comparisons go by the sign of the wrapped
+//! difference, `>>` shifts in zeros and division by zero follows the
+//! runtime routine. Operands that can't change the result (`x + 0`,
+//! `x * 1`) are dropped, and so are ones that make it constant (`x * 0`)
+//! if evaluating them has no effect. Branches and loops whose condition
+//! is constant are reduced to what runs.
+//!
+//! Constants assigned to scalar locals are propagated into later reads
+//! until the local may be assigned again. Where control flow joins, only
+//! the values every path agrees on are kept, and locals a loop assigns
+//! are forgotten for the whole loop. Locals whose address is taken are
+//! never propagated.
+
+use lc3b_c_ast::{
+    AssignOp, BinaryOp, Block, BlockItem, Declaration, Expression, ForInit, Function,
+    Initializer, Program, Statement, TopLevelItem, Type, UnaryOp,
+};
+use std::collections::{HashMap, HashSet};
+
+/// The locals known to hold a constant at some point in a function
+type Constants = HashMap<String, i16>;
+
+/// Fold the bodies of `program`'s functions
+pub(crate) fn fold_program(program: &mut Program) {
+    for item in &mut program.items {
+        if let TopLevelItem::Function(func) = item {
+            fold_function(func);
+        }
+    }
+}
+
+fn fold_function(func: &mut Function) {
+    let mut scan = Scan::default();
+    scan.block(&func.body);
+    let mut folder = Folder {
+        candidates: HashSet::new(),
+        addressed: scan.addressed,
+    };
+    for param in &func.parameters {
+        folder.declare(&param.name, &param.ty, false);
+    }
+    folder.block(&mut func.body, &mut Constants::new());
+}
+
+struct Folder {
+    /// Locals and parameters whose values may be propagated
+    candidates: HashSet<String>,
+    addressed: HashSet<String>,
+}
+
+impl Folder {
+    /// Note a new local or parameter, returning whether it may be propagated
+    fn declare(&mut self, name: &str, ty: &Type, array: bool) -> bool {
+        let scalar = matches!(ty, Type::Int | Type::Uint16 | Type::Short { .. } | Type::Char);
+        if scalar && !array && !self.addressed.contains(name) {
+            self.candidates.insert(name.to_string());
+            true
+        } else {
+            self.candidates.remove(name);
+            false
+        }
+    }
+
+    fn block(&mut self, block: &mut Block, constants: &mut Constants) {
+        for item in &mut block.items {
+            match item {
+                BlockItem::Declaration(decl) => self.declaration(decl, constants),
+                BlockItem::Statement(stmt) => self.statement(stmt, constants),
+            }
+        }
+    }
+
+    fn declaration(&mut self, decl: &mut Declaration, constants: &mut Constants) {
+        for declarator in &mut decl.declarators {
+            let value = match &mut declarator.initializer {
+                Some(Initializer::Expression(expr)) => self.expression(expr, constants),
+                Some(Initializer::List(values)) => {
+                    for value in values {
+                        self.expression(value, constants);
+                    }
+                    None
+                }
+                _ => None,
+            };
+            constants.remove(&declarator.name);
+            let array = declarator.array_len.is_some();
+            if let (true, Some(value)) = (self.declare(&declarator.name, &decl.ty, array), value)
+            {
+                constants.insert(declarator.name.clone(), value);
+            }
+        }
+    }
+
+    fn statement(&mut self, stmt: &mut Statement, constants: &mut Constants) {
+        match stmt {
+            Statement::Compound(block) => self.block(block, constants),
+            Statement::Expression(expr) | Statement::Return(Some(expr)) => {
+                self.expression(expr, constants);
+            }
+            Statement::If { condition, then_branch, else_branch } => {
+                match self.expression(condition, constants) {
+                    Some(value) => {
+                        let taken =
+                            if value != 0 { Some(then_branch) } else { else_branch.as_mut() };
+                        *stmt = taken.map_or(Statement::Empty, |branch| take_statement(branch));
+                        self.statement(stmt, constants);
+                    }
+                    None => {
+                        let mut otherwise = constants.clone();
+                        self.statement(then_branch, constants);
+                        if let Some(else_branch) = else_branch {
+                            self.statement(else_branch, &mut otherwise);
+                        }
+                        constants.retain(|name, value| otherwise.get(name) == Some(value));
+                    }
+                }
+            }
+            Statement::While { condition, body } => {
+                forget_writes(&[&*condition], body, constants);
+                if self.expression(condition, &mut constants.clone()) == Some(0) {
+                    *stmt = Statement::Empty;
+                } else {
+                    self.statement(body, &mut constants.clone());
+                }
+            }
+            Statement::DoWhile { body, condition } => {
+                forget_writes(&[&*condition], body, constants);
+                self.statement(body, &mut constants.clone());
+                self.expression(condition, &mut constants.clone());
+            }
+            Statement::For { init, condition, update, body } => {
+                match init {
+                    Some(ForInit::Declaration(decl)) => self.declaration(decl, constants),
+                    Some(ForInit::Expression(expr)) => {
+                        self.expression(expr, constants);
+                    }
+                    None => {}
+                }
+                let tested: Vec<&Expression> = condition.iter().chain(update.iter()).collect();
+                forget_writes(&tested, body, constants);
+                let test = match condition {
+                    Some(condition) => self.expression(condition, &mut constants.clone()),
+                    None => None,
+                };
+                match test {
+                    // The body never runs; a declaration has to stay for
+                    // what comes after
+                    Some(0) => match init.take() {
+                        Some(ForInit::Declaration(decl)) => {
+                            *init = Some(ForInit::Declaration(decl));
+                            *update = None;
+                            **body = Statement::Empty;
+                        }
+                        Some(ForInit::Expression(expr)) => *stmt = Statement::Expression(expr),
+                        None => *stmt = Statement::Empty,
+                    },
+                    test => {
+                        if test.is_some() {
+                            *condition = None;
+                        }
+                        self.statement(body, &mut constants.clone());
+                        if let Some(update) = update {
+                            self.expression(update, &mut constants.clone());
+                        }
+                    }
+                }
+            }
+            Statement::Return(None) | Statement::Break | Statement::Continue
+            | Statement::Empty => {}
+        }
+    }
+
+    /// Fold `expr`, then update `constants` for the locals it assigns.
+    /// Returns its value if it is now constant.
+    fn expression(&self, expr: &mut Expression, constants: &mut Constants) -> Option<i16> {
+        let mut scan = Scan::default();
+        scan.expression(expr);
+        // A local the expression assigns may be read after the assignment
+        constants.retain(|name, _| !scan.writes.contains(name));
+        fold(expr, constants);
+        if let Expression::Assignment { op: AssignOp::Assign, target, value } = expr {
+            if let (Expression::Identifier(name), Some(value)) = (&**target, constant(value)) {
+                if self.candidates.contains(name) {
+                    constants.insert(name.clone(), value);
+                }
+            }
+        }
+        constant(expr)
+    }
+}
+
+/// Forget the locals a loop's `expressions` and `body` may assign
+fn forget_writes(expressions: &[&Expression], body: &Statement, constants: &mut Constants) {
+    let mut scan = Scan::default();
+    for expr in expressions {
+        scan.expression(expr);
+    }
+    scan.statement(body);
+    constants.retain(|name, _| !scan.writes.contains(name));
+}
+
+fn take_statement(stmt: &mut Statement) -> Statement {
+    std::mem::replace(stmt, Statement::Empty)
+}
+
+fn take_expression(expr: &mut Expression) -> Expression {
+    std::mem::replace(expr, Expression::IntLiteral(0))
+}
+
+/// Fold `expr` bottom up, reading locals from `constants`
+fn fold(expr: &mut Expression, constants: &Constants) {
+    match expr {
+        Expression::Identifier(name) => {
+            if let Some(&value) = constants.get(name) {
+                *expr = Expression::IntLiteral(value.into());
+            }
+        }
+        Expression::Binary { op, left, right } => {
+            fold(left, constants);
+            fold(right, constants);
+            if let Some(folded) = simplify(*op, left, right) {
+                *expr = folded;
+            }
+        }
+        Expression::Unary { op, operand } => {
+            fold(operand, constants);
+            let value = constant(operand).and_then(|value| match op {
+                UnaryOp::Negate => Some(value.wrapping_neg()),
+                UnaryOp::BitNot => Some(!value),
+                UnaryOp::LogicalNot => Some((value == 0).into()),
+                UnaryOp::Deref | UnaryOp::AddressOf => None,
+            });
+            if let Some(value) = value {
+                *expr = Expression::IntLiteral(value.into());
+            }
+        }
+        Expression::Assignment { target, value, .. } => {
+            if !matches!(**target, Expression::Identifier(_)) {
+                fold(target, constants);
+            }
+            fold(value, constants);
+        }
+        Expression::Conditional { condition, then_value, else_value } => {
+            fold(condition, constants);
+            match constant(condition) {
+                Some(value) => {
+                    let taken = if value != 0 { then_value } else { else_value };
+                    *expr = take_expression(taken);
+                    fold(expr, constants);
+                }
+                None => {
+                    fold(then_value, constants);
+                    fold(else_value, constants);
+                }
+            }
+        }
+        Expression::Call { arguments, .. } => {
+            for argument in arguments {
+                fold(argument, constants);
+            }
+        }
+        Expression::Subscript { array, index } => {
+            fold(array, constants);
+            fold(index, constants);
+        }
+        Expression::Member { object, .. } => fold(object, constants),
+        Expression::IntLiteral(_)
+        | Expression::CharLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::PostIncrement(_)
+        | Expression::PostDecrement(_)
+        | Expression::PreIncrement(_)
+        | Expression::PreDecrement(_) => {}
+    }
+}
+
+/// What `left op right` reduces to, if anything simpler
+fn simplify(op: BinaryOp, left: &mut Expression, right: &mut Expression) -> Option<Expression> {
+    use BinaryOp::*;
+    let literal = |value: i16| Some(Expression::IntLiteral(value.into()));
+    match (constant(left), constant(right)) {
+        (Some(a), Some(b)) => literal(evaluate(op, a, b)),
+        (Some(a), None) => match (op, a) {
+            (Add | BitOr | BitXor, 0) | (Mul, 1) | (BitAnd, -1) => Some(take_expression(right)),
+            (Sub, 0) => Some(Expression::Unary {
+                op: UnaryOp::Negate,
+                operand: Box::new(take_expression(right)),
+            }),
+            (Mul | BitAnd, 0) if is_pure(right) => literal(0),
+            (BitOr, -1) if is_pure(right) => literal(-1),
+            // The right operand is evaluated only if the left doesn't
+            // settle the result
+            (LogicalAnd, 0) => literal(0),
+            (LogicalOr, 0) | (LogicalAnd, _) => Some(truth(take_expression(right))),
+            (LogicalOr, _) => literal(1),
+            _ => None,
+        },
+        (None, Some(b)) => match (op, b) {
+            (Add | Sub | BitOr | BitXor | ShiftLeft | ShiftRight, 0)
+            | (Mul | Div, 1)
+            | (BitAnd, -1) => Some(take_expression(left)),
+            (Mul | BitAnd, 0) | (Mod, 1) if is_pure(left) => literal(0),
+            (BitOr, -1) if is_pure(left) => literal(-1),
+            (LogicalAnd, 0) if is_pure(left) => literal(0),
+            (LogicalOr, 0) | (LogicalAnd, _) => Some(truth(take_expression(left))),
+            (LogicalOr, _) if is_pure(left) => literal(1),
+            _ => None,
+        },
+        (None, None) => None,
+    }
+}
+
+/// `expr != 0`, unless `expr` is already 0 or 1
+fn truth(expr: Expression) -> Expression {
+    use BinaryOp::*;
+    match expr {
+        Expression::Binary {
+            op: Equal | NotEqual | Less | LessEqual | Greater | GreaterEqual | LogicalAnd
+            | LogicalOr,
+            ..
+        }
+        | Expression::Unary { op: UnaryOp::LogicalNot, .. } => expr,
+        _ => Expression::Binary {
+            op: NotEqual,
+            left: Box::new(expr),
+            right: Box::new(Expression::IntLiteral(0)),
+        },
+    }
+}
+
+/// `a op b` as the generated code computes it
+fn evaluate(op: BinaryOp, a: i16, b: i16) -> i16 {
+    use BinaryOp::*;
+    let shifted = |shift: fn(u16, u32) -> u16| {
+        // Shifting a 16-bit word 16 or more times leaves nothing
+        match u32::from(b as u16) {
+            count @ 0..=15 => shift(a as u16, count) as i16,
+            _ => 0,
+        }
+    };
+    match op {
+        Add => a.wrapping_add(b),
+        Sub => a.wrapping_sub(b),
+        Mul => a.wrapping_mul(b),
+        Div if b == 0 => if a < 0 { 1 } else { -1 },
+        Div => a.wrapping_div(b),
+        Mod if b == 0 => a,
+        Mod => a.wrapping_rem(b),
+        BitAnd => a & b,
+        BitOr => a | b,
+        BitXor => a ^ b,
+        ShiftLeft => shifted(|value, count| value << count),
+        ShiftRight => shifted(|value, count| value >> count),
+        Equal => (a == b).into(),
+        NotEqual => (a != b).into(),
+        Less => (a.wrapping_sub(b) < 0).into(),
+        LessEqual => (a.wrapping_sub(b) <= 0).into(),
+        Greater => (a.wrapping_sub(b) > 0).into(),
+        GreaterEqual => (a.wrapping_sub(b) >= 0).into(),
+        LogicalAnd => (a != 0 && b != 0).into(),
+        LogicalOr => (a != 0 || b != 0).into(),
+    }
+}
+
+/// The value of a literal, as the 16-bit word it compiles to
+fn constant(expr: &Expression) -> Option<i16> {
+    match expr {
+        Expression::IntLiteral(value) => Some(*value as i16),
+        Expression::CharLiteral(c) => Some(*c as u32 as i16),
+        _ => None,
+    }
+}
+
+/// Whether evaluating `expr` can be skipped: it assigns nothing, calls
+/// nothing and reads no memory but locals, which might be device registers
+fn is_pure(expr: &Expression) -> bool {
+    match expr {
+        Expression::IntLiteral(_)
+        | Expression::CharLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::Identifier(_) => true,
+        Expression::Binary { left, right, .. } => is_pure(left) && is_pure(right),
+        Expression::Unary { op: UnaryOp::Deref, .. } => false,
+        Expression::Unary { operand, .. } => is_pure(operand),
+        Expression::Conditional { condition, then_value, else_value } => {
+            is_pure(condition) && is_pure(then_value) && is_pure(else_value)
+        }
+        _ => false,
+    }
+}
+
+/// The locals some code may assign (or declare), and those whose address
+/// is taken
+#[derive(Default)]
+struct Scan {
+    writes: HashSet<String>,
+    addressed: HashSet<String>,
+}
+
+impl Scan {
+    fn block(&mut self, block: &Block) {
+        for item in &block.items {
+            match item {
+                BlockItem::Declaration(decl) => self.declaration(decl),
+                BlockItem::Statement(stmt) => self.statement(stmt),
+            }
+        }
+    }
+
+    fn declaration(&mut self, decl: &Declaration) {
+        for declarator in &decl.declarators {
+            self.writes.insert(declarator.name.clone());
+            match &declarator.initializer {
+                Some(Initializer::Expression(expr)) => self.expression(expr),
+                Some(Initializer::List(values)) => values.iter().for_each(|v| self.expression(v)),
+                _ => {}
+            }
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Compound(block) => self.block(block),
+            Statement::Expression(expr) | Statement::Return(Some(expr)) => self.expression(expr),
+            Statement::If { condition, then_branch, else_branch } => {
+                self.expression(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While { condition, body } | Statement::DoWhile { body, condition } => {
+                self.expression(condition);
+                self.statement(body);
+            }
+            Statement::For { init, condition, update, body } => {
+                match init {
+                    Some(ForInit::Declaration(decl)) => self.declaration(decl),
+                    Some(ForInit::Expression(expr)) => self.expression(expr),
+                    None => {}
+                }
+                condition.iter().chain(update).for_each(|e| self.expression(e));
+                self.statement(body);
+            }
+            Statement::Return(None) | Statement::Break | Statement::Continue
+            | Statement::Empty => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Assignment { target, value, .. } => {
+                match target.as_ref() {
+                    Expression::Identifier(name) => {
+                        self.writes.insert(name.clone());
+                    }
+                    target => self.expression(target),
+                }
+                self.expression(value);
+            }
+            Expression::PostIncrement(name)
+            | Expression::PostDecrement(name)
+            | Expression::PreIncrement(name)
+            | Expression::PreDecrement(name) => {
+                self.writes.insert(name.clone());
+            }
+            Expression::Unary { op, operand } => {
+                if let (UnaryOp::AddressOf, Expression::Identifier(name)) = (op, operand.as_ref()) {
+                    self.addressed.insert(name.clone());
+                }
+                self.expression(operand);
+            }
+            Expression::Binary { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::Conditional { condition, then_value, else_value } => {
+                self.expression(condition);
+                self.expression(then_value);
+                self.expression(else_value);
+            }
+            Expression::Call { arguments, .. } => arguments.iter().for_each(|a| self.expression(a)),
+            Expression::Subscript { array, index } => {
+                self.expression(array);
+                self.expression(index);
+            }
+            Expression::Member { object, .. } => self.expression(object),
+            Expression::IntLiteral(_)
+            | Expression::CharLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::Identifier(_) => {}
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Sizes and member offsets of types
+//!
+//! Memory holds one 16-bit word per address, so every scalar and pointer
+//! takes one word (and one address). A struct's members follow each other
+//! without padding.
+
+use crate::CompileError;
+use lc3b_c_ast::{StructDefinition, Type};
+use std::collections::HashMap;
+
+/// Where a struct member is, in words from the start of the struct
+#[derive(Debug, Clone)]
+pub(crate) struct FieldLayout {
+    pub offset: usize,
+    pub ty: Type,
+    /// Element count, if the member is an array
+    pub array_len: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct StructLayout {
+    size: usize,
+    fields: HashMap<String, FieldLayout>,
+}
+
+/// The structs a program defines
+#[derive(Debug, Default)]
+pub(crate) struct Layouts {
+    structs: HashMap<String, StructLayout>,
+}
+
+impl Layouts {
+    /// Lay out `def`. Its members may only be structs defined before it,
+    /// though pointers to any struct (itself included) are fine.
+    pub fn define(&mut self, def: &StructDefinition) -> Result<(), CompileError> {
+        if self.structs.contains_key(&def.name) {
+            return Err(CompileError::new(format!("struct '{}' is defined twice", def.name)));
+        }
+        let mut size = 0;
+        let mut fields = HashMap::new();
+        for field in &def.fields {
+            let words = self.size_of(&field.ty)? * field.array_len.unwrap_or(1);
+            let layout = FieldLayout {
+                offset: size,
+                ty: field.ty.clone(),
+                array_len: field.array_len,
+            };
+            fields.insert(field.name.clone(), layout);
+            size += words;
+        }
+        if size == 0 {
+            return Err(CompileError::new(format!("struct '{}' has no members", def.name)));
+        }
+        self.structs.insert(def.name.clone(), StructLayout { size, fields });
+        Ok(())
+    }
+
+    /// How many words a value of type `ty` takes
+    pub fn size_of(&self, ty: &Type) -> Result<usize, CompileError> {
+        match ty {
+            Type::Void => Err(CompileError::new("void has no size")),
+            Type::Struct(name) => self
+                .structs
+                .get(name)
+                .map(|layout| layout.size)
+                .ok_or_else(|| CompileError::new(format!("struct '{}' is not defined", name))),
+            _ => Ok(1),
+        }
+    }
+
+    /// Member `name` of struct type `ty`
+    pub fn field(&self, ty: &Type, name: &str) -> Result<&FieldLayout, CompileError> {
+        let Type::Struct(struct_name) = ty else {
+            return Err(CompileError::new(format!(
+                "member '{}' of something that is not a struct",
+                name
+            )));
+        };
+        let layout = self
+            .structs
+            .get(struct_name)
+            .ok_or_else(|| CompileError::new(format!("struct '{}' is not defined", struct_name)))?;
+        layout.fields.get(name).ok_or_else(|| {
+            CompileError::new(format!("struct '{}' has no member '{}'", struct_name, name))
+        })
+    }
+}
@@ -2,10 +2,44 @@
 
 //! C to LC-3B Assembly Compiler
 //!
-//! This crate compiles a subset of C to LC-3B assembly text.
+//! This crate compiles a subset of C to LC-3B assembly text, or straight to
+//! machine words with `compile_to_words`.
+//!
+//! # Calling convention
+//!
+//! Memory holds a word per address and LDW/STW double their offsets, so
+//! the stack is laid out in two-word slots.
+//!
+//! - The caller pushes the arguments right to left, a slot each, then
+//!   `JSR`s to the function and pops them again once it returns. With
+//!   `CompileOptions::register_args` set, that many leading arguments go
+//!   in R0, R1 and R2 instead, and only the rest are pushed. The callee
+//!   stores them below R5 like locals on entry.
+//! - The callee pushes a two-slot frame record: the return address (R7)
+//!   at the new frame pointer R5 and the caller's R5 in the slot above.
+//!   Argument `i` is then at `R5 + 4 + 2i`, and locals that live in
+//!   memory are below R5.
+//! - The return value is in R0.
+//! - R5 and R6 are restored on return. R0 to R4 are not, and R7 holds the
+//!   return address. Expression temporaries are on the stack, and locals
+//!   kept in R1 to R4 that are still needed after a call are saved
+//!   around it by the caller.
+//!
+//! Each call gets its own frame, so functions may recurse. Function and
+//! runtime routine entry points are at even addresses, since `JSR` can
+//! only reach an even number of words past the next instruction.
 
+mod asm;
 mod codegen;
+mod debug_info;
+mod fold;
 mod headers;
+mod layout;
+mod peephole;
+mod regalloc;
+mod runtime;
 
-pub use codegen::{compile, CompileError, CompileOptions};
+pub use codegen::{compile, compile_to_words, CompileError, CompileOptions, CompiledWords};
+pub use debug_info::line_markers;
 pub use headers::{available_headers, get_header, Header};
+pub use lc3b_c_grammar::SourceLocation;
@@ -0,0 +1,303 @@
+//! Peephole optimization, for `CompileOptions::opt_level` 2
+//!
+//! Code generation works an expression at a time, so neighbouring
+//! instructions often redo each other's work. These rewrites are applied
+//! until none is left to make:
+//!
+//! - `ADD Rx, Rx, #0` only sets the condition codes. It goes if the
+//!   instruction before it set them from Rx already, or if nothing tests
+//!   them before they are set again.
+//! - A load from where the instruction before stored is a copy of the
+//!   stored register, or nothing if it loads the same register.
+//! - The left operand of a binary operation is pushed while the right one
+//!   is evaluated into R0, then popped back into R0 with the right one
+//!   moved to R7. When evaluating the right one takes only a few plain
+//!   instructions that don't need the left one, they evaluate it in R7
+//!   instead, and the push and pop go.
+//! - A branch to a label that follows it directly goes.
+//!
+//! Comments and line markers don't separate instructions. Labels do, as
+//! code elsewhere may jump to them.
+
+use crate::asm::{self, Line, Op, R0, R6, R7};
+use lc3b_isa::{AddInstruction, AndInstruction, Instruction, Register, XorInstruction};
+
+/// Rewrite `lines` until no rewrite applies
+pub(crate) fn optimize(lines: &mut Vec<Line>) {
+    loop {
+        let mut changed = false;
+        let mut i = 0;
+        while i < lines.len() {
+            if matches!(lines[i], Line::Instruction(_)) {
+                changed |= redundant_test(lines, i)
+                    || load_after_store(lines, i)
+                    || push_around_operand(lines, i)
+                    || branch_to_next(lines, i);
+            }
+            i += 1;
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+/// `ADD Rx, Rx, #0` where the condition codes are already set from Rx, or
+/// aren't needed
+fn redundant_test(lines: &mut Vec<Line>, i: usize) -> bool {
+    let Op::Fixed(ins) = instruction(lines, i) else {
+        return false;
+    };
+    let Some(reg) = ins.destination_register() else {
+        return false;
+    };
+    if *ins != asm::test(reg) {
+        return false;
+    }
+    let already_set = previous_instruction(lines, i)
+        .is_some_and(|prev| destination(instruction(lines, prev)) == Some(reg));
+    if already_set || !condition_needed(lines, i) {
+        lines.remove(i);
+        return true;
+    }
+    false
+}
+
+/// `STW Ra, Rb, #n` then `LDW Rc, Rb, #n`
+fn load_after_store(lines: &mut Vec<Line>, i: usize) -> bool {
+    let Some(next) = next_instruction(lines, i) else {
+        return false;
+    };
+    let (
+        Op::Fixed(Instruction::Stw(stored, base, offset)),
+        Op::Fixed(Instruction::Ldr(loaded, load_base, load_offset)),
+    ) = (instruction(lines, i), instruction(lines, next))
+    else {
+        return false;
+    };
+    if (base, offset) != (load_base, load_offset) {
+        return false;
+    }
+    if stored == loaded {
+        // The copy is only worth keeping for its condition codes, which
+        // leaves nothing to gain
+        if condition_needed(lines, next) {
+            return false;
+        }
+        lines.remove(next);
+    } else {
+        lines[next] = Line::Instruction(Op::Fixed(asm::copy(*loaded, *stored)));
+    }
+    true
+}
+
+/// Push R0, evaluate into R0 with a few plain instructions that leave R6
+/// and R7 alone, then pop: keep the pushed value in R7 instead
+fn push_around_operand(lines: &mut Vec<Line>, i: usize) -> bool {
+    let is = |lines: &[Line], at: Option<usize>, expected: Instruction| {
+        at.is_some_and(|at| *instruction(lines, at) == Op::Fixed(expected))
+    };
+    let push = Instruction::add_imm(R6, R6, -1).expect("in range");
+    let pop = Instruction::add_imm(R6, R6, 1).expect("in range");
+    let top = |reg| Instruction::ldr(reg, R6, 0).expect("in range");
+    let Some(store) = next_instruction(lines, i) else {
+        return false;
+    };
+    let store_top = Instruction::stw(R0, R6, 0).expect("in range");
+    if !is(lines, Some(i), push) || !is(lines, Some(store), store_top) {
+        return false;
+    }
+    // The operand's instructions, and whether they read R0 before writing it
+    let mut operand = Vec::new();
+    let mut reads_pushed = false;
+    let mut written = false;
+    let mut last = store;
+    let first_after = loop {
+        let Some(j) = next_instruction(lines, last) else {
+            return false;
+        };
+        let op = instruction(lines, j);
+        let Some(reads) = registers_read(op) else {
+            break j;
+        };
+        let destination = destination(op);
+        if reads.iter().chain(&destination).any(|&r| r == R6 || r == R7) {
+            break j;
+        }
+        reads_pushed |= reads.contains(&R0) && !written;
+        written |= destination == Some(R0);
+        operand.push(j);
+        last = j;
+    };
+    if !written {
+        return false;
+    }
+    let second_after = next_instruction(lines, first_after);
+    let third_after = second_after.and_then(|j| next_instruction(lines, j));
+    if is(lines, Some(first_after), top(R7))
+        && is(lines, second_after, pop)
+        && !condition_needed(lines, second_after.unwrap())
+    {
+        // Popped into R7: copy it there to start with
+        remove(lines, &[store, first_after, second_after.unwrap()]);
+        lines[i] = Line::Instruction(Op::Fixed(asm::copy(R7, R0)));
+        return true;
+    }
+    if reads_pushed
+        || !is(lines, Some(first_after), asm::copy(R7, R0))
+        || !is(lines, second_after, top(R0))
+        || !is(lines, third_after, pop)
+        || condition_needed(lines, third_after.unwrap())
+    {
+        return false;
+    }
+    // Moved to R7 and popped into R0: evaluate into R7 to start with
+    for &j in &operand {
+        if let Line::Instruction(op) = &mut lines[j] {
+            rename(op, R0, R7);
+        }
+    }
+    let popped = [first_after, second_after.unwrap(), third_after.unwrap()];
+    remove(lines, &popped);
+    remove(lines, &[i, store]);
+    true
+}
+
+/// `BR label` with only labels (and comments) between it and `label:`
+fn branch_to_next(lines: &mut Vec<Line>, i: usize) -> bool {
+    let Op::Branch(_, target) = instruction(lines, i) else {
+        return false;
+    };
+    for line in &lines[i + 1..] {
+        match line {
+            Line::Label(label) if label == target => {
+                lines.remove(i);
+                return true;
+            }
+            Line::Label(_) | Line::Blank | Line::Comment(_) | Line::Marker(_) => {}
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn instruction(lines: &[Line], i: usize) -> &Op {
+    match &lines[i] {
+        Line::Instruction(op) => op,
+        line => unreachable!("not an instruction: {:?}", line),
+    }
+}
+
+/// Remove the lines at `indices`, which are in order
+fn remove(lines: &mut Vec<Line>, indices: &[usize]) {
+    for &i in indices.iter().rev() {
+        lines.remove(i);
+    }
+}
+
+/// Lines that don't separate the instructions either side of them
+fn is_transparent(line: &Line) -> bool {
+    matches!(line, Line::Blank | Line::Comment(_) | Line::Marker(_))
+}
+
+/// The instruction that runs after `lines[i]`, unless a label comes first
+fn next_instruction(lines: &[Line], i: usize) -> Option<usize> {
+    let j = i + 1 + lines[i + 1..].iter().position(|line| !is_transparent(line))?;
+    matches!(lines[j], Line::Instruction(_)).then_some(j)
+}
+
+/// The instruction that runs before `lines[i]`, unless a label comes between
+fn previous_instruction(lines: &[Line], i: usize) -> Option<usize> {
+    let j = lines[..i].iter().rposition(|line| !is_transparent(line))?;
+    matches!(lines[j], Line::Instruction(_)).then_some(j)
+}
+
+/// The register an instruction writes, which it sets the condition codes
+/// from
+fn destination(op: &Op) -> Option<Register> {
+    match op {
+        Op::Fixed(ins) if ins.sets_condition_codes() => ins.destination_register(),
+        Op::Lea(reg, _) => Some(*reg),
+        _ => None,
+    }
+}
+
+/// The registers a plain instruction (one that neither branches nor
+/// calls) reads, or None for any other instruction
+fn registers_read(op: &Op) -> Option<Vec<Register>> {
+    match op {
+        // Clearing a register doesn't depend on it
+        Op::Fixed(Instruction::AndInstruction(AndInstruction::AndImm(_, _, imm)))
+            if imm.value() == 0 =>
+        {
+            Some(Vec::new())
+        }
+        Op::Fixed(ins) if !ins.is_control_flow() => Some(ins.source_registers().collect()),
+        Op::Lea(..) => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+/// Have a plain instruction use register `to` wherever it uses `from`
+fn rename(op: &mut Op, from: Register, to: Register) {
+    let r = |reg: Register| if reg == from { to } else { reg };
+    let renamed = match *op {
+        Op::Fixed(Instruction::AddInstruction(AddInstruction::AddReg(dr, sr1, sr2))) => {
+            Instruction::add_reg(r(dr), r(sr1), r(sr2))
+        }
+        Op::Fixed(Instruction::AddInstruction(AddInstruction::AddImm(dr, sr, imm))) => {
+            Instruction::AddInstruction(AddInstruction::AddImm(r(dr), r(sr), imm))
+        }
+        Op::Fixed(Instruction::AndInstruction(AndInstruction::AndReg(dr, sr1, sr2))) => {
+            Instruction::and_reg(r(dr), r(sr1), r(sr2))
+        }
+        Op::Fixed(Instruction::AndInstruction(AndInstruction::AndImm(dr, sr, imm))) => {
+            Instruction::AndInstruction(AndInstruction::AndImm(r(dr), r(sr), imm))
+        }
+        Op::Fixed(Instruction::XorInstruction(XorInstruction::XorReg(dr, sr1, sr2))) => {
+            Instruction::xor_reg(r(dr), r(sr1), r(sr2))
+        }
+        Op::Fixed(Instruction::XorInstruction(XorInstruction::XorImm(dr, sr, imm))) => {
+            Instruction::XorInstruction(XorInstruction::XorImm(r(dr), r(sr), imm))
+        }
+        Op::Fixed(Instruction::Ldb(dr, base, offset)) => Instruction::Ldb(r(dr), r(base), offset),
+        Op::Fixed(Instruction::Ldi(dr, base, offset)) => Instruction::Ldi(r(dr), r(base), offset),
+        Op::Fixed(Instruction::Ldr(dr, base, offset)) => Instruction::Ldr(r(dr), r(base), offset),
+        Op::Fixed(Instruction::Shf(dr, sr, right, arithmetic, amount)) => {
+            Instruction::Shf(r(dr), r(sr), right, arithmetic, amount)
+        }
+        Op::Fixed(Instruction::Stb(sr, base, offset)) => Instruction::Stb(r(sr), r(base), offset),
+        Op::Fixed(Instruction::Sti(sr, base, offset)) => Instruction::Sti(r(sr), r(base), offset),
+        Op::Fixed(Instruction::Stw(sr, base, offset)) => Instruction::Stw(r(sr), r(base), offset),
+        Op::Lea(ref mut reg, _) => {
+            *reg = r(*reg);
+            return;
+        }
+        _ => return,
+    };
+    *op = Op::Fixed(renamed);
+}
+
+/// Whether a branch may test the condition codes `lines[i]` leaves
+/// before they are set again
+fn condition_needed(lines: &[Line], i: usize) -> bool {
+    for line in &lines[i + 1..] {
+        match line {
+            Line::Instruction(op) => {
+                if destination(op).is_some() {
+                    return false;
+                }
+                // Stores leave the condition codes alone; anything else
+                // that isn't a plain instruction branches, and the code
+                // it goes to might test them
+                if registers_read(op).is_none() {
+                    return true;
+                }
+            }
+            // Code that falls through to a label runs on past it
+            Line::Label(_) | Line::Blank | Line::Comment(_) | Line::Marker(_) => {}
+            _ => return true,
+        }
+    }
+    true
+}
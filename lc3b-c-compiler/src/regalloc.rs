@@ -0,0 +1,468 @@
+//! Register allocation for locals
+//!
+//! A function body becomes a control flow graph with a node per expression
+//! evaluated (and per scalar declaration), liveness is computed over it,
+//! and the scalar locals and parameters are coloured with R1 to R4 by how
+//! their live ranges interfere, the most used (weighted by loop nesting)
+//! first. Those that don't fit, and any whose address is taken, stay in
+//! the stack frame.
+//!
+//! Expressions are evaluated in R0 with R7 as the only other scratch
+//! register, so a local's register changes only where the local is
+//! assigned. Calls are the exception: the caller saves the registers of
+//! the locals live across each call, and R1 around runtime routines,
+//! which take an operand in it.
+
+use lc3b_c_ast::{
+    AssignOp, Block, BlockItem, Declaration, Expression, ForInit, Function, Initializer,
+    Statement, Type, UnaryOp,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Registers locals may be given, the one runtime routines use last
+const REGISTERS: [u8; 4] = [4, 3, 2, 1];
+
+/// Loop nesting past which a use counts no more
+const MAX_DEPTH: u32 = 5;
+
+/// Where a function's scalar locals and parameters live
+#[derive(Debug, Default)]
+pub(crate) struct Allocation {
+    registers: HashMap<String, u8>,
+    /// Registers live across each call, by the call expression's address
+    saves: HashMap<*const Expression, Vec<u8>>,
+    /// Whether anything is left in the stack frame
+    needs_frame: bool,
+}
+
+impl Allocation {
+    /// The register `name` lives in, if it was given one
+    pub fn register(&self, name: &str) -> Option<u8> {
+        self.registers.get(name).copied()
+    }
+
+    /// Whether some local lives in `reg`
+    pub fn uses(&self, reg: u8) -> bool {
+        self.registers.values().any(|&r| r == reg)
+    }
+
+    /// The registers to save around `call`, which must be an expression of
+    /// the function this allocation is for
+    pub fn saved_across(&self, call: &Expression) -> &[u8] {
+        self.saves.get(&(call as *const Expression)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether any local or parameter lives below R5
+    pub fn needs_frame(&self) -> bool {
+        self.needs_frame
+    }
+
+    /// Locals and parameters given registers, in name order
+    pub fn assignments(&self) -> Vec<(&str, u8)> {
+        let mut assignments: Vec<_> =
+            self.registers.iter().map(|(name, &reg)| (name.as_str(), reg)).collect();
+        assignments.sort();
+        assignments
+    }
+}
+
+/// Allocate registers for `func`. `clobbers` says whether calling a
+/// function (rather than inlining a trap) loses the caller's registers.
+pub(crate) fn allocate(func: &Function, clobbers: impl Fn(&str) -> bool) -> Allocation {
+    let mut scan = Scan::default();
+    for param in &func.parameters {
+        scan.declare(&param.name, &param.ty, false);
+    }
+    scan.block(&func.body);
+    let candidates: HashSet<&str> =
+        scan.scalars.difference(&scan.in_memory).copied().collect();
+
+    let mut cfg = Cfg {
+        nodes: Vec::new(),
+        loops: Vec::new(),
+        depth: 0,
+        candidates: &candidates,
+        clobbers: &clobbers,
+        weights: HashMap::new(),
+    };
+    let exit = cfg.node(Vec::new());
+    let body = cfg.block(&func.body, exit, exit);
+    let entry = cfg.node(vec![body]);
+    for param in &func.parameters {
+        if candidates.contains(param.name.as_str()) {
+            cfg.nodes[entry].kills.insert(&param.name);
+            cfg.nodes[entry].writes.insert(&param.name);
+        }
+    }
+
+    // Variables interfere if both are live at once, or one is assigned
+    // while the other is live or still to be read. Parameters all arrive
+    // at once, so they interfere whether or not they are used.
+    let live_in = cfg.liveness();
+    let mut interference = Interference::default();
+    for (n, node) in cfg.nodes.iter().enumerate() {
+        interference.all(&live_in[n], &live_in[n]);
+        let live_out = node.succ.iter().flat_map(|&s| &live_in[s]);
+        let busy: BTreeSet<&str> = live_out.chain(&node.uses).copied().collect();
+        interference.all(&node.writes, &busy);
+    }
+    let params = &cfg.nodes[entry].writes;
+    interference.all(params, params);
+
+    // Colour the busiest first; ties go by name so output is stable
+    let mut order: Vec<&str> = candidates.iter().copied().collect();
+    order.sort_by_key(|name| (std::cmp::Reverse(cfg.weights.get(name).copied()), *name));
+    let mut allocation = Allocation {
+        needs_frame: scan.scalars.len() > candidates.len() || scan.objects,
+        ..Allocation::default()
+    };
+    for name in order {
+        let neighbours = interference.0.get(name);
+        let free = REGISTERS.iter().copied().find(|&reg| {
+            neighbours.is_none_or(|neighbours| {
+                neighbours.iter().all(|n| allocation.register(n) != Some(reg))
+            })
+        });
+        match free {
+            Some(reg) => {
+                allocation.registers.insert(name.to_string(), reg);
+            }
+            None => allocation.needs_frame = true,
+        }
+    }
+
+    for (n, node) in cfg.nodes.iter().enumerate() {
+        let mut live: Vec<u8> =
+            live_in[n].iter().filter_map(|name| allocation.register(name)).collect();
+        live.sort_unstable();
+        for &call in &node.calls {
+            allocation.saves.insert(call, live.clone());
+        }
+    }
+    allocation
+}
+
+/// Which variables interfere with which
+#[derive(Default)]
+struct Interference<'a>(HashMap<&'a str, BTreeSet<&'a str>>);
+
+impl<'a> Interference<'a> {
+    /// Make each of `these` interfere with each of `those`
+    fn all(&mut self, these: &BTreeSet<&'a str>, those: &BTreeSet<&'a str>) {
+        for &a in these {
+            for &b in those {
+                if a != b {
+                    self.0.entry(a).or_default().insert(b);
+                    self.0.entry(b).or_default().insert(a);
+                }
+            }
+        }
+    }
+}
+
+/// What a function declares, and which of its variables must be in memory
+#[derive(Default)]
+struct Scan<'a> {
+    scalars: HashSet<&'a str>,
+    /// Names whose address is taken, or that also name an array or struct
+    in_memory: HashSet<&'a str>,
+    /// Whether there are local arrays or structs
+    objects: bool,
+}
+
+impl<'a> Scan<'a> {
+    fn declare(&mut self, name: &'a str, ty: &Type, array: bool) {
+        if array || matches!(ty, Type::Struct(_)) {
+            self.in_memory.insert(name);
+            self.objects = true;
+        } else {
+            self.scalars.insert(name);
+        }
+    }
+
+    fn block(&mut self, block: &'a Block) {
+        for item in &block.items {
+            match item {
+                BlockItem::Declaration(decl) => self.declaration(decl),
+                BlockItem::Statement(stmt) => self.statement(stmt),
+            }
+        }
+    }
+
+    fn declaration(&mut self, decl: &'a Declaration) {
+        for declarator in &decl.declarators {
+            self.declare(&declarator.name, &decl.ty, declarator.array_len.is_some());
+            match &declarator.initializer {
+                Some(Initializer::Expression(expr)) => self.expression(expr),
+                Some(Initializer::List(values)) => values.iter().for_each(|v| self.expression(v)),
+                _ => {}
+            }
+        }
+    }
+
+    fn statement(&mut self, stmt: &'a Statement) {
+        match stmt {
+            Statement::Compound(block) => self.block(block),
+            Statement::Expression(expr) | Statement::Return(Some(expr)) => self.expression(expr),
+            Statement::If { condition, then_branch, else_branch } => {
+                self.expression(condition);
+                self.statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While { condition, body } | Statement::DoWhile { body, condition } => {
+                self.expression(condition);
+                self.statement(body);
+            }
+            Statement::For { init, condition, update, body } => {
+                match init {
+                    Some(ForInit::Declaration(decl)) => self.declaration(decl),
+                    Some(ForInit::Expression(expr)) => self.expression(expr),
+                    None => {}
+                }
+                condition.iter().chain(update).for_each(|e| self.expression(e));
+                self.statement(body);
+            }
+            Statement::Return(None) | Statement::Break | Statement::Continue
+            | Statement::Empty => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &'a Expression) {
+        if let Expression::Unary { op: UnaryOp::AddressOf, operand } = expr {
+            if let Expression::Identifier(name) = operand.as_ref() {
+                self.in_memory.insert(name);
+            }
+        }
+        for child in children(expr) {
+            self.expression(child);
+        }
+    }
+}
+
+/// The subexpressions `expr` evaluates
+fn children(expr: &Expression) -> Vec<&Expression> {
+    match expr {
+        Expression::Binary { left, right, .. } => vec![left, right],
+        Expression::Unary { operand, .. } => vec![operand],
+        Expression::Assignment { target, value, .. } => vec![target, value],
+        Expression::Conditional { condition, then_value, else_value } => {
+            vec![condition, then_value, else_value]
+        }
+        Expression::Call { arguments, .. } => arguments.iter().collect(),
+        Expression::Subscript { array, index } => vec![array, index],
+        Expression::Member { object, .. } => vec![object],
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Default)]
+struct Node<'a> {
+    /// Variables read
+    uses: BTreeSet<&'a str>,
+    /// Variables certainly assigned, so not live before unless read
+    kills: BTreeSet<&'a str>,
+    /// Variables that may be assigned
+    writes: BTreeSet<&'a str>,
+    /// Calls that lose the caller's registers
+    calls: Vec<*const Expression>,
+    succ: Vec<usize>,
+}
+
+/// A control flow graph under construction. Statements are added from the
+/// last, each given the node it continues to and returning its first.
+struct Cfg<'a, 'c, F> {
+    nodes: Vec<Node<'a>>,
+    /// Where `break` and `continue` go in the enclosing loops
+    loops: Vec<(usize, usize)>,
+    depth: u32,
+    candidates: &'c HashSet<&'a str>,
+    clobbers: &'c F,
+    /// How heavily each variable is used
+    weights: HashMap<&'a str, u32>,
+}
+
+impl<'a, F: Fn(&str) -> bool> Cfg<'a, '_, F> {
+    fn node(&mut self, succ: Vec<usize>) -> usize {
+        self.nodes.push(Node { succ, ..Node::default() });
+        self.nodes.len() - 1
+    }
+
+    /// A node evaluating `expr`, then going on to `succ`
+    fn expression_node(&mut self, expr: &'a Expression, succ: Vec<usize>) -> usize {
+        let n = self.node(succ);
+        if let Expression::Assignment { op: AssignOp::Assign, target, .. } = expr {
+            if let Expression::Identifier(name) = target.as_ref() {
+                if self.candidates.contains(name.as_str()) {
+                    self.nodes[n].kills.insert(name);
+                }
+            }
+        }
+        self.expression(n, expr);
+        n
+    }
+
+    fn mention(&mut self, n: usize, name: &'a str, read: bool, written: bool) {
+        if !self.candidates.contains(name) {
+            return;
+        }
+        if read {
+            self.nodes[n].uses.insert(name);
+        }
+        if written {
+            self.nodes[n].writes.insert(name);
+        }
+        *self.weights.entry(name).or_default() += 4u32.pow(self.depth.min(MAX_DEPTH));
+    }
+
+    fn expression(&mut self, n: usize, expr: &'a Expression) {
+        match expr {
+            Expression::Identifier(name) => self.mention(n, name, true, false),
+            Expression::PostIncrement(name)
+            | Expression::PostDecrement(name)
+            | Expression::PreIncrement(name)
+            | Expression::PreDecrement(name) => self.mention(n, name, true, true),
+            Expression::Assignment { op, target, value } => {
+                match target.as_ref() {
+                    Expression::Identifier(name) => {
+                        self.mention(n, name, *op != AssignOp::Assign, true)
+                    }
+                    target => self.expression(n, target),
+                }
+                self.expression(n, value);
+            }
+            Expression::Call { function, arguments } => {
+                for argument in arguments {
+                    self.expression(n, argument);
+                }
+                if (self.clobbers)(function) {
+                    self.nodes[n].calls.push(expr);
+                }
+            }
+            _ => {
+                for child in children(expr) {
+                    self.expression(n, child);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, block: &'a Block, next: usize, exit: usize) -> usize {
+        let mut next = next;
+        for item in block.items.iter().rev() {
+            next = match item {
+                BlockItem::Declaration(decl) => self.declaration(decl, next),
+                BlockItem::Statement(stmt) => self.statement(stmt, next, exit),
+            };
+        }
+        next
+    }
+
+    fn declaration(&mut self, decl: &'a Declaration, next: usize) -> usize {
+        let mut next = next;
+        for declarator in decl.declarators.iter().rev() {
+            let n = self.node(vec![next]);
+            match &declarator.initializer {
+                Some(Initializer::Expression(expr)) => self.expression(n, expr),
+                Some(Initializer::List(values)) => {
+                    values.iter().for_each(|value| self.expression(n, value))
+                }
+                _ => {}
+            }
+            // Register locals are zeroed when declared without a value
+            let name = declarator.name.as_str();
+            if self.candidates.contains(name) {
+                self.nodes[n].kills.insert(name);
+                self.mention(n, name, false, true);
+            }
+            next = n;
+        }
+        next
+    }
+
+    /// `exit` is where `return` goes
+    fn statement(&mut self, stmt: &'a Statement, next: usize, exit: usize) -> usize {
+        match stmt {
+            Statement::Compound(block) => self.block(block, next, exit),
+            Statement::Expression(expr) => self.expression_node(expr, vec![next]),
+            Statement::Return(Some(expr)) => self.expression_node(expr, vec![exit]),
+            Statement::Return(None) => exit,
+            Statement::Break => self.loops.last().map_or(next, |&(brk, _)| brk),
+            Statement::Continue => self.loops.last().map_or(next, |&(_, cont)| cont),
+            Statement::Empty => next,
+            Statement::If { condition, then_branch, else_branch } => {
+                let then_entry = self.statement(then_branch, next, exit);
+                let else_entry = match else_branch {
+                    Some(else_branch) => self.statement(else_branch, next, exit),
+                    None => next,
+                };
+                self.expression_node(condition, vec![then_entry, else_entry])
+            }
+            Statement::While { condition, body } => {
+                self.depth += 1;
+                let test = self.expression_node(condition, vec![next]);
+                let body = self.loop_body(body, next, test, exit);
+                self.nodes[test].succ.push(body);
+                self.depth -= 1;
+                test
+            }
+            Statement::DoWhile { body, condition } => {
+                self.depth += 1;
+                let test = self.expression_node(condition, vec![next]);
+                let body = self.loop_body(body, next, test, exit);
+                self.nodes[test].succ.push(body);
+                self.depth -= 1;
+                body
+            }
+            Statement::For { init, condition, update, body } => {
+                self.depth += 1;
+                let test = match condition {
+                    Some(condition) => self.expression_node(condition, vec![next]),
+                    None => self.node(Vec::new()),
+                };
+                let step = match update {
+                    Some(update) => self.expression_node(update, vec![test]),
+                    None => test,
+                };
+                let body = self.loop_body(body, next, step, exit);
+                self.nodes[test].succ.push(body);
+                self.depth -= 1;
+                match init {
+                    Some(ForInit::Declaration(decl)) => self.declaration(decl, test),
+                    Some(ForInit::Expression(expr)) => self.expression_node(expr, vec![test]),
+                    None => test,
+                }
+            }
+        }
+    }
+
+    fn loop_body(&mut self, body: &'a Statement, brk: usize, cont: usize, exit: usize) -> usize {
+        self.loops.push((brk, cont));
+        let entry = self.statement(body, cont, exit);
+        self.loops.pop();
+        entry
+    }
+
+    /// The variables live on entry to each node
+    fn liveness(&self) -> Vec<BTreeSet<&'a str>> {
+        let mut live_in = vec![BTreeSet::new(); self.nodes.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            // Nodes are numbered roughly last statement first, so this
+            // order settles in few passes
+            for (n, node) in self.nodes.iter().enumerate() {
+                let mut live: BTreeSet<&str> =
+                    node.succ.iter().flat_map(|&s| &live_in[s]).copied().collect();
+                live.retain(|name| !node.kills.contains(name));
+                live.extend(&node.uses);
+                if live != live_in[n] {
+                    live_in[n] = live;
+                    changed = true;
+                }
+            }
+        }
+        live_in
+    }
+}
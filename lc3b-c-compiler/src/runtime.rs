@@ -0,0 +1,144 @@
+//! Runtime routines for operations the LC-3b has no instruction for
+
+/// A subroutine the generated code calls with `JSR`. Each one used is
+/// emitted once, after the program's functions. Operands go in R0 and R1;
+/// every other register is preserved (R7 aside, which `JSR` overwrites).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Routine {
+    /// R0 = R0 * R1, the low 16 bits of the product
+    Multiply,
+    /// R0 = R0 / R1 and R1 = R0 % R1 with C's signed semantics: the
+    /// quotient truncates toward zero and the remainder takes the sign of
+    /// the dividend. Dividing by zero gives a quotient of -1 (1 if the
+    /// dividend is negative) and leaves the dividend as the remainder.
+    DivMod,
+}
+
+impl Routine {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Routine::Multiply => "rt_mul",
+            Routine::DivMod => "rt_divmod",
+        }
+    }
+
+    /// The routine's assembly: labels end in `:`, comments start with
+    /// `;`, and every other line is one instruction
+    pub(crate) fn source(self) -> &'static str {
+        match self {
+            Routine::Multiply => MULTIPLY,
+            Routine::DivMod => DIVMOD,
+        }
+    }
+}
+
+/// Shift-and-add over the bits of the multiplier
+const MULTIPLY: &str = "
+rt_mul:
+ADD R6, R6, #-1
+STW R1, R6, #0
+ADD R6, R6, #-1
+STW R2, R6, #0
+ADD R6, R6, #-1
+STW R3, R6, #0
+; R2 = product, R0 = multiplicand shifted left, R1 = multiplier shifted right
+AND R2, R2, #0
+rt_mul_loop:
+ADD R1, R1, #0
+BRz rt_mul_done
+AND R3, R1, #1
+BRz rt_mul_skip
+ADD R2, R2, R0
+rt_mul_skip:
+ADD R0, R0, R0
+RSHFL R1, R1, #1
+BR rt_mul_loop
+rt_mul_done:
+ADD R0, R2, #0
+LDW R3, R6, #0
+ADD R6, R6, #1
+LDW R2, R6, #0
+ADD R6, R6, #1
+LDW R1, R6, #0
+ADD R6, R6, #1
+RET
+";
+
+/// Restoring division of the magnitudes, signs fixed up after
+const DIVMOD: &str = "
+rt_divmod:
+ADD R6, R6, #-1
+STW R2, R6, #0
+ADD R6, R6, #-1
+STW R3, R6, #0
+ADD R6, R6, #-1
+STW R4, R6, #0
+ADD R6, R6, #-1
+STW R5, R6, #0
+; R5 bit 0: negate the quotient, bit 1: negate the remainder
+AND R5, R5, #0
+ADD R0, R0, #0
+BRzp rt_divmod_dividend
+NOT R0, R0
+ADD R0, R0, #1
+ADD R5, R5, #3
+rt_divmod_dividend:
+ADD R1, R1, #0
+BRzp rt_divmod_divisor
+NOT R1, R1
+ADD R1, R1, #1
+XOR R5, R5, #1
+rt_divmod_divisor:
+; Shift the dividend through R2 a bit at a time, 16 times; R0 collects
+; the quotient bits as the dividend's shift out
+AND R2, R2, #0
+AND R3, R3, #0
+ADD R3, R3, #15
+ADD R3, R3, #1
+rt_divmod_loop:
+ADD R2, R2, R2
+ADD R0, R0, #0
+BRzp rt_divmod_shift
+ADD R2, R2, #1
+rt_divmod_shift:
+ADD R0, R0, R0
+; Subtract the divisor if R2 >= R1, unsigned. R1 is at most x8000 and
+; R2 below twice R1, so a set top bit in R2 means it is, and one in R1
+; (which is then x8000, with R2 below it) that it is not.
+ADD R2, R2, #0
+BRn rt_divmod_take
+ADD R1, R1, #0
+BRn rt_divmod_next
+NOT R4, R1
+ADD R4, R4, #1
+ADD R4, R2, R4
+BRn rt_divmod_next
+rt_divmod_take:
+NOT R4, R1
+ADD R4, R4, #1
+ADD R2, R2, R4
+ADD R0, R0, #1
+rt_divmod_next:
+ADD R3, R3, #-1
+BRp rt_divmod_loop
+AND R4, R5, #1
+BRz rt_divmod_remainder
+NOT R0, R0
+ADD R0, R0, #1
+rt_divmod_remainder:
+AND R4, R5, #2
+BRz rt_divmod_done
+NOT R2, R2
+ADD R2, R2, #1
+rt_divmod_done:
+ADD R1, R2, #0
+LDW R5, R6, #0
+ADD R6, R6, #1
+LDW R4, R6, #0
+ADD R6, R6, #1
+LDW R3, R6, #0
+ADD R6, R6, #1
+LDW R2, R6, #0
+ADD R6, R6, #1
+RET
+";
@@ -1,5 +1,8 @@
 #![forbid(unsafe_code)]
 
+use std::ops::Range;
+
+use pest::error::{InputLocation, LineColLocation};
 use pest::{iterators::Pairs, Parser};
 
 #[derive(pest_derive::Parser)]
@@ -13,6 +16,30 @@ pub fn parse(source: &str) -> Result<Pairs<'_, Rule>, Box<Error>> {
     CParser::parse(Rule::program, source).map_err(Box::new)
 }
 
+/// Where in the source a syntax error is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// 1-based line of the start of `span`
+    pub line: usize,
+    /// 1-based column, in characters, of the start of `span`
+    pub column: usize,
+    /// Byte offsets into the source
+    pub span: Range<usize>,
+}
+
+impl From<&Error> for SourceLocation {
+    fn from(error: &Error) -> Self {
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+        };
+        let span = match error.location {
+            InputLocation::Pos(pos) => pos..pos,
+            InputLocation::Span((start, end)) => start..end,
+        };
+        SourceLocation { line, column, span }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
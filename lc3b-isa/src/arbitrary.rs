@@ -0,0 +1,111 @@
+//! `Arbitrary` implementations that only ever produce in-range operands.
+//!
+//! `Instruction` is generated by decoding an arbitrary word, so every
+//! generated value is canonical and survives an encode/decode round trip.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{
+    AddInstruction, AndInstruction, Bit, Condition, Immediate4, Immediate5, Instruction,
+    PCOffset11, PCOffset6, PCOffset9, Register, TrapVect8, XorInstruction,
+};
+
+impl<'a> Arbitrary<'a> for Register {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Register::from_index(u.int_in_range(0..=7)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Immediate5 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Immediate5(u.int_in_range(0..=0x1F)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Immediate4 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Immediate4(u.int_in_range(0..=0xF)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for PCOffset6 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(PCOffset6::from_raw(u.int_in_range(0..=0x3F)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for PCOffset9 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(PCOffset9(u.int_in_range(0..=0x1FF)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for PCOffset11 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(PCOffset11(u.int_in_range(0..=0x7FF)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for TrapVect8 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(TrapVect8(u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Bit {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Bit::new(u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Condition {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Condition {
+            n: u.arbitrary()?,
+            z: u.arbitrary()?,
+            p: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for AddInstruction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            AddInstruction::AddReg(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?)
+        } else {
+            AddInstruction::AddImm(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?)
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for AndInstruction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            AndInstruction::AndReg(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?)
+        } else {
+            AndInstruction::AndImm(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?)
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for XorInstruction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            XorInstruction::XorReg(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?)
+        } else {
+            XorInstruction::XorImm(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?)
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Instruction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let word: u16 = u.arbitrary()?;
+        // Every 16-bit word decodes (see tests/round_trip_test.rs)
+        Ok(Instruction::try_from(word).expect("all words decode"))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        <u16 as Arbitrary>::size_hint(depth)
+    }
+}
@@ -0,0 +1,127 @@
+//! Range-checked constructors for every instruction form.
+//!
+//! Signed operands are given as the value that ends up in the encoded field
+//! (before any hardware left-shift), so `ldr(.., 1)` addresses BaseR + 2.
+
+use crate::{
+    AddInstruction, AndInstruction, Bit, Condition, Immediate4, Immediate5, Instruction,
+    OperandError, PCOffset11, PCOffset6, PCOffset9, Register, TrapVect8, XorInstruction,
+};
+
+fn check_range(kind: &'static str, value: i16, min: i16, max: i16) -> Result<(), OperandError> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(OperandError::OutOfRange {
+            kind,
+            value: value as i32,
+            min: min as i32,
+            max: max as i32,
+        })
+    }
+}
+
+fn pc_offset9(value: i16) -> Result<PCOffset9, OperandError> {
+    check_range("PCOffset9", value, -256, 255)?;
+    Ok(PCOffset9::new(value))
+}
+
+impl Instruction {
+    pub fn add_reg(dr: Register, sr1: Register, sr2: Register) -> Self {
+        Instruction::AddInstruction(AddInstruction::AddReg(dr, sr1, sr2))
+    }
+
+    pub fn add_imm(dr: Register, sr1: Register, imm5: i8) -> Result<Self, OperandError> {
+        let imm5 = Immediate5::from_signed(imm5)?;
+        Ok(Instruction::AddInstruction(AddInstruction::AddImm(dr, sr1, imm5)))
+    }
+
+    pub fn and_reg(dr: Register, sr1: Register, sr2: Register) -> Self {
+        Instruction::AndInstruction(AndInstruction::AndReg(dr, sr1, sr2))
+    }
+
+    pub fn and_imm(dr: Register, sr1: Register, imm5: i8) -> Result<Self, OperandError> {
+        let imm5 = Immediate5::from_signed(imm5)?;
+        Ok(Instruction::AndInstruction(AndInstruction::AndImm(dr, sr1, imm5)))
+    }
+
+    pub fn xor_reg(dr: Register, sr1: Register, sr2: Register) -> Self {
+        Instruction::XorInstruction(XorInstruction::XorReg(dr, sr1, sr2))
+    }
+
+    pub fn xor_imm(dr: Register, sr1: Register, imm5: i8) -> Result<Self, OperandError> {
+        let imm5 = Immediate5::from_signed(imm5)?;
+        Ok(Instruction::XorInstruction(XorInstruction::XorImm(dr, sr1, imm5)))
+    }
+
+    /// BR with the given condition flags. All three false encodes a no-op.
+    pub fn br(n: bool, z: bool, p: bool, offset9: i16) -> Result<Self, OperandError> {
+        Ok(Instruction::Br(Condition { n, z, p }, pc_offset9(offset9)?))
+    }
+
+    pub fn jmp(base: Register) -> Self {
+        Instruction::Jmp(base)
+    }
+
+    pub fn ret() -> Self {
+        Instruction::Ret
+    }
+
+    pub fn jsr(offset11: i16) -> Result<Self, OperandError> {
+        check_range("PCOffset11", offset11, -1024, 1023)?;
+        Ok(Instruction::Jsr(PCOffset11::new(offset11)))
+    }
+
+    pub fn jsrr(base: Register) -> Self {
+        Instruction::Jsrr(base)
+    }
+
+    pub fn ldb(dr: Register, base: Register, offset6: i8) -> Result<Self, OperandError> {
+        Ok(Instruction::Ldb(dr, base, PCOffset6::new(offset6)?))
+    }
+
+    pub fn ldi(dr: Register, base: Register, offset6: i8) -> Result<Self, OperandError> {
+        Ok(Instruction::Ldi(dr, base, PCOffset6::new(offset6)?))
+    }
+
+    /// LDW; the word load is the `Ldr` variant
+    pub fn ldr(dr: Register, base: Register, offset6: i8) -> Result<Self, OperandError> {
+        Ok(Instruction::Ldr(dr, base, PCOffset6::new(offset6)?))
+    }
+
+    pub fn lea(dr: Register, offset9: i16) -> Result<Self, OperandError> {
+        Ok(Instruction::Lea(dr, pc_offset9(offset9)?))
+    }
+
+    pub fn stb(sr: Register, base: Register, offset6: i8) -> Result<Self, OperandError> {
+        Ok(Instruction::Stb(sr, base, PCOffset6::new(offset6)?))
+    }
+
+    pub fn sti(sr: Register, base: Register, offset6: i8) -> Result<Self, OperandError> {
+        Ok(Instruction::Sti(sr, base, PCOffset6::new(offset6)?))
+    }
+
+    pub fn stw(sr: Register, base: Register, offset6: i8) -> Result<Self, OperandError> {
+        Ok(Instruction::Stw(sr, base, PCOffset6::new(offset6)?))
+    }
+
+    pub fn lshf(dr: Register, sr: Register, amount: u8) -> Result<Self, OperandError> {
+        Ok(Instruction::Shf(dr, sr, Bit::new(false), Bit::new(false), Immediate4::new(amount)?))
+    }
+
+    pub fn rshfl(dr: Register, sr: Register, amount: u8) -> Result<Self, OperandError> {
+        Ok(Instruction::Shf(dr, sr, Bit::new(true), Bit::new(false), Immediate4::new(amount)?))
+    }
+
+    pub fn rshfa(dr: Register, sr: Register, amount: u8) -> Result<Self, OperandError> {
+        Ok(Instruction::Shf(dr, sr, Bit::new(true), Bit::new(true), Immediate4::new(amount)?))
+    }
+
+    pub fn trap(vector: u8) -> Self {
+        Instruction::Trap(TrapVect8::new(vector))
+    }
+
+    pub fn rti() -> Self {
+        Instruction::Rti
+    }
+}
@@ -0,0 +1,77 @@
+use alloc::vec::Vec;
+
+use crate::{DecodeError, Instruction};
+
+/// Byte order used when packing 16-bit words into bytes
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Endianness {
+    /// Most significant byte first (LC-3 object file convention)
+    #[default]
+    Big,
+    /// Least significant byte first
+    Little,
+}
+
+impl Endianness {
+    fn word_to_bytes(self, word: u16) -> [u8; 2] {
+        match self {
+            Endianness::Big => word.to_be_bytes(),
+            Endianness::Little => word.to_le_bytes(),
+        }
+    }
+
+    fn word_from_bytes(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Endianness::Big => u16::from_be_bytes(bytes),
+            Endianness::Little => u16::from_le_bytes(bytes),
+        }
+    }
+}
+
+impl Instruction {
+    /// Encode as two bytes, most significant first
+    pub fn to_be_bytes(&self) -> [u8; 2] {
+        u16::from(self).to_be_bytes()
+    }
+
+    /// Encode as two bytes, least significant first
+    pub fn to_le_bytes(&self) -> [u8; 2] {
+        u16::from(self).to_le_bytes()
+    }
+
+    /// Decode from two bytes in the given byte order
+    pub fn from_bytes(bytes: [u8; 2], endianness: Endianness) -> Result<Self, DecodeError> {
+        Instruction::try_from(endianness.word_from_bytes(bytes))
+    }
+
+    /// Append this instruction's encoding to `out`
+    pub fn encode_into(&self, out: &mut Vec<u8>, endianness: Endianness) {
+        out.extend_from_slice(&endianness.word_to_bytes(self.into()));
+    }
+}
+
+/// Big-endian, matching `From<&Instruction> for [u8; 2]`
+impl TryFrom<[u8; 2]> for Instruction {
+    type Error = DecodeError;
+
+    fn try_from(bytes: [u8; 2]) -> Result<Self, Self::Error> {
+        Instruction::from_bytes(bytes, Endianness::Big)
+    }
+}
+
+/// Append raw words (instructions or data) to `out` in the given byte order
+pub fn encode_words_into(words: &[u16], out: &mut Vec<u8>, endianness: Endianness) {
+    out.reserve(words.len() * 2);
+    for &word in words {
+        out.extend_from_slice(&endianness.word_to_bytes(word));
+    }
+}
+
+/// Unpack bytes into words. A trailing odd byte is ignored.
+pub fn words_from_bytes(bytes: &[u8], endianness: Endianness) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|pair| endianness.word_from_bytes([pair[0], pair[1]]))
+        .collect()
+}
@@ -0,0 +1,101 @@
+//! Assembly text for instructions.
+
+use core::fmt;
+
+use crate::{AddInstruction, AndInstruction, Immediate5, Instruction, XorInstruction};
+
+/// Trap vectors with their own mnemonic
+fn trap_alias(vector: u8) -> Option<&'static str> {
+    match vector {
+        0x20 => Some("GETC"),
+        0x21 => Some("OUT"),
+        0x22 => Some("PUTS"),
+        0x23 => Some("IN"),
+        0x24 => Some("PUTSP"),
+        0x25 => Some("HALT"),
+        _ => None,
+    }
+}
+
+fn imm5(imm: &Immediate5) -> i8 {
+    ((imm.value() << 3) as i8) >> 3
+}
+
+/// Disassembly in the syntax `FromStr` parses back, with numeric offsets
+/// as the instruction stores them (LEA's doubled to the byte offset the
+/// assembler takes), e.g. `ADD R1, R2, #-3`, `BRnz #-4`, `LEA R0, #6`,
+/// `HALT`. Two encodings have no such spelling: a BR with no condition
+/// flags prints as `NOP`, and a left shift with the arithmetic bit set
+/// prints as a plain `LSHF`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::AddInstruction(AddInstruction::AddReg(dr, sr1, sr2)) => {
+                write!(f, "ADD {}, {}, {}", dr, sr1, sr2)
+            }
+            Instruction::AddInstruction(AddInstruction::AddImm(dr, sr1, imm)) => {
+                write!(f, "ADD {}, {}, #{}", dr, sr1, imm5(imm))
+            }
+            Instruction::AndInstruction(AndInstruction::AndReg(dr, sr1, sr2)) => {
+                write!(f, "AND {}, {}, {}", dr, sr1, sr2)
+            }
+            Instruction::AndInstruction(AndInstruction::AndImm(dr, sr1, imm)) => {
+                write!(f, "AND {}, {}, #{}", dr, sr1, imm5(imm))
+            }
+            Instruction::XorInstruction(XorInstruction::XorReg(dr, sr1, sr2)) => {
+                write!(f, "XOR {}, {}, {}", dr, sr1, sr2)
+            }
+            Instruction::XorInstruction(XorInstruction::XorImm(dr, sr, _)) if self.is_not() => {
+                write!(f, "NOT {}, {}", dr, sr)
+            }
+            Instruction::XorInstruction(XorInstruction::XorImm(dr, sr1, imm)) => {
+                write!(f, "XOR {}, {}, #{}", dr, sr1, imm5(imm))
+            }
+            Instruction::Br(condition, offset) => {
+                if condition.suffix().is_empty() {
+                    write!(f, "NOP")
+                } else {
+                    write!(f, "BR{} #{}", condition.suffix(), offset.sign_extend())
+                }
+            }
+            Instruction::Jmp(base) => write!(f, "JMP {}", base),
+            Instruction::Jsr(offset) => write!(f, "JSR #{}", offset.sign_extend()),
+            Instruction::Jsrr(base) => write!(f, "JSRR {}", base),
+            Instruction::Ldb(dr, base, offset) => {
+                write!(f, "LDB {}, {}, #{}", dr, base, offset.sign_extend())
+            }
+            Instruction::Ldi(dr, base, offset) => {
+                write!(f, "LDI {}, {}, #{}", dr, base, offset.sign_extend())
+            }
+            Instruction::Ldr(dr, base, offset) => {
+                write!(f, "LDW {}, {}, #{}", dr, base, offset.sign_extend())
+            }
+            Instruction::Lea(dr, offset) => {
+                write!(f, "LEA {}, #{}", dr, i32::from(offset.sign_extend()) * 2)
+            }
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::Rti => write!(f, "RTI"),
+            Instruction::Shf(dr, sr, right, arithmetic, amount) => {
+                let mnemonic = match (right.value(), arithmetic.value()) {
+                    (false, _) => "LSHF",
+                    (true, false) => "RSHFL",
+                    (true, true) => "RSHFA",
+                };
+                write!(f, "{} {}, {}, #{}", mnemonic, dr, sr, amount.0)
+            }
+            Instruction::Stb(sr, base, offset) => {
+                write!(f, "STB {}, {}, #{}", sr, base, offset.sign_extend())
+            }
+            Instruction::Sti(sr, base, offset) => {
+                write!(f, "STI {}, {}, #{}", sr, base, offset.sign_extend())
+            }
+            Instruction::Stw(sr, base, offset) => {
+                write!(f, "STW {}, {}, #{}", sr, base, offset.sign_extend())
+            }
+            Instruction::Trap(vector) => match trap_alias(vector.value()) {
+                Some(alias) => write!(f, "{}", alias),
+                None => write!(f, "TRAP x{:02X}", vector.value()),
+            },
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Error constructing a register or operand from a value or string
+#[derive(Debug, Clone, PartialEq)]
+// Serialize only: `kind` names the operand type with a &'static str
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum OperandError {
+    /// Numeric value does not fit the operand's bit width
+    OutOfRange {
+        kind: &'static str,
+        value: i32,
+        min: i32,
+        max: i32,
+    },
+    /// Text could not be parsed as a number
+    InvalidLiteral(String),
+    /// Text is not a register name
+    UnknownRegister(String),
+}
+
+impl fmt::Display for OperandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperandError::OutOfRange { kind, value, min, max } => {
+                write!(f, "{} value {} out of range ({} to {})", kind, value, min, max)
+            }
+            OperandError::InvalidLiteral(s) => write!(f, "invalid numeric literal: {}", s),
+            OperandError::UnknownRegister(s) => write!(f, "unhandled register identifier: {}", s),
+        }
+    }
+}
+
+impl core::error::Error for OperandError {}
+
+/// Error parsing a single assembly statement into an `Instruction`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ParseInstructionError {
+    /// Nothing but whitespace or a comment
+    Empty,
+    /// Mnemonic is not an LC-3b instruction or trap alias
+    UnknownMnemonic(String),
+    /// Wrong number of operands for the mnemonic
+    OperandCount {
+        mnemonic: String,
+        expected: usize,
+        found: usize,
+    },
+    /// LEA takes a byte offset, which must be even
+    UnalignedOffset(i32),
+    /// An operand was malformed or out of range
+    Operand(OperandError),
+}
+
+impl From<OperandError> for ParseInstructionError {
+    fn from(err: OperandError) -> Self {
+        ParseInstructionError::Operand(err)
+    }
+}
+
+impl fmt::Display for ParseInstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseInstructionError::Empty => write!(f, "empty statement"),
+            ParseInstructionError::UnknownMnemonic(s) => write!(f, "unknown mnemonic: {}", s),
+            ParseInstructionError::OperandCount { mnemonic, expected, found } => write!(
+                f,
+                "{} takes {} operand(s), found {}",
+                mnemonic, expected, found
+            ),
+            ParseInstructionError::UnalignedOffset(offset) => {
+                write!(f, "LEA target must be word-aligned (offset {} is not even)", offset)
+            }
+            ParseInstructionError::Operand(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl core::error::Error for ParseInstructionError {}
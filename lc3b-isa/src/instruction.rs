@@ -1,25 +1,31 @@
 #![allow(dead_code)]
 
-use std::str::FromStr;
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::str::FromStr;
 
-use crate::Register;
+use crate::{OperandError, Register};
 
 /// Decode error for invalid instructions
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DecodeError {
     pub word: u16,
     pub reason: String,
 }
 
-impl std::fmt::Display for DecodeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "Failed to decode 0x{:04X}: {}", self.word, self.reason)
     }
 }
 
-impl std::error::Error for DecodeError {}
+impl core::error::Error for DecodeError {}
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Instruction {
     AddInstruction(AddInstruction),
     AndInstruction(AndInstruction),
@@ -262,21 +268,21 @@ impl TryFrom<u16> for Instruction {
                 // LDB
                 let dr = Register::from_index(((word >> 9) & 0x7) as u8);
                 let base = Register::from_index(((word >> 6) & 0x7) as u8);
-                let offset = PCOffset6((word & 0x3F) as u8);
+                let offset = PCOffset6::from_raw((word & 0x3F) as u8);
                 Ok(Instruction::Ldb(dr, base, offset))
             }
             0b1010 => {
                 // LDI
                 let dr = Register::from_index(((word >> 9) & 0x7) as u8);
                 let base = Register::from_index(((word >> 6) & 0x7) as u8);
-                let offset = PCOffset6((word & 0x3F) as u8);
+                let offset = PCOffset6::from_raw((word & 0x3F) as u8);
                 Ok(Instruction::Ldi(dr, base, offset))
             }
             0b0110 => {
                 // LDR
                 let dr = Register::from_index(((word >> 9) & 0x7) as u8);
                 let base = Register::from_index(((word >> 6) & 0x7) as u8);
-                let offset = PCOffset6((word & 0x3F) as u8);
+                let offset = PCOffset6::from_raw((word & 0x3F) as u8);
                 Ok(Instruction::Ldr(dr, base, offset))
             }
             0b1110 => {
@@ -316,21 +322,21 @@ impl TryFrom<u16> for Instruction {
                 // STB
                 let sr = Register::from_index(((word >> 9) & 0x7) as u8);
                 let base = Register::from_index(((word >> 6) & 0x7) as u8);
-                let offset = PCOffset6((word & 0x3F) as u8);
+                let offset = PCOffset6::from_raw((word & 0x3F) as u8);
                 Ok(Instruction::Stb(sr, base, offset))
             }
             0b1011 => {
                 // STI
                 let sr = Register::from_index(((word >> 9) & 0x7) as u8);
                 let base = Register::from_index(((word >> 6) & 0x7) as u8);
-                let offset = PCOffset6((word & 0x3F) as u8);
+                let offset = PCOffset6::from_raw((word & 0x3F) as u8);
                 Ok(Instruction::Sti(sr, base, offset))
             }
             0b0111 => {
                 // STW
                 let sr = Register::from_index(((word >> 9) & 0x7) as u8);
                 let base = Register::from_index(((word >> 6) & 0x7) as u8);
-                let offset = PCOffset6((word & 0x3F) as u8);
+                let offset = PCOffset6::from_raw((word & 0x3F) as u8);
                 Ok(Instruction::Stw(sr, base, offset))
             }
             0b1111 => {
@@ -346,43 +352,131 @@ impl TryFrom<u16> for Instruction {
     }
 }
 
+/// Decode a run of words loaded at `origin`, yielding each word's address
+/// alongside its decoded instruction. Addresses wrap at 0xFFFF.
+pub fn decode_words(
+    origin: u16,
+    words: &[u16],
+) -> impl Iterator<Item = (u16, Result<Instruction, DecodeError>)> + '_ {
+    words.iter().enumerate().map(move |(i, &word)| {
+        let address = origin.wrapping_add(i as u16);
+        (address, Instruction::try_from(word))
+    })
+}
+
+impl Instruction {
+    /// NOT DR, SR — canonically XOR DR, SR, #-1, which is also what decoding
+    /// opcode 1001 with an all-ones imm5 produces
+    pub fn not(dr: Register, sr: Register) -> Self {
+        Instruction::XorInstruction(XorInstruction::XorImm(dr, sr, Immediate5(0x1F)))
+    }
+
+    /// True if this is the XOR form that implements NOT
+    pub fn is_not(&self) -> bool {
+        matches!(
+            self,
+            Instruction::XorInstruction(XorInstruction::XorImm(_, _, Immediate5(0x1F)))
+        )
+    }
+
+    /// Decode a word, rejecting encodings whose reserved bits are not zero.
+    ///
+    /// `TryFrom<u16>` ignores unused fields so legacy binaries still load; this
+    /// is the checked path for tools that want to flag malformed words.
+    pub fn decode_strict(word: u16) -> Result<Self, DecodeError> {
+        check_reserved_bits(word)?;
+        Instruction::try_from(word)
+    }
+}
+
+/// Verify that every bit the ISA marks as reserved (must be zero) is clear
+fn check_reserved_bits(word: u16) -> Result<(), DecodeError> {
+    let opcode = (word >> 12) & 0xF;
+    let reserved = |mask: u16, field: &str| -> Result<(), DecodeError> {
+        if word & mask != 0 {
+            Err(DecodeError {
+                word,
+                reason: format!(
+                    "reserved bits {} must be zero (found {:#06x})",
+                    field,
+                    word & mask
+                ),
+            })
+        } else {
+            Ok(())
+        }
+    };
+
+    match opcode {
+        // ADD / AND / XOR register mode: bits [4:3] unused
+        0b0001 | 0b0101 | 0b1001 if (word >> 5) & 0x1 == 0 => reserved(0x0018, "[4:3]"),
+        // JMP / RET: bits [11:9] and [5:0] unused
+        0b1100 => {
+            reserved(0x0E00, "[11:9]")?;
+            reserved(0x003F, "[5:0]")
+        }
+        // JSRR: bits [10:9] and [5:0] unused
+        0b0100 if (word >> 11) & 0x1 == 0 => {
+            reserved(0x0600, "[10:9]")?;
+            reserved(0x003F, "[5:0]")
+        }
+        // RTI: bits [11:0] unused
+        0b1000 => reserved(0x0FFF, "[11:0]"),
+        // TRAP: bits [11:8] unused
+        0b1111 => reserved(0x0F00, "[11:8]"),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AddInstruction {
     AddReg(Register, Register, Register),
     AddImm(Register, Register, Immediate5),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AndInstruction {
     AndReg(Register, Register, Register),
     AndImm(Register, Register, Immediate5),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum XorInstruction {
     XorReg(Register, Register, Register),
     XorImm(Register, Register, Immediate5),
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8"))]
 pub struct Immediate5(pub(crate) u8);
 
 impl Immediate5 {
-    pub fn new(imm5: u8) -> eyre::Result<Self> {
+    pub fn new(imm5: u8) -> Result<Self, OperandError> {
         if imm5 >= 32 {
-            return Err(eyre::eyre!("value `{}` too large, must be < 32", imm5));
+            return Err(OperandError::OutOfRange {
+                kind: "Immediate5",
+                value: imm5 as i32,
+                min: 0,
+                max: 31,
+            });
         }
 
         Ok(Immediate5(imm5))
     }
 
     /// Create from a signed value (-16 to 15)
-    pub fn from_signed(value: i8) -> eyre::Result<Self> {
-        if value < -16 || value > 15 {
-            return Err(eyre::eyre!(
-                "Immediate5 value {} out of range (-16 to 15)",
-                value
-            ));
+    pub fn from_signed(value: i8) -> Result<Self, OperandError> {
+        if !(-16..=15).contains(&value) {
+            return Err(OperandError::OutOfRange {
+                kind: "Immediate5",
+                value: value as i32,
+                min: -16,
+                max: 15,
+            });
         }
         // Store as 5-bit value
         Ok(Immediate5((value as u8) & 0x1F))
@@ -394,12 +488,14 @@ impl Immediate5 {
 }
 
 impl FromStr for Immediate5 {
-    type Err = eyre::Report;
+    type Err = OperandError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // Strip optional # prefix
         let s = s.strip_prefix('#').unwrap_or(s);
-        let value: i8 = s.parse()?;
+        let value: i8 = s
+            .parse()
+            .map_err(|_| OperandError::InvalidLiteral(s.to_string()))?;
         Self::from_signed(value)
     }
 }
@@ -410,27 +506,92 @@ impl Immediate5 {
     }
 }
 
+/// From the raw 5-bit field, as `new` does
+impl TryFrom<u8> for Immediate5 {
+    type Error = OperandError;
+
+    fn try_from(raw: u8) -> Result<Self, Self::Error> {
+        Self::new(raw)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8"))]
 pub struct Immediate4(pub u8);
 
 impl Immediate4 {
-    pub fn new(val: u8) -> eyre::Result<Self> {
+    pub fn new(val: u8) -> Result<Self, OperandError> {
         if val >= 16 {
-            return Err(eyre::eyre!("value `{}` too large, must be < 16", val));
+            return Err(OperandError::OutOfRange {
+                kind: "Immediate4",
+                value: val as i32,
+                min: 0,
+                max: 15,
+            });
         }
 
         Ok(Immediate4(val))
     }
 }
 
-#[derive(Debug, Default, PartialEq, Clone, Copy)]
+/// From the raw 4-bit field, as `new` does
+impl TryFrom<u8> for Immediate4 {
+    type Error = OperandError;
+
+    fn try_from(raw: u8) -> Result<Self, Self::Error> {
+        Self::new(raw)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Condition {
     pub n: bool,
     pub z: bool,
     pub p: bool,
 }
 
-impl std::ops::BitAnd for Condition {
+impl Condition {
+    /// `BR` / `BRnzp`: branch regardless of the condition codes
+    pub const ALWAYS: Condition = Condition { n: true, z: true, p: true };
+
+    /// Parse the part of a BR mnemonic after `BR`, e.g. `"nz"`. Letters are
+    /// case-insensitive and may come in any order; an empty suffix is
+    /// `ALWAYS`, matching the bare `BR` mnemonic.
+    pub fn from_suffix(suffix: &str) -> Option<Condition> {
+        if suffix.is_empty() {
+            return Some(Condition::ALWAYS);
+        }
+        let mut condition = Condition::default();
+        for c in suffix.chars() {
+            match c.to_ascii_lowercase() {
+                'n' => condition.n = true,
+                'z' => condition.z = true,
+                'p' => condition.p = true,
+                _ => return None,
+            }
+        }
+        Some(condition)
+    }
+
+    /// Canonical lowercase suffix in `nzp` order. `ALWAYS` gives `"nzp"`, so
+    /// formatted branches are always spelled out; no flags gives `""`.
+    pub fn suffix(&self) -> &'static str {
+        match (self.n, self.z, self.p) {
+            (false, false, false) => "",
+            (false, false, true) => "p",
+            (false, true, false) => "z",
+            (false, true, true) => "zp",
+            (true, false, false) => "n",
+            (true, false, true) => "np",
+            (true, true, false) => "nz",
+            (true, true, true) => "nzp",
+        }
+    }
+}
+
+impl core::ops::BitAnd for Condition {
     type Output = bool;
 
     /// Returns true if any condition flag matches between self and rhs
@@ -440,6 +601,8 @@ impl std::ops::BitAnd for Condition {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u16"))]
 pub struct PCOffset9(pub u16);
 
 impl PCOffset9 {
@@ -460,21 +623,40 @@ impl PCOffset9 {
 }
 
 impl FromStr for PCOffset9 {
-    type Err = eyre::Report;
+    type Err = OperandError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // Strip optional # prefix
         let s = s.strip_prefix('#').unwrap_or(s);
-        let value: i16 = s.parse()?;
+        let value: i16 = s
+            .parse()
+            .map_err(|_| OperandError::InvalidLiteral(s.to_string()))?;
         // Check range: -256 to 255 (9-bit signed)
-        if value < -256 || value > 255 {
-            return Err(eyre::eyre!("PCOffset9 value {} out of range (-256 to 255)", value));
+        if !(-256..=255).contains(&value) {
+            return Err(OperandError::OutOfRange {
+                kind: "PCOffset9",
+                value: value as i32,
+                min: -256,
+                max: 255,
+            });
         }
         Ok(PCOffset9::new(value))
     }
 }
 
+/// From the raw 9-bit field as stored, rejecting any higher bits
+impl TryFrom<u16> for PCOffset9 {
+    type Error = OperandError;
+
+    fn try_from(raw: u16) -> Result<Self, Self::Error> {
+        check_raw_field("PCOffset9", raw, 0x1FF)?;
+        Ok(PCOffset9(raw))
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u16"))]
 pub struct PCOffset11(pub u16);
 
 impl PCOffset11 {
@@ -494,21 +676,41 @@ impl PCOffset11 {
     }
 }
 
+/// From the raw 11-bit field as stored, rejecting any higher bits
+impl TryFrom<u16> for PCOffset11 {
+    type Error = OperandError;
+
+    fn try_from(raw: u16) -> Result<Self, Self::Error> {
+        check_raw_field("PCOffset11", raw, 0x7FF)?;
+        Ok(PCOffset11(raw))
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8"))]
 pub struct PCOffset6(u8);
 
 impl PCOffset6 {
     /// Create a new PCOffset6 from a signed value (-32 to 31)
-    pub fn new(value: i8) -> eyre::Result<Self> {
-        if value < -32 || value > 31 {
-            return Err(eyre::eyre!(
-                "PCOffset6 value {} out of range (-32 to 31)",
-                value
-            ));
+    pub fn new(value: i8) -> Result<Self, OperandError> {
+        if !(-32..=31).contains(&value) {
+            return Err(OperandError::OutOfRange {
+                kind: "PCOffset6",
+                value: value as i32,
+                min: -32,
+                max: 31,
+            });
         }
         Ok(PCOffset6((value as u8) & 0x3F))
     }
 
+    /// Create from a raw 6-bit field as it appears in an encoded instruction.
+    /// Bits above bit 5 are discarded.
+    pub fn from_raw(raw: u8) -> Self {
+        PCOffset6(raw & 0x3F)
+    }
+
     /// Sign-extend the 6-bit offset to 16 bits
     pub fn sign_extend(&self) -> i16 {
         if self.0 & 0x20 != 0 {
@@ -519,12 +721,37 @@ impl PCOffset6 {
         }
     }
 
+    /// The raw 6-bit field value
     pub fn value(&self) -> u8 {
         self.0
     }
 }
 
+/// From the raw 6-bit field, rejecting any higher bits, unlike `from_raw`
+impl TryFrom<u8> for PCOffset6 {
+    type Error = OperandError;
+
+    fn try_from(raw: u8) -> Result<Self, Self::Error> {
+        check_raw_field("PCOffset6", raw.into(), 0x3F)?;
+        Ok(PCOffset6(raw))
+    }
+}
+
+/// Check a raw field value fits in the bits under `mask`
+fn check_raw_field(kind: &'static str, raw: u16, mask: u16) -> Result<(), OperandError> {
+    if raw & !mask != 0 {
+        return Err(OperandError::OutOfRange {
+            kind,
+            value: raw as i32,
+            min: 0,
+            max: mask as i32,
+        });
+    }
+    Ok(())
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bit(bool);
 
 impl Bit {
@@ -538,6 +765,7 @@ impl Bit {
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrapVect8(pub u8);
 
 impl TrapVect8 {
@@ -1,8 +1,36 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+
+mod builder;
+
+mod bytes;
+pub use bytes::*;
+
+mod disassemble;
+
+mod error;
+pub use error::*;
+
 mod instruction;
 pub use instruction::*;
 
+mod metadata;
+
 mod opcode;
 pub use opcode::*;
 
+mod parse;
+pub use parse::*;
+
+mod psr;
+pub use psr::*;
+
 mod register;
 pub use register::*;
+
+mod timing;
+pub use timing::*;
@@ -0,0 +1,116 @@
+use crate::{AddInstruction, AndInstruction, Instruction, OpCode, Register, XorInstruction};
+
+impl Instruction {
+    /// The 4-bit opcode this instruction encodes to (bits [15:12])
+    pub fn opcode(&self) -> u8 {
+        OpCode::from(self).into()
+    }
+
+    /// The register written by this instruction, if any.
+    /// JSR, JSRR and TRAP write the return address to R7; RTI pops R6.
+    pub fn destination_register(&self) -> Option<Register> {
+        match *self {
+            Instruction::AddInstruction(AddInstruction::AddReg(dr, _, _))
+            | Instruction::AddInstruction(AddInstruction::AddImm(dr, _, _))
+            | Instruction::AndInstruction(AndInstruction::AndReg(dr, _, _))
+            | Instruction::AndInstruction(AndInstruction::AndImm(dr, _, _))
+            | Instruction::XorInstruction(XorInstruction::XorReg(dr, _, _))
+            | Instruction::XorInstruction(XorInstruction::XorImm(dr, _, _))
+            | Instruction::Ldb(dr, _, _)
+            | Instruction::Ldi(dr, _, _)
+            | Instruction::Ldr(dr, _, _)
+            | Instruction::Lea(dr, _)
+            | Instruction::Shf(dr, _, _, _, _) => Some(dr),
+            Instruction::Jsr(_) | Instruction::Jsrr(_) | Instruction::Trap(_) => {
+                Some(Register::Register7)
+            }
+            Instruction::Rti => Some(Register::Register6),
+            Instruction::Br(..)
+            | Instruction::Jmp(_)
+            | Instruction::Ret
+            | Instruction::Stb(..)
+            | Instruction::Sti(..)
+            | Instruction::Stw(..) => None,
+        }
+    }
+
+    /// The registers read by this instruction, in operand order
+    pub fn source_registers(&self) -> impl Iterator<Item = Register> {
+        let sources: [Option<Register>; 2] = match *self {
+            Instruction::AddInstruction(AddInstruction::AddReg(_, sr1, sr2))
+            | Instruction::AndInstruction(AndInstruction::AndReg(_, sr1, sr2))
+            | Instruction::XorInstruction(XorInstruction::XorReg(_, sr1, sr2)) => {
+                [Some(sr1), Some(sr2)]
+            }
+            Instruction::AddInstruction(AddInstruction::AddImm(_, sr1, _))
+            | Instruction::AndInstruction(AndInstruction::AndImm(_, sr1, _))
+            | Instruction::XorInstruction(XorInstruction::XorImm(_, sr1, _))
+            | Instruction::Shf(_, sr1, _, _, _) => [Some(sr1), None],
+            Instruction::Jmp(base)
+            | Instruction::Jsrr(base)
+            | Instruction::Ldb(_, base, _)
+            | Instruction::Ldi(_, base, _)
+            | Instruction::Ldr(_, base, _) => [Some(base), None],
+            Instruction::Stb(sr, base, _)
+            | Instruction::Sti(sr, base, _)
+            | Instruction::Stw(sr, base, _) => [Some(sr), Some(base)],
+            Instruction::Ret => [Some(Register::Register7), None],
+            Instruction::Rti => [Some(Register::Register6), None],
+            Instruction::Br(..) | Instruction::Jsr(_) | Instruction::Lea(..) | Instruction::Trap(_) => {
+                [None, None]
+            }
+        };
+        sources.into_iter().flatten()
+    }
+
+    /// True for conditional branches (BR with any condition bits)
+    pub fn is_branch(&self) -> bool {
+        matches!(self, Instruction::Br(..))
+    }
+
+    /// True for any instruction that may redirect the PC
+    pub fn is_control_flow(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Br(..)
+                | Instruction::Jmp(_)
+                | Instruction::Jsr(_)
+                | Instruction::Jsrr(_)
+                | Instruction::Ret
+                | Instruction::Rti
+                | Instruction::Trap(_)
+        )
+    }
+
+    /// True if executing this instruction updates the N/Z/P condition codes
+    pub fn sets_condition_codes(&self) -> bool {
+        matches!(
+            self,
+            Instruction::AddInstruction(_)
+                | Instruction::AndInstruction(_)
+                | Instruction::XorInstruction(_)
+                | Instruction::Ldb(..)
+                | Instruction::Ldi(..)
+                | Instruction::Ldr(..)
+                | Instruction::Lea(..)
+                | Instruction::Shf(..)
+        )
+    }
+
+    /// True if this instruction loads from memory
+    pub fn reads_memory(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Ldb(..)
+                | Instruction::Ldi(..)
+                | Instruction::Ldr(..)
+                | Instruction::Rti
+                | Instruction::Sti(..)
+        )
+    }
+
+    /// True if this instruction stores to memory
+    pub fn writes_memory(&self) -> bool {
+        matches!(self, Instruction::Stb(..) | Instruction::Sti(..) | Instruction::Stw(..))
+    }
+}
@@ -1,4 +1,10 @@
-#[allow(dead_code)]
+use crate::Instruction;
+
+/// The sixteen LC-3b opcodes (bits [15:12] of an instruction word).
+///
+/// Names follow the `Instruction` variants: word loads/stores are `LDR`/`STW`,
+/// NOT is XOR with an all-ones immediate, and RET is JMP R7.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum OpCode {
     ADD,
     AND,
@@ -9,14 +15,13 @@ pub enum OpCode {
     LDI,
     LDR,
     LEA,
-    NOT,
-    RET,
     RTI,
     SHF,
     STB,
     STI,
-    STR,
+    STW,
     TRAP,
+    XOR,
 }
 
 impl From<OpCode> for u8 {
@@ -31,14 +36,36 @@ impl From<OpCode> for u8 {
             OpCode::LDI => 0b00001010,
             OpCode::LDR => 0b00000110,
             OpCode::LEA => 0b00001110,
-            OpCode::NOT => 0b00001001,
-            OpCode::RET => 0b00001100,
             OpCode::RTI => 0b00001000,
             OpCode::SHF => 0b00001101,
             OpCode::STB => 0b00000011,
             OpCode::STI => 0b00001011,
-            OpCode::STR => 0b00000111,
+            OpCode::STW => 0b00000111,
             OpCode::TRAP => 0b00001111,
+            OpCode::XOR => 0b00001001,
+        }
+    }
+}
+
+impl From<&Instruction> for OpCode {
+    fn from(inst: &Instruction) -> Self {
+        match inst {
+            Instruction::AddInstruction(_) => OpCode::ADD,
+            Instruction::AndInstruction(_) => OpCode::AND,
+            Instruction::Br(..) => OpCode::BR,
+            Instruction::Jmp(_) | Instruction::Ret => OpCode::JMP,
+            Instruction::Jsr(_) | Instruction::Jsrr(_) => OpCode::JSR,
+            Instruction::Ldb(..) => OpCode::LDB,
+            Instruction::Ldi(..) => OpCode::LDI,
+            Instruction::Ldr(..) => OpCode::LDR,
+            Instruction::Lea(..) => OpCode::LEA,
+            Instruction::Rti => OpCode::RTI,
+            Instruction::Shf(..) => OpCode::SHF,
+            Instruction::Stb(..) => OpCode::STB,
+            Instruction::Sti(..) => OpCode::STI,
+            Instruction::Stw(..) => OpCode::STW,
+            Instruction::Trap(_) => OpCode::TRAP,
+            Instruction::XorInstruction(_) => OpCode::XOR,
         }
     }
 }
@@ -0,0 +1,185 @@
+//! Single-statement parsing, shared with the assembler.
+//!
+//! Operands are the literal field values the assembler would emit for a
+//! numeric operand: BR and JSR offsets are taken as-is, while LEA takes an
+//! even byte offset and stores half of it. Labels are not supported.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str::FromStr;
+
+use crate::{Condition, Instruction, OperandError, ParseInstructionError, Register};
+
+/// Parse a numeric operand: `#10`, `10`, `#-3`, or hex `x1F`
+pub fn parse_literal(s: &str) -> Result<i32, OperandError> {
+    let invalid = || OperandError::InvalidLiteral(s.to_string());
+    if let Some(hex) = s.strip_prefix('x').or_else(|| s.strip_prefix('X')) {
+        return u16::from_str_radix(hex, 16)
+            .map(i32::from)
+            .map_err(|_| invalid());
+    }
+    let decimal = s.strip_prefix('#').unwrap_or(s);
+    decimal.parse::<i16>().map(i32::from).map_err(|_| invalid())
+}
+
+fn literal_in_range(kind: &'static str, s: &str, min: i32, max: i32) -> Result<i32, OperandError> {
+    in_range(kind, parse_literal(s)?, min, max)
+}
+
+fn in_range(kind: &'static str, value: i32, min: i32, max: i32) -> Result<i32, OperandError> {
+    if (min..=max).contains(&value) {
+        Ok(value)
+    } else {
+        Err(OperandError::OutOfRange { kind, value, min, max })
+    }
+}
+
+fn expect_operands(
+    mnemonic: &str,
+    operands: &[&str],
+    expected: usize,
+) -> Result<(), ParseInstructionError> {
+    if operands.len() == expected {
+        Ok(())
+    } else {
+        Err(ParseInstructionError::OperandCount {
+            mnemonic: mnemonic.to_string(),
+            expected,
+            found: operands.len(),
+        })
+    }
+}
+
+impl Instruction {
+    /// Build an instruction from a mnemonic and its already-split operands.
+    /// The mnemonic is case-insensitive and may be a trap alias like `HALT`.
+    pub fn from_parts(mnemonic: &str, operands: &[&str]) -> Result<Self, ParseInstructionError> {
+        let upper: String = mnemonic.to_uppercase();
+        let ops = operands;
+
+        if let Some(condition) = upper.strip_prefix("BR").and_then(Condition::from_suffix) {
+            expect_operands(&upper, ops, 1)?;
+            let offset = literal_in_range("PCOffset9", ops[0], -256, 255)?;
+            return Ok(Instruction::br(condition.n, condition.z, condition.p, offset as i16)?);
+        }
+
+        let inst = match upper.as_str() {
+            "ADD" | "AND" | "XOR" => {
+                expect_operands(&upper, ops, 3)?;
+                let dr = Register::from_str(ops[0])?;
+                let sr1 = Register::from_str(ops[1])?;
+                if let Ok(sr2) = Register::from_str(ops[2]) {
+                    match upper.as_str() {
+                        "ADD" => Instruction::add_reg(dr, sr1, sr2),
+                        "AND" => Instruction::and_reg(dr, sr1, sr2),
+                        _ => Instruction::xor_reg(dr, sr1, sr2),
+                    }
+                } else {
+                    let imm5 = literal_in_range("Immediate5", ops[2], -16, 15)? as i8;
+                    match upper.as_str() {
+                        "ADD" => Instruction::add_imm(dr, sr1, imm5)?,
+                        "AND" => Instruction::and_imm(dr, sr1, imm5)?,
+                        _ => Instruction::xor_imm(dr, sr1, imm5)?,
+                    }
+                }
+            }
+            "NOT" => {
+                expect_operands(&upper, ops, 2)?;
+                Instruction::not(Register::from_str(ops[0])?, Register::from_str(ops[1])?)
+            }
+            "JMP" | "JSRR" => {
+                expect_operands(&upper, ops, 1)?;
+                let base = Register::from_str(ops[0])?;
+                if upper == "JMP" {
+                    Instruction::jmp(base)
+                } else {
+                    Instruction::jsrr(base)
+                }
+            }
+            "JSR" => {
+                expect_operands(&upper, ops, 1)?;
+                let offset = literal_in_range("PCOffset11", ops[0], -1024, 1023)?;
+                Instruction::jsr(offset as i16)?
+            }
+            "LEA" => {
+                expect_operands(&upper, ops, 2)?;
+                let dr = Register::from_str(ops[0])?;
+                let offset = parse_literal(ops[1])?;
+                if offset % 2 != 0 {
+                    return Err(ParseInstructionError::UnalignedOffset(offset));
+                }
+                let stored = in_range("PCOffset9", offset / 2, -256, 255)?;
+                Instruction::lea(dr, stored as i16)?
+            }
+            "LDB" | "LDW" | "LDI" | "STB" | "STW" | "STI" => {
+                expect_operands(&upper, ops, 3)?;
+                let reg = Register::from_str(ops[0])?;
+                let base = Register::from_str(ops[1])?;
+                let offset6 = literal_in_range("PCOffset6", ops[2], -32, 31)? as i8;
+                match upper.as_str() {
+                    "LDB" => Instruction::ldb(reg, base, offset6)?,
+                    "LDW" => Instruction::ldr(reg, base, offset6)?,
+                    "LDI" => Instruction::ldi(reg, base, offset6)?,
+                    "STB" => Instruction::stb(reg, base, offset6)?,
+                    "STW" => Instruction::stw(reg, base, offset6)?,
+                    _ => Instruction::sti(reg, base, offset6)?,
+                }
+            }
+            "LSHF" | "RSHFL" | "RSHFA" => {
+                expect_operands(&upper, ops, 3)?;
+                let dr = Register::from_str(ops[0])?;
+                let sr = Register::from_str(ops[1])?;
+                let amount = literal_in_range("Immediate4", ops[2], 0, 15)? as u8;
+                match upper.as_str() {
+                    "LSHF" => Instruction::lshf(dr, sr, amount)?,
+                    "RSHFL" => Instruction::rshfl(dr, sr, amount)?,
+                    _ => Instruction::rshfa(dr, sr, amount)?,
+                }
+            }
+            "TRAP" => {
+                expect_operands(&upper, ops, 1)?;
+                let vector = literal_in_range("TrapVect8", ops[0], 0, 0xFF)?;
+                Instruction::trap(vector as u8)
+            }
+            "RET" | "RTI" | "GETC" | "OUT" | "PUTS" | "IN" | "PUTSP" | "HALT" => {
+                expect_operands(&upper, ops, 0)?;
+                match upper.as_str() {
+                    "RET" => Instruction::ret(),
+                    "RTI" => Instruction::rti(),
+                    "GETC" => Instruction::trap(0x20),
+                    "OUT" => Instruction::trap(0x21),
+                    "PUTS" => Instruction::trap(0x22),
+                    "IN" => Instruction::trap(0x23),
+                    "PUTSP" => Instruction::trap(0x24),
+                    _ => Instruction::trap(0x25),
+                }
+            }
+            _ => return Err(ParseInstructionError::UnknownMnemonic(mnemonic.to_string())),
+        };
+
+        Ok(inst)
+    }
+}
+
+/// Parse one statement such as `ADD R1, R2, #3`. A trailing `;` comment is
+/// ignored; labels and directives are not accepted.
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let statement = s.split(';').next().unwrap_or_default().trim();
+        let (mnemonic, rest) = statement
+            .split_once(char::is_whitespace)
+            .unwrap_or((statement, ""));
+        if mnemonic.is_empty() {
+            return Err(ParseInstructionError::Empty);
+        }
+        let operands: Vec<&str> = rest
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|op| !op.is_empty())
+            .collect();
+        Instruction::from_parts(mnemonic, &operands)
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{Condition, OperandError};
+
+/// Execution privilege, PSR[15]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Privilege {
+    /// PSR[15] = 0
+    #[default]
+    Supervisor,
+    /// PSR[15] = 1
+    User,
+}
+
+/// Processor Status Register: privilege in bit 15, priority level in
+/// bits [10:8], and condition codes N/Z/P in bits [2:0]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "PsrFields"))]
+pub struct Psr {
+    privilege: Privilege,
+    priority: u8,
+    condition: Condition,
+}
+
+impl Psr {
+    /// Create a PSR, checking that `priority` is in 0..=7
+    pub fn new(
+        privilege: Privilege,
+        priority: u8,
+        condition: Condition,
+    ) -> Result<Self, OperandError> {
+        let mut psr = Psr {
+            privilege,
+            priority: 0,
+            condition,
+        };
+        psr.set_priority(priority)?;
+        Ok(psr)
+    }
+
+    /// User mode, priority 0, Z set (the usual state when a user program starts)
+    pub fn user() -> Self {
+        Psr {
+            privilege: Privilege::User,
+            priority: 0,
+            condition: Condition { n: false, z: true, p: false },
+        }
+    }
+
+    pub fn privilege(&self) -> Privilege {
+        self.privilege
+    }
+
+    pub fn set_privilege(&mut self, privilege: Privilege) {
+        self.privilege = privilege;
+    }
+
+    pub fn is_user(&self) -> bool {
+        self.privilege == Privilege::User
+    }
+
+    pub fn is_supervisor(&self) -> bool {
+        self.privilege == Privilege::Supervisor
+    }
+
+    /// Priority level, 0 (lowest) to 7 (highest)
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    pub fn set_priority(&mut self, priority: u8) -> Result<(), OperandError> {
+        if priority > 7 {
+            return Err(OperandError::OutOfRange {
+                kind: "PSR priority",
+                value: priority as i32,
+                min: 0,
+                max: 7,
+            });
+        }
+        self.priority = priority;
+        Ok(())
+    }
+
+    pub fn condition(&self) -> Condition {
+        self.condition
+    }
+
+    pub fn set_condition(&mut self, condition: Condition) {
+        self.condition = condition;
+    }
+}
+
+/// `Psr`'s fields as serialized, checked by `Psr::new` when deserializing
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct PsrFields {
+    privilege: Privilege,
+    priority: u8,
+    condition: Condition,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PsrFields> for Psr {
+    type Error = OperandError;
+
+    fn try_from(fields: PsrFields) -> Result<Self, Self::Error> {
+        Psr::new(fields.privilege, fields.priority, fields.condition)
+    }
+}
+
+impl From<Psr> for u16 {
+    fn from(psr: Psr) -> Self {
+        let privilege = match psr.privilege {
+            Privilege::Supervisor => 0,
+            Privilege::User => 1u16 << 15,
+        };
+        let priority = (psr.priority as u16 & 0x7) << 8;
+        let n = if psr.condition.n { 1u16 << 2 } else { 0 };
+        let z = if psr.condition.z { 1u16 << 1 } else { 0 };
+        let p = if psr.condition.p { 1u16 } else { 0 };
+        privilege | priority | n | z | p
+    }
+}
+
+/// Unpack a PSR word. Unused bits are ignored.
+impl From<u16> for Psr {
+    fn from(word: u16) -> Self {
+        Psr {
+            privilege: if word & 0x8000 != 0 {
+                Privilege::User
+            } else {
+                Privilege::Supervisor
+            },
+            priority: ((word >> 8) & 0x7) as u8,
+            condition: Condition {
+                n: word & 0x4 != 0,
+                z: word & 0x2 != 0,
+                p: word & 0x1 != 0,
+            },
+        }
+    }
+}
@@ -1,6 +1,10 @@
-use std::str::FromStr;
+use alloc::string::ToString;
+use core::{fmt, str::FromStr};
+
+use crate::OperandError;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Register {
     Register0,
     Register1,
@@ -12,8 +16,20 @@ pub enum Register {
     Register7,
 }
 
+/// How registers are spelled when formatted
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RegisterNaming {
+    /// `R0` through `R7`
+    #[default]
+    Numeric,
+    /// `R0`-`R4`, then `FP`, `SP`, `LR` for R5-R7
+    Conventional,
+}
+
+/// Accepts `R0`-`R7` plus the aliases `FP` (R5), `SP` (R6), and `LR`/`RA` (R7)
 impl FromStr for Register {
-    type Err = eyre::Report;
+    type Err = OperandError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let reg = match s {
@@ -22,10 +38,10 @@ impl FromStr for Register {
             "r2" | "R2" => Register::Register2,
             "r3" | "R3" => Register::Register3,
             "r4" | "R4" => Register::Register4,
-            "r5" | "R5" => Register::Register5,
-            "r6" | "R6" => Register::Register6,
-            "r7" | "R7" => Register::Register7,
-            unknown => return Err(eyre::eyre!("unhandled register identifier: {}", unknown)),
+            "r5" | "R5" | "fp" | "FP" => Register::Register5,
+            "r6" | "R6" | "sp" | "SP" => Register::Register6,
+            "r7" | "R7" | "lr" | "LR" | "ra" | "RA" => Register::Register7,
+            unknown => return Err(OperandError::UnknownRegister(unknown.to_string())),
         };
 
         Ok(reg)
@@ -60,3 +76,40 @@ impl Register {
         }
     }
 }
+
+impl Register {
+    /// Frame pointer by convention
+    pub const FP: Register = Register::Register5;
+    /// Stack pointer by convention
+    pub const SP: Register = Register::Register6;
+    /// Link register: JSR, JSRR and TRAP store the return address here
+    pub const LR: Register = Register::Register7;
+
+    pub fn name(&self, naming: RegisterNaming) -> &'static str {
+        match (naming, *self) {
+            (RegisterNaming::Conventional, Register::Register5) => "FP",
+            (RegisterNaming::Conventional, Register::Register6) => "SP",
+            (RegisterNaming::Conventional, Register::Register7) => "LR",
+            (_, Register::Register0) => "R0",
+            (_, Register::Register1) => "R1",
+            (_, Register::Register2) => "R2",
+            (_, Register::Register3) => "R3",
+            (_, Register::Register4) => "R4",
+            (_, Register::Register5) => "R5",
+            (_, Register::Register6) => "R6",
+            (_, Register::Register7) => "R7",
+        }
+    }
+}
+
+/// `R6` by default; the alternate form (`{:#}`) uses conventional names like `SP`
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let naming = if f.alternate() {
+            RegisterNaming::Conventional
+        } else {
+            RegisterNaming::Numeric
+        };
+        f.pad(self.name(naming))
+    }
+}
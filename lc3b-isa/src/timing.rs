@@ -0,0 +1,83 @@
+use crate::Instruction;
+
+/// Estimates how many clock cycles an instruction takes to execute
+pub trait TimingModel {
+    /// Cycles for one execution of `inst`, including fetch and decode.
+    /// `branch_taken` only matters for conditional branches.
+    fn cycles(&self, inst: &Instruction, branch_taken: bool) -> u32;
+}
+
+/// Cycle counts derived from the LC-3b microarchitecture state diagram
+/// (Patt & Patel, Appendix C).
+///
+/// Every state takes one cycle except memory-access states, which stall
+/// for `memory_latency` cycles waiting on the ready signal. Fetch is
+/// states 18, 33, 35 and decode is state 32.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateMachineTiming {
+    /// Cycles spent in each memory-access state
+    pub memory_latency: u32,
+}
+
+impl StateMachineTiming {
+    pub fn new(memory_latency: u32) -> Self {
+        Self { memory_latency }
+    }
+
+    /// Cycles shared by every instruction: fetch (MAR, memory, IR) plus decode
+    pub fn fetch_decode_cycles(&self) -> u32 {
+        3 + self.memory_latency
+    }
+}
+
+impl Default for StateMachineTiming {
+    /// Five-cycle memory, as assumed throughout the LC-3b textbook
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl TimingModel for StateMachineTiming {
+    fn cycles(&self, inst: &Instruction, branch_taken: bool) -> u32 {
+        let mem = self.memory_latency;
+        let execute = match inst {
+            // One ALU/address state: 1, 5, 9, 13, 14
+            Instruction::AddInstruction(_)
+            | Instruction::AndInstruction(_)
+            | Instruction::XorInstruction(_)
+            | Instruction::Shf(..)
+            | Instruction::Lea(..) => 1,
+            // State 0, then 22 when taken
+            Instruction::Br(..) => {
+                if branch_taken {
+                    2
+                } else {
+                    1
+                }
+            }
+            // State 12
+            Instruction::Jmp(_) | Instruction::Ret => 1,
+            // State 4, then 20/21
+            Instruction::Jsr(_) | Instruction::Jsrr(_) => 2,
+            // Address, memory, then register/memory write: 6-25-27, 2-29-31, 7-23-16, 3-24-17
+            Instruction::Ldr(..) | Instruction::Ldb(..) => 2 + mem,
+            Instruction::Stw(..) | Instruction::Stb(..) => 2 + mem,
+            // Pointer fetch, then a second access through it
+            Instruction::Ldi(..) => 3 + 2 * mem,
+            Instruction::Sti(..) => 3 + 2 * mem,
+            // State 15, vector read (28), PC load (30)
+            Instruction::Trap(_) => 2 + mem,
+            // Pop PC and PSR, adjust R6, restore privilege
+            Instruction::Rti => 5 + 2 * mem,
+        };
+        self.fetch_decode_cycles() + execute
+    }
+}
+
+impl Instruction {
+    /// Cycle estimate under the default `StateMachineTiming` model
+    pub fn cycles(&self, branch_taken: bool) -> u32 {
+        StateMachineTiming::default().cycles(self, branch_taken)
+    }
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use lc3b_isa::{Instruction, PCOffset6};
+
+#[test]
+fn arbitrary_instructions_round_trip() {
+    let bytes: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+    let mut u = Unstructured::new(&bytes);
+    while !u.is_empty() {
+        let inst = Instruction::arbitrary(&mut u).unwrap();
+        let word: u16 = (&inst).into();
+        assert_eq!(Instruction::try_from(word).unwrap(), inst, "{:#06x}", word);
+    }
+}
+
+#[test]
+fn arbitrary_operands_stay_in_range() {
+    let bytes = [0xFFu8; 64];
+    let mut u = Unstructured::new(&bytes);
+    for _ in 0..16 {
+        let offset = PCOffset6::arbitrary(&mut u).unwrap();
+        assert!((-32..=31).contains(&offset.sign_extend()));
+    }
+}
@@ -0,0 +1,38 @@
+use lc3b_isa::{Instruction, OperandError, Register};
+
+#[test]
+fn builders_encode_like_handwritten_words() {
+    let cases = [
+        (Instruction::add_imm(Register::Register1, Register::Register1, 1).unwrap(), 0x1261),
+        (Instruction::and_imm(Register::Register0, Register::Register0, 0).unwrap(), 0x5020),
+        (Instruction::br(false, true, false, -2).unwrap(), 0b0000_010_111111110),
+        (Instruction::ldr(Register::Register0, Register::Register5, -1).unwrap(), 0b0110_000_101_111111),
+        (Instruction::stw(Register::Register7, Register::Register6, 0).unwrap(), 0b0111_111_110_000000),
+        (Instruction::rshfa(Register::Register2, Register::Register3, 15).unwrap(), 0b1101_010_011_1_1_1111),
+        (Instruction::jsr(1023).unwrap(), 0b0100_1_01111111111),
+        (Instruction::trap(0x25), 0xF025),
+        (Instruction::ret(), 0xC1C0),
+    ];
+    for (inst, expected) in cases {
+        assert_eq!(u16::from(&inst), expected, "{:?}", inst);
+    }
+}
+
+#[test]
+fn builders_reject_out_of_range_operands() {
+    assert!(matches!(
+        Instruction::add_imm(Register::Register0, Register::Register0, 16),
+        Err(OperandError::OutOfRange { kind: "Immediate5", .. })
+    ));
+    assert!(matches!(
+        Instruction::br(true, true, true, 256),
+        Err(OperandError::OutOfRange { kind: "PCOffset9", .. })
+    ));
+    assert!(matches!(
+        Instruction::jsr(-1025),
+        Err(OperandError::OutOfRange { kind: "PCOffset11", .. })
+    ));
+    assert!(Instruction::ldb(Register::Register0, Register::Register0, 32).is_err());
+    assert!(Instruction::lshf(Register::Register0, Register::Register0, 16).is_err());
+    assert!(Instruction::lea(Register::Register0, -257).is_err());
+}
@@ -0,0 +1,31 @@
+use lc3b_isa::{encode_words_into, words_from_bytes, Endianness, Instruction, TrapVect8};
+
+#[test]
+fn instruction_byte_orders() {
+    let halt = Instruction::Trap(TrapVect8::new(0x25));
+    assert_eq!(halt.to_be_bytes(), [0xF0, 0x25]);
+    assert_eq!(halt.to_le_bytes(), [0x25, 0xF0]);
+
+    assert_eq!(Instruction::try_from([0xF0u8, 0x25u8]).unwrap(), halt);
+    assert_eq!(Instruction::from_bytes([0x25, 0xF0], Endianness::Little).unwrap(), halt);
+}
+
+#[test]
+fn encode_into_appends() {
+    let halt = Instruction::Trap(TrapVect8::new(0x25));
+    let mut out = vec![0xAA];
+    halt.encode_into(&mut out, Endianness::Big);
+    halt.encode_into(&mut out, Endianness::Little);
+    assert_eq!(out, [0xAA, 0xF0, 0x25, 0x25, 0xF0]);
+}
+
+#[test]
+fn words_round_trip_through_bytes() {
+    let words = [0x3000, 0x1261, 0xF025];
+    for endianness in [Endianness::Big, Endianness::Little] {
+        let mut bytes = Vec::new();
+        encode_words_into(&words, &mut bytes, endianness);
+        assert_eq!(bytes.len(), 6);
+        assert_eq!(words_from_bytes(&bytes, endianness), words);
+    }
+}
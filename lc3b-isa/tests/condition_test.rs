@@ -0,0 +1,33 @@
+use lc3b_isa::Condition;
+
+#[test]
+fn from_suffix_accepts_any_order_and_case() {
+    assert_eq!(Condition::from_suffix(""), Some(Condition::ALWAYS));
+    assert_eq!(
+        Condition::from_suffix("nz"),
+        Some(Condition { n: true, z: true, p: false })
+    );
+    assert_eq!(Condition::from_suffix("PN"), Condition::from_suffix("np"));
+    assert_eq!(Condition::from_suffix("nzp"), Some(Condition::ALWAYS));
+    assert_eq!(Condition::from_suffix("x"), None);
+    assert_eq!(Condition::from_suffix("nq"), None);
+}
+
+#[test]
+fn suffix_round_trips_every_combination() {
+    for bits in 0..8u8 {
+        let condition = Condition {
+            n: bits & 4 != 0,
+            z: bits & 2 != 0,
+            p: bits & 1 != 0,
+        };
+        let suffix = condition.suffix();
+        if bits == 0 {
+            // An empty suffix parses back as ALWAYS, not as "never"
+            assert_eq!(suffix, "");
+            continue;
+        }
+        assert_eq!(Condition::from_suffix(suffix), Some(condition));
+    }
+    assert_eq!(Condition::ALWAYS.suffix(), "nzp");
+}
@@ -0,0 +1,19 @@
+use lc3b_isa::{decode_words, Instruction, TrapVect8};
+
+#[test]
+fn yields_addresses_from_origin() {
+    let words = [0x1261, 0x1261, 0xF025];
+    let decoded: Vec<_> = decode_words(0x3000, &words).collect();
+
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded[0].0, 0x3000);
+    assert_eq!(decoded[2].0, 0x3002);
+    assert_eq!(decoded[2].1, Ok(Instruction::Trap(TrapVect8::new(0x25))));
+}
+
+#[test]
+fn addresses_wrap_around() {
+    let words = [0x0000, 0x0000];
+    let addresses: Vec<u16> = decode_words(0xFFFF, &words).map(|(addr, _)| addr).collect();
+    assert_eq!(addresses, [0xFFFF, 0x0000]);
+}
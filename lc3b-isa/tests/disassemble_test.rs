@@ -0,0 +1,38 @@
+use lc3b_isa::Instruction;
+
+fn disassemble(word: u16) -> String {
+    Instruction::try_from(word).unwrap().to_string()
+}
+
+#[test]
+fn formats_operands() {
+    assert_eq!(disassemble(0b0001_001_010_1_11101), "ADD R1, R2, #-3");
+    assert_eq!(disassemble(0b0101_011_100_000_101), "AND R3, R4, R5");
+    assert_eq!(disassemble(0b1001_011_010_1_11111), "NOT R3, R2");
+    assert_eq!(disassemble(0b0000_110_111111100), "BRnz #-4");
+    assert_eq!(disassemble(0b0000_111_000000010), "BRnzp #2");
+    assert_eq!(disassemble(0b0000_000_000000000), "NOP");
+    assert_eq!(disassemble(0b1110_000_000000011), "LEA R0, #6");
+    assert_eq!(disassemble(0b0110_001_110_111110), "LDW R1, R6, #-2");
+    assert_eq!(disassemble(0b1101_001_010_11_0011), "RSHFA R1, R2, #3");
+    assert_eq!(disassemble(0b0100_1_11111111111), "JSR #-1");
+    assert_eq!(disassemble(0b1100_000_111_000000), "RET");
+    assert_eq!(disassemble(0xF025), "HALT");
+    assert_eq!(disassemble(0xF030), "TRAP x30");
+}
+
+#[test]
+fn every_word_parses_back() {
+    for word in 0..=u16::MAX {
+        let inst = Instruction::try_from(word).unwrap();
+        let text = inst.to_string();
+        match inst {
+            // No spelling of their own; see the Display impl
+            Instruction::Br(condition, _) if condition.suffix().is_empty() => continue,
+            Instruction::Shf(..) if text.starts_with("LSHF") && word & 0x0030 != 0 => continue,
+            _ => {}
+        }
+        let parsed: Instruction = text.parse().unwrap_or_else(|e| panic!("{}: {}", text, e));
+        assert_eq!(parsed, inst, "{:#06x} disassembled as {}", word, text);
+    }
+}
@@ -0,0 +1,85 @@
+use lc3b_isa::{
+    parse_literal, Condition, Instruction, OperandError, PCOffset9, ParseInstructionError,
+    Register,
+};
+
+fn word(s: &str) -> u16 {
+    u16::from(&s.parse::<Instruction>().unwrap())
+}
+
+#[test]
+fn parses_every_mnemonic() {
+    assert_eq!(word("ADD R1, R2, #3"), 0b0001_001_010_1_00011);
+    assert_eq!(word("add r1, r2, r3"), 0b0001_001_010_000_011);
+    assert_eq!(word("AND R0, R0, #0"), 0x5020);
+    assert_eq!(word("XOR R3, R1, x7"), 0b1001_011_001_1_00111);
+    assert_eq!(word("NOT R1, R2"), 0b1001_001_010_1_11111);
+    assert_eq!(word("BRnz #-2"), 0b0000_110_111111110);
+    assert_eq!(word("JMP R2"), 0xC080);
+    assert_eq!(word("RET"), 0xC1C0);
+    assert_eq!(word("JSR #5"), 0b0100_1_00000000101);
+    assert_eq!(word("JSRR R3"), 0b0100_0_00_011_000000);
+    assert_eq!(word("LDB R4, R2, #10"), 0b0010_100_010_001010);
+    assert_eq!(word("LDW R4, R2, #-1"), 0b0110_100_010_111111);
+    assert_eq!(word("LDI R1, R0, #0"), 0b1010_001_000_000000);
+    assert_eq!(word("LEA R4, #4"), 0b1110_100_000000010);
+    assert_eq!(word("STB R1, R6, #1"), 0b0011_001_110_000001);
+    assert_eq!(word("STW R7, R6, #0"), 0b0111_111_110_000000);
+    assert_eq!(word("STI R1, R0, #2"), 0b1011_001_000_000010);
+    assert_eq!(word("RSHFA R2, R3, #15"), 0b1101_010_011_11_1111);
+    assert_eq!(word("TRAP x25"), 0xF025);
+    assert_eq!(word("HALT"), 0xF025);
+    assert_eq!(word("RTI"), 0x8000);
+}
+
+#[test]
+fn ignores_whitespace_and_trailing_comment() {
+    let inst: Instruction = "  BRz   #1 ; skip next".parse().unwrap();
+    assert_eq!(
+        inst,
+        Instruction::Br(Condition { n: false, z: true, p: false }, PCOffset9::new(1))
+    );
+    assert_eq!(word("ADD R1 R1 #1"), word("ADD R1, R1, #1"));
+}
+
+#[test]
+fn reports_errors() {
+    assert_eq!("".parse::<Instruction>(), Err(ParseInstructionError::Empty));
+    assert_eq!(
+        "FOO R1".parse::<Instruction>(),
+        Err(ParseInstructionError::UnknownMnemonic("FOO".to_string()))
+    );
+    assert!(matches!(
+        "ADD R1, R2".parse::<Instruction>(),
+        Err(ParseInstructionError::OperandCount { expected: 3, found: 2, .. })
+    ));
+    assert!(matches!(
+        "ADD R1, R2, #16".parse::<Instruction>(),
+        Err(ParseInstructionError::Operand(OperandError::OutOfRange { kind: "Immediate5", .. }))
+    ));
+    assert_eq!(
+        "LEA R0, #3".parse::<Instruction>(),
+        Err(ParseInstructionError::UnalignedOffset(3))
+    );
+    assert!(matches!(
+        "JMP R8".parse::<Instruction>(),
+        Err(ParseInstructionError::Operand(OperandError::UnknownRegister(_)))
+    ));
+    assert!("BRx #1".parse::<Instruction>().is_err());
+    assert!("BR label".parse::<Instruction>().is_err());
+}
+
+#[test]
+fn literal_forms() {
+    assert_eq!(parse_literal("#-3"), Ok(-3));
+    assert_eq!(parse_literal("12"), Ok(12));
+    assert_eq!(parse_literal("xFF"), Ok(255));
+    assert_eq!(parse_literal("X10"), Ok(16));
+    assert!(parse_literal("#abc").is_err());
+}
+
+#[test]
+fn register_field_operands() {
+    let inst: Instruction = "JSRR R7".parse().unwrap();
+    assert_eq!(inst, Instruction::Jsrr(Register::Register7));
+}
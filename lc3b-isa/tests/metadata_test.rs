@@ -0,0 +1,88 @@
+use lc3b_isa::{
+    AddInstruction, Condition, Immediate5, Instruction, PCOffset11, PCOffset6, PCOffset9,
+    Register, TrapVect8,
+};
+
+fn sample_instructions() -> Vec<Instruction> {
+    vec![
+        Instruction::AddInstruction(AddInstruction::AddReg(
+            Register::Register0,
+            Register::Register1,
+            Register::Register2,
+        )),
+        Instruction::AddInstruction(AddInstruction::AddImm(
+            Register::Register3,
+            Register::Register4,
+            Immediate5::new(1).unwrap(),
+        )),
+        Instruction::Br(Condition { n: true, z: false, p: false }, PCOffset9::new(3)),
+        Instruction::Jmp(Register::Register2),
+        Instruction::Jsr(PCOffset11::new(-5)),
+        Instruction::Jsrr(Register::Register4),
+        Instruction::Ldb(Register::Register1, Register::Register2, PCOffset6::new(1).unwrap()),
+        Instruction::Ldr(Register::Register1, Register::Register2, PCOffset6::new(1).unwrap()),
+        Instruction::Lea(Register::Register0, PCOffset9::new(2)),
+        Instruction::Ret,
+        Instruction::Rti,
+        Instruction::Stw(Register::Register5, Register::Register6, PCOffset6::new(0).unwrap()),
+        Instruction::Trap(TrapVect8::new(0x25)),
+    ]
+}
+
+#[test]
+fn opcode_matches_encoding() {
+    for inst in sample_instructions() {
+        let word: u16 = (&inst).into();
+        assert_eq!((word >> 12) as u8, inst.opcode(), "{:?}", inst);
+    }
+}
+
+#[test]
+fn register_reads_and_writes() {
+    let add = Instruction::AddInstruction(AddInstruction::AddReg(
+        Register::Register0,
+        Register::Register1,
+        Register::Register2,
+    ));
+    assert_eq!(add.destination_register(), Some(Register::Register0));
+    assert_eq!(
+        add.source_registers().collect::<Vec<_>>(),
+        [Register::Register1, Register::Register2]
+    );
+
+    let stw = Instruction::Stw(Register::Register5, Register::Register6, PCOffset6::new(0).unwrap());
+    assert_eq!(stw.destination_register(), None);
+    assert_eq!(
+        stw.source_registers().collect::<Vec<_>>(),
+        [Register::Register5, Register::Register6]
+    );
+
+    let jsr = Instruction::Jsr(PCOffset11::new(4));
+    assert_eq!(jsr.destination_register(), Some(Register::Register7));
+    assert_eq!(jsr.source_registers().count(), 0);
+}
+
+#[test]
+fn control_flow_classification() {
+    let br = Instruction::Br(Condition::default(), PCOffset9::new(0));
+    assert!(br.is_branch());
+    assert!(br.is_control_flow());
+    assert!(!br.sets_condition_codes());
+
+    assert!(Instruction::Ret.is_control_flow());
+    assert!(!Instruction::Ret.is_branch());
+
+    let lea = Instruction::Lea(Register::Register0, PCOffset9::new(2));
+    assert!(!lea.is_control_flow());
+    assert!(lea.sets_condition_codes());
+}
+
+#[test]
+fn memory_access_classification() {
+    let ldr = Instruction::Ldr(Register::Register1, Register::Register2, PCOffset6::new(1).unwrap());
+    assert!(ldr.reads_memory());
+    assert!(!ldr.writes_memory());
+
+    let stw = Instruction::Stw(Register::Register5, Register::Register6, PCOffset6::new(0).unwrap());
+    assert!(stw.writes_memory());
+}
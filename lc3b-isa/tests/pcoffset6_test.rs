@@ -0,0 +1,38 @@
+use lc3b_isa::{Instruction, PCOffset6, Register};
+
+#[test]
+fn new_accepts_full_signed_range() {
+    assert_eq!(PCOffset6::new(-32).unwrap().sign_extend(), -32);
+    assert_eq!(PCOffset6::new(31).unwrap().sign_extend(), 31);
+    assert_eq!(PCOffset6::new(-1).unwrap().value(), 0x3F);
+}
+
+#[test]
+fn new_rejects_out_of_range() {
+    assert!(PCOffset6::new(32).is_err());
+    assert!(PCOffset6::new(-33).is_err());
+}
+
+#[test]
+fn from_raw_masks_to_six_bits() {
+    assert_eq!(PCOffset6::from_raw(0xFF).value(), 0x3F);
+    assert_eq!(PCOffset6::from_raw(0x20).sign_extend(), -32);
+}
+
+#[test]
+fn build_stw_outside_crate() {
+    let inst = Instruction::Stw(Register::Register1, Register::Register6, PCOffset6::new(-2).unwrap());
+    let word: u16 = (&inst).into();
+    assert_eq!(word, 0b0111_001_110_111110);
+    assert_eq!(Instruction::try_from(word).unwrap(), inst);
+}
+
+#[test]
+fn out_of_range_error_reports_bounds() {
+    let err = PCOffset6::new(40).unwrap_err();
+    assert_eq!(
+        err,
+        lc3b_isa::OperandError::OutOfRange { kind: "PCOffset6", value: 40, min: -32, max: 31 }
+    );
+    assert_eq!(err.to_string(), "PCOffset6 value 40 out of range (-32 to 31)");
+}
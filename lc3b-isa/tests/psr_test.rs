@@ -0,0 +1,35 @@
+use lc3b_isa::{Condition, Privilege, Psr};
+
+#[test]
+fn pack_and_unpack() {
+    let psr = Psr::new(Privilege::User, 3, Condition { n: true, z: false, p: false }).unwrap();
+    let word: u16 = psr.into();
+    assert_eq!(word, 0b1000_0011_0000_0100);
+    assert_eq!(Psr::from(word), psr);
+}
+
+#[test]
+fn unpack_ignores_unused_bits() {
+    let psr = Psr::from(0b0111_1100_1111_1010);
+    assert_eq!(psr.privilege(), Privilege::Supervisor);
+    assert_eq!(psr.priority(), 4);
+    assert_eq!(psr.condition(), Condition { n: false, z: true, p: false });
+}
+
+#[test]
+fn priority_is_range_checked() {
+    assert!(Psr::new(Privilege::Supervisor, 8, Condition::default()).is_err());
+
+    let mut psr = Psr::user();
+    assert!(psr.set_priority(7).is_ok());
+    assert!(psr.set_priority(9).is_err());
+    assert_eq!(psr.priority(), 7);
+}
+
+#[test]
+fn user_default_state() {
+    let psr = Psr::user();
+    assert!(psr.is_user());
+    assert!(!psr.is_supervisor());
+    assert_eq!(u16::from(psr), 0x8002);
+}
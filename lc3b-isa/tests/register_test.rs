@@ -0,0 +1,28 @@
+use lc3b_isa::{Instruction, Register, RegisterNaming};
+
+#[test]
+fn aliases_parse_to_conventional_registers() {
+    assert_eq!("SP".parse::<Register>(), Ok(Register::Register6));
+    assert_eq!("fp".parse::<Register>(), Ok(Register::Register5));
+    assert_eq!("LR".parse::<Register>(), Ok(Register::Register7));
+    assert_eq!("ra".parse::<Register>(), Ok(Register::Register7));
+    assert!("Sp".parse::<Register>().is_err());
+    assert_eq!(Register::SP, Register::Register6);
+}
+
+#[test]
+fn aliases_work_in_statements() {
+    let alias: Instruction = "ADD SP, SP, #-1".parse().unwrap();
+    let numeric: Instruction = "ADD R6, R6, #-1".parse().unwrap();
+    assert_eq!(alias, numeric);
+}
+
+#[test]
+fn display_honors_naming_style() {
+    assert_eq!(Register::Register6.to_string(), "R6");
+    assert_eq!(format!("{:#}", Register::Register6), "SP");
+    assert_eq!(format!("{:#}", Register::Register2), "R2");
+    assert_eq!(format!("{:>3}", Register::Register1), " R1");
+    assert_eq!(Register::Register7.name(RegisterNaming::Conventional), "LR");
+    assert_eq!(Register::Register5.name(RegisterNaming::Numeric), "R5");
+}
@@ -0,0 +1,51 @@
+//! Exhaustive encode/decode consistency over the full 16-bit word space
+
+use lc3b_isa::{Instruction, OpCode, Register};
+
+#[test]
+fn every_word_decodes() {
+    for word in 0..=u16::MAX {
+        assert!(Instruction::try_from(word).is_ok(), "{:#06x} failed to decode", word);
+    }
+}
+
+#[test]
+fn strict_words_encode_back_exactly() {
+    for word in 0..=u16::MAX {
+        if let Ok(inst) = Instruction::decode_strict(word) {
+            let encoded: u16 = (&inst).into();
+            assert_eq!(encoded, word, "{:?} re-encoded as {:#06x}", inst, encoded);
+        }
+    }
+}
+
+#[test]
+fn decode_is_idempotent_after_encode() {
+    for word in 0..=u16::MAX {
+        let inst = Instruction::try_from(word).unwrap();
+        let encoded: u16 = (&inst).into();
+        assert_eq!(Instruction::try_from(encoded).unwrap(), inst, "{:#06x}", word);
+        assert_eq!(inst.opcode() as u16, word >> 12, "{:#06x}", word);
+    }
+}
+
+#[test]
+fn not_is_canonical_xor() {
+    let not = Instruction::not(Register::Register3, Register::Register2);
+    let word: u16 = (&not).into();
+    assert_eq!(word, 0b1001_011_010_1_11111);
+
+    let decoded = Instruction::try_from(word).unwrap();
+    assert_eq!(decoded, not);
+    assert!(decoded.is_not());
+    assert_eq!(OpCode::from(&decoded), OpCode::XOR);
+}
+
+#[test]
+fn store_word_uses_stw_opcode() {
+    let word = 0b0111_001_110_000010;
+    let inst = Instruction::try_from(word).unwrap();
+    assert!(matches!(inst, Instruction::Stw(..)));
+    assert_eq!(OpCode::from(&inst), OpCode::STW);
+    assert_eq!(u8::from(OpCode::STW), 0b0111);
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "serde")]
+
+use lc3b_isa::{
+    AddInstruction, Condition, Immediate4, Immediate5, Instruction, PCOffset11, PCOffset6,
+    PCOffset9, Privilege, Psr, Register,
+};
+
+#[test]
+fn instruction_round_trips_through_json() {
+    let instructions = [
+        Instruction::AddInstruction(AddInstruction::AddImm(
+            Register::Register1,
+            Register::Register2,
+            Immediate5::from_signed(-3).unwrap(),
+        )),
+        Instruction::Br(Condition { n: true, z: false, p: true }, PCOffset9::new(-4)),
+        Instruction::Ldr(Register::Register0, Register::Register6, PCOffset6::new(5).unwrap()),
+        Instruction::Ret,
+    ];
+
+    for inst in instructions {
+        let json = serde_json::to_string(&inst).unwrap();
+        let decoded: Instruction = serde_json::from_str(&json).unwrap();
+        assert_eq!(inst, decoded, "round trip failed for {}", json);
+    }
+}
+
+#[test]
+fn register_serializes_as_variant_name() {
+    let json = serde_json::to_string(&Register::Register7).unwrap();
+    assert_eq!(json, "\"Register7\"");
+}
+
+#[test]
+fn operand_out_of_range_is_rejected() {
+    assert!(serde_json::from_str::<Immediate5>("31").is_ok());
+    assert!(serde_json::from_str::<Immediate5>("32").is_err());
+    assert!(serde_json::from_str::<Immediate4>("16").is_err());
+    assert!(serde_json::from_str::<PCOffset6>("64").is_err());
+    assert!(serde_json::from_str::<PCOffset9>("512").is_err());
+    assert!(serde_json::from_str::<PCOffset11>("2047").is_ok());
+    assert!(serde_json::from_str::<PCOffset11>("2048").is_err());
+
+    let json = r#"{"AddInstruction":{"AddImm":["Register1","Register2",200]}}"#;
+    let err = serde_json::from_str::<Instruction>(json).unwrap_err();
+    assert!(err.to_string().contains("Immediate5 value 200 out of range"), "{}", err);
+}
+
+#[test]
+fn psr_priority_is_range_checked() {
+    let psr = Psr::new(Privilege::User, 7, Condition::default()).unwrap();
+    let json = serde_json::to_string(&psr).unwrap();
+    assert_eq!(serde_json::from_str::<Psr>(&json).unwrap(), psr);
+
+    let json = json.replace("\"priority\":7", "\"priority\":8");
+    let err = serde_json::from_str::<Psr>(&json).unwrap_err();
+    assert!(err.to_string().contains("PSR priority value 8 out of range"), "{}", err);
+}
+
+#[test]
+fn parse_errors_serialize() {
+    let err = "ADD R1, R2, #99".parse::<Instruction>().unwrap_err();
+    let json = serde_json::to_string(&err).unwrap();
+    assert!(json.contains(r#""kind":"Immediate5""#), "{}", json);
+}
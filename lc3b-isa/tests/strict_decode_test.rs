@@ -0,0 +1,52 @@
+use lc3b_isa::Instruction;
+
+#[test]
+fn strict_accepts_well_formed_words() {
+    let words = [
+        0b0001_000_001_0_00_010, // ADD R0, R1, R2
+        0b0101_000_001_1_11111,  // AND R0, R1, #-1
+        0b1100_000_111_000000,   // RET
+        0b0100_0_00_011_000000,  // JSRR R3
+        0b0100_1_00000000101,    // JSR
+        0b1000_000000000000,     // RTI
+        0b1111_0000_00100101,    // TRAP x25
+    ];
+    for word in words {
+        assert_eq!(
+            Instruction::decode_strict(word).unwrap(),
+            Instruction::try_from(word).unwrap(),
+            "{:#06x}",
+            word
+        );
+    }
+}
+
+#[test]
+fn strict_rejects_add_register_mode_garbage() {
+    let word = 0b0001_000_001_0_11_010;
+    assert!(Instruction::try_from(word).is_ok());
+    let err = Instruction::decode_strict(word).unwrap_err();
+    assert_eq!(err.word, word);
+    assert!(err.reason.contains("[4:3]"), "{}", err.reason);
+}
+
+#[test]
+fn strict_rejects_jmp_and_jsrr_garbage() {
+    let err = Instruction::decode_strict(0b1100_010_011_000000).unwrap_err();
+    assert!(err.reason.contains("[11:9]"), "{}", err.reason);
+
+    let err = Instruction::decode_strict(0b1100_000_011_000001).unwrap_err();
+    assert!(err.reason.contains("[5:0]"), "{}", err.reason);
+
+    let err = Instruction::decode_strict(0b0100_0_01_011_000000).unwrap_err();
+    assert!(err.reason.contains("[10:9]"), "{}", err.reason);
+}
+
+#[test]
+fn strict_rejects_rti_and_trap_garbage() {
+    let err = Instruction::decode_strict(0b1000_000000000001).unwrap_err();
+    assert!(err.reason.contains("[11:0]"), "{}", err.reason);
+
+    let err = Instruction::decode_strict(0b1111_0001_00100101).unwrap_err();
+    assert!(err.reason.contains("[11:8]"), "{}", err.reason);
+}
@@ -0,0 +1,41 @@
+use lc3b_isa::{
+    AddInstruction, Condition, Instruction, PCOffset6, PCOffset9, Register, StateMachineTiming,
+    TimingModel, TrapVect8,
+};
+
+#[test]
+fn default_model_cycle_counts() {
+    let add = Instruction::AddInstruction(AddInstruction::AddReg(
+        Register::Register0,
+        Register::Register1,
+        Register::Register2,
+    ));
+    assert_eq!(add.cycles(false), 9);
+
+    let ldr = Instruction::Ldr(Register::Register0, Register::Register1, PCOffset6::new(0).unwrap());
+    assert_eq!(ldr.cycles(false), 15);
+
+    assert_eq!(Instruction::Trap(TrapVect8::new(0x25)).cycles(false), 15);
+}
+
+#[test]
+fn taken_branch_costs_one_more_cycle() {
+    let br = Instruction::Br(Condition { n: true, z: true, p: true }, PCOffset9::new(-1));
+    assert_eq!(br.cycles(true), br.cycles(false) + 1);
+}
+
+#[test]
+fn memory_latency_scales_memory_instructions() {
+    let fast = StateMachineTiming::new(1);
+    let slow = StateMachineTiming::new(10);
+    let ldr = Instruction::Ldr(Register::Register0, Register::Register1, PCOffset6::new(0).unwrap());
+    let add = Instruction::AddInstruction(AddInstruction::AddReg(
+        Register::Register0,
+        Register::Register1,
+        Register::Register2,
+    ));
+
+    // Fetch pays the latency once; LDR pays it again for the data access
+    assert_eq!(slow.cycles(&add, false) - fast.cycles(&add, false), 9);
+    assert_eq!(slow.cycles(&ldr, false) - fast.cycles(&ldr, false), 18);
+}
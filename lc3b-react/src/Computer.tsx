@@ -11,6 +11,7 @@ import About, { AboutTab } from "./About";
 import Instructions from "./Instructions";
 import Assembly from "./Assembly";
 import { SamplePrograms } from "./SamplePrograms";
+import { loadSamples, SampleProgram } from "./samples";
 import { ThemeToggle } from "./ThemeContext";
 import Agent from "./Agent";
 import { useAgent } from "./AgentContext";
@@ -96,6 +97,7 @@ function Computer() {
   const { status: agentStatus, setLC3BState } = useAgent();
   const [compileError, setCompileError] = useState<string | null>(null);
   const [availableHeaders, setAvailableHeaders] = useState<string[]>([]);
+  const [samples, setSamples] = useState<SampleProgram[]>([]);
   const [expandedHeader, setExpandedHeader] = useState<string | null>(null);
   const [wasmLoaded, setWasmLoaded] = useState(false);
   const [programLoaded, setProgramLoaded] = useState(false);
@@ -150,30 +152,23 @@ function Computer() {
       setWasmLoaded(true);
       setWasmMemoryBytes(wasm_memory_size());
       setAvailableHeaders(get_available_headers());
+      setSamples(loadSamples());
     });
   }, []);
 
   const updateState = () => {
     const computer = computerRef.current;
     if (computer) {
-      const newPc = computer.program_counter();
-      const newConditions = {
-        n: computer.condition_n(),
-        z: computer.condition_z(),
-        p: computer.condition_p(),
-      };
+      const state = computer.cpu_state();
+      const newPc = state.pc;
+      const newConditions = { n: state.n, z: state.z, p: state.p };
+      const r = state.registers;
       const newRegisters = {
-        r0: computer.register(0),
-        r1: computer.register(1),
-        r2: computer.register(2),
-        r3: computer.register(3),
-        r4: computer.register(4),
-        r5: computer.register(5),
-        r6: computer.register(6),
-        r7: computer.register(7),
+        r0: r[0], r1: r[1], r2: r[2], r3: r[3],
+        r4: r[4], r5: r[5], r6: r[6], r7: r[7],
       };
       const newConsoleOutput = computer.console_output();
-      const newIsHalted = computer.is_halted();
+      const newIsHalted = state.halted;
       
       setPc(newPc);
       setConditions(newConditions);
@@ -253,11 +248,11 @@ function Computer() {
     }
   };
 
-  const handleReadMemory = (addr: number): number => {
+  const handleReadMemoryRange = (start: number, len: number): Uint16Array => {
     if (computerRef.current) {
-      return computerRef.current.read_memory(addr);
+      return computerRef.current.read_memory_range(start, len);
     }
-    return 0;
+    return new Uint16Array(len);
   };
 
   const handleLoadSample = (code: string, mode: "assembly" | "c") => {
@@ -512,7 +507,7 @@ function Computer() {
             <ConditionCodes n={conditions.n} z={conditions.z} p={conditions.p} />
             <RegisterSet registers={registers} modifiedRegister={modifiedRegister} />
             {programLoaded && (
-              <MemoryViewer programCounter={pc} readMemory={handleReadMemory} />
+              <MemoryViewer programCounter={pc} readMemoryRange={handleReadMemoryRange} />
             )}
           </div>
         </div>
@@ -531,6 +526,7 @@ function Computer() {
       {/* Samples Tab */}
       <div className={`flex-1 overflow-y-auto bg-[var(--bg-primary)] ${activeTab === "samples" ? "" : "hidden"}`}>
         <SamplePrograms 
+          samples={samples}
           onLoadSample={handleLoadSample}
           activeSubtab={examplesSubtab}
           onSubtabChange={setExamplesSubtab}
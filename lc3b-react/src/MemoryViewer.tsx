@@ -2,7 +2,7 @@ import { useState } from "react";
 
 export interface MemoryViewerProps {
   programCounter: number;
-  readMemory: (addr: number) => number;
+  readMemoryRange: (start: number, len: number) => Uint16Array;
 }
 
 function formatHex(value: number, digits: number = 4): string {
@@ -486,14 +486,15 @@ function MemoryRow({ addr, value, isPC }: MemoryRowProps) {
   );
 }
 
-function MemoryViewer({ programCounter, readMemory }: MemoryViewerProps) {
-  // Show 16 words centered on PC (PC-8 to PC+7)
+function MemoryViewer({ programCounter, readMemoryRange }: MemoryViewerProps) {
+  // Show 16 words centered on PC (PC-8 to PC+7), fetched in one call
   const startAddr = Math.max(0, programCounter - 8);
-  const endAddr = Math.min(0xffff, startAddr + 15);
+  const words = readMemoryRange(startAddr, 16);
 
   const rows = [];
-  for (let addr = startAddr; addr <= endAddr; addr++) {
-    const value = readMemory(addr);
+  for (let i = 0; i < words.length; i++) {
+    const addr = startAddr + i;
+    const value = words[i];
     rows.push(
       <MemoryRow
         key={addr}
@@ -1,15 +1,16 @@
-import { assemblyExamples, cExamples, SampleProgram } from "./samples";
+import { SampleProgram } from "./samples";
 
 type ExampleTab = "assembly" | "c";
 
 interface SampleProgramsProps {
+  samples: SampleProgram[];
   onLoadSample: (code: string, mode: "assembly" | "c") => void;
   activeSubtab: ExampleTab;
   onSubtabChange: (subtab: ExampleTab) => void;
 }
 
-export function SamplePrograms({ onLoadSample, activeSubtab, onSubtabChange }: SampleProgramsProps) {
-  const examples: SampleProgram[] = activeSubtab === "assembly" ? assemblyExamples : cExamples;
+export function SamplePrograms({ samples, onLoadSample, activeSubtab, onSubtabChange }: SampleProgramsProps) {
+  const examples = samples.filter((sample) => sample.language === activeSubtab);
 
   return (
     <div className="p-6">
@@ -29,6 +29,8 @@ fn main() {
         out_dir: wasm_pkg_path.display().to_string(),
         disable_dts: false, // Keep TypeScript definitions for React
         target: Target::Web,
+        // For WasmComputer::snapshot and restore
+        extra_options: vec!["--features".to_string(), "serde".to_string()],
         ..Default::default()
     };
     let mut build = Build::try_from_opts(build_opts).unwrap();
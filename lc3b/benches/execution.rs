@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use lc3b::{BufferedIO, Computer};
+
+const BUDGET: usize = 1_000_000;
+
+/// Count R1 down forever, wrapping around; runs stop at `BUDGET`
+fn countdown() -> Vec<u16> {
+    vec![
+        0b0001_001_001_1_11111, // x3000 ADD R1, R1, #-1
+        0b0000_111_111111110,   // x3001 BRnzp x3000
+    ]
+}
+
+fn machine(program: &[u16]) -> Computer<BufferedIO> {
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(program, 0x3000);
+    computer
+}
+
+fn bench_run(c: &mut Criterion) {
+    let program = countdown();
+    c.bench_function("run countdown", |b| {
+        b.iter_batched_ref(
+            || machine(&program),
+            |computer| black_box(computer.run(BUDGET)),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn bench_run_fast(c: &mut Criterion) {
+    let program = countdown();
+    c.bench_function("run_fast countdown", |b| {
+        b.iter_batched_ref(
+            || machine(&program),
+            |computer| black_box(computer.run_fast(BUDGET)),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_run, bench_run_fast);
+criterion_main!(benches);
@@ -0,0 +1,108 @@
+/// Optional checks on data accesses made by LDB/LDW/LDI/STB/STW/STI.
+/// All are off by default, matching the permissive behavior programs
+/// written for this emulator rely on.
+///
+/// Instruction fetch is not checked: memory is word-addressed and the PC
+/// advances one word per instruction, so there is no odd fetch address.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AccessChecks {
+    /// LDW/STW/LDI/STI to an odd address fail with `Error::AlignmentError`
+    pub alignment: bool,
+    /// User-mode accesses to system space (below `USER_PROGRAM_START`) or
+    /// the I/O page (`IO_PAGE_START` and up) fail with
+    /// `Error::InvalidMemoryAccess`
+    pub system_space: bool,
+    /// Reading a register or memory word nothing has written yet stops
+    /// the run with `Error::UninitializedRead` once the instruction has
+    /// retired, so resuming continues with the next one. Such reads are
+    /// always reported to `Observer::on_uninitialized_read`.
+    pub uninitialized: bool,
+}
+
+impl AccessChecks {
+    /// Every check enabled
+    pub fn strict() -> Self {
+        AccessChecks {
+            alignment: true,
+            system_space: true,
+            uninitialized: true,
+        }
+    }
+}
+
+/// Restrictions on a memory range set with `Computer::protect`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Protection {
+    /// STB/STW/STI into the range fail with `Error::WriteProtected`
+    pub read_only: bool,
+    /// Fetching an instruction from the range fails with
+    /// `Error::ExecuteProtected`
+    pub no_execute: bool,
+}
+
+impl Protection {
+    /// For code
+    pub const READ_ONLY: Protection = Protection {
+        read_only: true,
+        no_execute: false,
+    };
+
+    /// For data
+    pub const NO_EXECUTE: Protection = Protection {
+        read_only: false,
+        no_execute: true,
+    };
+}
+
+/// State read before anything wrote it. Memory counts as written once a
+/// program, the OS, an instruction or `write_memory` stores to it;
+/// registers once an instruction or `set_register` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UninitializedRead {
+    Register(u8),
+    Memory(u16),
+}
+
+impl std::fmt::Display for UninitializedRead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UninitializedRead::Register(index) => write!(f, "R{}", index),
+            UninitializedRead::Memory(addr) => write!(f, "memory at {:#06x}", addr),
+        }
+    }
+}
+
+/// Faults delivered to the program through the interrupt vector table, as
+/// in Appendix C, instead of stopping execution with an `Error`. All are
+/// off by default.
+///
+/// The handler is entered in supervisor mode at the current priority with
+/// the PSR and the address after the faulting instruction pushed, like
+/// `PRIVILEGE_MODE_EXCEPTION`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Exceptions {
+    /// Words that do not decode vector through `ILLEGAL_OPCODE_EXCEPTION`
+    pub illegal_opcode: bool,
+    /// `Error::InvalidMemoryAccess`, `Error::WriteProtected` and
+    /// `Error::ExecuteProtected` vector through
+    /// `ACCESS_CONTROL_VIOLATION_EXCEPTION`
+    pub access_violation: bool,
+    /// `Error::AlignmentError` vectors through `UNALIGNED_ACCESS_EXCEPTION`
+    pub unaligned_access: bool,
+    /// Treat opcodes 0b1010 and 0b1011 as unassigned, as Appendix C does,
+    /// rather than as LDI/STI. Whether they then vector or fail with
+    /// `Error::InstructionDecode` depends on `illegal_opcode`.
+    pub strict_opcodes: bool,
+}
+
+impl Exceptions {
+    /// Every fault vectored, with the Appendix C opcode set
+    pub fn all() -> Self {
+        Exceptions {
+            illegal_opcode: true,
+            access_violation: true,
+            unaligned_access: true,
+            strict_opcodes: true,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use super::Computer;
+use crate::Error;
+
+/// Extra test a breakpoint applies when the PC reaches its address
+pub type BreakpointCondition<I, O> = Box<dyn Fn(&Computer<I, O>) -> bool>;
+
+/// Test `Computer::set_stop_predicate` applies after each instruction
+pub type StopPredicate<I, O> = Box<dyn FnMut(&Computer<I, O>) -> bool>;
+
+/// Why a run stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// HALT executed (or the machine was already halted)
+    Halted,
+    /// The instruction budget ran out
+    MaxInstructions,
+    /// The budget set with `Computer::set_fuel` ran out
+    OutOfFuel,
+    /// The stop predicate or the observer asked to stop after the last
+    /// instruction
+    StopRequested,
+    /// The PC reached an enabled breakpoint; the instruction there has not
+    /// executed yet
+    Breakpoint(u16),
+    /// An instruction wrote to a watched address; that instruction has
+    /// executed
+    Watchpoint(u16),
+    /// `run_to`, `step_over` or `step_out` arrived at this PC
+    Reached(u16),
+    /// `run_for_cycles` used up its cycle budget
+    CycleLimit,
+    /// With loop detection on: the instruction at this PC is a taken BR to
+    /// itself and no interrupt can ever break the loop
+    InfiniteLoop(u16),
+    /// With loop detection on: the detection window passed without any
+    /// register, memory, condition code or I/O change; stopped at this PC
+    NoProgress(u16),
+    /// Fetching, decoding, or executing an instruction failed
+    Error(Error),
+}
+
+/// What halted the machine (`Computer::halt_reason`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HaltReason {
+    /// HALT (TRAP x25) in native trap mode
+    Trap,
+    /// A write cleared the clock enable bit of MCR, as the OS HALT
+    /// routine does
+    MachineControl,
+    /// The I/O asked to halt (`IO::halt_requested`), e.g. on Ctrl-C
+    Io,
+    /// The host called `Computer::halt`
+    Host,
+}
+
+impl std::fmt::Display for HaltReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HaltReason::Trap => "HALT trap",
+            HaltReason::MachineControl => "MCR clock disabled",
+            HaltReason::Io => "halted by I/O",
+            HaltReason::Host => "halted by host",
+        })
+    }
+}
+
+impl StopReason {
+    /// `Err` for `StopReason::Error`, otherwise the reason unchanged
+    pub fn into_result(self) -> Result<StopReason, Error> {
+        match self {
+            StopReason::Error(e) => Err(e),
+            reason => Ok(reason),
+        }
+    }
+}
@@ -1,12 +1,96 @@
-use lc3b_isa::{AddInstruction, AndInstruction, Condition, Instruction, PCOffset6, PCOffset9, PCOffset11, Register, XorInstruction};
-
-use crate::{Error, Memory, Observer, IO, USER_PROGRAM_START};
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::RangeInclusive;
+
+use lc3b_assembler::{AssembledProgram, SymbolTable};
+use lc3b_isa::{
+    AddInstruction, AndInstruction, Condition, Instruction, PCOffset6, PCOffset9, PCOffset11, Privilege, Psr,
+    Register, StateMachineTiming, TimingModel, XorInstruction,
+};
+
+use super::journal::{Journal, JournalEntry};
+use super::snapshot::{capture_pages, capture_written, restore_pages};
+use super::{
+    BreakpointCondition, Frame, FrameKind, HaltReason, Snapshot, StackEntry, StopPredicate,
+    StopReason, Watch, WatchChange, WatchExpr, SNAPSHOT_PAGE_SIZE,
+};
+use crate::{
+    device::overlap,
+    hexdump,
+    mmio::{DeviceRegisters, Rng},
+    os::OS_JUMP_TABLE_LEN,
+    AccessChecks, Device, Error, Exceptions, Interrupt, Memory, Observer, Protection, TrapMode,
+    UninitializedRead, ACCESS_CONTROL_VIOLATION_EXCEPTION, DDR, DEFAULT_SUPERVISOR_STACK,
+    ILLEGAL_OPCODE_EXCEPTION, INTERRUPT_VECTOR_TABLE, IO, IO_PAGE_START, OS_SOURCE,
+    PRIVILEGE_MODE_EXCEPTION, UNALIGNED_ACCESS_EXCEPTION, USER_PROGRAM_START, USER_STACK_START,
+};
+
+/// Host-side service routine for a TRAP vector
+pub type TrapHandler<I, O> = Box<dyn FnMut(&mut Computer<I, O>)>;
 
 pub struct Computer<I: IO, O: Observer = ()> {
     program_counter: u16,
-    condition: Condition,
+    psr: Psr,
     registers: [u16; 8],
+    /// Bit n set once Rn has been written
+    initialized_registers: u8,
+    /// R6 of whichever privilege mode is not currently running
+    saved_ssp: u16,
+    saved_usp: u16,
+    pending_interrupts: Vec<Interrupt>,
+    trap_mode: TrapMode,
+    trap_handlers: HashMap<u8, TrapHandler<I, O>>,
+    access_checks: AccessChecks,
+    exceptions: Exceptions,
+    /// First uninitialized read by the current instruction, kept while
+    /// `AccessChecks::uninitialized` is on
+    uninitialized_read: Option<UninitializedRead>,
+    /// First console word or character the I/O's encoding rejected during
+    /// the current instruction
+    encoding_error: Option<u32>,
+    /// Protected ranges in the order added; restrictions on overlapping
+    /// ranges combine
+    protections: Vec<(RangeInclusive<u16>, Protection)>,
+    /// Breakpoint addresses, each with an optional condition
+    breakpoints: BTreeMap<u16, Option<BreakpointCondition<I, O>>>,
+    watchpoints: BTreeSet<u16>,
+    /// Watched address written by the most recent instruction
+    watchpoint_hit: Option<u16>,
+    stop_predicate: Option<StopPredicate<I, O>>,
+    /// Watch expressions, re-evaluated after every instruction
+    watches: Vec<Watch>,
+    next_watch_id: usize,
+    watch_changes: Vec<WatchChange>,
+    /// Shadow call stack: JSR, JSRR, vector-table TRAPs, interrupts and
+    /// exceptions push; RET and RTI pop
+    call_stack: Vec<Frame>,
+    timing: Box<dyn TimingModel>,
+    /// Simulated clock cycles and instructions completed since creation
+    cycles: u64,
+    instructions_retired: u64,
+    /// Instructions executed by every call so far. Unlike
+    /// `instructions_retired`, step_back and snapshot restores don't
+    /// rewind it.
+    instructions_executed: u64,
+    /// Instructions runs may still execute across calls; None is unlimited
+    fuel: Option<u64>,
+    /// Why the machine halted; None while it can run
+    halt_reason: Option<HaltReason>,
+    /// No-progress window while loop detection is on
+    loop_window: Option<u64>,
+    /// `instructions_retired` when architectural state last changed
+    last_change: u64,
+    /// Undo history; None while journaling is off
+    journal: Option<Journal>,
     memory: Memory,
+    /// Addresses written by load_program and load_assembled
+    program_ranges: Vec<RangeInclusive<u16>>,
+    devices: DeviceRegisters,
+    /// Devices attached with attach_device
+    bus: Vec<Box<dyn Device>>,
+    /// Set whenever an instruction reads or writes a device register
+    device_accessed: bool,
+    symbols: SymbolTable,
     io: I,
     observer: O,
 }
@@ -23,14 +107,54 @@ impl<I: IO, O: Observer> Computer<I, O> {
     pub fn with_observer(io: I, observer: O) -> Self {
         Computer {
             program_counter: USER_PROGRAM_START,
-            condition: Condition::default(),
+            psr: Psr::new(Privilege::User, 0, Condition::default()).unwrap(),
             registers: [0u16; 8],
+            initialized_registers: 0,
+            saved_ssp: DEFAULT_SUPERVISOR_STACK,
+            saved_usp: 0,
+            pending_interrupts: Vec::new(),
+            trap_mode: TrapMode::default(),
+            trap_handlers: HashMap::new(),
+            access_checks: AccessChecks::default(),
+            exceptions: Exceptions::default(),
+            uninitialized_read: None,
+            encoding_error: None,
+            protections: Vec::new(),
+            breakpoints: BTreeMap::new(),
+            watchpoints: BTreeSet::new(),
+            watchpoint_hit: None,
+            stop_predicate: None,
+            watches: Vec::new(),
+            next_watch_id: 0,
+            watch_changes: Vec::new(),
+            call_stack: Vec::new(),
+            timing: Box::new(StateMachineTiming::default()),
+            cycles: 0,
+            instructions_retired: 0,
+            instructions_executed: 0,
+            fuel: None,
+            halt_reason: None,
+            loop_window: None,
+            last_change: 0,
+            journal: None,
             memory: Memory::default(),
+            program_ranges: Vec::new(),
+            devices: DeviceRegisters::default(),
+            bus: Vec::new(),
+            device_accessed: false,
+            symbols: SymbolTable::new(),
             io,
             observer,
         }
     }
 
+    /// Seed the random number device (`RNG`) so runs are reproducible
+    /// with a seed of the caller's choosing; `DEFAULT_RNG_SEED` otherwise
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.devices.rng = Rng::new(seed);
+        self
+    }
+
     // --- Accessors ---
 
     pub fn io(&self) -> &I {
@@ -54,19 +178,78 @@ impl<I: IO, O: Observer> Computer<I, O> {
     }
 
     pub fn condition(&self) -> Condition {
-        self.condition
+        self.psr.condition()
     }
 
     pub fn condition_n(&self) -> bool {
-        self.condition.n
+        self.psr.condition().n
     }
 
     pub fn condition_z(&self) -> bool {
-        self.condition.z
+        self.psr.condition().z
     }
 
     pub fn condition_p(&self) -> bool {
-        self.condition.p
+        self.psr.condition().p
+    }
+
+    /// Processor status: privilege, priority level, and condition codes
+    pub fn psr(&self) -> Psr {
+        self.psr
+    }
+
+    /// Overwrite the condition codes, notifying the observer if they change
+    pub fn set_condition(&mut self, condition: Condition) {
+        if condition != self.psr.condition() {
+            self.psr.set_condition(condition);
+            self.observer.on_condition_change(condition);
+        }
+    }
+
+    /// Overwrite the PSR. Changing privilege swaps R6 with the saved stack
+    /// pointer the same way entering and leaving supervisor mode does.
+    pub fn set_psr(&mut self, psr: Psr) {
+        if psr.is_supervisor() && self.psr.is_user() {
+            self.saved_usp = self.registers[6];
+            self.store_register(Register::Register6, self.saved_ssp);
+        } else if psr.is_user() && self.psr.is_supervisor() {
+            self.saved_ssp = self.registers[6];
+            self.store_register(Register::Register6, self.saved_usp);
+        }
+        let condition_changed = psr.condition() != self.psr.condition();
+        self.psr = psr;
+        if condition_changed {
+            self.observer.on_condition_change(psr.condition());
+        }
+    }
+
+    /// Supervisor stack pointer. While in supervisor mode this is R6;
+    /// otherwise it is the value R6 will be loaded with on the next
+    /// interrupt.
+    pub fn supervisor_stack_pointer(&self) -> u16 {
+        if self.psr.is_supervisor() {
+            self.registers[6]
+        } else {
+            self.saved_ssp
+        }
+    }
+
+    pub fn set_supervisor_stack_pointer(&mut self, ssp: u16) {
+        if self.psr.is_supervisor() {
+            self.store_register(Register::Register6, ssp);
+        } else {
+            self.saved_ssp = ssp;
+        }
+    }
+
+    /// User stack pointer: R6 in user mode, otherwise the value saved when
+    /// the processor last entered supervisor mode
+    pub fn user_stack_pointer(&self) -> u16 {
+        if self.psr.is_user() {
+            self.registers[6]
+        } else {
+            self.saved_usp
+        }
     }
 
     pub fn register(&self, index: u8) -> u16 {
@@ -77,35 +260,428 @@ impl<I: IO, O: Observer> Computer<I, O> {
         &self.registers
     }
 
+    /// Write a register by index (0-7), notifying the observer
+    pub fn set_register(&mut self, index: u8, value: u16) {
+        self.store_register(Register::from_index(index), value);
+    }
+
+    pub fn trap_mode(&self) -> TrapMode {
+        self.trap_mode
+    }
+
+    pub fn set_trap_mode(&mut self, mode: TrapMode) {
+        self.trap_mode = mode;
+    }
+
+    /// Run `handler` on the host whenever TRAP `vector` executes, in
+    /// either trap mode and in place of any built-in or in-memory routine.
+    /// The handler sees the PC of the TRAP instruction; execution resumes
+    /// at the following instruction. Replaces any earlier handler.
+    pub fn register_trap_handler(
+        &mut self,
+        vector: u8,
+        handler: impl FnMut(&mut Computer<I, O>) + 'static,
+    ) {
+        self.trap_handlers.insert(vector, Box::new(handler));
+    }
+
+    /// Remove the host handler for `vector`. Returns false if none was set.
+    pub fn unregister_trap_handler(&mut self, vector: u8) -> bool {
+        self.trap_handlers.remove(&vector).is_some()
+    }
+
+    pub fn access_checks(&self) -> AccessChecks {
+        self.access_checks
+    }
+
+    pub fn set_access_checks(&mut self, checks: AccessChecks) {
+        self.access_checks = checks;
+    }
+
+    pub fn exceptions(&self) -> Exceptions {
+        self.exceptions
+    }
+
+    /// Choose which faults the program handles through the vector table;
+    /// the rest stop execution with an `Error`
+    pub fn set_exceptions(&mut self, exceptions: Exceptions) {
+        self.exceptions = exceptions;
+    }
+
+    /// Restrict how instructions may use `range`, e.g. `Protection::READ_ONLY`
+    /// over a loaded program so stray stores into it fail instead of
+    /// corrupting it. `write_memory` and program loading are not affected.
+    pub fn protect(&mut self, range: RangeInclusive<u16>, protection: Protection) {
+        self.protections.push((range, protection));
+    }
+
+    pub fn protections(&self) -> &[(RangeInclusive<u16>, Protection)] {
+        &self.protections
+    }
+
+    pub fn clear_protections(&mut self) {
+        self.protections.clear();
+    }
+
+    /// Combined restrictions on `addr`
+    pub fn protection_at(&self, addr: u16) -> Protection {
+        self.protections
+            .iter()
+            .filter(|(range, _)| range.contains(&addr))
+            .fold(Protection::default(), |acc, (_, protection)| Protection {
+                read_only: acc.read_only || protection.read_only,
+                no_execute: acc.no_execute || protection.no_execute,
+            })
+    }
+
+    // --- Reset ---
+
+    /// Return the processor to its power-on state so a loaded program can
+    /// run again: registers zeroed (R6 included), PC at
+    /// `USER_PROGRAM_START`, user mode at priority 0 with cleared condition
+    /// codes, no pending interrupts, counters and built-in devices reset,
+    /// journal emptied, and the halted state cleared. Memory, breakpoints,
+    /// watchpoints, symbols, protections, trap handlers, the trap mode,
+    /// attached devices and the remaining fuel are kept.
+    pub fn reset(&mut self) {
+        for index in 0..8 {
+            if self.registers[index] != 0 {
+                self.store_register(Register::from_index(index as u8), 0);
+            }
+        }
+        self.initialized_registers = 0;
+        let psr = Psr::new(Privilege::User, 0, Condition::default()).unwrap();
+        if self.psr.condition() != psr.condition() {
+            self.observer.on_condition_change(psr.condition());
+        }
+        self.psr = psr;
+        self.saved_ssp = DEFAULT_SUPERVISOR_STACK;
+        self.saved_usp = 0;
+        self.pending_interrupts.clear();
+        self.uninitialized_read = None;
+        self.encoding_error = None;
+        self.watchpoint_hit = None;
+        self.call_stack.clear();
+        self.cycles = 0;
+        self.instructions_retired = 0;
+        self.instructions_executed = 0;
+        self.last_change = 0;
+        self.devices = DeviceRegisters::with_seed(self.devices.rng.seed);
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+        self.halt_reason = None;
+        self.set_pc(USER_PROGRAM_START);
+    }
+
+    /// Zero all memory, forgetting which words were ever written. The
+    /// journal is emptied since it refers to the old contents; the
+    /// processor state is untouched.
+    pub fn reset_memory(&mut self) {
+        self.memory = Memory::default();
+        self.program_ranges.clear();
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+    }
+
     // --- Memory ---
 
     pub fn load_program(&mut self, words: &[u16], start_addr: u16) {
-        self.memory.load_words(start_addr, words);
+        self.load_segment(start_addr, words);
         let old_pc = self.program_counter;
         self.program_counter = start_addr;
         self.observer.on_pc_change(old_pc, start_addr);
     }
 
+    /// Load an LC-3 object image: big-endian words, the first of which is
+    /// the origin the rest load at. The PC is pointed at the origin, which
+    /// is returned.
+    pub fn load_object(&mut self, bytes: &[u8]) -> Result<u16, Error> {
+        if !bytes.len().is_multiple_of(2) {
+            return Err(Error::InvalidObjectFile(format!("odd length of {} bytes", bytes.len())));
+        }
+        let words = lc3b_isa::words_from_bytes(bytes, lc3b_isa::Endianness::Big);
+        let Some((&origin, words)) = words.split_first() else {
+            return Err(Error::InvalidObjectFile("missing origin word".to_string()));
+        };
+        self.load_program(words, origin);
+        Ok(origin)
+    }
+
+    /// Load every segment of an assembled program at its own address and
+    /// point the PC at its origin. With `with_symbols`, its labels are
+    /// added to the symbol table.
+    pub fn load_assembled(&mut self, program: &AssembledProgram, with_symbols: bool) {
+        for (origin, words) in program.segments() {
+            self.load_segment(origin, words);
+        }
+        if with_symbols {
+            self.load_symbols(&program.symbols);
+        }
+        self.set_pc(program.origin);
+    }
+
+    fn load_segment(&mut self, origin: u16, words: &[u16]) {
+        self.memory.load_words(origin, words);
+        if !words.is_empty() {
+            let end = origin.wrapping_add(words.len() as u16 - 1);
+            if end >= origin {
+                self.program_ranges.push(origin..=end);
+            } else {
+                // Wrapped past xFFFF
+                self.program_ranges.push(origin..=0xFFFF);
+                self.program_ranges.push(0..=end);
+            }
+        }
+    }
+
+    /// Address ranges loaded as program code or data, in load order
+    pub fn program_ranges(&self) -> &[RangeInclusive<u16>] {
+        &self.program_ranges
+    }
+
+    pub fn is_program_address(&self, addr: u16) -> bool {
+        self.program_ranges.iter().any(|range| range.contains(&addr))
+    }
+
+    /// Assemble and load the built-in OS (see `OS_SOURCE`), point every
+    /// trap vector at it, set R6 to `USER_STACK_START`, and switch to
+    /// `TrapMode::VectorTable`. The PC is left alone, so this can be called
+    /// before or after loading a program. Trap vectors a program installs
+    /// must be written after this call.
+    pub fn load_os(&mut self) -> Result<(), Error> {
+        let os = lc3b_assembler::assemble(OS_SOURCE)
+            .map_err(|e| Error::ParseAssembly(format!("{:?}", e)))?;
+        self.memory.load_words(os.origin, &os.words);
+        self.load_symbols(&os.symbols);
+
+        let catch_all = os.origin + OS_JUMP_TABLE_LEN - 1;
+        for vector in 0..=0xFFu16 {
+            let handler = match vector {
+                0x20..=0x25 => os.origin + (vector - 0x20),
+                _ => catch_all,
+            };
+            self.memory.write_word(vector << 1, handler);
+        }
+
+        self.store_register(Register::Register6, USER_STACK_START);
+        self.trap_mode = TrapMode::VectorTable;
+        Ok(())
+    }
+
+    /// Read memory directly. Device registers are not consulted, so this
+    /// never consumes keyboard input.
     pub fn read_memory(&self, addr: u16) -> u16 {
         self.memory.read_word(addr)
     }
 
+    /// Up to `len` words from `start`, read directly like `read_memory`.
+    /// The slice stops at the end of memory rather than wrapping.
+    pub fn read_memory_range(&self, start: u16, len: usize) -> &[u16] {
+        self.memory.words(start, len)
+    }
+
+    /// `read_memory_range` formatted eight words per line with an ASCII
+    /// column, e.g. `x3000: 0048 0069 ...  Hi`
+    pub fn hexdump(&self, start: u16, len: usize) -> String {
+        hexdump(self.read_memory_range(start, len), start)
+    }
+
+    /// Addresses whose contents differ from `snapshot`, ascending
+    pub fn diff_memory(&self, snapshot: &Snapshot<I>) -> Vec<u16> {
+        let mut changed = Vec::new();
+        for page in 0..(0x10000 / SNAPSHOT_PAGE_SIZE) {
+            let start = (page * SNAPSHOT_PAGE_SIZE) as u16;
+            let current = self.memory.words(start, SNAPSHOT_PAGE_SIZE);
+            match snapshot.pages.get(&(page as u16)) {
+                Some(saved) => {
+                    for (offset, (now, then)) in current.iter().zip(saved).enumerate() {
+                        if now != then {
+                            changed.push(start + offset as u16);
+                        }
+                    }
+                }
+                None => {
+                    for (offset, &now) in current.iter().enumerate() {
+                        if now != 0 {
+                            changed.push(start + offset as u16);
+                        }
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    /// Write memory directly, bypassing device registers
     pub fn write_memory(&mut self, addr: u16, value: u16) {
         let old = self.memory.read_word(addr);
         self.memory.write_word(addr, value);
         self.observer.on_memory_write(addr, old, value);
     }
 
+    // --- Data accesses made by instructions (device registers routed to IO) ---
+
+    /// Apply the enabled `AccessChecks` to a data access by the instruction
+    /// at the current PC
+    fn check_access(&self, addr: u16, word: bool) -> Result<(), Error> {
+        if word && self.access_checks.alignment && addr & 1 != 0 {
+            return Err(Error::AlignmentError(format!(
+                "word access to odd address {:#06x} at {:#06x}",
+                addr, self.program_counter
+            )));
+        }
+        if self.access_checks.system_space
+            && self.psr.is_user()
+            && !(USER_PROGRAM_START..IO_PAGE_START).contains(&addr)
+        {
+            return Err(Error::InvalidMemoryAccess(addr));
+        }
+        Ok(())
+    }
+
+    /// Fail if the memory word at `addr` is read-only
+    fn check_store(&self, addr: u16) -> Result<(), Error> {
+        if self.protection_at(addr).read_only {
+            return Err(Error::WriteProtected {
+                pc: self.program_counter,
+                addr,
+            });
+        }
+        Ok(())
+    }
+
+    /// Report a read of never-written state to the observer, and keep it
+    /// for `next_instruction` to fail with if the check is on
+    fn uninitialized(&mut self, read: UninitializedRead) {
+        self.observer.on_uninitialized_read(self.program_counter, read);
+        if self.access_checks.uninitialized && self.uninitialized_read.is_none() {
+            self.uninitialized_read = Some(read);
+        }
+    }
+
+    fn load_word(&mut self, addr: u16) -> u16 {
+        if let Some(value) = self.read_device(addr) {
+            if let Some(ch) = self.devices.keyboard.rejected.take() {
+                self.encoding_error.get_or_insert(ch as u32);
+            }
+            self.observer.on_memory_read(addr, value);
+            return value;
+        }
+        if !self.memory.is_written(addr) {
+            self.uninitialized(UninitializedRead::Memory(addr));
+        }
+        let value = self.memory.read_word(addr);
+        self.observer.on_memory_read(addr, value);
+        value
+    }
+
+    /// The value of the device register at `addr`, if a device maps it
+    fn read_device(&mut self, addr: u16) -> Option<u16> {
+        let io: &mut dyn IO = &mut self.io;
+        let value = self
+            .devices
+            .iter_mut()
+            .into_iter()
+            .chain(self.bus.iter_mut().map(|device| &mut **device))
+            .find(|device| device.range().contains(&addr))
+            .map(|device| device.read_word(addr, io));
+        if value.is_some() {
+            self.device_accessed = true;
+            // Device reads can consume input
+            self.progress();
+        }
+        value
+    }
+
+    fn store_word(&mut self, addr: u16, value: u16) {
+        if self.watchpoints.contains(&addr) {
+            self.watchpoint_hit = Some(addr);
+        }
+        let printed = (addr == DDR).then(|| self.io.encoding().decode(value));
+        let io: &mut dyn IO = &mut self.io;
+        if let Some(device) = self
+            .devices
+            .iter_mut()
+            .into_iter()
+            .chain(self.bus.iter_mut().map(|device| &mut **device))
+            .find(|device| device.range().contains(&addr))
+        {
+            device.write_word(addr, value, io);
+            match printed {
+                Some(Some(ch)) => self.observer.on_io_output(ch),
+                Some(None) => {
+                    self.encoding_error.get_or_insert(value as u32);
+                }
+                None => {}
+            }
+            self.device_accessed = true;
+            self.last_change = self.instructions_retired;
+            return;
+        }
+        let old = self.memory.read_word(addr);
+        if old != value {
+            self.progress();
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.record_memory_write(addr, old, self.memory.is_written(addr));
+        }
+        self.memory.write_word(addr, value);
+        self.observer.on_memory_write(addr, old, value);
+    }
+
+    // --- Devices ---
+
+    /// Map `device` into memory. Fails if its range overlaps a built-in
+    /// device or one already attached.
+    pub fn attach_device(&mut self, device: impl Device) -> Result<(), Error> {
+        let range = device.range();
+        let taken = self
+            .devices
+            .ranges()
+            .into_iter()
+            .chain(self.bus.iter().map(|device| device.range()));
+        for existing in taken {
+            if let Some(addr) = overlap(&range, &existing) {
+                return Err(Error::DeviceOverlap(addr));
+            }
+        }
+        self.bus.push(Box::new(device));
+        Ok(())
+    }
+
+    /// The first attached device of type `T`
+    pub fn device<T: Device>(&self) -> Option<&T> {
+        self.bus
+            .iter()
+            .find_map(|device| (&**device as &dyn Any).downcast_ref::<T>())
+    }
+
+    pub fn device_mut<T: Device>(&mut self) -> Option<&mut T> {
+        self.bus
+            .iter_mut()
+            .find_map(|device| (&mut **device as &mut dyn Any).downcast_mut::<T>())
+    }
+
     // --- Register operations (with observer notifications) ---
 
-    fn load_register(&self, register: Register) -> u16 {
-        self.registers[register.to_index()]
+    fn load_register(&mut self, register: Register) -> u16 {
+        let index = register.to_index();
+        if self.initialized_registers & 1 << index == 0 {
+            self.uninitialized(UninitializedRead::Register(index as u8));
+        }
+        self.registers[index]
     }
 
     fn store_register(&mut self, register: Register, value: u16) {
         let index = register.to_index();
         let old = self.registers[index];
         self.registers[index] = value;
+        self.initialized_registers |= 1 << index;
+        if old != value {
+            self.progress();
+        }
         self.observer.on_register_write(index as u8, old, value);
     }
 
@@ -116,13 +692,15 @@ impl<I: IO, O: Observer> Computer<I, O> {
             z: signed_value == 0,
             p: signed_value > 0,
         };
-        if new_cond != self.condition {
-            self.condition = new_cond;
+        if new_cond != self.psr.condition() {
+            self.progress();
+            self.psr.set_condition(new_cond);
             self.observer.on_condition_change(new_cond);
         }
     }
 
-    fn set_pc(&mut self, new_pc: u16) {
+    /// Move the PC; the next instruction executes from `new_pc`
+    pub fn set_pc(&mut self, new_pc: u16) {
         let old_pc = self.program_counter;
         self.program_counter = new_pc;
         if old_pc != new_pc {
@@ -130,41 +708,850 @@ impl<I: IO, O: Observer> Computer<I, O> {
         }
     }
 
+    // --- Interrupts ---
+
+    /// Request an interrupt. It is serviced before the next instruction
+    /// once its priority exceeds the current PSR priority; until then it
+    /// stays pending.
+    pub fn raise_interrupt(&mut self, interrupt: Interrupt) {
+        self.pending_interrupts.push(interrupt);
+    }
+
+    pub fn pending_interrupts(&self) -> &[Interrupt] {
+        &self.pending_interrupts
+    }
+
+    /// Highest-priority request able to preempt the running code: a raised
+    /// interrupt or a device request (the keyboard when KBSR has ready and
+    /// IE set, the timer when TMR does). Raised interrupts win ties.
+    fn take_interrupt(&mut self) -> Option<Interrupt> {
+        let current = self.psr.priority();
+        let raised = self
+            .pending_interrupts
+            .iter()
+            .enumerate()
+            .filter(|(_, interrupt)| interrupt.priority() > current)
+            .max_by_key(|(_, interrupt)| interrupt.priority())
+            .map(|(index, interrupt)| (index, *interrupt));
+
+        let io: &mut dyn IO = &mut self.io;
+        let device = self
+            .devices
+            .iter_mut()
+            .into_iter()
+            .chain(self.bus.iter_mut().map(|device| &mut **device))
+            .filter_map(|device| device.interrupt_request(io))
+            .filter(|interrupt| interrupt.priority() > current)
+            .max_by_key(|interrupt| interrupt.priority());
+
+        match (raised, device) {
+            (Some((_, interrupt)), Some(device)) if device.priority() > interrupt.priority() => {
+                Some(device)
+            }
+            (Some((index, interrupt)), _) => {
+                self.pending_interrupts.remove(index);
+                Some(interrupt)
+            }
+            (None, device) => device,
+        }
+    }
+
+    fn push_word(&mut self, value: u16) {
+        let sp = self.registers[6].wrapping_sub(2);
+        self.store_register(Register::Register6, sp);
+        self.store_word(sp, value);
+    }
+
+    fn pop_word(&mut self) -> u16 {
+        let sp = self.registers[6];
+        let value = self.load_word(sp);
+        self.store_register(Register::Register6, sp.wrapping_add(2));
+        value
+    }
+
+    /// Switch to the supervisor stack if needed, enter supervisor mode at
+    /// `priority`, and push the old PSR then `return_pc`
+    fn enter_supervisor(&mut self, priority: u8, return_pc: u16) {
+        let old_psr = self.psr;
+        if old_psr.is_user() {
+            self.saved_usp = self.registers[6];
+            self.store_register(Register::Register6, self.saved_ssp);
+        }
+        self.psr = Psr::new(Privilege::Supervisor, priority, old_psr.condition())
+            .expect("priority comes from a valid Psr or Interrupt");
+
+        self.push_word(u16::from(old_psr));
+        self.push_word(return_pc);
+    }
+
+    fn vector_handler(&self, vector: u8) -> u16 {
+        self.memory.read_word(INTERRUPT_VECTOR_TABLE + ((vector as u16) << 1))
+    }
+
+    /// Enter the handler for `interrupt` at its priority level. Runs
+    /// between instructions, so the PC pushed is the next instruction.
+    fn initiate_interrupt(&mut self, interrupt: Interrupt) {
+        let pc = self.program_counter;
+        self.enter_supervisor(interrupt.priority(), pc);
+        let handler = self.vector_handler(interrupt.vector());
+        self.call_stack.push(Frame {
+            kind: FrameKind::Interrupt(interrupt.vector()),
+            call_site: pc,
+            target: handler,
+            return_address: pc,
+        });
+        self.set_pc(handler);
+    }
+
+    /// Enter the handler for exception `vector` from inside `execute`,
+    /// keeping the current priority. The PC pushed is the instruction
+    /// after the faulting one.
+    fn initiate_exception(&mut self, vector: u8) {
+        let return_pc = self.program_counter.wrapping_add(1);
+        self.enter_supervisor(self.psr.priority(), return_pc);
+        let handler = self.vector_handler(vector);
+        self.call_stack.push(Frame {
+            kind: FrameKind::Interrupt(vector),
+            call_site: self.program_counter,
+            target: handler,
+            return_address: return_pc,
+        });
+        // next_instruction adds 1 after execute
+        self.program_counter = handler.wrapping_sub(1);
+    }
+
+    /// The exception vector `error` is delivered through, if
+    /// `set_exceptions` enabled it
+    fn exception_vector(&self, error: &Error) -> Option<u8> {
+        match error {
+            Error::InstructionDecode { .. } if self.exceptions.illegal_opcode => {
+                Some(ILLEGAL_OPCODE_EXCEPTION)
+            }
+            Error::InvalidMemoryAccess(_)
+            | Error::WriteProtected { .. }
+            | Error::ExecuteProtected(_)
+                if self.exceptions.access_violation =>
+            {
+                Some(ACCESS_CONTROL_VIOLATION_EXCEPTION)
+            }
+            Error::AlignmentError(_) if self.exceptions.unaligned_access => {
+                Some(UNALIGNED_ACCESS_EXCEPTION)
+            }
+            _ => None,
+        }
+    }
+
+    /// The instruction at `pc`, checking no-execute protection and, with
+    /// `Exceptions::strict_opcodes`, the Appendix C opcode set
+    fn fetch(&mut self, pc: u16) -> Result<Instruction, Error> {
+        if self.protection_at(pc).no_execute {
+            return Err(Error::ExecuteProtected(pc));
+        }
+        let inst = self
+            .memory
+            .decode(pc)
+            .map_err(|e| Error::InstructionDecode {
+                address: pc,
+                reason: e.to_string(),
+            })?;
+        if self.exceptions.strict_opcodes
+            && matches!(inst, Instruction::Ldi(..) | Instruction::Sti(..))
+        {
+            return Err(Error::InstructionDecode {
+                address: pc,
+                reason: format!("Unknown opcode: {:04b}", self.memory.read_word(pc) >> 12),
+            });
+        }
+        Ok(inst)
+    }
+
+    // --- Symbols ---
+
+    /// Add the labels from `symbols`, replacing any existing label of the
+    /// same name. `load_os` adds the OS's own labels this way.
+    pub fn load_symbols(&mut self, symbols: &SymbolTable) {
+        for (name, addr) in symbols.iter() {
+            self.symbols.insert(name, addr);
+        }
+    }
+
+    pub fn clear_symbols(&mut self) {
+        self.symbols = SymbolTable::new();
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    pub fn symbol_at(&self, addr: u16) -> Option<&str> {
+        self.symbols.symbol_at(addr)
+    }
+
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.symbols.address_of(name)
+    }
+
+    /// `addr` as `LABEL` or `LABEL+n` using the nearest label at or below
+    /// it, or as `x3004` when there is none
+    pub fn describe_address(&self, addr: u16) -> String {
+        match self.symbols.nearest(addr) {
+            Some((name, 0)) => name.to_string(),
+            Some((name, offset)) => format!("{}+{}", name, offset),
+            None => format!("x{:04X}", addr),
+        }
+    }
+
+    /// One line per active call, innermost first, e.g.
+    /// `#0 SUB called from MAIN+1`
+    pub fn backtrace(&self) -> Vec<String> {
+        self.call_stack
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(depth, frame)| {
+                format!(
+                    "#{} {} called from {}",
+                    depth,
+                    self.describe_address(frame.target),
+                    self.describe_address(frame.call_site)
+                )
+            })
+            .collect()
+    }
+
+    // --- Breakpoints ---
+
+    /// Stop runs when the PC reaches `addr`. Returns false if a breakpoint
+    /// was already set there (its condition, if any, is removed).
+    pub fn add_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.insert(addr, None).is_none()
+    }
+
+    /// Stop runs when the PC reaches `addr` and `condition` holds, e.g.
+    /// `|c| c.register(0) == 0`. Replaces any breakpoint at `addr`.
+    pub fn add_conditional_breakpoint(
+        &mut self,
+        addr: u16,
+        condition: impl Fn(&Computer<I, O>) -> bool + 'static,
+    ) {
+        self.breakpoints.insert(addr, Some(Box::new(condition)));
+    }
+
+    /// Break at a label, like `break main`
+    pub fn add_symbol_breakpoint(&mut self, name: &str) -> Result<bool, Error> {
+        let addr = self
+            .symbols
+            .address_of(name)
+            .ok_or_else(|| Error::UndefinedLabel(name.to_string()))?;
+        Ok(self.add_breakpoint(addr))
+    }
+
+    /// Returns false if there was no breakpoint at `addr`
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.remove(&addr).is_some()
+    }
+
+    /// Breakpoint addresses in ascending order
+    pub fn list_breakpoints(&self) -> Vec<u16> {
+        self.breakpoints.keys().copied().collect()
+    }
+
+    pub fn has_breakpoint(&self, addr: u16) -> bool {
+        self.breakpoints.contains_key(&addr)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Stop runs after any instruction that writes the word at `addr`,
+    /// whether or not the value changes. Returns false if already watched.
+    pub fn add_watchpoint(&mut self, addr: u16) -> bool {
+        self.watchpoints.insert(addr)
+    }
+
+    /// Returns false if `addr` was not watched
+    pub fn remove_watchpoint(&mut self, addr: u16) -> bool {
+        self.watchpoints.remove(&addr)
+    }
+
+    /// Watched addresses in ascending order
+    pub fn list_watchpoints(&self) -> Vec<u16> {
+        self.watchpoints.iter().copied().collect()
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Stop runs with `StopReason::StopRequested` after the first
+    /// instruction for which `predicate` returns true, e.g. to run until
+    /// the output contains some text or a wall-clock deadline passes.
+    /// Replaces any previous predicate.
+    pub fn set_stop_predicate(
+        &mut self,
+        predicate: impl FnMut(&Computer<I, O>) -> bool + 'static,
+    ) {
+        self.stop_predicate = Some(Box::new(predicate));
+    }
+
+    pub fn clear_stop_predicate(&mut self) {
+        self.stop_predicate = None;
+    }
+
+    fn stop_requested(&mut self) -> bool {
+        let observer_stop = self.observer.stop_requested();
+        let Some(mut predicate) = self.stop_predicate.take() else {
+            return observer_stop;
+        };
+        let stop = predicate(self);
+        self.stop_predicate = Some(predicate);
+        observer_stop || stop
+    }
+
+    // --- Watch expressions ---
+
+    /// The value of `expression` (see `WatchExpr`) in the current state,
+    /// e.g. `R3 + 2`, `[x4000]` or `label+1`
+    pub fn evaluate(&self, expression: &str) -> Result<u16, Error> {
+        self.evaluate_expr(&expression.parse()?)
+    }
+
+    pub fn evaluate_expr(&self, expr: &WatchExpr) -> Result<u16, Error> {
+        Ok(match expr {
+            WatchExpr::Register(index) => self.registers[*index as usize],
+            WatchExpr::ProgramCounter => self.program_counter,
+            WatchExpr::Constant(value) => *value,
+            WatchExpr::Label(name) => self
+                .symbols
+                .address_of(name)
+                .ok_or_else(|| Error::UndefinedLabel(name.clone()))?,
+            WatchExpr::Memory(addr) => self.memory.read_word(self.evaluate_expr(addr)?),
+            WatchExpr::Add(lhs, rhs) => {
+                self.evaluate_expr(lhs)?.wrapping_add(self.evaluate_expr(rhs)?)
+            }
+            WatchExpr::Sub(lhs, rhs) => {
+                self.evaluate_expr(lhs)?.wrapping_sub(self.evaluate_expr(rhs)?)
+            }
+        })
+    }
+
+    /// Re-evaluate `expression` after every instruction and log each
+    /// change of its value to `watch_changes`. Cheaper to show than
+    /// watchpoints since runs don't stop. Returns an id for `remove_watch`.
+    pub fn add_watch(&mut self, expression: &str) -> Result<usize, Error> {
+        let expr: WatchExpr = expression.parse()?;
+        let id = self.next_watch_id;
+        self.next_watch_id += 1;
+        self.watches.push(Watch {
+            id,
+            source: expression.to_string(),
+            value: self.evaluate_expr(&expr).ok(),
+            expr,
+        });
+        Ok(id)
+    }
+
+    /// Returns false if there was no watch with that id
+    pub fn remove_watch(&mut self, id: usize) -> bool {
+        let before = self.watches.len();
+        self.watches.retain(|watch| watch.id != id);
+        self.watches.len() != before
+    }
+
+    pub fn clear_watches(&mut self) {
+        self.watches.clear();
+        self.watch_changes.clear();
+    }
+
+    /// Each watch as (id, expression, value after the last instruction);
+    /// the value is None while the expression can't be evaluated
+    pub fn watches(&self) -> Vec<(usize, &str, Option<u16>)> {
+        self.watches
+            .iter()
+            .map(|watch| (watch.id, watch.source.as_str(), watch.value))
+            .collect()
+    }
+
+    /// Value changes since the last `take_watch_changes`, oldest first
+    pub fn watch_changes(&self) -> &[WatchChange] {
+        &self.watch_changes
+    }
+
+    pub fn take_watch_changes(&mut self) -> Vec<WatchChange> {
+        std::mem::take(&mut self.watch_changes)
+    }
+
+    fn update_watches(&mut self, pc: u16) {
+        let values: Vec<Option<u16>> = self
+            .watches
+            .iter()
+            .map(|watch| self.evaluate_expr(&watch.expr).ok())
+            .collect();
+        for (watch, new) in self.watches.iter_mut().zip(values) {
+            if watch.value != new {
+                self.watch_changes.push(WatchChange {
+                    id: watch.id,
+                    pc,
+                    old: watch.value,
+                    new,
+                });
+                watch.value = new;
+            }
+        }
+    }
+
+    fn breakpoint_hit(&self, pc: u16) -> bool {
+        match self.breakpoints.get(&pc) {
+            Some(Some(condition)) => condition(self),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    // --- Reverse execution ---
+
+    /// Record up to `capacity` instructions so they can be undone with
+    /// `step_back`. Replaces any existing history.
+    pub fn enable_journal(&mut self, capacity: usize) {
+        self.journal = Some(Journal::new(capacity));
+    }
+
+    /// Stop recording and drop the history
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Number of instructions `step_back` can currently undo
+    pub fn journal_len(&self) -> usize {
+        self.journal.as_ref().map_or(0, Journal::len)
+    }
+
+    /// Undo up to `count` instructions, newest first, and return how many
+    /// were undone. Registers, PC, PSR, stack pointers and memory are
+    /// restored and reported to the observer; I/O side effects (output
+    /// written, input consumed, halting) are not.
+    pub fn step_back(&mut self, count: usize) -> usize {
+        let mut undone = 0;
+        while undone < count {
+            let Some(entry) = self.journal.as_mut().and_then(Journal::pop) else {
+                break;
+            };
+            self.restore_entry(entry);
+            undone += 1;
+        }
+        undone
+    }
+
+    fn journal_entry(&self) -> JournalEntry {
+        JournalEntry {
+            program_counter: self.program_counter,
+            psr: self.psr,
+            registers: self.registers,
+            initialized_registers: self.initialized_registers,
+            saved_ssp: self.saved_ssp,
+            saved_usp: self.saved_usp,
+            call_stack: self.call_stack.clone(),
+            cycles: self.cycles,
+            instructions_retired: self.instructions_retired,
+            devices: self.devices,
+            pending_interrupts: self.pending_interrupts.clone(),
+            halt_reason: self.halt_reason,
+            memory: Vec::new(),
+        }
+    }
+
+    fn restore_entry(&mut self, entry: JournalEntry) {
+        for &(addr, old, written) in entry.memory.iter().rev() {
+            let current = self.memory.read_word(addr);
+            self.memory.write_word(addr, old);
+            if !written {
+                self.memory.forget_write(addr);
+            }
+            self.observer.on_memory_write(addr, current, old);
+        }
+        for (index, &value) in entry.registers.iter().enumerate() {
+            if self.registers[index] != value {
+                self.store_register(Register::from_index(index as u8), value);
+            }
+        }
+        self.initialized_registers = entry.initialized_registers;
+        if entry.psr.condition() != self.psr.condition() {
+            self.observer.on_condition_change(entry.psr.condition());
+        }
+        self.psr = entry.psr;
+        self.saved_ssp = entry.saved_ssp;
+        self.saved_usp = entry.saved_usp;
+        self.call_stack = entry.call_stack;
+        self.cycles = entry.cycles;
+        self.instructions_retired = entry.instructions_retired;
+        self.devices = entry.devices;
+        self.pending_interrupts = entry.pending_interrupts;
+        self.halt_reason = entry.halt_reason;
+        self.set_pc(entry.program_counter);
+    }
+
+    // --- Halting ---
+
+    pub fn is_halted(&self) -> bool {
+        self.halt_reason.is_some()
+    }
+
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt_reason
+    }
+
+    /// Halt the machine as HALT would; runs stop with `StopReason::Halted`
+    /// until `clear_halt` or `reset`
+    pub fn halt(&mut self) {
+        self.halt_with(HaltReason::Host);
+    }
+
+    /// Let a halted machine continue from where it stopped
+    pub fn clear_halt(&mut self) {
+        self.halt_reason = None;
+        self.devices.machine_control.halted = false;
+    }
+
+    /// The first halt wins; later ones while halted are ignored
+    fn halt_with(&mut self, reason: HaltReason) {
+        if self.halt_reason.is_none() {
+            self.halt_reason = Some(reason);
+            self.devices.machine_control.halted = true;
+            self.io.on_halt();
+        }
+    }
+
+    /// Halt for an MCR write or an I/O request during the last instruction
+    fn check_halt_requests(&mut self) {
+        if self.halt_reason.is_some() {
+            return;
+        }
+        if self.devices.machine_control.halted {
+            self.halt_with(HaltReason::MachineControl);
+        } else if self.io.halt_requested() {
+            self.halt_with(HaltReason::Io);
+        }
+    }
+
     // --- Execution ---
 
     pub fn next_instruction(&mut self) -> Result<(), Error> {
-        if self.io.is_halted() {
+        if self.is_halted() {
             return Ok(());
         }
 
-        let pc = self.program_counter;
-        let word = self.memory.read_word(pc);
+        if self.journal.is_some() {
+            let entry = self.journal_entry();
+            if let Some(journal) = &mut self.journal {
+                journal.push(entry);
+            }
+        }
 
-        match Instruction::try_from(word) {
-            Ok(inst) => {
-                self.observer.on_instruction_start(pc, &inst);
-                self.execute(inst)?;
-                self.observer.on_instruction_end(pc, &inst);
+        if let Some(interrupt) = self.take_interrupt() {
+            self.initiate_interrupt(interrupt);
+        }
 
-                // Increment PC
+        let pc = self.program_counter;
+        let inst = match self.fetch(pc) {
+            Ok(inst) => inst,
+            Err(e) => {
+                let vector = self.exception_vector(&e).ok_or(e)?;
+                self.initiate_exception(vector);
                 self.set_pc(self.program_counter.wrapping_add(1));
-                Ok(())
+                return Ok(());
             }
-            Err(e) => Err(Error::InstructionDecode {
-                address: pc,
-                reason: e.to_string(),
-            }),
+        };
+        self.observer.on_instruction_start(pc, &inst);
+        self.uninitialized_read = None;
+        self.encoding_error = None;
+        self.io.set_clock(self.instructions_executed);
+        if let Err(e) = self.execute(inst) {
+            let vector = self.exception_vector(&e).ok_or(e)?;
+            self.initiate_exception(vector);
+        }
+        self.check_halt_requests();
+        // The reads and console I/O already happened, so the instruction
+        // retires as usual and the error stops the run after it
+        let error = self.access_error(pc);
+        self.observer.on_instruction_end(pc, &inst);
+        if self.is_halted() {
+            self.observer.on_halt();
+        }
+
+        // Increment PC
+        self.set_pc(self.program_counter.wrapping_add(1));
+
+        let branch_taken = self.program_counter != pc.wrapping_add(1);
+        let cycles = self.timing.cycles(&inst, branch_taken);
+        self.cycles += u64::from(cycles);
+        self.instructions_retired += 1;
+        self.consume_fuel();
+        for device in self.devices.iter_mut() {
+            device.tick(cycles);
+        }
+        for device in &mut self.bus {
+            device.tick(cycles);
+        }
+        if !self.watches.is_empty() {
+            self.update_watches(pc);
+        }
+        match error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// The uninitialized read or rejected console character noted while
+    /// executing the instruction at `pc`, if any
+    fn access_error(&mut self, pc: u16) -> Option<Error> {
+        let uninitialized = self.uninitialized_read.take();
+        let rejected = self.encoding_error.take();
+        match (uninitialized, rejected) {
+            (Some(read), _) => Some(Error::UninitializedRead { pc, read }),
+            (None, Some(value)) => Some(Error::NotAscii { pc, value }),
+            (None, None) => None,
+        }
+    }
+
+    /// Run until halted, a breakpoint or watchpoint is hit, an error
+    /// occurs, or max_instructions have executed, and report which
+    pub fn run(&mut self, max_instructions: usize) -> StopReason {
+        self.run_counted(max_instructions, |_| None).1
+    }
+
+    /// The old `run` signature: the number of instructions executed, or
+    /// the error that stopped execution
+    pub fn run_count(&mut self, max_instructions: usize) -> Result<usize, Error> {
+        match self.run_counted(max_instructions, |_| None) {
+            (_, StopReason::Error(e)) => Err(e),
+            (count, _) => Ok(count),
+        }
+    }
+
+    /// Run until the PC reaches `addr`, stopping with `Reached(addr)`.
+    /// Breakpoints, watchpoints and the budget still apply.
+    pub fn run_to(&mut self, addr: u16, max_instructions: usize) -> StopReason {
+        self.run_counted(max_instructions, |c| {
+            (c.program_counter == addr).then_some(StopReason::Reached(addr))
+        })
+        .1
+    }
+
+    /// Execute one instruction, treating a call (JSR, JSRR, or a
+    /// vector-table TRAP) as a single step that ends when it returns
+    pub fn step_over(&mut self, max_instructions: usize) -> StopReason {
+        let depth = self.call_stack.len();
+        self.run_counted(max_instructions, |c| {
+            (c.call_stack.len() <= depth).then_some(StopReason::Reached(c.program_counter))
+        })
+        .1
+    }
+
+    /// Run until the current subroutine or handler returns to its caller.
+    /// At the top level there is nothing to return from, so this runs
+    /// like `run`.
+    pub fn step_out(&mut self, max_instructions: usize) -> StopReason {
+        let depth = self.call_stack.len();
+        self.run_counted(max_instructions, |c| {
+            (c.call_stack.len() < depth).then_some(StopReason::Reached(c.program_counter))
+        })
+        .1
+    }
+
+    /// Current subroutine nesting as seen by step_over/step_out
+    pub fn call_depth(&self) -> usize {
+        self.call_stack.len()
+    }
+
+    /// Active calls, outermost first. Built from JSR/JSRR/TRAP/interrupt
+    /// entry and RET/RTI, so code that returns some other way (JMP through
+    /// a saved R7, or a handler that never returns) can leave stale frames.
+    pub fn call_stack(&self) -> Vec<Frame> {
+        self.call_stack.clone()
+    }
+
+    /// `depth` stack slots from R6 upward (older entries), a slot being
+    /// the two-address stride LDW/STW use, and stopping at the end of
+    /// memory. R5 is taken as the frame pointer; when it is below R6 no
+    /// frame is active.
+    pub fn stack_view(&self, depth: usize) -> Vec<StackEntry> {
+        let sp = self.registers[6];
+        let fp = self.registers[5];
+        // Saved R7 at FP, the caller's FP in the next slot
+        let frame_top = (fp >= sp).then(|| fp.saturating_add(2));
+        (0..depth)
+            .map_while(|slot| sp.checked_add(u16::try_from(slot * 2).ok()?))
+            .map(|addr| StackEntry {
+                addr,
+                value: self.memory.read_word(addr),
+                in_frame: frame_top.is_some_and(|top| addr <= top),
+            })
+            .collect()
+    }
+
+    /// Run until at least `cycles` more simulated clock cycles have
+    /// elapsed, stopping with `CycleLimit`. The last instruction may
+    /// overshoot; other stop conditions still apply.
+    pub fn run_for_cycles(&mut self, cycles: u64) -> StopReason {
+        if cycles == 0 {
+            return StopReason::CycleLimit;
+        }
+        let end = self.cycles.saturating_add(cycles);
+        self.run_counted(usize::MAX, |c| {
+            (c.cycles >= end).then_some(StopReason::CycleLimit)
+        })
+        .1
+    }
+
+    /// Simulated clock cycles so far under the current timing model
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Instructions completed so far
+    pub fn instructions_retired(&self) -> u64 {
+        self.instructions_retired
+    }
+
+    /// Instructions executed across all calls, including any step_back
+    /// later undid
+    pub fn instructions_executed(&self) -> u64 {
+        self.instructions_executed
+    }
+
+    /// Limit how many more instructions runs may execute in total, so a
+    /// host can slice execution into many short `run` calls while still
+    /// enforcing one overall budget. Runs that use it up stop with
+    /// `StopReason::OutOfFuel`; None (the default) removes the limit.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    /// Remaining budget, or None when unlimited
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Extend a limited budget by `amount`; no effect when unlimited
+    pub fn add_fuel(&mut self, amount: u64) {
+        if let Some(fuel) = &mut self.fuel {
+            *fuel = fuel.saturating_add(amount);
+        }
+    }
+
+    fn consume_fuel(&mut self) {
+        self.instructions_executed += 1;
+        if let Some(fuel) = &mut self.fuel {
+            *fuel = fuel.saturating_sub(1);
         }
     }
 
-    /// Run until halted or max_instructions reached
-    pub fn run(&mut self, max_instructions: usize) -> Result<usize, Error> {
+    /// Average cycles per instruction, or 0.0 before the first one
+    pub fn cycles_per_instruction(&self) -> f64 {
+        if self.instructions_retired == 0 {
+            0.0
+        } else {
+            self.cycles as f64 / self.instructions_retired as f64
+        }
+    }
+
+    /// Replace the timing model used for cycle counting (by default
+    /// `StateMachineTiming` with five-cycle memory)
+    pub fn set_timing_model(&mut self, timing: impl TimingModel + 'static) {
+        self.timing = Box::new(timing);
+    }
+
+    /// Run until `stop` returns a reason after at least one instruction,
+    /// or another stop condition fires. A breakpoint at the starting PC is
+    /// ignored, so calling again after stopping at one resumes past it.
+    fn run_counted(
+        &mut self,
+        max_instructions: usize,
+        stop: impl Fn(&Self) -> Option<StopReason>,
+    ) -> (usize, StopReason) {
+        self.watchpoint_hit = None;
         let mut count = 0;
-        while !self.io.is_halted() && count < max_instructions {
-            self.next_instruction()?;
+        loop {
+            if self.is_halted() {
+                return (count, StopReason::Halted);
+            }
+            if count > 0 {
+                if let Some(reason) = stop(self) {
+                    return (count, reason);
+                }
+            }
+            if count > 0 && self.breakpoint_hit(self.program_counter) {
+                return (count, StopReason::Breakpoint(self.program_counter));
+            }
+            if let Some(reason) = self.detect_loop() {
+                return (count, reason);
+            }
+            if self.fuel == Some(0) {
+                return (count, StopReason::OutOfFuel);
+            }
+            if count >= max_instructions {
+                return (count, StopReason::MaxInstructions);
+            }
+            if let Err(e) = self.next_instruction() {
+                return (count, StopReason::Error(e));
+            }
             count += 1;
+            if let Some(addr) = self.watchpoint_hit.take() {
+                return (count, StopReason::Watchpoint(addr));
+            }
+            if self.stop_requested() {
+                return (count, StopReason::StopRequested);
+            }
+        }
+    }
+
+    // --- Loop detection ---
+
+    /// Stop runs that can no longer make progress: with
+    /// `StopReason::InfiniteLoop` at a taken BR to itself when no interrupt
+    /// can arrive, and, if `window` is non-zero, with
+    /// `StopReason::NoProgress` after `window` instructions that changed no
+    /// register, memory word, condition code or device.
+    pub fn enable_loop_detection(&mut self, window: u64) {
+        self.loop_window = Some(window);
+        self.last_change = self.instructions_retired;
+    }
+
+    pub fn disable_loop_detection(&mut self) {
+        self.loop_window = None;
+    }
+
+    fn progress(&mut self) {
+        self.last_change = self.instructions_retired;
+    }
+
+    fn detect_loop(&self) -> Option<StopReason> {
+        let window = self.loop_window?;
+        let pc = self.program_counter;
+        if window != 0 && self.instructions_retired - self.last_change > window {
+            return Some(StopReason::NoProgress(pc));
         }
-        Ok(count)
+        match Instruction::try_from(self.memory.read_word(pc)) {
+            Ok(Instruction::Br(condition, offset))
+                if offset.sign_extend() == -1
+                    && condition & self.psr.condition()
+                    && !self.interrupt_possible() =>
+            {
+                Some(StopReason::InfiniteLoop(pc))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether anything could still interrupt the running code. Attached
+    /// devices are assumed able to.
+    fn interrupt_possible(&self) -> bool {
+        let timer = &self.devices.timer;
+        !self.pending_interrupts.is_empty()
+            || self.devices.keyboard.interrupt_enable
+            || (timer.interrupt_enable && timer.interval != 0)
+            || !self.bus.is_empty()
     }
 
     fn execute(&mut self, instruction: Instruction) -> Result<(), Error> {
@@ -188,13 +1575,13 @@ impl<I: IO, O: Observer> Computer<I, O> {
                 self.perform_jsrr_instruction(register);
             }
             Instruction::Ldb(dr, base, offset) => {
-                self.perform_ldb_instruction(dr, base, offset);
+                self.perform_ldb_instruction(dr, base, offset)?;
             }
             Instruction::Ldi(dr, base, offset) => {
-                self.perform_ldi_instruction(dr, base, offset);
+                self.perform_ldi_instruction(dr, base, offset)?;
             }
             Instruction::Ldr(dr, base, offset) => {
-                self.perform_ldr_instruction(dr, base, offset);
+                self.perform_ldr_instruction(dr, base, offset)?;
             }
             Instruction::Lea(dr, pcoffset9) => {
                 self.perform_lea_instruction(dr, pcoffset9);
@@ -205,24 +1592,25 @@ impl<I: IO, O: Observer> Computer<I, O> {
             Instruction::Ret => {
                 // RET is just JMP R7
                 self.perform_jmp_instruction(Register::Register7);
+                self.call_stack.pop();
             }
             Instruction::Rti => {
-                return Err(Error::UnimplementedInstruction("RTI".to_string()));
+                self.perform_rti_instruction();
             }
-            Instruction::Shf(dr, sr, a, d, amount) => {
-                self.perform_shf_instruction(dr, sr, a, d, amount);
+            Instruction::Shf(dr, sr, d, a, amount) => {
+                self.perform_shf_instruction(dr, sr, d, a, amount);
             }
             Instruction::Stb(sr, base, offset) => {
-                self.perform_stb_instruction(sr, base, offset);
+                self.perform_stb_instruction(sr, base, offset)?;
             }
             Instruction::Sti(sr, base, offset) => {
-                self.perform_sti_instruction(sr, base, offset);
+                self.perform_sti_instruction(sr, base, offset)?;
             }
             Instruction::Stw(sr, base, offset) => {
-                self.perform_stw_instruction(sr, base, offset);
+                self.perform_stw_instruction(sr, base, offset)?;
             }
             Instruction::Trap(trap_vect8) => {
-                self.perform_trap(trap_vect8.value());
+                self.perform_trap_instruction(trap_vect8.value());
             }
         }
         Ok(())
@@ -307,7 +1695,7 @@ impl<I: IO, O: Observer> Computer<I, O> {
 
     pub fn perform_br_instruction(&mut self, condition: Condition, offset: PCOffset9) {
         // Check if any of the specified condition flags match the current condition codes
-        if condition & self.condition {
+        if condition & self.psr.condition() {
             // The offset is relative to the incremented PC (PC+1)
             // Since next_instruction will add 1 after execute, we compute:
             // new_pc = (current_pc + 1) + offset - 1 = current_pc + offset
@@ -332,7 +1720,14 @@ impl<I: IO, O: Observer> Computer<I, O> {
         // So we set PC = target - 1 = PC + LSHF(SEXT(offset), 1)
         let signed_offset = offset.sign_extend();
         let shifted_offset = signed_offset << 1; // LSHF by 1 (multiply by 2 for word alignment)
+        let call_site = self.program_counter;
         self.program_counter = (self.program_counter as i16).wrapping_add(shifted_offset) as u16;
+        self.call_stack.push(Frame {
+            kind: FrameKind::Subroutine,
+            call_site,
+            target: self.program_counter.wrapping_add(1),
+            return_address: return_addr,
+        });
     }
 
     pub fn perform_jsrr_instruction(&mut self, base: Register) {
@@ -347,6 +1742,12 @@ impl<I: IO, O: Observer> Computer<I, O> {
 
         // Jump to address in base register
         // Since next_instruction adds 1 after execute, we set PC = target - 1
+        self.call_stack.push(Frame {
+            kind: FrameKind::Subroutine,
+            call_site: self.program_counter,
+            target,
+            return_address: return_addr,
+        });
         self.program_counter = target.wrapping_sub(1);
     }
 
@@ -369,17 +1770,54 @@ impl<I: IO, O: Observer> Computer<I, O> {
         self.set_condition_codes(result);
     }
 
-    pub fn perform_stw_instruction(&mut self, sr: Register, base: Register, offset: PCOffset6) {
+    pub fn perform_rti_instruction(&mut self) {
+        if self.psr.is_user() {
+            self.initiate_exception(PRIVILEGE_MODE_EXCEPTION);
+            return;
+        }
+
+        // PC = MEM[R6], then PSR = MEM[R6 + 2]
+        let return_pc = self.pop_word();
+        let psr = Psr::from(self.pop_word());
+        let condition_changed = psr.condition() != self.psr.condition();
+        self.psr = psr;
+        if condition_changed {
+            self.observer.on_condition_change(psr.condition());
+        }
+        if psr.is_user() {
+            self.saved_ssp = self.registers[6];
+            self.store_register(Register::Register6, self.saved_usp);
+        }
+
+        // next_instruction adds 1 after execute
+        self.program_counter = return_pc.wrapping_sub(1);
+        self.call_stack.pop();
+    }
+
+    pub fn perform_stw_instruction(
+        &mut self,
+        sr: Register,
+        base: Register,
+        offset: PCOffset6,
+    ) -> Result<(), Error> {
         // STW: MEM[BaseR + LSHF(SEXT(offset6), 1)] = SR
         let base_val = self.load_register(base);
         let signed_offset = offset.sign_extend();
         let shifted_offset = (signed_offset << 1) as u16; // LSHF by 1 for word alignment
         let address = base_val.wrapping_add(shifted_offset);
+        self.check_access(address, true)?;
+        self.check_store(address)?;
         let value = self.load_register(sr);
-        self.memory.write_word(address, value);
+        self.store_word(address, value);
+        Ok(())
     }
 
-    pub fn perform_ldb_instruction(&mut self, dr: Register, base: Register, offset: PCOffset6) {
+    pub fn perform_ldb_instruction(
+        &mut self,
+        dr: Register,
+        base: Register,
+        offset: PCOffset6,
+    ) -> Result<(), Error> {
         // LDB: DR = SEXT(mem[BaseR + SEXT(offset6)][7:0])
         // Note: No shift for byte addressing (unlike LDR/STW which shift by 1)
         let base_val = self.load_register(base);
@@ -390,7 +1828,8 @@ impl<I: IO, O: Observer> Computer<I, O> {
         // 1. Get the word address (byte_address >> 1)
         // 2. Determine which byte (low or high) based on LSB of byte_address
         let word_address = byte_address >> 1;
-        let word = self.memory.read_word(word_address);
+        self.check_access(word_address, false)?;
+        let word = self.load_word(word_address);
 
         let byte = if byte_address & 1 == 0 {
             // Even address: low byte (bits [7:0])
@@ -410,9 +1849,15 @@ impl<I: IO, O: Observer> Computer<I, O> {
 
         self.store_register(dr, result);
         self.set_condition_codes(result);
+        Ok(())
     }
 
-    pub fn perform_ldi_instruction(&mut self, dr: Register, base: Register, offset: PCOffset6) {
+    pub fn perform_ldi_instruction(
+        &mut self,
+        dr: Register,
+        base: Register,
+        offset: PCOffset6,
+    ) -> Result<(), Error> {
         // LDI: DR = mem[mem[BaseR + LSHF(SEXT(offset6), 1)]]
         // First, compute the address of the pointer
         let base_val = self.load_register(base);
@@ -421,27 +1866,42 @@ impl<I: IO, O: Observer> Computer<I, O> {
         let pointer_address = base_val.wrapping_add(shifted_offset);
 
         // Read the pointer (target address) from memory
-        let target_address = self.memory.read_word(pointer_address);
+        self.check_access(pointer_address, true)?;
+        let target_address = self.load_word(pointer_address);
 
         // Read the value at the target address
-        let result = self.memory.read_word(target_address);
+        self.check_access(target_address, true)?;
+        let result = self.load_word(target_address);
 
         self.store_register(dr, result);
         self.set_condition_codes(result);
+        Ok(())
     }
 
-    pub fn perform_ldr_instruction(&mut self, dr: Register, base: Register, offset: PCOffset6) {
+    pub fn perform_ldr_instruction(
+        &mut self,
+        dr: Register,
+        base: Register,
+        offset: PCOffset6,
+    ) -> Result<(), Error> {
         // LDR: DR = mem[BaseR + LSHF(SEXT(offset6), 1)]
         let base_val = self.load_register(base);
         let signed_offset = offset.sign_extend();
         let shifted_offset = (signed_offset << 1) as u16; // LSHF by 1 for word alignment
         let address = base_val.wrapping_add(shifted_offset);
-        let result = self.memory.read_word(address);
+        self.check_access(address, true)?;
+        let result = self.load_word(address);
         self.store_register(dr, result);
         self.set_condition_codes(result);
+        Ok(())
     }
 
-    pub fn perform_stb_instruction(&mut self, sr: Register, base: Register, offset: PCOffset6) {
+    pub fn perform_stb_instruction(
+        &mut self,
+        sr: Register,
+        base: Register,
+        offset: PCOffset6,
+    ) -> Result<(), Error> {
         // STB: mem[BaseR + SEXT(offset6)] = SR[7:0]
         // Note: No shift for byte addressing
         let base_val = self.load_register(base);
@@ -457,7 +1917,12 @@ impl<I: IO, O: Observer> Computer<I, O> {
         // 3. Replace the appropriate byte
         // 4. Write the word back
         let word_address = byte_address >> 1;
-        let existing_word = self.memory.read_word(word_address);
+        self.check_access(word_address, false)?;
+        self.check_store(word_address)?;
+        // Read-modify-write: the untouched byte may legitimately be unset
+        let existing_word = self
+            .read_device(word_address)
+            .unwrap_or_else(|| self.memory.read_word(word_address));
 
         let new_word = if byte_address & 1 == 0 {
             // Even address: replace low byte (bits [7:0])
@@ -467,10 +1932,16 @@ impl<I: IO, O: Observer> Computer<I, O> {
             (existing_word & 0x00FF) | ((byte_value as u16) << 8)
         };
 
-        self.memory.write_word(word_address, new_word);
+        self.store_word(word_address, new_word);
+        Ok(())
     }
 
-    pub fn perform_sti_instruction(&mut self, sr: Register, base: Register, offset: PCOffset6) {
+    pub fn perform_sti_instruction(
+        &mut self,
+        sr: Register,
+        base: Register,
+        offset: PCOffset6,
+    ) -> Result<(), Error> {
         // STI: mem[mem[BaseR + LSHF(SEXT(offset6), 1)]] = SR
         // First, compute the address of the pointer
         let base_val = self.load_register(base);
@@ -479,19 +1950,23 @@ impl<I: IO, O: Observer> Computer<I, O> {
         let pointer_address = base_val.wrapping_add(shifted_offset);
 
         // Read the pointer (target address) from memory
-        let target_address = self.memory.read_word(pointer_address);
+        self.check_access(pointer_address, true)?;
+        let target_address = self.load_word(pointer_address);
 
         // Write the value to the target address
+        self.check_access(target_address, true)?;
+        self.check_store(target_address)?;
         let value = self.load_register(sr);
-        self.memory.write_word(target_address, value);
+        self.store_word(target_address, value);
+        Ok(())
     }
 
     pub fn perform_shf_instruction(
         &mut self,
         dr: Register,
         sr: Register,
-        a: lc3b_isa::Bit,
         d: lc3b_isa::Bit,
+        a: lc3b_isa::Bit,
         amount: lc3b_isa::Immediate4,
     ) {
         // SHF: Shift instruction
@@ -517,35 +1992,100 @@ impl<I: IO, O: Observer> Computer<I, O> {
 
     // --- TRAP implementation ---
 
+    pub fn perform_trap_instruction(&mut self, vector: u8) {
+        self.observer.on_trap(vector);
+        // Service routines do I/O outside the device registers
+        self.progress();
+        // Take the handler out so it can borrow the computer mutably
+        if let Some(mut handler) = self.trap_handlers.remove(&vector) {
+            handler(self);
+            // Keep a replacement the handler registered for itself
+            self.trap_handlers.entry(vector).or_insert(handler);
+            return;
+        }
+
+        match self.trap_mode {
+            TrapMode::Native => self.perform_trap(vector),
+            TrapMode::VectorTable => {
+                // R7 = PC + 1, PC = MEM[LSHF(ZEXT(trapvect8), 1)]
+                let return_addr = self.program_counter.wrapping_add(1);
+                self.store_register(Register::Register7, return_addr);
+                let handler = self.memory.read_word((vector as u16) << 1);
+                self.call_stack.push(Frame {
+                    kind: FrameKind::Trap(vector),
+                    call_site: self.program_counter,
+                    target: handler,
+                    return_address: return_addr,
+                });
+                // next_instruction adds 1 after execute
+                self.program_counter = handler.wrapping_sub(1);
+            }
+        }
+    }
+
+    fn write_output(&mut self, ch: char) {
+        self.io.write_char(ch);
+        self.observer.on_io_output(ch);
+    }
+
+    /// The character a console word prints as under the I/O's encoding,
+    /// or None after noting the rejection
+    fn console_char(&mut self, word: u16) -> Option<char> {
+        let ch = self.io.encoding().decode(word);
+        if ch.is_none() {
+            self.encoding_error.get_or_insert(word as u32);
+        }
+        ch
+    }
+
+    /// Store a typed character in R0 under the I/O's encoding
+    fn store_input(&mut self, ch: char) {
+        match self.io.encoding().encode(ch) {
+            Some(word) => self.store_register(Register::Register0, word),
+            None => {
+                self.encoding_error.get_or_insert(ch as u32);
+            }
+        }
+    }
+
+    /// Print a console word, false if the encoding rejected it
+    fn write_word_output(&mut self, word: u16) -> bool {
+        match self.console_char(word) {
+            Some(ch) => {
+                self.write_output(ch);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn perform_trap(&mut self, vector: u8) {
         match vector {
             0x20 => {
                 // GETC - read character into R0
                 if let Some(ch) = self.io.read_char() {
-                    self.store_register(Register::Register0, ch as u16);
+                    self.store_input(ch);
                 }
             }
             0x21 => {
                 // OUT - write character from R0
-                let ch = (self.registers[0] & 0xFF) as u8 as char;
-                self.io.write_char(ch);
+                self.write_word_output(self.registers[0]);
             }
             0x22 => {
                 // PUTS - write null-terminated string starting at address in R0
                 let mut addr = self.registers[0];
                 loop {
                     let word = self.memory.read_word(addr);
-                    if word == 0 {
+                    if word == 0 || !self.write_word_output(word) {
                         break;
                     }
-                    self.io.write_char((word & 0xFF) as u8 as char);
                     addr = addr.wrapping_add(1);
                 }
             }
             0x23 => {
                 // IN - prompt and read character with echo
                 if let Some(ch) = self.io.read_char_with_echo() {
-                    self.store_register(Register::Register0, ch as u16);
+                    self.store_input(ch);
                 }
             }
             0x24 => {
@@ -556,24 +2096,21 @@ impl<I: IO, O: Observer> Computer<I, O> {
                     if word == 0 {
                         break;
                     }
-                    // Low byte first
-                    let ch1 = (word & 0xFF) as u8 as char;
-                    if ch1 == '\0' {
+                    // Low byte first, then high
+                    let low = word & 0xFF;
+                    if low == 0 || !self.write_word_output(low) {
                         break;
                     }
-                    self.io.write_char(ch1);
-                    // High byte second
-                    let ch2 = ((word >> 8) & 0xFF) as u8 as char;
-                    if ch2 == '\0' {
+                    let high = word >> 8;
+                    if high == 0 || !self.write_word_output(high) {
                         break;
                     }
-                    self.io.write_char(ch2);
                     addr = addr.wrapping_add(1);
                 }
             }
             0x25 => {
                 // HALT
-                self.io.halt();
+                self.halt_with(HaltReason::Trap);
             }
             _ => {
                 // Unknown trap vector - could log or ignore
@@ -581,3 +2118,125 @@ impl<I: IO, O: Observer> Computer<I, O> {
         }
     }
 }
+
+impl<I: IO> Computer<I, ()> {
+    /// `run` for long batch jobs. While nothing needs per-instruction
+    /// attention (no breakpoints, watchpoints, journal, loop detection,
+    /// attached devices, or possible interrupts) instructions execute in a
+    /// tight loop that skips the halted check, device ticks and interrupt
+    /// polling; those resume after any TRAP or device register access.
+    /// Otherwise this falls back to `run`. Results match `run`.
+    pub fn run_fast(&mut self, max_instructions: usize) -> StopReason {
+        let mut count = 0;
+        loop {
+            if self.is_halted() {
+                return StopReason::Halted;
+            }
+            if self.fuel == Some(0) {
+                return StopReason::OutOfFuel;
+            }
+            if count >= max_instructions {
+                return StopReason::MaxInstructions;
+            }
+            if !self.fast_path_allowed() {
+                return self.run(max_instructions - count);
+            }
+            while count < max_instructions && self.fuel != Some(0) {
+                let pc = self.program_counter;
+                if !self.protections.is_empty() && self.protection_at(pc).no_execute {
+                    return StopReason::Error(Error::ExecuteProtected(pc));
+                }
+                let inst = match self.memory.decode(pc) {
+                    Ok(inst) => inst,
+                    Err(e) => {
+                        return StopReason::Error(Error::InstructionDecode {
+                            address: pc,
+                            reason: e.to_string(),
+                        })
+                    }
+                };
+                self.device_accessed = false;
+                self.uninitialized_read = None;
+                self.encoding_error = None;
+                self.io.set_clock(self.instructions_executed);
+                if let Err(e) = self.execute(inst) {
+                    return StopReason::Error(e);
+                }
+                self.program_counter = self.program_counter.wrapping_add(1);
+                let branch_taken = self.program_counter != pc.wrapping_add(1);
+                self.cycles += u64::from(self.timing.cycles(&inst, branch_taken));
+                self.instructions_retired += 1;
+                self.consume_fuel();
+                count += 1;
+                if let Some(e) = self.access_error(pc) {
+                    self.check_halt_requests();
+                    return StopReason::Error(e);
+                }
+                // Either may halt, enable an interrupt or start the timer
+                if self.device_accessed || matches!(inst, Instruction::Trap(_)) {
+                    self.check_halt_requests();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn fast_path_allowed(&self) -> bool {
+        self.breakpoints.is_empty()
+            && self.watchpoints.is_empty()
+            && self.watches.is_empty()
+            && self.stop_predicate.is_none()
+            && self.journal.is_none()
+            && self.loop_window.is_none()
+            && self.exceptions == Exceptions::default()
+            && self.devices.timer.interval == 0
+            && !self.interrupt_possible()
+    }
+}
+
+impl<I: IO + Clone, O: Observer> Computer<I, O> {
+    /// Capture the machine state, including a copy of the I/O buffers
+    pub fn snapshot(&self) -> Snapshot<I> {
+        Snapshot {
+            program_counter: self.program_counter,
+            psr: self.psr,
+            registers: self.registers,
+            saved_ssp: self.saved_ssp,
+            saved_usp: self.saved_usp,
+            call_stack: self.call_stack.clone(),
+            cycles: self.cycles,
+            instructions_retired: self.instructions_retired,
+            pending_interrupts: self.pending_interrupts.clone(),
+            trap_mode: self.trap_mode,
+            devices: self.devices,
+            halt_reason: self.halt_reason,
+            pages: capture_pages(&self.memory),
+            initialized_registers: self.initialized_registers,
+            written: capture_written(&self.memory),
+            io: self.io.clone(),
+        }
+    }
+
+    /// Return to a snapshot's machine state. The step_back history is
+    /// cleared since it no longer leads back from here.
+    pub fn restore(&mut self, snapshot: &Snapshot<I>) {
+        self.program_counter = snapshot.program_counter;
+        self.psr = snapshot.psr;
+        self.registers = snapshot.registers;
+        self.initialized_registers = snapshot.initialized_registers;
+        self.saved_ssp = snapshot.saved_ssp;
+        self.saved_usp = snapshot.saved_usp;
+        self.call_stack = snapshot.call_stack.clone();
+        self.cycles = snapshot.cycles;
+        self.instructions_retired = snapshot.instructions_retired;
+        self.pending_interrupts = snapshot.pending_interrupts.clone();
+        self.trap_mode = snapshot.trap_mode;
+        self.devices = snapshot.devices;
+        self.halt_reason = snapshot.halt_reason;
+        self.memory = restore_pages(&snapshot.pages, &snapshot.written);
+        self.io = snapshot.io.clone();
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+    }
+}
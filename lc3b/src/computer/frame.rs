@@ -0,0 +1,35 @@
+/// What entered a call-stack frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameKind {
+    /// JSR or JSRR
+    Subroutine,
+    /// TRAP dispatched through the vector table
+    Trap(u8),
+    /// Interrupt or exception, by vector
+    Interrupt(u8),
+}
+
+/// One entry of the shadow call stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frame {
+    pub kind: FrameKind,
+    /// The JSR/JSRR/TRAP, or the instruction that was interrupted or faulted
+    pub call_site: u16,
+    /// Entry point of the subroutine or handler
+    pub target: u16,
+    /// Where RET or RTI is expected to go
+    pub return_address: u16,
+}
+
+/// One word of the R6 stack from `Computer::stack_view`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StackEntry {
+    pub addr: u16,
+    pub value: u16,
+    /// Between R6 and the frame's saved R5, following the C compiler's
+    /// layout: locals below R5, saved R7 at R5, caller's R5 one slot up
+    pub in_frame: bool,
+}
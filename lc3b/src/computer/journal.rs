@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use lc3b_isa::Psr;
+
+use super::{Frame, HaltReason};
+use crate::{mmio::DeviceRegisters, Interrupt};
+
+/// Machine state from just before one instruction, plus the memory words
+/// that instruction overwrote
+pub(crate) struct JournalEntry {
+    pub program_counter: u16,
+    pub psr: Psr,
+    pub registers: [u16; 8],
+    pub initialized_registers: u8,
+    pub saved_ssp: u16,
+    pub saved_usp: u16,
+    pub call_stack: Vec<Frame>,
+    pub cycles: u64,
+    pub instructions_retired: u64,
+    pub devices: DeviceRegisters,
+    pub pending_interrupts: Vec<Interrupt>,
+    pub halt_reason: Option<HaltReason>,
+    /// (address, previous value, written before) in write order
+    pub memory: Vec<(u16, u16, bool)>,
+}
+
+/// Undo history for `step_back`, dropping the oldest entries past
+/// `capacity`
+pub(crate) struct Journal {
+    capacity: usize,
+    entries: VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    pub fn new(capacity: usize) -> Self {
+        Journal {
+            capacity,
+            entries: VecDeque::with_capacity(capacity.min(1024)),
+        }
+    }
+
+    pub fn push(&mut self, entry: JournalEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn record_memory_write(&mut self, addr: u16, old: u16, written: bool) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.memory.push((addr, old, written));
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<JournalEntry> {
+        self.entries.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
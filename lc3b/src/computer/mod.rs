@@ -1,2 +1,21 @@
+mod access;
+pub use access::*;
+
+mod breakpoint;
+pub use breakpoint::*;
+
+mod frame;
+pub use frame::*;
+
+mod journal;
+
+mod watch;
+pub(crate) use watch::Watch;
+pub use watch::{WatchChange, WatchExpr};
+
+mod snapshot;
+pub use snapshot::{Snapshot, SNAPSHOT_PAGE_SIZE};
+
+#[allow(clippy::module_inception)]
 mod computer;
 pub use computer::*;
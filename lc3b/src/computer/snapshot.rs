@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use lc3b_isa::Psr;
+
+use super::{Frame, HaltReason};
+use crate::{mmio::DeviceRegisters, Error, Interrupt, Memory, TrapMode};
+
+/// Words per stored memory page
+pub const SNAPSHOT_PAGE_SIZE: usize = 256;
+
+/// Saved machine state from `Computer::snapshot`: registers, PC, PSR,
+/// stack pointers, cycle counters, device state, the halt state, the I/O
+/// buffers, every memory page that is not all zeros, and which registers
+/// and memory words have been written (see `AccessChecks::uninitialized`).
+///
+/// Debugger settings (breakpoints, watchpoints, trap handlers, access
+/// checks) and the observer are not part of the machine state and are
+/// left alone by `restore`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot<I> {
+    pub(crate) program_counter: u16,
+    pub(crate) psr: Psr,
+    pub(crate) registers: [u16; 8],
+    pub(crate) saved_ssp: u16,
+    pub(crate) saved_usp: u16,
+    pub(crate) call_stack: Vec<Frame>,
+    pub(crate) cycles: u64,
+    pub(crate) instructions_retired: u64,
+    pub(crate) pending_interrupts: Vec<Interrupt>,
+    pub(crate) trap_mode: TrapMode,
+    pub(crate) devices: DeviceRegisters,
+    pub(crate) halt_reason: Option<HaltReason>,
+    /// Page number -> SNAPSHOT_PAGE_SIZE words
+    pub(crate) pages: BTreeMap<u16, Vec<u16>>,
+    /// Bit n set once Rn has been written
+    pub(crate) initialized_registers: u8,
+    /// Group n -> which of the 64 words from n * 64 have been written,
+    /// for the groups with any
+    pub(crate) written: BTreeMap<u16, u64>,
+    pub(crate) io: I,
+}
+
+impl<I> Snapshot<I> {
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    pub fn psr(&self) -> Psr {
+        self.psr
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn registers(&self) -> &[u16; 8] {
+        &self.registers
+    }
+
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt_reason
+    }
+
+    pub fn io(&self) -> &I {
+        &self.io
+    }
+
+    /// Word at `addr` as it was when the snapshot was taken
+    pub fn read_memory(&self, addr: u16) -> u16 {
+        let page = addr / SNAPSHOT_PAGE_SIZE as u16;
+        let offset = addr as usize % SNAPSHOT_PAGE_SIZE;
+        self.pages.get(&page).and_then(|words| words.get(offset)).copied().unwrap_or(0)
+    }
+
+    /// Number of non-zero pages stored
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Check a snapshot read back from outside, e.g. deserialized, is one
+    /// `Computer::snapshot` could have taken. The PSR and interrupts are
+    /// range checked as they deserialize; this checks the memory pages.
+    pub fn validate(&self) -> Result<(), Error> {
+        for (&page, words) in &self.pages {
+            if page as usize >= 0x10000 / SNAPSHOT_PAGE_SIZE {
+                return Err(Error::InvalidSnapshot(format!("no memory page {}", page)));
+            }
+            if words.len() != SNAPSHOT_PAGE_SIZE {
+                return Err(Error::InvalidSnapshot(format!(
+                    "page {} holds {} words, not {}",
+                    page,
+                    words.len(),
+                    SNAPSHOT_PAGE_SIZE
+                )));
+            }
+        }
+        if let Some(&group) = self.written.keys().find(|&&group| group as usize >= 0x10000 / 64) {
+            return Err(Error::InvalidSnapshot(format!("no written group {}", group)));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn capture_pages(memory: &Memory) -> BTreeMap<u16, Vec<u16>> {
+    let mut pages = BTreeMap::new();
+    for page in 0..(0x10000 / SNAPSHOT_PAGE_SIZE) {
+        let start = (page * SNAPSHOT_PAGE_SIZE) as u16;
+        let words: Vec<u16> = (0..SNAPSHOT_PAGE_SIZE as u16)
+            .map(|offset| memory.read_word(start + offset))
+            .collect();
+        if words.iter().any(|&word| word != 0) {
+            pages.insert(page as u16, words);
+        }
+    }
+    pages
+}
+
+pub(crate) fn capture_written(memory: &Memory) -> BTreeMap<u16, u64> {
+    (0..0x10000 / 64)
+        .map(|group| (group as u16, memory.written_group(group)))
+        .filter(|&(_, bits)| bits != 0)
+        .collect()
+}
+
+pub(crate) fn restore_pages(
+    pages: &BTreeMap<u16, Vec<u16>>,
+    written: &BTreeMap<u16, u64>,
+) -> Memory {
+    let mut memory = Memory::default();
+    for (&page, words) in pages {
+        let words = &words[..words.len().min(SNAPSHOT_PAGE_SIZE)];
+        memory.load_words(page.wrapping_mul(SNAPSHOT_PAGE_SIZE as u16), words);
+    }
+    // load_words counted every word as written, zeros included
+    for group in 0..0x10000 / 64 {
+        memory.set_written_group(group, written.get(&(group as u16)).copied().unwrap_or(0));
+    }
+    memory
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+
+/// An expression over machine state for `Computer::add_watch` and
+/// `Computer::evaluate`: registers (`R3`), `PC`, numbers (`x4000`, `#-2`,
+/// `12`), labels, memory words (`[x4000]`, `[R5 + 2]`), and `+`/`-` with
+/// 16-bit wraparound
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchExpr {
+    Register(u8),
+    ProgramCounter,
+    Constant(u16),
+    Label(String),
+    Memory(Box<WatchExpr>),
+    Add(Box<WatchExpr>, Box<WatchExpr>),
+    Sub(Box<WatchExpr>, Box<WatchExpr>),
+}
+
+impl FromStr for WatchExpr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { source: s, pos: 0 };
+        let expr = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.pos != s.len() {
+            return Err(parser.error("unexpected input"));
+        }
+        Ok(expr)
+    }
+}
+
+impl fmt::Display for WatchExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchExpr::Register(index) => write!(f, "R{}", index),
+            WatchExpr::ProgramCounter => write!(f, "PC"),
+            WatchExpr::Constant(value) => write!(f, "x{:04X}", value),
+            WatchExpr::Label(name) => write!(f, "{}", name),
+            WatchExpr::Memory(addr) => write!(f, "[{}]", addr),
+            WatchExpr::Add(lhs, rhs) => write!(f, "{} + {}", lhs, rhs),
+            WatchExpr::Sub(lhs, rhs) => write!(f, "{} - {}", lhs, rhs),
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &str) -> Error {
+        Error::InvalidExpression(format!(
+            "{} at column {} of `{}`",
+            reason,
+            self.pos + 1,
+            self.source
+        ))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.source[self.pos..].chars().next()
+    }
+
+    fn expression(&mut self) -> Result<WatchExpr, Error> {
+        let mut expr = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = Box::new(self.term()?);
+            expr = if op == '+' {
+                WatchExpr::Add(Box::new(expr), rhs)
+            } else {
+                WatchExpr::Sub(Box::new(expr), rhs)
+            };
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<WatchExpr, Error> {
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let addr = self.expression()?;
+                if self.peek() != Some(']') {
+                    return Err(self.error("expected `]`"));
+                }
+                self.pos += 1;
+                Ok(WatchExpr::Memory(Box::new(addr)))
+            }
+            Some('#') => {
+                self.pos += 1;
+                let negative = self.peek() == Some('-');
+                if negative {
+                    self.pos += 1;
+                }
+                let digits = self.word();
+                let value: i32 = digits.parse().map_err(|_| self.error("expected a number"))?;
+                let value = if negative { -value } else { value };
+                Ok(WatchExpr::Constant(value as u16))
+            }
+            Some(c) if c.is_ascii_alphanumeric() || c == '_' => {
+                let start = self.pos;
+                let word = self.word();
+                parse_word(word).ok_or_else(|| {
+                    self.pos = start;
+                    self.error("expected a register, number or label")
+                })
+            }
+            _ => Err(self.error("expected a register, number, label or `[`")),
+        }
+    }
+
+    fn word(&mut self) -> &'a str {
+        let rest = &self.source[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+}
+
+/// A register, `PC`, a hex or decimal number, or else a label
+fn parse_word(word: &str) -> Option<WatchExpr> {
+    let upper = word.to_ascii_uppercase();
+    if let Some(index) = upper.strip_prefix('R') {
+        if let Ok(index @ 0..=7) = index.parse::<u8>() {
+            return Some(WatchExpr::Register(index));
+        }
+    }
+    if upper == "PC" {
+        return Some(WatchExpr::ProgramCounter);
+    }
+    if word.starts_with(|c: char| c.is_ascii_digit()) {
+        let value = match upper.strip_prefix("0X") {
+            Some(hex) => u16::from_str_radix(hex, 16).ok()?,
+            None => word.parse().ok()?,
+        };
+        return Some(WatchExpr::Constant(value));
+    }
+    if let Some(hex) = upper.strip_prefix('X') {
+        if let Ok(value) = u16::from_str_radix(hex, 16) {
+            return Some(WatchExpr::Constant(value));
+        }
+    }
+    Some(WatchExpr::Label(word.to_string()))
+}
+
+/// A watch expression's value changing across one instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchChange {
+    /// The id `add_watch` returned
+    pub id: usize,
+    /// The instruction after which the value changed
+    pub pc: u16,
+    /// None if the expression could not be evaluated before (e.g. its
+    /// label was not loaded yet)
+    pub old: Option<u16>,
+    pub new: Option<u16>,
+}
+
+/// A registered watch expression and its last value
+pub(crate) struct Watch {
+    pub id: usize,
+    pub source: String,
+    pub expr: WatchExpr,
+    pub value: Option<u16>,
+}
@@ -1,2 +1,5 @@
 /// Starting address for user programs in LC-3b
 pub const USER_PROGRAM_START: u16 = 0x3000;
+
+/// First address of the memory-mapped device page
+pub const IO_PAGE_START: u16 = 0xFE00;
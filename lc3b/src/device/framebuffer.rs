@@ -0,0 +1,82 @@
+use std::ops::RangeInclusive;
+
+use super::Device;
+use crate::IO;
+
+/// First word of video memory
+pub const FRAMEBUFFER_START: u16 = 0xC000;
+pub const FRAMEBUFFER_WIDTH: usize = 128;
+pub const FRAMEBUFFER_HEIGHT: usize = 124;
+
+/// A 128x124 display mapped at xC000-xFDFF, one word per pixel in
+/// row-major order, like the classic LC-3 video memory.
+///
+/// Pixels are 15-bit colour: bits [14:10] red, [9:5] green, [4:0] blue.
+/// The region ends right below the device page, where the OS's user stack
+/// also starts, so programs that draw should keep R6 elsewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Framebuffer {
+    pixels: Vec<u16>,
+}
+
+impl Framebuffer {
+    pub fn new() -> Self {
+        Framebuffer {
+            pixels: vec![0; FRAMEBUFFER_WIDTH * FRAMEBUFFER_HEIGHT],
+        }
+    }
+
+    /// Every pixel, row by row
+    pub fn pixels(&self) -> &[u16] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Option<u16> {
+        if x < FRAMEBUFFER_WIDTH && y < FRAMEBUFFER_HEIGHT {
+            Some(self.pixels[y * FRAMEBUFFER_WIDTH + x])
+        } else {
+            None
+        }
+    }
+
+    /// The image as 8-bit RGBA, ready for an HTML canvas `ImageData`
+    pub fn to_rgba(&self) -> Vec<u8> {
+        // Scale a 5-bit channel to 8 bits, filling the low bits
+        let expand = |c: u16| ((c << 3) | (c >> 2)) as u8;
+        self.pixels
+            .iter()
+            .flat_map(|&pixel| {
+                [
+                    expand((pixel >> 10) & 0x1F),
+                    expand((pixel >> 5) & 0x1F),
+                    expand(pixel & 0x1F),
+                    0xFF,
+                ]
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Device for Framebuffer {
+    fn range(&self) -> RangeInclusive<u16> {
+        FRAMEBUFFER_START..=FRAMEBUFFER_START + (FRAMEBUFFER_WIDTH * FRAMEBUFFER_HEIGHT) as u16 - 1
+    }
+
+    fn read_word(&mut self, addr: u16, _io: &mut dyn IO) -> u16 {
+        self.pixels[(addr - FRAMEBUFFER_START) as usize]
+    }
+
+    fn write_word(&mut self, addr: u16, value: u16, _io: &mut dyn IO) {
+        self.pixels[(addr - FRAMEBUFFER_START) as usize] = value;
+    }
+}
@@ -0,0 +1,42 @@
+//! Memory-mapped peripherals.
+//!
+//! A device claims a range of addresses; loads and stores there go to the
+//! device instead of memory. The built-in keyboard, display, timer and
+//! machine control register are devices too, and more can be attached
+//! with `Computer::attach_device`.
+
+mod framebuffer;
+pub use framebuffer::*;
+
+use std::any::Any;
+use std::ops::RangeInclusive;
+
+use crate::{Interrupt, IO};
+
+pub trait Device: Any {
+    /// Addresses this device answers, as LDW/STW compute them
+    fn range(&self) -> RangeInclusive<u16>;
+
+    /// A load from `addr` inside `range()`
+    fn read_word(&mut self, addr: u16, io: &mut dyn IO) -> u16;
+
+    /// A store to `addr` inside `range()`
+    fn write_word(&mut self, addr: u16, value: u16, io: &mut dyn IO);
+
+    /// Called after every instruction with the clock cycles it took
+    fn tick(&mut self, _cycles: u32) {}
+
+    /// The interrupt this device is requesting right now, if any. Checked
+    /// before every instruction, so a device keeps requesting until the
+    /// program acknowledges it.
+    fn interrupt_request(&mut self, _io: &mut dyn IO) -> Option<Interrupt> {
+        None
+    }
+}
+
+/// First address two ranges share, if they overlap
+pub(crate) fn overlap(a: &RangeInclusive<u16>, b: &RangeInclusive<u16>) -> Option<u16> {
+    let start = *a.start().max(b.start());
+    let end = *a.end().min(b.end());
+    (start <= end).then_some(start)
+}
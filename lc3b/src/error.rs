@@ -1,4 +1,6 @@
-#[derive(thiserror::Error, Debug)]
+use crate::UninitializedRead;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     #[error("could not parse assembly: {0}")]
     ParseAssembly(String),
@@ -34,4 +36,31 @@ pub enum Error {
 
     #[error("alignment error: {0}")]
     AlignmentError(String),
+
+    #[error("device range overlaps another device at {0:#06x}")]
+    DeviceOverlap(u16),
+
+    #[error("read of uninitialized {read} at {pc:#06x}")]
+    UninitializedRead { pc: u16, read: UninitializedRead },
+
+    #[error("store to read-only memory at {addr:#06x} by instruction at {pc:#06x}")]
+    WriteProtected { pc: u16, addr: u16 },
+
+    #[error("instruction fetch from no-execute memory at {0:#06x}")]
+    ExecuteProtected(u16),
+
+    #[error("invalid expression: {0}")]
+    InvalidExpression(String),
+
+    #[error("non-ASCII console character {value:#06x} at {pc:#06x}")]
+    NotAscii { pc: u16, value: u32 },
+
+    #[error("invalid I/O log at line {line}: {reason}")]
+    InvalidIoLog { line: usize, reason: String },
+
+    #[error("invalid object file: {0}")]
+    InvalidObjectFile(String),
+
+    #[error("invalid snapshot: {0}")]
+    InvalidSnapshot(String),
 }
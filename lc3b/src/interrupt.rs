@@ -0,0 +1,89 @@
+use crate::Error;
+
+/// Base of the interrupt vector table. The handler address for vector `v`
+/// is read from `INTERRUPT_VECTOR_TABLE + LSHF(v, 1)`.
+pub const INTERRUPT_VECTOR_TABLE: u16 = 0x0200;
+
+/// Exception vector for RTI executed in user mode
+pub const PRIVILEGE_MODE_EXCEPTION: u8 = 0x00;
+
+/// Exception vector for a word that does not decode, when
+/// `Exceptions::illegal_opcode` is on
+pub const ILLEGAL_OPCODE_EXCEPTION: u8 = 0x01;
+
+/// Exception vector for a protected or system-space access, when
+/// `Exceptions::access_violation` is on
+pub const ACCESS_CONTROL_VIOLATION_EXCEPTION: u8 = 0x02;
+
+/// Exception vector for a word access to an odd address, when
+/// `Exceptions::unaligned_access` is on
+pub const UNALIGNED_ACCESS_EXCEPTION: u8 = 0x03;
+
+/// Interrupt vector used by the timer device (TMR/TMI)
+pub const TIMER_INTERRUPT_VECTOR: u8 = 0x81;
+
+/// Supervisor stack pointer used until a program sets its own
+pub const DEFAULT_SUPERVISOR_STACK: u16 = 0x3000;
+
+/// An interrupt request: which vector to dispatch through, and the
+/// priority the processor runs the handler at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "InterruptFields"))]
+pub struct Interrupt {
+    vector: u8,
+    priority: u8,
+}
+
+impl Interrupt {
+    /// Keyboard interrupt, raised while KBSR has both ready and IE set
+    pub const KEYBOARD: Interrupt = Interrupt {
+        vector: 0x80,
+        priority: 4,
+    };
+
+    /// Create an interrupt request, checking that `priority` is in 1..=7.
+    /// Priority 0 could never preempt anything.
+    pub fn new(vector: u8, priority: u8) -> Result<Self, Error> {
+        if !(1..=7).contains(&priority) {
+            return Err(Error::ValueOutOfRange {
+                kind: "interrupt priority",
+                value: priority as i32,
+                min: 1,
+                max: 7,
+            });
+        }
+        Ok(Interrupt { vector, priority })
+    }
+
+    pub fn vector(&self) -> u8 {
+        self.vector
+    }
+
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Address of this interrupt's entry in the vector table
+    pub fn table_entry(&self) -> u16 {
+        INTERRUPT_VECTOR_TABLE + ((self.vector as u16) << 1)
+    }
+}
+
+/// `Interrupt`'s fields as serialized, checked by `Interrupt::new` when
+/// deserializing
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct InterruptFields {
+    vector: u8,
+    priority: u8,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<InterruptFields> for Interrupt {
+    type Error = Error;
+
+    fn try_from(fields: InterruptFields) -> Result<Self, Self::Error> {
+        Interrupt::new(fields.vector, fields.priority)
+    }
+}
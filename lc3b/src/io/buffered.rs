@@ -1,13 +1,24 @@
 use std::collections::VecDeque;
 
-use super::IO;
+use super::{Encoding, IO};
 
 /// Buffered I/O for WASM and testing
 /// Collects output in a string, accepts input from a queue
+///
+/// Output positions count characters ever written, so a UI can pull just
+/// what is new with `take_output_since`. With `set_output_limit` only the
+/// most recent output is kept, for programs that print without end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BufferedIO {
     output: String,
     input: VecDeque<char>,
-    halted: bool,
+    /// Characters in `output`
+    output_chars: usize,
+    /// Position of the first character in `output`
+    output_start: usize,
+    output_limit: Option<usize>,
+    encoding: Encoding,
 }
 
 impl BufferedIO {
@@ -15,18 +26,74 @@ impl BufferedIO {
         Self {
             output: String::new(),
             input: VecDeque::new(),
-            halted: false,
+            output_chars: 0,
+            output_start: 0,
+            output_limit: None,
+            encoding: Encoding::default(),
         }
     }
 
-    /// Get all output written so far
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Get all output written so far (the most recent part, if limited)
     pub fn output(&self) -> &str {
         &self.output
     }
 
     /// Clear output buffer
     pub fn clear_output(&mut self) {
-        self.output.clear();
+        self.drain_output();
+    }
+
+    /// Keep the most recent `limit` characters of output, dropping the
+    /// oldest first; None keeps everything. While running, output may
+    /// overshoot by a quarter of the limit before it is trimmed, so
+    /// dropping stays cheap.
+    pub fn set_output_limit(&mut self, limit: Option<usize>) {
+        self.output_limit = limit;
+        self.trim_output(0);
+    }
+
+    pub fn output_limit(&self) -> Option<usize> {
+        self.output_limit
+    }
+
+    /// Characters written so far, including any dropped or drained since;
+    /// the position the next one will have
+    pub fn output_position(&self) -> usize {
+        self.output_start + self.output_chars
+    }
+
+    /// Position of the oldest character still held
+    pub fn output_start(&self) -> usize {
+        self.output_start
+    }
+
+    /// Remove and return all output held
+    pub fn drain_output(&mut self) -> String {
+        self.output_start += self.output_chars;
+        self.output_chars = 0;
+        std::mem::take(&mut self.output)
+    }
+
+    /// Output written from `position` on, forgetting everything before it.
+    /// A UI keeps `output_position()` from its last call and passes it
+    /// here to get only the new characters; output already dropped by the
+    /// limit is skipped.
+    pub fn take_output_since(&mut self, position: usize) -> String {
+        let skip = position.saturating_sub(self.output_start).min(self.output_chars);
+        let split = self.byte_offset(skip);
+        self.output.drain(..split);
+        self.output_start += skip;
+        self.output_chars -= skip;
+        self.output.clone()
     }
 
     /// Queue input characters (for testing or WASM keyboard input)
@@ -41,11 +108,35 @@ impl BufferedIO {
         }
     }
 
-    /// Reset halted state (to rerun)
+    /// Drop output and pending input (to rerun)
     pub fn reset(&mut self) {
-        self.halted = false;
         self.output.clear();
         self.input.clear();
+        self.output_chars = 0;
+        self.output_start = 0;
+    }
+
+    /// Byte offset of the character `chars` into `output`
+    fn byte_offset(&self, chars: usize) -> usize {
+        self.output
+            .char_indices()
+            .nth(chars)
+            .map_or(self.output.len(), |(offset, _)| offset)
+    }
+
+    /// Drop the oldest output once it passes the limit by more than
+    /// `slack` characters
+    fn trim_output(&mut self, slack: usize) {
+        let Some(limit) = self.output_limit else {
+            return;
+        };
+        if self.output_chars > limit + slack {
+            let excess = self.output_chars - limit;
+            let split = self.byte_offset(excess);
+            self.output.drain(..split);
+            self.output_start += excess;
+            self.output_chars = limit;
+        }
     }
 }
 
@@ -58,17 +149,20 @@ impl Default for BufferedIO {
 impl IO for BufferedIO {
     fn write_char(&mut self, ch: char) {
         self.output.push(ch);
+        self.output_chars += 1;
+        let slack = self.output_limit.map_or(0, |limit| limit / 4);
+        self.trim_output(slack);
     }
 
     fn read_char(&mut self) -> Option<char> {
         self.input.pop_front()
     }
 
-    fn halt(&mut self) {
-        self.halted = true;
+    fn has_input(&mut self) -> bool {
+        !self.input.is_empty()
     }
 
-    fn is_halted(&self) -> bool {
-        self.halted
+    fn encoding(&self) -> Encoding {
+        self.encoding
     }
 }
@@ -0,0 +1,59 @@
+use super::IO;
+
+/// Console I/O through closures, for one-off embeddings (tests, scripting
+/// hosts) that don't warrant an IO type of their own
+pub struct CallbackIO {
+    write: Box<dyn FnMut(char)>,
+    read: Box<dyn FnMut() -> Option<char>>,
+    has_input: Option<Box<dyn FnMut() -> bool>>,
+    on_halt: Option<Box<dyn FnMut()>>,
+}
+
+impl CallbackIO {
+    /// `write` receives each output character; `read` supplies input, or
+    /// None when there is none
+    pub fn new(
+        write: impl FnMut(char) + 'static,
+        read: impl FnMut() -> Option<char> + 'static,
+    ) -> Self {
+        Self {
+            write: Box::new(write),
+            read: Box::new(read),
+            has_input: None,
+            on_halt: None,
+        }
+    }
+
+    /// Answer KBSR polls with `has_input`. Without it no input is ever
+    /// reported waiting, though GETC still calls `read`.
+    pub fn with_has_input(mut self, has_input: impl FnMut() -> bool + 'static) -> Self {
+        self.has_input = Some(Box::new(has_input));
+        self
+    }
+
+    /// Run `on_halt` when the machine halts
+    pub fn with_halt(mut self, on_halt: impl FnMut() + 'static) -> Self {
+        self.on_halt = Some(Box::new(on_halt));
+        self
+    }
+}
+
+impl IO for CallbackIO {
+    fn write_char(&mut self, ch: char) {
+        (self.write)(ch);
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        (self.read)()
+    }
+
+    fn has_input(&mut self) -> bool {
+        self.has_input.as_mut().is_some_and(|has_input| has_input())
+    }
+
+    fn on_halt(&mut self) {
+        if let Some(on_halt) = &mut self.on_halt {
+            on_halt();
+        }
+    }
+}
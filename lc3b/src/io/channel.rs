@@ -0,0 +1,52 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use super::IO;
+
+/// Console I/O over mpsc channels, so a host on other threads (a TUI, a
+/// test harness, a network frontend) can type into and read from a
+/// running machine.
+///
+/// Reading blocks until the host sends a character, as GETC waits for a
+/// key; once every sender is gone reads return None. Output sent after
+/// the receiver is dropped is discarded.
+pub struct ChannelIO {
+    input: Receiver<char>,
+    output: Sender<char>,
+    /// A character taken off `input` by `has_input` but not read yet
+    peeked: Option<char>,
+}
+
+impl ChannelIO {
+    pub fn new(input: Receiver<char>, output: Sender<char>) -> Self {
+        Self {
+            input,
+            output,
+            peeked: None,
+        }
+    }
+
+    /// A ChannelIO along with the host's ends: the sender for keyboard
+    /// input and the receiver for console output
+    pub fn channels() -> (Self, Sender<char>, Receiver<char>) {
+        let (input_tx, input_rx) = channel();
+        let (output_tx, output_rx) = channel();
+        (Self::new(input_rx, output_tx), input_tx, output_rx)
+    }
+}
+
+impl IO for ChannelIO {
+    fn write_char(&mut self, ch: char) {
+        let _ = self.output.send(ch);
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        self.peeked.take().or_else(|| self.input.recv().ok())
+    }
+
+    fn has_input(&mut self) -> bool {
+        if self.peeked.is_none() {
+            self.peeked = self.input.try_recv().ok();
+        }
+        self.peeked.is_some()
+    }
+}
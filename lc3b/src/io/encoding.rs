@@ -0,0 +1,38 @@
+/// How console words map to characters (`IO::encoding`): what OUT, PUTS,
+/// PUTSP and DDR print for a word, and what GETC, IN and KBDR store for a
+/// typed character
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Encoding {
+    /// Only x00-x7F; anything else is dropped, and the run stops with
+    /// `Error::NotAscii` once the instruction has retired
+    Ascii,
+    /// The low byte of each word, as on the real machine; a typed
+    /// character beyond U+00FF is stored as `?`
+    #[default]
+    Latin1,
+    /// Each word is one UTF-16 code unit within the Basic Multilingual
+    /// Plane. Surrogates print as U+FFFD, and a typed character beyond the
+    /// BMP is stored as xFFFD. PUTSP still packs single bytes.
+    Utf16,
+}
+
+impl Encoding {
+    /// The character `word` prints as; None if `Ascii` rejects it
+    pub fn decode(self, word: u16) -> Option<char> {
+        match self {
+            Encoding::Ascii => (word < 0x80).then_some(word as u8 as char),
+            Encoding::Latin1 => Some((word & 0xFF) as u8 as char),
+            Encoding::Utf16 => Some(char::from_u32(word as u32).unwrap_or('\u{FFFD}')),
+        }
+    }
+
+    /// The word a typed `ch` is stored as; None if `Ascii` rejects it
+    pub fn encode(self, ch: char) -> Option<u16> {
+        match self {
+            Encoding::Ascii => ch.is_ascii().then_some(ch as u16),
+            Encoding::Latin1 => Some(u8::try_from(ch).unwrap_or(b'?') as u16),
+            Encoding::Utf16 => Some(u16::try_from(ch as u32).unwrap_or(0xFFFD)),
+        }
+    }
+}
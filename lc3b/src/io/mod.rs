@@ -1,8 +1,24 @@
 mod buffered;
+mod callback;
+mod channel;
+mod encoding;
+mod record;
+mod scripted;
 mod stdio;
+mod tee;
+#[cfg(feature = "terminal")]
+mod terminal;
 
 pub use buffered::BufferedIO;
+pub use callback::CallbackIO;
+pub use channel::ChannelIO;
+pub use encoding::Encoding;
+pub use record::{IoEvent, IoLog, IoRecord, RecordingIO};
+pub use scripted::{ScriptMismatch, ScriptStep, ScriptedIO};
 pub use stdio::StdIO;
+pub use tee::TeeIO;
+#[cfg(feature = "terminal")]
+pub use terminal::TerminalIO;
 
 /// I/O handler for LC-3b TRAP instructions
 /// Implement this trait to provide console I/O for different platforms
@@ -32,9 +48,36 @@ pub trait IO {
         }
     }
 
-    /// Called when HALT executes (TRAP x25)
-    fn halt(&mut self);
+    /// Whether a character is waiting to be read, without consuming it:
+    /// the KBSR ready bit and the keyboard interrupt both poll this, and a
+    /// program that only calls GETC once it reports true never blocks.
+    /// The default reports no input, so polling programs never block.
+    fn has_input(&mut self) -> bool {
+        false
+    }
+
+    /// Whether the display can accept another character (DSR ready bit)
+    fn display_ready(&mut self) -> bool {
+        true
+    }
+
+    /// How console words and characters convert, for every trap and
+    /// device register
+    fn encoding(&self) -> Encoding {
+        Encoding::Latin1
+    }
+
+    /// Told the machine's instruction count (`Computer::instructions_executed`)
+    /// before each instruction, for I/O that timestamps what it sees
+    fn set_clock(&mut self, _instructions: u64) {}
 
-    /// Check if halted
-    fn is_halted(&self) -> bool;
+    /// Called when the machine halts, whatever the reason
+    /// (`Computer::halt_reason`)
+    fn on_halt(&mut self) {}
+
+    /// Whether the I/O wants the machine halted, e.g. on Ctrl-C; checked
+    /// after each instruction that used it. Reports each request once.
+    fn halt_requested(&mut self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use super::{Encoding, IO};
+use crate::Error;
+
+/// One console interaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IoEvent {
+    /// A character the program read
+    Input(char),
+    /// A character the program printed
+    Output(char),
+    Halt,
+}
+
+/// An event and when it happened, in instructions executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IoRecord {
+    pub at: u64,
+    pub event: IoEvent,
+}
+
+/// Console interaction recorded by `RecordingIO`.
+///
+/// Besides serde, logs round-trip through a line-oriented text form (via
+/// `Display` and `FromStr`) that is easy to paste into a bug report: one
+/// record per line as `<at> in <code point>`, `<at> out <code point>` or
+/// `<at> halt`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IoLog {
+    records: Vec<IoRecord>,
+}
+
+impl IoLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> &[IoRecord] {
+        &self.records
+    }
+
+    pub fn push(&mut self, at: u64, event: IoEvent) {
+        self.records.push(IoRecord { at, event });
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Everything the program read, in order
+    pub fn input(&self) -> String {
+        self.records
+            .iter()
+            .filter_map(|r| match r.event {
+                IoEvent::Input(ch) => Some(ch),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Everything the program printed, in order
+    pub fn output(&self) -> String {
+        self.records
+            .iter()
+            .filter_map(|r| match r.event {
+                IoEvent::Output(ch) => Some(ch),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for IoLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for record in &self.records {
+            match record.event {
+                IoEvent::Input(ch) => writeln!(f, "{} in {}", record.at, ch as u32)?,
+                IoEvent::Output(ch) => writeln!(f, "{} out {}", record.at, ch as u32)?,
+                IoEvent::Halt => writeln!(f, "{} halt", record.at)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for IoLog {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut log = IoLog::new();
+        for (index, line) in s.lines().enumerate() {
+            let error = |reason: &str| Error::InvalidIoLog {
+                line: index + 1,
+                reason: reason.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+            let at = fields[0].parse().map_err(|_| error("expected an instruction count"))?;
+            let ch = || {
+                match fields.get(2) {
+                    Some(code) => code.parse().ok().and_then(char::from_u32),
+                    None => None,
+                }
+                .ok_or_else(|| error("expected a character code point"))
+            };
+            let event = match fields.get(1) {
+                Some(&"in") => IoEvent::Input(ch()?),
+                Some(&"out") => IoEvent::Output(ch()?),
+                Some(&"halt") => IoEvent::Halt,
+                _ => return Err(error("expected `in`, `out` or `halt`")),
+            };
+            log.push(at, event);
+        }
+        Ok(log)
+    }
+}
+
+/// A log being fed back by `RecordingIO::replay`
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Replay {
+    /// Inputs not read yet, with when each became available
+    input: VecDeque<(u64, char)>,
+    /// The recorded output, to check the rerun against
+    output: Vec<char>,
+    /// How much of `output` the rerun has printed
+    printed: usize,
+    divergence: Option<u64>,
+}
+
+/// Wraps another IO and records every character read and printed, and the
+/// halt, with the instruction count at which it happened, counted from the
+/// first instruction after the recording started.
+///
+/// `replay` feeds a log's input back instead of the wrapped IO's, each
+/// character becoming available (to KBSR polls) at the instruction it did
+/// originally, so a deterministic program reruns exactly: a bug report
+/// only needs the program and its log. GETC takes replayed input as soon
+/// as it asks. Once the log's input runs out, reads fall through to the
+/// wrapped IO again. Output always goes to the wrapped IO, and the new
+/// recording continues during a replay.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordingIO<I: IO> {
+    inner: I,
+    log: IoLog,
+    /// Instructions since the recording started
+    clock: u64,
+    /// `Computer::instructions_executed` when it started, taken from the
+    /// first `set_clock` after
+    origin: Option<u64>,
+    /// When `has_input` first reported the next character waiting; that is
+    /// when it arrived, as far as the program can tell
+    ready_since: Option<u64>,
+    replay: Option<Replay>,
+}
+
+impl<I: IO> RecordingIO<I> {
+    pub fn new(inner: I) -> Self {
+        Self {
+            inner,
+            log: IoLog::new(),
+            clock: 0,
+            origin: None,
+            ready_since: None,
+            replay: None,
+        }
+    }
+
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+
+    pub fn log(&self) -> &IoLog {
+        &self.log
+    }
+
+    /// Take the log recorded so far, leaving an empty one
+    pub fn take_log(&mut self) -> IoLog {
+        self.ready_since = None;
+        self.origin = None;
+        self.clock = 0;
+        std::mem::take(&mut self.log)
+    }
+
+    pub fn clear_log(&mut self) {
+        self.take_log();
+    }
+
+    /// Feed `log`'s input back from now on, and start a fresh recording to
+    /// compare against it. Load the program first, so the rerun starts
+    /// where the recording did.
+    pub fn replay(&mut self, log: &IoLog) {
+        let mut replay = Replay::default();
+        for record in log.records() {
+            match record.event {
+                IoEvent::Input(ch) => replay.input.push_back((record.at, ch)),
+                IoEvent::Output(ch) => replay.output.push(ch),
+                IoEvent::Halt => {}
+            }
+        }
+        self.replay = Some(replay);
+        self.clear_log();
+    }
+
+    /// Stop replaying and read from the wrapped IO again
+    pub fn stop_replay(&mut self) {
+        self.replay = None;
+    }
+
+    /// Whether replayed input remains
+    pub fn is_replaying(&self) -> bool {
+        self.replay.as_ref().is_some_and(|r| !r.input.is_empty())
+    }
+
+    /// The instruction count at which the rerun first printed something
+    /// other than the recording did, if it has: a different character,
+    /// one past the end of the recorded output, or a halt before all of
+    /// it was printed
+    pub fn divergence(&self) -> Option<u64> {
+        self.replay.as_ref().and_then(|r| r.divergence)
+    }
+
+    fn record_input(&mut self, ch: char) {
+        let at = self.ready_since.take().unwrap_or(self.clock);
+        self.log.push(at, IoEvent::Input(ch));
+    }
+}
+
+impl<I: IO> IO for RecordingIO<I> {
+    fn write_char(&mut self, ch: char) {
+        self.log.push(self.clock, IoEvent::Output(ch));
+        if let Some(replay) = &mut self.replay {
+            if replay.divergence.is_none() && replay.output.get(replay.printed) != Some(&ch) {
+                replay.divergence = Some(self.clock);
+            }
+            replay.printed += 1;
+        }
+        self.inner.write_char(ch);
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        let replayed = self.replay.as_mut().and_then(|r| r.input.pop_front());
+        let ch = match replayed {
+            Some((_, ch)) => Some(ch),
+            None => self.inner.read_char(),
+        };
+        if let Some(ch) = ch {
+            self.record_input(ch);
+        }
+        ch
+    }
+
+    fn has_input(&mut self) -> bool {
+        let ready = match self.replay.as_ref().and_then(|r| r.input.front()) {
+            Some(&(at, _)) => at <= self.clock,
+            None => self.inner.has_input(),
+        };
+        if ready && self.ready_since.is_none() {
+            self.ready_since = Some(self.clock);
+        }
+        ready
+    }
+
+    fn display_ready(&mut self) -> bool {
+        self.inner.display_ready()
+    }
+
+    fn encoding(&self) -> Encoding {
+        self.inner.encoding()
+    }
+
+    fn set_clock(&mut self, instructions: u64) {
+        let origin = self.origin.get_or_insert(instructions);
+        // `Computer::reset` starts the count over
+        *origin = (*origin).min(instructions);
+        self.clock = instructions - *origin;
+        self.inner.set_clock(instructions);
+    }
+
+    fn on_halt(&mut self) {
+        self.log.push(self.clock, IoEvent::Halt);
+        if let Some(replay) = &mut self.replay {
+            if replay.divergence.is_none() && replay.printed < replay.output.len() {
+                replay.divergence = Some(self.clock);
+            }
+        }
+        self.inner.on_halt();
+    }
+
+    fn halt_requested(&mut self) -> bool {
+        self.inner.halt_requested()
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::IO;
+
+/// One step of a `ScriptedIO` conversation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptStep {
+    /// The program must print exactly this next
+    Expect(String),
+    /// Then the user types this
+    Input(String),
+}
+
+/// Where a program's console behavior left its script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptMismatch {
+    /// Index of the step that failed, or the step count if the program
+    /// printed past the end of the script
+    pub step: usize,
+    /// Output the script called for at that point ("" past the end)
+    pub expected: String,
+    /// What the program printed instead, up to the divergence; for an
+    /// unexpected read, the output matched so far
+    pub actual: String,
+    /// True if the program tried to read input while output was expected
+    pub unexpected_read: bool,
+}
+
+impl fmt::Display for ScriptMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unexpected_read {
+            writeln!(f, "step {}: program read input before printing", self.step)?;
+            writeln!(f, "expected: {:?}", self.expected)?;
+            return write!(f, "printed:  {:?}", self.actual);
+        }
+        let column = self
+            .expected
+            .chars()
+            .zip(self.actual.chars())
+            .take_while(|(e, a)| e == a)
+            .map(|(e, _)| format!("{:?}", e.to_string()).len() - 2)
+            .sum::<usize>();
+        writeln!(f, "step {}: console output diverged", self.step)?;
+        writeln!(f, "expected: {:?}", self.expected)?;
+        writeln!(f, "actual:   {:?}", self.actual)?;
+        // Under the first differing character, past `actual:   "` and the
+        // escaped matching prefix
+        write!(f, "{:>width$}", "^", width = 12 + column)
+    }
+}
+
+impl std::error::Error for ScriptMismatch {}
+
+/// Console I/O that follows an expect-style script, for testing
+/// interactive programs deterministically: each `Expect` must match the
+/// program's next output exactly, and each `Input` is released only once
+/// the output before it has matched. The first divergence halts the
+/// machine; `finish` reports it.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedIO {
+    steps: Vec<ScriptStep>,
+    /// Index of the step being matched
+    step: usize,
+    /// Output printed toward the current `Expect`
+    pending: String,
+    /// Everything printed
+    output: String,
+    input: VecDeque<char>,
+    mismatch: Option<ScriptMismatch>,
+    /// Set by a divergence until the machine halts for it
+    halt_request: bool,
+}
+
+impl ScriptedIO {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_steps(steps: impl IntoIterator<Item = ScriptStep>) -> Self {
+        let mut io = Self {
+            steps: steps.into_iter().collect(),
+            ..Self::default()
+        };
+        io.release_input();
+        io
+    }
+
+    /// Add an `Expect` step
+    pub fn expect(mut self, output: &str) -> Self {
+        self.steps.push(ScriptStep::Expect(output.to_string()));
+        self.release_input();
+        self
+    }
+
+    /// Add an `Input` step
+    pub fn input(mut self, input: &str) -> Self {
+        self.steps.push(ScriptStep::Input(input.to_string()));
+        self.release_input();
+        self
+    }
+
+    /// Everything the program printed
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Ok if the program followed the whole script, otherwise where it
+    /// diverged (including stopping short of the end)
+    pub fn finish(&self) -> Result<(), ScriptMismatch> {
+        if let Some(mismatch) = &self.mismatch {
+            return Err(mismatch.clone());
+        }
+        match self.steps.get(self.step) {
+            Some(ScriptStep::Expect(expected)) => Err(ScriptMismatch {
+                step: self.step,
+                expected: expected.clone(),
+                actual: self.pending.clone(),
+                unexpected_read: false,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Queue the input of every `Input` step up to the next `Expect`
+    fn release_input(&mut self) {
+        while let Some(ScriptStep::Input(text)) = self.steps.get(self.step) {
+            self.input.extend(text.chars());
+            self.step += 1;
+        }
+    }
+
+    fn fail(&mut self, expected: String, unexpected_read: bool) {
+        if self.mismatch.is_none() {
+            self.mismatch = Some(ScriptMismatch {
+                step: self.step,
+                expected,
+                actual: self.pending.clone(),
+                unexpected_read,
+            });
+        }
+        self.halt_request = true;
+    }
+}
+
+impl IO for ScriptedIO {
+    fn write_char(&mut self, ch: char) {
+        self.output.push(ch);
+        if self.mismatch.is_some() {
+            return;
+        }
+        self.pending.push(ch);
+        let expected = match self.steps.get(self.step) {
+            Some(ScriptStep::Expect(expected)) => expected.clone(),
+            _ => return self.fail(String::new(), false),
+        };
+        if !expected.starts_with(&self.pending) {
+            return self.fail(expected, false);
+        }
+        if expected == self.pending {
+            self.pending.clear();
+            self.step += 1;
+            self.release_input();
+        }
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        let ch = self.input.pop_front();
+        if ch.is_none() {
+            if let Some(ScriptStep::Expect(expected)) = self.steps.get(self.step) {
+                let expected = expected.clone();
+                self.fail(expected, true);
+            }
+        }
+        ch
+    }
+
+    fn has_input(&mut self) -> bool {
+        !self.input.is_empty()
+    }
+
+    fn halt_requested(&mut self) -> bool {
+        std::mem::take(&mut self.halt_request)
+    }
+}
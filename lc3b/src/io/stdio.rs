@@ -1,21 +1,22 @@
 use std::io::{self, Read, Write};
 
-use super::IO;
+use super::{Encoding, IO};
 
-/// Standard I/O for CLI usage
+/// Standard I/O for CLI usage. Input is line-buffered; `TerminalIO` (the
+/// `terminal` feature) reads single keystrokes instead.
+#[derive(Debug, Default)]
 pub struct StdIO {
-    halted: bool,
+    encoding: Encoding,
 }
 
 impl StdIO {
     pub fn new() -> Self {
-        Self { halted: false }
+        Self::default()
     }
-}
 
-impl Default for StdIO {
-    fn default() -> Self {
-        Self::new()
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
     }
 }
 
@@ -31,11 +32,13 @@ impl IO for StdIO {
         Some(buf[0] as char)
     }
 
-    fn halt(&mut self) {
-        self.halted = true;
+    /// Stdin is line-buffered and blocking, so a read always eventually
+    /// produces a character; report ready and let KBDR block
+    fn has_input(&mut self) -> bool {
+        true
     }
 
-    fn is_halted(&self) -> bool {
-        self.halted
+    fn encoding(&self) -> Encoding {
+        self.encoding
     }
 }
@@ -0,0 +1,96 @@
+use std::io::{self, Write};
+
+use super::{Encoding, IO};
+
+/// Wraps another IO and copies everything printed to a second sink (a log
+/// file, or a `Vec<u8>` to capture into) as UTF-8, so output can be shown
+/// and kept at once. Input and everything else pass straight through.
+///
+/// Writing to the sink never disturbs the machine: the first error is kept
+/// for `take_error` and later output is still attempted.
+pub struct TeeIO<I: IO, W: Write> {
+    inner: I,
+    sink: W,
+    error: Option<io::Error>,
+}
+
+impl<I: IO, W: Write> TeeIO<I, W> {
+    pub fn new(inner: I, sink: W) -> Self {
+        Self {
+            inner,
+            sink,
+            error: None,
+        }
+    }
+
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.inner
+    }
+
+    pub fn sink(&self) -> &W {
+        &self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut W {
+        &mut self.sink
+    }
+
+    pub fn into_parts(self) -> (I, W) {
+        (self.inner, self.sink)
+    }
+
+    /// The first error writing to the sink since the last call, if any
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn record(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            self.error.get_or_insert(e);
+        }
+    }
+}
+
+impl<I: IO, W: Write> IO for TeeIO<I, W> {
+    fn write_char(&mut self, ch: char) {
+        self.inner.write_char(ch);
+        let mut buf = [0; 4];
+        let result = self.sink.write_all(ch.encode_utf8(&mut buf).as_bytes());
+        self.record(result);
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        self.inner.read_char()
+    }
+
+    fn has_input(&mut self) -> bool {
+        self.inner.has_input()
+    }
+
+    fn display_ready(&mut self) -> bool {
+        self.inner.display_ready()
+    }
+
+    fn encoding(&self) -> Encoding {
+        self.inner.encoding()
+    }
+
+    fn set_clock(&mut self, instructions: u64) {
+        self.inner.set_clock(instructions);
+    }
+
+    /// Flushes the sink, so a log file is complete once the program ends
+    fn on_halt(&mut self) {
+        self.inner.on_halt();
+        let result = self.sink.flush();
+        self.record(result);
+    }
+
+    fn halt_requested(&mut self) -> bool {
+        self.inner.halt_requested()
+    }
+}
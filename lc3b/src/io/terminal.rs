@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+
+use super::IO;
+
+/// Console I/O with the terminal in raw mode, so keys arrive as they are
+/// pressed instead of a line at a time: KBSR reports a key only once one
+/// is waiting, GETC takes a single keystroke without Enter, and nothing
+/// is echoed unless the program (or IN) echoes it.
+///
+/// Ctrl-C halts the machine, since raw mode stops it from sending
+/// SIGINT. The terminal is restored when the value is dropped.
+pub struct TerminalIO {
+    pending: VecDeque<char>,
+    /// Ctrl-C was pressed and the machine has not halted for it yet
+    interrupted: bool,
+}
+
+impl TerminalIO {
+    /// Switch the terminal to raw mode
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self {
+            pending: VecDeque::new(),
+            interrupted: false,
+        })
+    }
+
+    /// Queue the key from `event`, if it is one a program can read
+    fn accept(&mut self, event: Event) {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        else {
+            return;
+        };
+        let ch = match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.interrupted = true;
+                return;
+            }
+            KeyCode::Char(ch) => ch,
+            KeyCode::Enter => '\n',
+            KeyCode::Tab => '\t',
+            KeyCode::Backspace => '\x08',
+            KeyCode::Esc => '\x1b',
+            _ => return,
+        };
+        self.pending.push_back(ch);
+    }
+
+    /// Move every key already pressed into `pending` without waiting
+    fn poll(&mut self) {
+        while let Ok(true) = event::poll(Duration::ZERO) {
+            match event::read() {
+                Ok(event) => self.accept(event),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl Drop for TerminalIO {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl IO for TerminalIO {
+    fn write_char(&mut self, ch: char) {
+        let mut stdout = io::stdout();
+        // Raw mode doesn't turn a newline into a carriage return as well
+        let _ = if ch == '\n' {
+            stdout.write_all(b"\r\n")
+        } else {
+            write!(stdout, "{}", ch)
+        };
+        let _ = stdout.flush();
+    }
+
+    /// Wait for a keystroke, like GETC polling KBSR. Returns None if
+    /// Ctrl-C halts the machine first.
+    fn read_char(&mut self) -> Option<char> {
+        loop {
+            self.poll();
+            if let Some(ch) = self.pending.pop_front() {
+                return Some(ch);
+            }
+            if self.interrupted {
+                return None;
+            }
+            match event::read() {
+                Ok(event) => self.accept(event),
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn has_input(&mut self) -> bool {
+        self.poll();
+        !self.pending.is_empty()
+    }
+
+    fn halt_requested(&mut self) -> bool {
+        std::mem::take(&mut self.interrupted)
+    }
+}
@@ -1,10 +1,25 @@
 #![allow(unexpected_cfgs)]
 
+pub use lc3b_assembler::{SourceMap, SymbolTable};
+
 mod io;
-pub use io::{BufferedIO, StdIO, IO};
+pub use io::{
+    BufferedIO, CallbackIO, ChannelIO, Encoding, IoEvent, IoLog, IoRecord, RecordingIO, ScriptMismatch,
+    ScriptStep, ScriptedIO, StdIO, TeeIO, IO,
+};
+#[cfg(feature = "terminal")]
+pub use io::TerminalIO;
+
+mod os;
+pub use os::{TrapMode, OS_SOURCE, USER_STACK_START};
 
 mod observer;
-pub use observer::{Observer, UIObserver};
+pub use observer::{
+    BranchStats, ChannelObserver, CompositeObserver, CoverageObserver, CoverageReport,
+    ExecutionEvent, LineCoverage, MemoryDelta, Observer, ProfileObserver, ProfileReport,
+    RegisterDelta, StatisticsObserver, StatisticsReport, TraceObserver, TraceStep, UIObserver,
+    TRACE_MAGIC,
+};
 
 mod computer;
 pub use computer::*;
@@ -12,13 +27,27 @@ pub use computer::*;
 mod constants;
 pub use constants::*;
 
+mod device;
+pub use device::{
+    Device, Framebuffer, FRAMEBUFFER_HEIGHT, FRAMEBUFFER_START, FRAMEBUFFER_WIDTH,
+};
+
 mod error;
 pub use error::*;
 
+mod interrupt;
+pub use interrupt::*;
+
 mod memory;
 pub use memory::*;
 
+mod mmio;
+pub use mmio::*;
+
 mod program;
 pub use program::*;
 
+mod samples;
+pub use samples::{Sample, SampleLanguage, SAMPLES};
+
 pub mod wasm;
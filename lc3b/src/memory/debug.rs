@@ -9,6 +9,32 @@ pub fn dump_words_to_binary(words: &[u16]) -> String {
     string
 }
 
+/// Eight words per line: address, words in hex, then each word's low
+/// byte as ASCII (`.` if not printable)
+pub(crate) fn hexdump(words: &[u16], start: u16) -> String {
+    let mut string = String::new();
+
+    for (line, chunk) in words.chunks(8).enumerate() {
+        let addr = start.wrapping_add(line as u16 * 8);
+        string += &format!("x{:04X}:", addr);
+        for word in chunk {
+            string += &format!(" {:04X}", word);
+        }
+        string += &" ".repeat((8 - chunk.len()) * 5 + 2);
+        for word in chunk {
+            let byte = (word & 0xFF) as u8;
+            string.push(if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            });
+        }
+        string.push('\n');
+    }
+
+    string
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -20,4 +46,13 @@ mod tests {
 
         assert_eq!(expected, dumped);
     }
+
+    #[test]
+    fn test_hexdump() {
+        let data = [0x0048, 0x0069, 0xF025, 0, 1, 2, 3, 4, 0x0021];
+
+        let expected = "x3000: 0048 0069 F025 0000 0001 0002 0003 0004  Hi%.....\n\
+                        x3008: 0021                                     !\n";
+        assert_eq!(expected, super::hexdump(&data, 0x3000));
+    }
 }
@@ -1,14 +1,31 @@
 use std::fmt::Debug;
 
+use lc3b_isa::{DecodeError, Instruction};
+
 mod debug;
+pub(crate) use debug::hexdump;
 
 /// LC-3b memory: 65536 addressable 16-bit words (128KB total)
 /// Each address holds one 16-bit word.
-pub struct Memory([u16; 65536]);
+pub struct Memory {
+    /// Boxed so a Computer stays cheap to move
+    words: Box<[u16; 65536]>,
+    /// One bit per address, set once the word has been written
+    written: Box<[u64; 1024]>,
+    /// Instructions decoded by `decode`, cleared when their word is written
+    decoded: Box<[Option<Instruction>]>,
+}
 
 impl Default for Memory {
     fn default() -> Self {
-        Memory([0; 65536])
+        Memory {
+            words: vec![0; 65536]
+                .into_boxed_slice()
+                .try_into()
+                .expect("slice has 65536 words"),
+            written: Box::new([0; 1024]),
+            decoded: vec![None; 65536].into_boxed_slice(),
+        }
     }
 }
 
@@ -21,19 +38,64 @@ impl Debug for Memory {
 impl Memory {
     /// Read a 16-bit word from the given address
     pub fn read_word(&self, addr: u16) -> u16 {
-        self.0[addr as usize]
+        self.words[addr as usize]
+    }
+
+    /// Up to `len` words starting at `start`, stopping at the end of memory
+    pub fn words(&self, start: u16, len: usize) -> &[u16] {
+        let start = start as usize;
+        let end = start.saturating_add(len).min(self.words.len());
+        &self.words[start..end]
+    }
+
+    /// True once anything has written the word at `addr`
+    pub fn is_written(&self, addr: u16) -> bool {
+        self.written[addr as usize / 64] & 1 << (addr % 64) != 0
+    }
+
+    fn mark_written(&mut self, addr: u16) {
+        self.written[addr as usize / 64] |= 1 << (addr % 64);
+        self.decoded[addr as usize] = None;
+    }
+
+    /// Count the word at `addr` as never written again, as when undoing
+    /// its first write
+    pub(crate) fn forget_write(&mut self, addr: u16) {
+        self.written[addr as usize / 64] &= !(1 << (addr % 64));
+    }
+
+    /// Written bits for the 64 words from `group * 64`
+    pub(crate) fn written_group(&self, group: usize) -> u64 {
+        self.written[group]
+    }
+
+    pub(crate) fn set_written_group(&mut self, group: usize, bits: u64) {
+        self.written[group] = bits;
+    }
+
+    /// Decode the word at `addr` as an instruction, reusing the previous
+    /// decode if the word has not been written since
+    pub fn decode(&mut self, addr: u16) -> Result<Instruction, DecodeError> {
+        if let Some(inst) = self.decoded[addr as usize] {
+            return Ok(inst);
+        }
+        let inst = Instruction::try_from(self.words[addr as usize])?;
+        self.decoded[addr as usize] = Some(inst);
+        Ok(inst)
     }
 
     /// Write a 16-bit word to the given address
     pub fn write_word(&mut self, addr: u16, value: u16) {
-        self.0[addr as usize] = value;
+        self.words[addr as usize] = value;
+        self.mark_written(addr);
     }
 
     /// Load a slice of words into memory starting at the given address
     pub fn load_words(&mut self, start_addr: u16, words: &[u16]) {
         for (i, &word) in words.iter().enumerate() {
             let addr = start_addr.wrapping_add(i as u16);
-            self.0[addr as usize] = word;
+            self.words[addr as usize] = word;
+            self.mark_written(addr);
         }
     }
 }
@@ -63,5 +125,22 @@ mod tests {
         assert_eq!(memory.read_word(0x3000), 0x1260);
         assert_eq!(memory.read_word(0x3001), 0x12A5);
         assert_eq!(memory.read_word(0x3002), 0x1642);
+        assert!(memory.is_written(0x3002));
+        assert!(!memory.is_written(0x3003));
+    }
+
+    #[test]
+    pub fn test_decode_cache_invalidated_by_writes() {
+        use lc3b_isa::Instruction;
+
+        let mut memory = Memory::default();
+        memory.write_word(0x3000, 0xF025);
+        assert_eq!(memory.decode(0x3000), Instruction::try_from(0xF025));
+        assert_eq!(memory.decode(0x3000), Instruction::try_from(0xF025));
+
+        memory.write_word(0x3000, 0xC1C0);
+        assert_eq!(memory.decode(0x3000), Instruction::try_from(0xC1C0));
+        memory.load_words(0x3000, &[0x1260]);
+        assert_eq!(memory.decode(0x3000), Instruction::try_from(0x1260));
     }
 }
@@ -0,0 +1,325 @@
+//! Memory-mapped device registers.
+//!
+//! Addresses are the values LDR/LDI/STW/STI compute, so a program reaches
+//! the keyboard status register with a base register holding `xFE00`.
+
+use std::ops::RangeInclusive;
+
+use crate::{Device, Interrupt, IO, TIMER_INTERRUPT_VECTOR};
+
+/// Keyboard status register: bit 15 is set while a character is waiting,
+/// bit 14 enables keyboard interrupts
+pub const KBSR: u16 = 0xFE00;
+
+/// Keyboard data register: reading it consumes the waiting character
+pub const KBDR: u16 = 0xFE02;
+
+/// Display status register: bit 15 is set when the display can accept a
+/// character, bit 14 enables display interrupts
+pub const DSR: u16 = 0xFE04;
+
+/// Display data register: writing bits [7:0] outputs a character
+pub const DDR: u16 = 0xFE06;
+
+/// Timer status/control register: bit 15 is set each time the interval
+/// elapses (write it as 0 to acknowledge), bit 14 enables the timer
+/// interrupt, bit 13 counts clock cycles instead of instructions, and
+/// bits [10:8] hold the interrupt priority
+pub const TMR: u16 = 0xFE08;
+
+/// Timer interval register: ticks between expirations; 0 stops the timer.
+/// Writing it restarts the count.
+pub const TMI: u16 = 0xFE0A;
+
+/// TMR bit 13
+pub const TIMER_COUNT_CYCLES: u16 = 0x2000;
+
+/// Random number register: each read returns the next pseudo-random word;
+/// a write reseeds the generator with the value written
+pub const RNG: u16 = 0xFE0C;
+
+/// Seed the random number generator starts from unless
+/// `Computer::with_seed` picks another
+pub const DEFAULT_RNG_SEED: u64 = 0x4C43_3362;
+
+/// Machine control register: clearing bit 15 stops the clock (halts)
+pub const MCR: u16 = 0xFFFE;
+
+/// Status register bit 15
+pub const STATUS_READY: u16 = 0x8000;
+
+/// Status register bit 14
+pub const STATUS_INTERRUPT_ENABLE: u16 = 0x4000;
+
+/// True if `addr` is one of the device registers
+pub fn is_device_register(addr: u16) -> bool {
+    matches!(addr, KBSR | KBDR | DSR | DDR | TMR | TMI | RNG | MCR)
+}
+
+/// The built-in devices. Their state is plain data so snapshots and the
+/// step_back journal can copy it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct DeviceRegisters {
+    pub keyboard: Keyboard,
+    pub display: Display,
+    pub timer: Timer,
+    pub rng: Rng,
+    pub machine_control: MachineControl,
+}
+
+impl DeviceRegisters {
+    /// Power-on state with the generator seeded from `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        DeviceRegisters {
+            rng: Rng::new(seed),
+            ..Self::default()
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> [&mut dyn Device; 5] {
+        [
+            &mut self.keyboard,
+            &mut self.display,
+            &mut self.timer,
+            &mut self.rng,
+            &mut self.machine_control,
+        ]
+    }
+
+    pub fn ranges(&self) -> [RangeInclusive<u16>; 5] {
+        [
+            self.keyboard.range(),
+            self.display.range(),
+            self.timer.range(),
+            self.rng.range(),
+            self.machine_control.range(),
+        ]
+    }
+}
+
+fn status(ready: bool, interrupt_enable: bool) -> u16 {
+    let ready = if ready { STATUS_READY } else { 0 };
+    let ie = if interrupt_enable {
+        STATUS_INTERRUPT_ENABLE
+    } else {
+        0
+    };
+    ready | ie
+}
+
+/// KBSR and KBDR
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Keyboard {
+    pub interrupt_enable: bool,
+    /// A character KBDR read that the I/O's encoding rejects, for the
+    /// Computer to report
+    pub rejected: Option<char>,
+}
+
+impl Device for Keyboard {
+    fn range(&self) -> RangeInclusive<u16> {
+        KBSR..=KBDR
+    }
+
+    fn read_word(&mut self, addr: u16, io: &mut dyn IO) -> u16 {
+        match addr {
+            KBSR => status(io.has_input(), self.interrupt_enable),
+            KBDR => match io.read_char() {
+                Some(ch) => io.encoding().encode(ch).unwrap_or_else(|| {
+                    self.rejected = Some(ch);
+                    0
+                }),
+                None => 0,
+            },
+            _ => 0,
+        }
+    }
+
+    fn write_word(&mut self, addr: u16, value: u16, _io: &mut dyn IO) {
+        // Only the interrupt-enable bit of a status register is writable
+        if addr == KBSR {
+            self.interrupt_enable = value & STATUS_INTERRUPT_ENABLE != 0;
+        }
+    }
+
+    fn interrupt_request(&mut self, io: &mut dyn IO) -> Option<Interrupt> {
+        (self.interrupt_enable && io.has_input()).then_some(Interrupt::KEYBOARD)
+    }
+}
+
+/// DSR and DDR
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Display {
+    pub interrupt_enable: bool,
+}
+
+impl Device for Display {
+    fn range(&self) -> RangeInclusive<u16> {
+        DSR..=DDR
+    }
+
+    fn read_word(&mut self, addr: u16, io: &mut dyn IO) -> u16 {
+        match addr {
+            DSR => status(io.display_ready(), self.interrupt_enable),
+            _ => 0,
+        }
+    }
+
+    fn write_word(&mut self, addr: u16, value: u16, io: &mut dyn IO) {
+        match addr {
+            DSR => self.interrupt_enable = value & STATUS_INTERRUPT_ENABLE != 0,
+            DDR => {
+                // The Computer reports words the encoding rejects
+                if let Some(ch) = io.encoding().decode(value) {
+                    io.write_char(ch);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// TMR and TMI
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Timer {
+    pub ready: bool,
+    pub interrupt_enable: bool,
+    pub count_cycles: bool,
+    pub priority: u8,
+    pub interval: u16,
+    /// Ticks since the last expiration
+    pub count: u64,
+}
+
+impl Device for Timer {
+    fn range(&self) -> RangeInclusive<u16> {
+        TMR..=TMI
+    }
+
+    fn read_word(&mut self, addr: u16, _io: &mut dyn IO) -> u16 {
+        match addr {
+            TMR => {
+                let mut value = status(self.ready, self.interrupt_enable);
+                if self.count_cycles {
+                    value |= TIMER_COUNT_CYCLES;
+                }
+                value | (self.priority as u16 & 0x7) << 8
+            }
+            TMI => self.interval,
+            _ => 0,
+        }
+    }
+
+    fn write_word(&mut self, addr: u16, value: u16, _io: &mut dyn IO) {
+        match addr {
+            TMR => {
+                if value & STATUS_READY == 0 {
+                    self.ready = false;
+                }
+                self.interrupt_enable = value & STATUS_INTERRUPT_ENABLE != 0;
+                self.count_cycles = value & TIMER_COUNT_CYCLES != 0;
+                self.priority = ((value >> 8) & 0x7) as u8;
+            }
+            TMI => {
+                self.interval = value;
+                self.count = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        if self.interval == 0 {
+            return;
+        }
+        self.count += if self.count_cycles { cycles as u64 } else { 1 };
+        if self.count >= self.interval as u64 {
+            self.count %= self.interval as u64;
+            self.ready = true;
+        }
+    }
+
+    fn interrupt_request(&mut self, _io: &mut dyn IO) -> Option<Interrupt> {
+        if self.ready && self.interrupt_enable {
+            Interrupt::new(TIMER_INTERRUPT_VECTOR, self.priority).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// RNG: SplitMix64, so a seed gives the same sequence on every platform
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Rng {
+    /// Seed given at construction, restored by `Computer::reset`
+    pub seed: u64,
+    pub state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { seed, state: seed }
+    }
+
+    fn next_word(&mut self) -> u16 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 48) as u16
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(DEFAULT_RNG_SEED)
+    }
+}
+
+impl Device for Rng {
+    fn range(&self) -> RangeInclusive<u16> {
+        RNG..=RNG
+    }
+
+    fn read_word(&mut self, _addr: u16, _io: &mut dyn IO) -> u16 {
+        self.next_word()
+    }
+
+    fn write_word(&mut self, _addr: u16, value: u16, _io: &mut dyn IO) {
+        self.state = value as u64;
+    }
+}
+
+/// MCR. Clearing the clock enable bit asks the machine to halt; the
+/// Computer keeps `halted` in step with its own halt state so MCR reads
+/// back 0 while halted.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct MachineControl {
+    pub halted: bool,
+}
+
+impl Device for MachineControl {
+    fn range(&self) -> RangeInclusive<u16> {
+        MCR..=MCR
+    }
+
+    fn read_word(&mut self, _addr: u16, _io: &mut dyn IO) -> u16 {
+        if self.halted {
+            0
+        } else {
+            STATUS_READY
+        }
+    }
+
+    fn write_word(&mut self, _addr: u16, value: u16, _io: &mut dyn IO) {
+        if value & STATUS_READY == 0 {
+            self.halted = true;
+        }
+    }
+}
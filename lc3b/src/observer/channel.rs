@@ -0,0 +1,102 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use lc3b_isa::{Condition, Instruction};
+
+use super::Observer;
+use crate::UninitializedRead;
+
+/// One `Observer` callback, as sent by `ChannelObserver`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionEvent {
+    RegisterWrite { reg: u8, old: u16, new: u16 },
+    MemoryWrite { addr: u16, old: u16, new: u16 },
+    MemoryRead { addr: u16, value: u16 },
+    PcChange { old: u16, new: u16 },
+    ConditionChange(Condition),
+    InstructionStart { pc: u16, inst: Instruction },
+    InstructionEnd { pc: u16, inst: Instruction },
+    UninitializedRead { pc: u16, read: UninitializedRead },
+    Trap(u8),
+    IoOutput(char),
+    Halt,
+}
+
+/// Forwards every event to a channel so another thread (a TUI, a GUI, a
+/// logger) can follow execution while the emulator runs. Sending never
+/// blocks; once the receiver is dropped events are discarded.
+pub struct ChannelObserver {
+    sender: Sender<ExecutionEvent>,
+    disconnected: bool,
+}
+
+impl ChannelObserver {
+    pub fn new(sender: Sender<ExecutionEvent>) -> Self {
+        ChannelObserver {
+            sender,
+            disconnected: false,
+        }
+    }
+
+    /// An observer and the receiving end of its channel
+    pub fn channel() -> (Self, Receiver<ExecutionEvent>) {
+        let (sender, receiver) = channel();
+        (Self::new(sender), receiver)
+    }
+
+    /// True once the receiver has gone away
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    fn send(&mut self, event: ExecutionEvent) {
+        if !self.disconnected && self.sender.send(event).is_err() {
+            self.disconnected = true;
+        }
+    }
+}
+
+impl Observer for ChannelObserver {
+    fn on_register_write(&mut self, reg: u8, old: u16, new: u16) {
+        self.send(ExecutionEvent::RegisterWrite { reg, old, new });
+    }
+
+    fn on_memory_write(&mut self, addr: u16, old: u16, new: u16) {
+        self.send(ExecutionEvent::MemoryWrite { addr, old, new });
+    }
+
+    fn on_pc_change(&mut self, old: u16, new: u16) {
+        self.send(ExecutionEvent::PcChange { old, new });
+    }
+
+    fn on_condition_change(&mut self, cond: Condition) {
+        self.send(ExecutionEvent::ConditionChange(cond));
+    }
+
+    fn on_instruction_start(&mut self, pc: u16, inst: &Instruction) {
+        self.send(ExecutionEvent::InstructionStart { pc, inst: *inst });
+    }
+
+    fn on_instruction_end(&mut self, pc: u16, inst: &Instruction) {
+        self.send(ExecutionEvent::InstructionEnd { pc, inst: *inst });
+    }
+
+    fn on_uninitialized_read(&mut self, pc: u16, read: UninitializedRead) {
+        self.send(ExecutionEvent::UninitializedRead { pc, read });
+    }
+
+    fn on_memory_read(&mut self, addr: u16, value: u16) {
+        self.send(ExecutionEvent::MemoryRead { addr, value });
+    }
+
+    fn on_trap(&mut self, vector: u8) {
+        self.send(ExecutionEvent::Trap(vector));
+    }
+
+    fn on_io_output(&mut self, ch: char) {
+        self.send(ExecutionEvent::IoOutput(ch));
+    }
+
+    fn on_halt(&mut self) {
+        self.send(ExecutionEvent::Halt);
+    }
+}
@@ -0,0 +1,226 @@
+use lc3b_isa::{Condition, Instruction};
+
+use super::Observer;
+use crate::UninitializedRead;
+
+/// Observers chosen at runtime, each notified in the order added.
+///
+/// For a fixed set known at compile time a tuple such as
+/// `(UIObserver, TraceObserver)` does the same job and keeps each
+/// observer reachable through `computer.observer().0`.
+#[derive(Default)]
+pub struct CompositeObserver {
+    observers: Vec<Box<dyn Observer>>,
+}
+
+impl CompositeObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder form of `push`
+    pub fn with(mut self, observer: impl Observer + 'static) -> Self {
+        self.push(observer);
+        self
+    }
+
+    pub fn push(&mut self, observer: impl Observer + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.observers.clear();
+    }
+}
+
+impl Observer for CompositeObserver {
+    fn on_register_write(&mut self, reg: u8, old: u16, new: u16) {
+        for observer in &mut self.observers {
+            observer.on_register_write(reg, old, new);
+        }
+    }
+
+    fn on_memory_write(&mut self, addr: u16, old: u16, new: u16) {
+        for observer in &mut self.observers {
+            observer.on_memory_write(addr, old, new);
+        }
+    }
+
+    fn on_pc_change(&mut self, old: u16, new: u16) {
+        for observer in &mut self.observers {
+            observer.on_pc_change(old, new);
+        }
+    }
+
+    fn on_condition_change(&mut self, cond: Condition) {
+        for observer in &mut self.observers {
+            observer.on_condition_change(cond);
+        }
+    }
+
+    fn on_instruction_start(&mut self, pc: u16, inst: &Instruction) {
+        for observer in &mut self.observers {
+            observer.on_instruction_start(pc, inst);
+        }
+    }
+
+    fn on_instruction_end(&mut self, pc: u16, inst: &Instruction) {
+        for observer in &mut self.observers {
+            observer.on_instruction_end(pc, inst);
+        }
+    }
+
+    fn on_uninitialized_read(&mut self, pc: u16, read: UninitializedRead) {
+        for observer in &mut self.observers {
+            observer.on_uninitialized_read(pc, read);
+        }
+    }
+
+    fn on_memory_read(&mut self, addr: u16, value: u16) {
+        for observer in &mut self.observers {
+            observer.on_memory_read(addr, value);
+        }
+    }
+
+    fn on_trap(&mut self, vector: u8) {
+        for observer in &mut self.observers {
+            observer.on_trap(vector);
+        }
+    }
+
+    fn on_io_output(&mut self, ch: char) {
+        for observer in &mut self.observers {
+            observer.on_io_output(ch);
+        }
+    }
+
+    fn on_halt(&mut self) {
+        for observer in &mut self.observers {
+            observer.on_halt();
+        }
+    }
+
+    /// True if any observer asks, polling every one
+    fn stop_requested(&mut self) -> bool {
+        self.observers
+            .iter_mut()
+            .fold(false, |stop, observer| observer.stop_requested() | stop)
+    }
+}
+
+impl<O: Observer + ?Sized> Observer for Box<O> {
+    fn on_register_write(&mut self, reg: u8, old: u16, new: u16) {
+        (**self).on_register_write(reg, old, new);
+    }
+
+    fn on_memory_write(&mut self, addr: u16, old: u16, new: u16) {
+        (**self).on_memory_write(addr, old, new);
+    }
+
+    fn on_pc_change(&mut self, old: u16, new: u16) {
+        (**self).on_pc_change(old, new);
+    }
+
+    fn on_condition_change(&mut self, cond: Condition) {
+        (**self).on_condition_change(cond);
+    }
+
+    fn on_instruction_start(&mut self, pc: u16, inst: &Instruction) {
+        (**self).on_instruction_start(pc, inst);
+    }
+
+    fn on_instruction_end(&mut self, pc: u16, inst: &Instruction) {
+        (**self).on_instruction_end(pc, inst);
+    }
+
+    fn on_uninitialized_read(&mut self, pc: u16, read: UninitializedRead) {
+        (**self).on_uninitialized_read(pc, read);
+    }
+
+    fn on_memory_read(&mut self, addr: u16, value: u16) {
+        (**self).on_memory_read(addr, value);
+    }
+
+    fn on_trap(&mut self, vector: u8) {
+        (**self).on_trap(vector);
+    }
+
+    fn on_io_output(&mut self, ch: char) {
+        (**self).on_io_output(ch);
+    }
+
+    fn on_halt(&mut self) {
+        (**self).on_halt();
+    }
+
+    fn stop_requested(&mut self) -> bool {
+        (**self).stop_requested()
+    }
+}
+
+/// Notify every element of a tuple, first to last
+macro_rules! tuple_observer {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Observer),+> Observer for ($($name,)+) {
+            fn on_register_write(&mut self, reg: u8, old: u16, new: u16) {
+                $(self.$index.on_register_write(reg, old, new);)+
+            }
+
+            fn on_memory_write(&mut self, addr: u16, old: u16, new: u16) {
+                $(self.$index.on_memory_write(addr, old, new);)+
+            }
+
+            fn on_pc_change(&mut self, old: u16, new: u16) {
+                $(self.$index.on_pc_change(old, new);)+
+            }
+
+            fn on_condition_change(&mut self, cond: Condition) {
+                $(self.$index.on_condition_change(cond);)+
+            }
+
+            fn on_instruction_start(&mut self, pc: u16, inst: &Instruction) {
+                $(self.$index.on_instruction_start(pc, inst);)+
+            }
+
+            fn on_instruction_end(&mut self, pc: u16, inst: &Instruction) {
+                $(self.$index.on_instruction_end(pc, inst);)+
+            }
+
+            fn on_uninitialized_read(&mut self, pc: u16, read: UninitializedRead) {
+                $(self.$index.on_uninitialized_read(pc, read);)+
+            }
+
+            fn on_memory_read(&mut self, addr: u16, value: u16) {
+                $(self.$index.on_memory_read(addr, value);)+
+            }
+
+            fn on_trap(&mut self, vector: u8) {
+                $(self.$index.on_trap(vector);)+
+            }
+
+            fn on_io_output(&mut self, ch: char) {
+                $(self.$index.on_io_output(ch);)+
+            }
+
+            fn on_halt(&mut self) {
+                $(self.$index.on_halt();)+
+            }
+
+            fn stop_requested(&mut self) -> bool {
+                false $(| self.$index.stop_requested())+
+            }
+        }
+    };
+}
+
+tuple_observer!(A 0, B 1);
+tuple_observer!(A 0, B 1, C 2);
+tuple_observer!(A 0, B 1, C 2, D 3);
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use lc3b_assembler::SourceMap;
+use lc3b_isa::Instruction;
+
+use super::Observer;
+
+/// Execution count for one source line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCoverage {
+    /// 1-based source line
+    pub line: usize,
+    /// Address of the instruction assembled from it
+    pub addr: u16,
+    pub hits: u64,
+}
+
+/// Summary produced by `CoverageObserver::report`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// One entry per instruction line, by line number
+    pub lines: Vec<LineCoverage>,
+}
+
+impl CoverageReport {
+    /// Instruction lines executed at least once
+    pub fn covered(&self) -> usize {
+        self.lines.iter().filter(|line| line.hits > 0).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Share of instruction lines executed, from 0.0 to 100.0; 100.0 for
+    /// a program without instructions
+    pub fn percent(&self) -> f64 {
+        if self.lines.is_empty() {
+            100.0
+        } else {
+            self.covered() as f64 * 100.0 / self.total() as f64
+        }
+    }
+
+    /// Instruction lines that never executed
+    pub fn uncovered_lines(&self) -> Vec<usize> {
+        self.lines
+            .iter()
+            .filter(|line| line.hits == 0)
+            .map(|line| line.line)
+            .collect()
+    }
+
+    /// `source` with each line prefixed by its hit count, `#####` for
+    /// instructions never executed, or `-` for lines without one (the
+    /// gcov layout)
+    pub fn annotate(&self, source: &str) -> String {
+        let hits: HashMap<usize, u64> = self.lines.iter().map(|l| (l.line, l.hits)).collect();
+        let mut out = String::new();
+        for (index, text) in source.lines().enumerate() {
+            let count = match hits.get(&(index + 1)) {
+                Some(0) => "#####".to_string(),
+                Some(n) => n.to_string(),
+                None => "-".to_string(),
+            };
+            out.push_str(&format!("{:>9}:{:>5}:{}\n", count, index + 1, text));
+        }
+        out
+    }
+}
+
+/// Records which addresses executed and how often, for mapping back to
+/// the source with the assembler's `SourceMap`. For C programs this gives
+/// coverage of the generated assembly.
+#[derive(Debug, Default)]
+pub struct CoverageObserver {
+    hits: HashMap<u16, u64>,
+}
+
+impl CoverageObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How many times the instruction at `addr` executed
+    pub fn hits(&self, addr: u16) -> u64 {
+        self.hits.get(&addr).copied().unwrap_or(0)
+    }
+
+    /// Every address executed, ascending
+    pub fn executed_addresses(&self) -> Vec<u16> {
+        let mut addrs: Vec<u16> = self.hits.keys().copied().collect();
+        addrs.sort_unstable();
+        addrs
+    }
+
+    pub fn report(&self, source_map: &SourceMap) -> CoverageReport {
+        let mut lines: Vec<LineCoverage> = source_map
+            .iter()
+            .map(|(addr, line)| LineCoverage {
+                line,
+                addr,
+                hits: self.hits(addr),
+            })
+            .collect();
+        lines.sort_by_key(|line| (line.line, line.addr));
+        CoverageReport { lines }
+    }
+
+    pub fn reset(&mut self) {
+        self.hits.clear();
+    }
+}
+
+impl Observer for CoverageObserver {
+    fn on_instruction_start(&mut self, pc: u16, _inst: &Instruction) {
+        *self.hits.entry(pc).or_default() += 1;
+    }
+}
@@ -1,9 +1,23 @@
+mod channel;
+mod composite;
+mod coverage;
+mod profile;
+mod statistics;
+mod trace;
 mod ui;
 
+pub use channel::{ChannelObserver, ExecutionEvent};
+pub use composite::CompositeObserver;
+pub use coverage::{CoverageObserver, CoverageReport, LineCoverage};
+pub use profile::{BranchStats, ProfileObserver, ProfileReport};
+pub use statistics::{StatisticsObserver, StatisticsReport};
+pub use trace::{MemoryDelta, RegisterDelta, TraceObserver, TraceStep, TRACE_MAGIC};
 pub use ui::UIObserver;
 
 use lc3b_isa::{Condition, Instruction};
 
+use crate::UninitializedRead;
+
 /// Observer for computer state changes
 /// All methods have default no-op implementations
 pub trait Observer {
@@ -24,6 +38,31 @@ pub trait Observer {
 
     /// Called after instruction completes
     fn on_instruction_end(&mut self, _pc: u16, _inst: &Instruction) {}
+
+    /// Called when the instruction at `pc` reads a register or memory
+    /// word that was never written
+    fn on_uninitialized_read(&mut self, _pc: u16, _read: UninitializedRead) {}
+
+    /// Called when an instruction reads a memory word or device register
+    /// (LDB, LDW, LDI and RTI's stack pops)
+    fn on_memory_read(&mut self, _addr: u16, _value: u16) {}
+
+    /// Called when a TRAP executes, before its service routine runs
+    fn on_trap(&mut self, _vector: u8) {}
+
+    /// Called for each character written to the console, by a TRAP or
+    /// through DDR
+    fn on_io_output(&mut self, _ch: char) {}
+
+    /// Called after the instruction that halted the machine
+    fn on_halt(&mut self) {}
+
+    /// Polled after each instruction of a run; returning true stops the
+    /// run with `StopReason::StopRequested`. Lets an observer end a run
+    /// once, say, the output it collected contains some text.
+    fn stop_requested(&mut self) -> bool {
+        false
+    }
 }
 
 /// No-op observer - does nothing, optimizes away
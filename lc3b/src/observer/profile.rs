@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, HashMap};
+
+use lc3b_isa::{Instruction, OpCode};
+
+use super::Observer;
+
+/// How often the BR at one address went each way
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BranchStats {
+    pub addr: u16,
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+/// Summary produced by `ProfileObserver::report`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub total_instructions: u64,
+    /// Executions per opcode, busiest first
+    pub opcodes: Vec<(OpCode, u64)>,
+    /// Executions per address, busiest first (ties by address)
+    pub hotspots: Vec<(u16, u64)>,
+    /// One entry per BR executed, by address
+    pub branches: Vec<BranchStats>,
+}
+
+/// Counts executed instructions per opcode and per address, and whether
+/// each branch was taken
+#[derive(Debug, Default)]
+pub struct ProfileObserver {
+    total: u64,
+    opcodes: HashMap<OpCode, u64>,
+    addresses: HashMap<u16, u64>,
+    branches: BTreeMap<u16, BranchStats>,
+    /// Address of a BR whose outcome the next PC change decides
+    pending_branch: Option<u16>,
+}
+
+impl ProfileObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn total_instructions(&self) -> u64 {
+        self.total
+    }
+
+    /// How many times the instruction at `addr` executed
+    pub fn count_at(&self, addr: u16) -> u64 {
+        self.addresses.get(&addr).copied().unwrap_or(0)
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        let mut opcodes: Vec<(OpCode, u64)> =
+            self.opcodes.iter().map(|(&op, &count)| (op, count)).collect();
+        opcodes.sort_by_key(|&(op, count)| (std::cmp::Reverse(count), u8::from(op)));
+
+        let mut hotspots: Vec<(u16, u64)> =
+            self.addresses.iter().map(|(&addr, &count)| (addr, count)).collect();
+        hotspots.sort_by_key(|&(addr, count)| (std::cmp::Reverse(count), addr));
+
+        ProfileReport {
+            total_instructions: self.total,
+            opcodes,
+            hotspots,
+            branches: self.branches.values().copied().collect(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Observer for ProfileObserver {
+    fn on_instruction_start(&mut self, pc: u16, inst: &Instruction) {
+        self.total += 1;
+        *self.opcodes.entry(OpCode::from(inst)).or_default() += 1;
+        *self.addresses.entry(pc).or_default() += 1;
+    }
+
+    fn on_instruction_end(&mut self, pc: u16, inst: &Instruction) {
+        if matches!(inst, Instruction::Br(..)) {
+            self.pending_branch = Some(pc);
+        }
+    }
+
+    fn on_pc_change(&mut self, _old: u16, new: u16) {
+        if let Some(addr) = self.pending_branch.take() {
+            let stats = self.branches.entry(addr).or_insert(BranchStats {
+                addr,
+                ..BranchStats::default()
+            });
+            if new == addr.wrapping_add(1) {
+                stats.not_taken += 1;
+            } else {
+                stats.taken += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+
+use lc3b_isa::{Instruction, OpCode};
+
+use super::{Observer, ProfileObserver};
+
+/// Summary produced by `StatisticsObserver::report`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatisticsReport {
+    pub instructions: u64,
+    /// As in `ProfileReport::opcodes`
+    pub opcodes: Vec<(OpCode, u64)>,
+    pub memory_reads: u64,
+    pub memory_writes: u64,
+    /// Most stack slots in use at once: how far R6 went below the highest
+    /// value it held, in the two-address slots PUSH/POP use
+    pub max_stack_depth: u16,
+    pub branches_taken: u64,
+    pub branches_not_taken: u64,
+}
+
+impl fmt::Display for StatisticsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "instructions:    {}", self.instructions)?;
+        for (opcode, count) in &self.opcodes {
+            writeln!(f, "  {:<5} {}", format!("{:?}", opcode), count)?;
+        }
+        writeln!(f, "memory reads:    {}", self.memory_reads)?;
+        writeln!(f, "memory writes:   {}", self.memory_writes)?;
+        writeln!(f, "max stack depth: {}", self.max_stack_depth)?;
+        write!(
+            f,
+            "branches:        {} taken, {} not taken",
+            self.branches_taken, self.branches_not_taken
+        )
+    }
+}
+
+/// Whole-run totals: instructions per opcode, memory traffic, stack depth
+/// and branch outcomes. The instruction and branch counts come from a
+/// `ProfileObserver` kept inside, whose per-address breakdown `profile`
+/// gives.
+#[derive(Debug, Default)]
+pub struct StatisticsObserver {
+    profile: ProfileObserver,
+    memory_reads: u64,
+    memory_writes: u64,
+    /// Highest R6 seen
+    stack_top: Option<u16>,
+    max_stack_depth: u16,
+}
+
+impl StatisticsObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The per-address profile the totals are drawn from
+    pub fn profile(&self) -> &ProfileObserver {
+        &self.profile
+    }
+
+    pub fn report(&self) -> StatisticsReport {
+        let profile = self.profile.report();
+        StatisticsReport {
+            instructions: profile.total_instructions,
+            opcodes: profile.opcodes,
+            memory_reads: self.memory_reads,
+            memory_writes: self.memory_writes,
+            max_stack_depth: self.max_stack_depth,
+            branches_taken: profile.branches.iter().map(|branch| branch.taken).sum(),
+            branches_not_taken: profile.branches.iter().map(|branch| branch.not_taken).sum(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Observer for StatisticsObserver {
+    fn on_register_write(&mut self, reg: u8, old: u16, new: u16) {
+        if reg != 6 {
+            return;
+        }
+        let top = self.stack_top.unwrap_or(old).max(old).max(new);
+        self.stack_top = Some(top);
+        self.max_stack_depth = self.max_stack_depth.max((top - new) / 2);
+    }
+
+    fn on_memory_read(&mut self, _addr: u16, _value: u16) {
+        self.memory_reads += 1;
+    }
+
+    fn on_memory_write(&mut self, _addr: u16, _old: u16, _new: u16) {
+        self.memory_writes += 1;
+    }
+
+    fn on_instruction_start(&mut self, pc: u16, inst: &Instruction) {
+        self.profile.on_instruction_start(pc, inst);
+    }
+
+    fn on_instruction_end(&mut self, pc: u16, inst: &Instruction) {
+        self.profile.on_instruction_end(pc, inst);
+    }
+
+    fn on_pc_change(&mut self, old: u16, new: u16) {
+        self.profile.on_pc_change(old, new);
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::{self, Read, Write};
+
+use lc3b_isa::{Condition, Instruction};
+
+use super::Observer;
+
+/// Leading bytes of a binary trace, followed by a version byte
+pub const TRACE_MAGIC: &[u8; 4] = b"LC3T";
+const TRACE_VERSION: u8 = 1;
+
+/// A register write during one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegisterDelta {
+    pub register: u8,
+    pub old: u16,
+    pub new: u16,
+}
+
+/// A memory write during one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryDelta {
+    pub addr: u16,
+    pub old: u16,
+    pub new: u16,
+}
+
+/// Everything one instruction changed, in the order it happened
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceStep {
+    pub pc: u16,
+    pub instruction: Instruction,
+    pub registers: Vec<RegisterDelta>,
+    pub memory: Vec<MemoryDelta>,
+    /// New condition codes, if they changed
+    pub condition: Option<Condition>,
+}
+
+/// Records every executed instruction with its register and memory deltas.
+///
+/// Traces export as JSON lines (one object per step) or a compact binary
+/// form that `read_binary` loads back.
+#[derive(Debug, Default)]
+pub struct TraceObserver {
+    steps: Vec<TraceStep>,
+    current: Option<TraceStep>,
+}
+
+impl TraceObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn steps(&self) -> &[TraceStep] {
+        &self.steps
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+        self.current = None;
+    }
+
+    /// One JSON object per line:
+    /// `{"pc":12288,"word":4129,"registers":[[0,0,1]],"memory":[],"condition":"p"}`.
+    /// Deltas are `[register or address, old, new]`; `condition` is null
+    /// when unchanged.
+    pub fn write_json_lines<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for step in &self.steps {
+            let registers: Vec<String> = step
+                .registers
+                .iter()
+                .map(|d| format!("[{},{},{}]", d.register, d.old, d.new))
+                .collect();
+            let memory: Vec<String> = step
+                .memory
+                .iter()
+                .map(|d| format!("[{},{},{}]", d.addr, d.old, d.new))
+                .collect();
+            let condition = match step.condition {
+                Some(cond) => format!("\"{}\"", cond.suffix().to_lowercase()),
+                None => "null".to_string(),
+            };
+            writeln!(
+                out,
+                "{{\"pc\":{},\"word\":{},\"registers\":[{}],\"memory\":[{}],\"condition\":{}}}",
+                step.pc,
+                u16::from(&step.instruction),
+                registers.join(","),
+                memory.join(","),
+                condition,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// `TRACE_MAGIC`, a version byte, then per step (little-endian):
+    /// pc, word, a flags byte (bit 3 = condition changed, bits 2..0 = NZP),
+    /// register and memory delta counts (one byte each), then the deltas
+    /// as (u8 register | u16 addr, u16 old, u16 new).
+    pub fn write_binary<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(TRACE_MAGIC)?;
+        out.write_all(&[TRACE_VERSION])?;
+        for step in &self.steps {
+            if step.registers.len() > u8::MAX as usize || step.memory.len() > u8::MAX as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("too many deltas at {:#06x}", step.pc),
+                ));
+            }
+            out.write_all(&step.pc.to_le_bytes())?;
+            out.write_all(&u16::from(&step.instruction).to_le_bytes())?;
+            let flags = match step.condition {
+                Some(cond) => 0x8 | (cond.n as u8) << 2 | (cond.z as u8) << 1 | cond.p as u8,
+                None => 0,
+            };
+            out.write_all(&[flags, step.registers.len() as u8, step.memory.len() as u8])?;
+            for delta in &step.registers {
+                out.write_all(&[delta.register])?;
+                out.write_all(&delta.old.to_le_bytes())?;
+                out.write_all(&delta.new.to_le_bytes())?;
+            }
+            for delta in &step.memory {
+                out.write_all(&delta.addr.to_le_bytes())?;
+                out.write_all(&delta.old.to_le_bytes())?;
+                out.write_all(&delta.new.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Load steps written by `write_binary`
+    pub fn read_binary<R: Read>(mut input: R) -> io::Result<Vec<TraceStep>> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut header = [0u8; 5];
+        input.read_exact(&mut header)?;
+        if &header[..4] != TRACE_MAGIC || header[4] != TRACE_VERSION {
+            return Err(invalid("not an LC-3b trace".to_string()));
+        }
+
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let mut reader = ByteReader { bytes: &bytes, pos: 0 };
+
+        let mut steps = Vec::new();
+        while !reader.is_empty() {
+            let pc = reader.u16()?;
+            let word = reader.u16()?;
+            let instruction = Instruction::try_from(word).map_err(|e| invalid(e.to_string()))?;
+            let flags = reader.u8()?;
+            let register_count = reader.u8()?;
+            let memory_count = reader.u8()?;
+            let registers = (0..register_count)
+                .map(|_| {
+                    Ok(RegisterDelta {
+                        register: reader.u8()?,
+                        old: reader.u16()?,
+                        new: reader.u16()?,
+                    })
+                })
+                .collect::<io::Result<_>>()?;
+            let memory = (0..memory_count)
+                .map(|_| {
+                    Ok(MemoryDelta {
+                        addr: reader.u16()?,
+                        old: reader.u16()?,
+                        new: reader.u16()?,
+                    })
+                })
+                .collect::<io::Result<_>>()?;
+            let condition = (flags & 0x8 != 0).then_some(Condition {
+                n: flags & 0x4 != 0,
+                z: flags & 0x2 != 0,
+                p: flags & 0x1 != 0,
+            });
+            steps.push(TraceStep {
+                pc,
+                instruction,
+                registers,
+                memory,
+                condition,
+            });
+        }
+        Ok(steps)
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl ByteReader<'_> {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes([self.u8()?, self.u8()?]))
+    }
+}
+
+impl Observer for TraceObserver {
+    fn on_instruction_start(&mut self, pc: u16, inst: &Instruction) {
+        self.current = Some(TraceStep {
+            pc,
+            instruction: *inst,
+            registers: Vec::new(),
+            memory: Vec::new(),
+            condition: None,
+        });
+    }
+
+    fn on_register_write(&mut self, reg: u8, old: u16, new: u16) {
+        if let Some(step) = &mut self.current {
+            step.registers.push(RegisterDelta {
+                register: reg,
+                old,
+                new,
+            });
+        }
+    }
+
+    fn on_memory_write(&mut self, addr: u16, old: u16, new: u16) {
+        if let Some(step) = &mut self.current {
+            step.memory.push(MemoryDelta { addr, old, new });
+        }
+    }
+
+    fn on_condition_change(&mut self, cond: Condition) {
+        if let Some(step) = &mut self.current {
+            step.condition = Some(cond);
+        }
+    }
+
+    fn on_instruction_end(&mut self, _pc: u16, _inst: &Instruction) {
+        if let Some(step) = self.current.take() {
+            self.steps.push(step);
+        }
+    }
+}
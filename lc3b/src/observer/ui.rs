@@ -1,11 +1,13 @@
-use lc3b_isa::Condition;
+use lc3b_isa::{Condition, Instruction};
 
-use super::Observer;
+use super::{MemoryDelta, Observer, RegisterDelta};
 
-/// Tracks state changes for UI updates
+/// Tracks state changes for UI updates. Each instruction starts a new
+/// change set, so after a step or a run the changes shown are those of
+/// the last instruction executed.
 pub struct UIObserver {
-    last_modified_register: Option<u8>,
-    last_modified_memory: Option<u16>,
+    register_changes: Vec<RegisterDelta>,
+    memory_changes: Vec<MemoryDelta>,
     condition_changed: bool,
     last_condition: Condition,
 }
@@ -13,28 +15,39 @@ pub struct UIObserver {
 impl UIObserver {
     pub fn new() -> Self {
         Self {
-            last_modified_register: None,
-            last_modified_memory: None,
+            register_changes: Vec::new(),
+            memory_changes: Vec::new(),
             condition_changed: false,
             last_condition: Condition::default(),
         }
     }
 
-    /// Call at start of each instruction to reset per-instruction tracking
+    /// Start a new change set. Instructions do this themselves; call it
+    /// to drop changes made outside one, such as `set_register`.
     pub fn reset_instruction_state(&mut self) {
-        self.last_modified_register = None;
-        self.last_modified_memory = None;
+        self.register_changes.clear();
+        self.memory_changes.clear();
         self.condition_changed = false;
     }
 
+    /// Every register write in the current change set, in order
+    pub fn register_changes(&self) -> &[RegisterDelta] {
+        &self.register_changes
+    }
+
+    /// Every memory write in the current change set, in order
+    pub fn memory_changes(&self) -> &[MemoryDelta] {
+        &self.memory_changes
+    }
+
     /// Get the last modified register index (0-7), if any
     pub fn last_modified_register(&self) -> Option<u8> {
-        self.last_modified_register
+        self.register_changes.last().map(|delta| delta.register)
     }
 
     /// Get the last modified memory address, if any
     pub fn last_modified_memory(&self) -> Option<u16> {
-        self.last_modified_memory
+        self.memory_changes.last().map(|delta| delta.addr)
     }
 
     /// Check if condition codes changed in the last instruction
@@ -55,12 +68,20 @@ impl Default for UIObserver {
 }
 
 impl Observer for UIObserver {
-    fn on_register_write(&mut self, reg: u8, _old: u16, _new: u16) {
-        self.last_modified_register = Some(reg);
+    fn on_instruction_start(&mut self, _pc: u16, _inst: &Instruction) {
+        self.reset_instruction_state();
+    }
+
+    fn on_register_write(&mut self, reg: u8, old: u16, new: u16) {
+        self.register_changes.push(RegisterDelta {
+            register: reg,
+            old,
+            new,
+        });
     }
 
-    fn on_memory_write(&mut self, addr: u16, _old: u16, _new: u16) {
-        self.last_modified_memory = Some(addr);
+    fn on_memory_write(&mut self, addr: u16, old: u16, new: u16) {
+        self.memory_changes.push(MemoryDelta { addr, old, new });
     }
 
     fn on_condition_change(&mut self, cond: Condition) {
@@ -0,0 +1,23 @@
+/// How the Computer executes TRAP
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrapMode {
+    /// GETC/OUT/PUTS/IN/PUTSP/HALT are serviced by the host through the
+    /// IO trait, without touching memory or R7
+    #[default]
+    Native,
+    /// TRAP saves the return address in R7 and jumps to
+    /// `MEM[LSHF(trapvect8, 1)]`, like the hardware
+    VectorTable,
+}
+
+/// Assembly source of the built-in OS loaded by `Computer::load_os`
+pub const OS_SOURCE: &str = include_str!("os.asm");
+
+/// Number of entries at the start of the OS image that the trap vector
+/// table points at: x20-x25 in order, then the catch-all
+pub(crate) const OS_JUMP_TABLE_LEN: u16 = 7;
+
+/// Initial user stack pointer set by `Computer::load_os`, just below the
+/// device registers
+pub const USER_STACK_START: u16 = 0xFE00;
@@ -0,0 +1,166 @@
+; Minimal LC-3b operating system: TRAP service routines used when the
+; Computer dispatches TRAP through the vector table (TrapMode::VectorTable).
+;
+; The first seven words are a jump table; Computer::load_os points trap
+; vectors x20-x25 at them in order and every other vector at the last one.
+; Routines save the registers they use on the stack at R6, except HALT,
+; which never returns.
+;
+; LEA only reaches targets an even number of words away, which is why some
+; routines load the DEVICES pointer partway through saving registers.
+        .ORIG x0400
+        BRnzp TRAP_GETC
+        BRnzp TRAP_OUT
+        BRnzp TRAP_PUTS
+        BRnzp TRAP_IN
+        BRnzp TRAP_PUTSP
+        BRnzp TRAP_HALT
+        BRnzp TRAP_HALT         ; unimplemented vectors halt
+
+; GETC: wait for a key, R0 <- character
+TRAP_GETC:
+        ADD R6, R6, #-4
+        STW R1, R6, #0
+        STW R2, R6, #1
+        LEA R1, DEVICES
+GETC_POLL:
+        LDI R2, R1, #0          ; KBSR
+        BRzp GETC_POLL
+        LDI R0, R1, #1          ; KBDR
+        LDW R2, R6, #1
+        LDW R1, R6, #0
+        ADD R6, R6, #4
+        RET
+
+; OUT: write the character in R0
+TRAP_OUT:
+        ADD R6, R6, #-4
+        STW R1, R6, #0
+        LEA R1, DEVICES
+        STW R2, R6, #1
+OUT_POLL:
+        LDI R2, R1, #2          ; DSR
+        BRzp OUT_POLL
+        STI R0, R1, #3          ; DDR
+        LDW R2, R6, #1
+        LDW R1, R6, #0
+        ADD R6, R6, #4
+        RET
+
+; PUTS: write the string at R0, one character per word, up to a zero word
+TRAP_PUTS:
+        ADD R6, R6, #-8
+        STW R1, R6, #0
+        STW R2, R6, #1
+        STW R3, R6, #2
+        STW R4, R6, #3
+        LEA R1, DEVICES
+        ADD R4, R0, #0
+PUTS_NEXT:
+        LDW R3, R4, #0
+        BRz PUTS_DONE
+PUTS_POLL:
+        LDI R2, R1, #2          ; DSR
+        BRzp PUTS_POLL
+        STI R3, R1, #3          ; DDR
+        ADD R4, R4, #1
+        BRnzp PUTS_NEXT
+PUTS_DONE:
+        LDW R4, R6, #3
+        LDW R3, R6, #2
+        LDW R2, R6, #1
+        LDW R1, R6, #0
+        ADD R6, R6, #8
+        RET
+
+; IN: print a prompt, wait for a key, echo it, R0 <- character
+TRAP_IN:
+        ADD R6, R6, #-8
+        STW R1, R6, #0
+        STW R2, R6, #1
+        STW R3, R6, #2
+        STW R4, R6, #3
+        LEA R1, DEVICES
+        LEA R4, IN_PROMPT
+IN_NEXT:
+        LDW R3, R4, #0
+        BRz IN_READ
+IN_POLL:
+        LDI R2, R1, #2          ; DSR
+        BRzp IN_POLL
+        STI R3, R1, #3          ; DDR
+        ADD R4, R4, #1
+        BRnzp IN_NEXT
+IN_READ:
+        LDI R2, R1, #0          ; KBSR
+        BRzp IN_READ
+        LDI R0, R1, #1          ; KBDR
+IN_ECHO:
+        LDI R2, R1, #2          ; DSR
+        BRzp IN_ECHO
+        STI R0, R1, #3          ; DDR
+        LDW R4, R6, #3
+        LDW R3, R6, #2
+        LDW R2, R6, #1
+        LDW R1, R6, #0
+        ADD R6, R6, #8
+        RET
+
+; PUTSP: write the string at R0, two characters per word (low byte first)
+TRAP_PUTSP:
+        ADD R6, R6, #-10
+        STW R1, R6, #0
+        STW R2, R6, #1
+        STW R3, R6, #2
+        STW R4, R6, #3
+        LEA R1, DEVICES
+        STW R5, R6, #4
+        ADD R4, R0, #0
+PUTSP_NEXT:
+        LDW R5, R4, #0
+        LSHF R3, R5, #8
+        RSHFL R3, R3, #8        ; low byte
+        BRz PUTSP_DONE
+PUTSP_LOW:
+        LDI R2, R1, #2          ; DSR
+        BRzp PUTSP_LOW
+        STI R3, R1, #3          ; DDR
+        RSHFL R3, R5, #8        ; high byte
+        BRz PUTSP_DONE
+PUTSP_HIGH:
+        LDI R2, R1, #2          ; DSR
+        BRzp PUTSP_HIGH
+        STI R3, R1, #3          ; DDR
+        ADD R4, R4, #1
+        BRnzp PUTSP_NEXT
+PUTSP_DONE:
+        LDW R5, R6, #4
+        LDW R4, R6, #3
+        LDW R3, R6, #2
+        LDW R2, R6, #1
+        LDW R1, R6, #0
+        ADD R6, R6, #10
+        RET
+
+; HALT: clear the MCR clock-enable bit. R7 becomes the pointer base and is
+; also the value stored: the address of DEVICES, whose bit 15 is clear,
+; which is all the MCR write needs.
+TRAP_HALT:
+        LEA R7, DEVICES
+        STI R7, R7, #4          ; MCR
+        BRnzp TRAP_HALT
+
+; Device register pointers, spaced for LDI/STI word offsets
+DEVICES:
+        .FILL xFE00             ; #0 KBSR
+        .FILL x0000
+        .FILL xFE02             ; #1 KBDR
+        .FILL x0000
+        .FILL xFE04             ; #2 DSR
+        .FILL x0000
+        .FILL xFE06             ; #3 DDR
+        .FILL x0000
+        .FILL xFFFE             ; #4 MCR
+IN_PROMPT:
+        .STRINGZ "Input a character> "
+        .END
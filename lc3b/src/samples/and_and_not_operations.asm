@@ -0,0 +1,7 @@
+; AND and NOT Example
+; Demonstrates logical operations
+
+ADD R1, R1, #15  ; R1 = 15 (0x000F)
+ADD R2, R2, #7   ; R2 = 7  (0x0007)
+AND R0, R1, R2   ; R0 = R1 AND R2 = 7
+NOT R3, R0       ; R3 = NOT R0
@@ -0,0 +1,10 @@
+// Arithmetic example
+// Demonstrates add and subtract
+
+int main() {
+    int a = 10;
+    int b = 3;
+    int sum = a + b;   // 13
+    int diff = a - b;  // 7
+    return diff;
+}
@@ -0,0 +1,9 @@
+// Bitwise AND example
+// Result is stored in R1
+
+int main() {
+    int a = 15;    // 0x000F in binary: 0000 0000 0000 1111
+    int b = 7;     // 0x0007 in binary: 0000 0000 0000 0111
+    int c = a & b; // Result: 7 (0x0007)
+    return c;
+}
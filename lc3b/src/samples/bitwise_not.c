@@ -0,0 +1,8 @@
+// Bitwise NOT example
+// Flips all bits
+
+int main() {
+    int a = 0;
+    int b = ~a;  // Result: -1 (0xFFFF, all bits set)
+    return b;
+}
@@ -0,0 +1,9 @@
+// Bitwise OR example
+// Result is stored in R1
+
+int main() {
+    int a = 12;    // 0x000C in binary: 0000 0000 0000 1100
+    int b = 5;     // 0x0005 in binary: 0000 0000 0000 0101
+    int c = a | b; // Result: 13 (0x000D)
+    return c;
+}
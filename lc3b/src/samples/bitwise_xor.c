@@ -0,0 +1,9 @@
+// Bitwise XOR example
+// XOR: bits differ = 1, bits same = 0
+
+int main() {
+    int a = 12;    // 0x000C in binary: 0000 0000 0000 1100
+    int b = 10;    // 0x000A in binary: 0000 0000 0000 1010
+    int c = a ^ b; // Result: 6 (0x0006)
+    return c;
+}
@@ -0,0 +1,32 @@
+; Character Output Example
+; Uses OUT (TRAP x21) to print characters one at a time
+
+.ORIG x3000
+
+; Print 'H'
+ADD R0, R0, #8    ; R0 = 8
+ADD R0, R0, #8    ; R0 = 16
+ADD R0, R0, #8    ; R0 = 24
+ADD R0, R0, #8    ; R0 = 32
+ADD R0, R0, #8    ; R0 = 40
+ADD R0, R0, #8    ; R0 = 48
+ADD R0, R0, #8    ; R0 = 56
+ADD R0, R0, #8    ; R0 = 64
+ADD R0, R0, #8    ; R0 = 72 = 'H'
+OUT              ; Print character in R0
+
+; Print 'i'
+ADD R0, R0, #8    ; R0 = 80
+ADD R0, R0, #8    ; R0 = 88
+ADD R0, R0, #8    ; R0 = 96
+ADD R0, R0, #9    ; R0 = 105 = 'i'
+OUT              ; Print character in R0
+
+; Print newline
+AND R0, R0, #0   ; R0 = 0
+ADD R0, R0, #10  ; R0 = 10 = newline
+OUT              ; Print newline
+
+HALT             ; Stop execution
+
+.END
@@ -0,0 +1,10 @@
+; Conditional Branching Example
+; Counts down from 3 to 0 using BR with labels
+
+ADD R0, R0, #3   ; R0 = 3 (counter)
+ADD R1, R1, #0   ; R1 = 0 (sum)
+loop:
+    ADD R1, R1, R0   ; sum += counter
+    ADD R0, R0, #-1  ; counter--
+    BRp loop         ; if positive, branch back to loop
+; R1 now contains 3+2+1 = 6
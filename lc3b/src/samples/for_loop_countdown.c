@@ -0,0 +1,13 @@
+#include <lc3b-io.h>
+
+// Countdown using a for loop
+// Prints: 5 4 3 2 1
+
+int main() {
+    for (int i = 5; i >= 1; i--) {
+        // Print digit (add '0' to convert to ASCII)
+        putchar('0' + i);
+        putchar(' ');
+    }
+    return 0;
+}
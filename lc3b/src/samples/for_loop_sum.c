@@ -0,0 +1,10 @@
+// For loop example
+// Calculates 1 + 2 + 3 + 4 + 5 = 15
+
+int main() {
+    int sum = 0;
+    for (int i = 1; i <= 5; i++) {
+        sum = sum + i;
+    }
+    return sum;
+}
@@ -0,0 +1,6 @@
+#include <lc3b-io.h>
+
+int main() {
+    puts("Hello, LC-3b!");
+    return 0;
+}
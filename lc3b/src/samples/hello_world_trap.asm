@@ -0,0 +1,19 @@
+; Hello World Example
+; Uses TRAP to print a string to the console
+
+.ORIG x3000
+
+; Load address of string into R0 using LEA
+LEA R0, hello    ; R0 = address of hello string
+
+; Print the string
+PUTS             ; TRAP x22 - print null-terminated string at R0
+
+; Halt the program
+HALT             ; TRAP x25 - stop execution
+
+; String data
+hello:
+.STRINGZ "Hello, LC-3b!"
+
+.END
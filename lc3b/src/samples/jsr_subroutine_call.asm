@@ -0,0 +1,13 @@
+; JSR Subroutine Example
+; Calls a subroutine that doubles R1, then returns
+
+ADD R1, R1, #5   ; R1 = 5
+JSR double       ; Call subroutine, R7 = return address
+ADD R2, R1, #0   ; R2 = R1 (copy result, R1 should be 10)
+BRnzp done       ; Skip over subroutine
+
+double:
+    ADD R1, R1, R1   ; R1 = R1 * 2
+    RET              ; Return to caller (PC = R7)
+done:
+    ADD R0, R0, #0   ; End of program
@@ -0,0 +1,134 @@
+//! Example programs shown by the web simulator, kept here so every front
+//! end shares one list
+
+/// What a sample is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleLanguage {
+    Assembly,
+    C,
+}
+
+impl SampleLanguage {
+    /// `assembly` or `c`
+    pub fn name(self) -> &'static str {
+        match self {
+            SampleLanguage::Assembly => "assembly",
+            SampleLanguage::C => "c",
+        }
+    }
+}
+
+/// A bundled example program
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub title: &'static str,
+    pub description: &'static str,
+    pub language: SampleLanguage,
+    pub source: &'static str,
+}
+
+/// Every bundled sample, assembly first, in the order they are shown
+pub const SAMPLES: &[Sample] = &[
+    Sample {
+        title: "Simple Addition",
+        description: "Demonstrates ADD instruction with registers and immediates",
+        language: SampleLanguage::Assembly,
+        source: include_str!("simple_addition.asm"),
+    },
+    Sample {
+        title: "AND and NOT Operations",
+        description: "Demonstrates logical AND and NOT instructions",
+        language: SampleLanguage::Assembly,
+        source: include_str!("and_and_not_operations.asm"),
+    },
+    Sample {
+        title: "Conditional Branching",
+        description: "Demonstrates BR instruction with labels",
+        language: SampleLanguage::Assembly,
+        source: include_str!("conditional_branching.asm"),
+    },
+    Sample {
+        title: "JSR Subroutine Call",
+        description: "Demonstrates JSR instruction to call a subroutine with PC-relative addressing",
+        language: SampleLanguage::Assembly,
+        source: include_str!("jsr_subroutine_call.asm"),
+    },
+    Sample {
+        title: "Hello World (TRAP)",
+        description: "Demonstrates LEA and TRAP instructions for console output using PUTS and HALT",
+        language: SampleLanguage::Assembly,
+        source: include_str!("hello_world_trap.asm"),
+    },
+    Sample {
+        title: "Character Output (TRAP)",
+        description: "Demonstrates OUT trap to print individual characters",
+        language: SampleLanguage::Assembly,
+        source: include_str!("character_output_trap.asm"),
+    },
+    Sample {
+        title: "Self-Modifying Code",
+        description: "Demonstrates von Neumann architecture by modifying an instruction at runtime to create a counter",
+        language: SampleLanguage::Assembly,
+        source: include_str!("self_modifying_code.asm"),
+    },
+    Sample {
+        title: "Hello World",
+        description: "Print a message to the console",
+        language: SampleLanguage::C,
+        source: include_str!("hello_world.c"),
+    },
+    Sample {
+        title: "Bitwise AND",
+        description: "Demonstrates the bitwise AND operator",
+        language: SampleLanguage::C,
+        source: include_str!("bitwise_and.c"),
+    },
+    Sample {
+        title: "Bitwise OR",
+        description: "Demonstrates the bitwise OR operator",
+        language: SampleLanguage::C,
+        source: include_str!("bitwise_or.c"),
+    },
+    Sample {
+        title: "Bitwise XOR",
+        description: "Demonstrates the bitwise XOR operator",
+        language: SampleLanguage::C,
+        source: include_str!("bitwise_xor.c"),
+    },
+    Sample {
+        title: "Bitwise NOT",
+        description: "Demonstrates the bitwise NOT (complement) operator",
+        language: SampleLanguage::C,
+        source: include_str!("bitwise_not.c"),
+    },
+    Sample {
+        title: "For Loop: Sum",
+        description: "Sum numbers 1 to 5 using a for loop",
+        language: SampleLanguage::C,
+        source: include_str!("for_loop_sum.c"),
+    },
+    Sample {
+        title: "For Loop: Countdown",
+        description: "Count down from 5 to 1",
+        language: SampleLanguage::C,
+        source: include_str!("for_loop_countdown.c"),
+    },
+    Sample {
+        title: "String: Print Characters",
+        description: "Print a string character by character",
+        language: SampleLanguage::C,
+        source: include_str!("string_print_characters.c"),
+    },
+    Sample {
+        title: "String: Using puts()",
+        description: "Print strings using the puts() function",
+        language: SampleLanguage::C,
+        source: include_str!("string_using_puts.c"),
+    },
+    Sample {
+        title: "Arithmetic",
+        description: "Basic arithmetic operations",
+        language: SampleLanguage::C,
+        source: include_str!("arithmetic.c"),
+    },
+];
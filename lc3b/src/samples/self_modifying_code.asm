@@ -0,0 +1,57 @@
+; Self-Modifying Code Example
+; Demonstrates how code and data share the same memory
+;
+; This program modifies the imm5 field of an ADD instruction
+; at runtime to create an incrementing counter.
+;
+; Note: LC-3b uses LDW/STW with base+offset addressing,
+; so we use LEA to get addresses into a base register.
+
+.ORIG x3000
+
+; === SETUP ===
+; Get base addresses for our data using LEA
+LEA R4, target    ; R4 = address of target instruction
+LEA R5, mask      ; R5 = address of mask
+
+; === MAIN LOOP ===
+loop:
+    ; Load the instruction we'll modify into R1
+    LDW R1, R4, #0    ; R1 = instruction at 'target'
+
+    ; Extract current imm5 value:
+    ; The imm5 field is in bits [4:0]
+    ; We mask with 0x001F to isolate these bits
+    LDW R2, R5, #0    ; R2 = mask value (0x001F)
+    AND R3, R1, R2    ; R3 = current counter value
+
+    ; Display counter in R0 (visible in register view)
+    ADD R0, R3, #0
+
+    ; Increment the counter by adding 1 to the instruction
+    ; Since imm5 is in the low bits, we can just ADD #1
+    ADD R1, R1, #1
+
+    ; Store modified instruction back to memory
+    STW R1, R4, #0    ; Write back to 'target'
+
+    ; Check if we've counted to 10
+    ADD R3, R3, #-10
+    BRn loop          ; Continue if counter < 10
+
+    HALT
+
+; === DATA ===
+; This ADD instruction gets modified each iteration
+; Initially: ADD R0, R0, #0
+; After 1st loop: ADD R0, R0, #1
+; After 2nd loop: ADD R0, R0, #2
+; ...and so on
+target:
+    ADD R0, R0, #0
+
+; Mask to extract bits [4:0]
+mask:
+    .FILL x001F
+
+.END
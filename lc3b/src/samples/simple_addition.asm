@@ -0,0 +1,6 @@
+; Simple Addition Example
+; Adds values in R1 and R2, stores result in R0
+
+ADD R1, R1, #5   ; R1 = 5
+ADD R2, R2, #3   ; R2 = 3
+ADD R0, R1, R2   ; R0 = R1 + R2 = 8
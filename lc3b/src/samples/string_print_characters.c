@@ -0,0 +1,10 @@
+#include <lc3b-io.h>
+
+// Print each character of "Hi" manually
+
+int main() {
+    putchar('H');
+    putchar('i');
+    putchar('!');
+    return 0;
+}
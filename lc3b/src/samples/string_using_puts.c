@@ -0,0 +1,10 @@
+#include <lc3b-io.h>
+
+// Print multiple strings
+
+int main() {
+    puts("Line 1");
+    puts("Line 2");
+    puts("Done!");
+    return 0;
+}
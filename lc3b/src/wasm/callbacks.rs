@@ -0,0 +1,67 @@
+use js_sys::Function;
+use wasm_bindgen::JsValue;
+
+use crate::Observer;
+use lc3b_isa::Instruction;
+
+/// JS functions registered on a `WasmComputer`, called as the machine runs
+/// so the page can react to events instead of polling after each step.
+///
+/// A callback that throws stops the run after the current instruction
+/// with `stop_requested`; the first exception is kept for `take_error`.
+#[derive(Default)]
+pub(super) struct CallbacksRegistry {
+    pub on_instruction: Option<Function>,
+    pub on_output: Option<Function>,
+    pub on_halt: Option<Function>,
+    pub on_memory_write: Option<Function>,
+    error: Option<JsValue>,
+    stop: bool,
+}
+
+impl CallbacksRegistry {
+    pub fn take_error(&mut self) -> Option<JsValue> {
+        self.error.take()
+    }
+
+    fn check(&mut self, result: Result<JsValue, JsValue>) {
+        if let Err(e) = result {
+            self.error.get_or_insert(e);
+            self.stop = true;
+        }
+    }
+}
+
+impl Observer for CallbacksRegistry {
+    fn on_instruction_end(&mut self, pc: u16, _inst: &Instruction) {
+        if let Some(f) = &self.on_instruction {
+            let result = f.call1(&JsValue::NULL, &pc.into());
+            self.check(result);
+        }
+    }
+
+    fn on_io_output(&mut self, ch: char) {
+        if let Some(f) = &self.on_output {
+            let result = f.call1(&JsValue::NULL, &ch.to_string().into());
+            self.check(result);
+        }
+    }
+
+    fn on_halt(&mut self) {
+        if let Some(f) = &self.on_halt {
+            let result = f.call0(&JsValue::NULL);
+            self.check(result);
+        }
+    }
+
+    fn on_memory_write(&mut self, addr: u16, old: u16, new: u16) {
+        if let Some(f) = &self.on_memory_write {
+            let result = f.call3(&JsValue::NULL, &addr.into(), &old.into(), &new.into());
+            self.check(result);
+        }
+    }
+
+    fn stop_requested(&mut self) -> bool {
+        std::mem::take(&mut self.stop)
+    }
+}
@@ -1,7 +1,23 @@
+mod callbacks;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
 use wasm_bindgen::prelude::*;
 
-use crate::{BufferedIO, Computer, Program, UIObserver, USER_PROGRAM_START, IO};
-use lc3b_c_compiler::{compile as compile_c, available_headers, CompileOptions};
+use crate::{
+    BufferedIO, Computer, FrameKind, Interrupt, IoLog, Program, RecordingIO, StatisticsObserver,
+    StopReason, UIObserver, SAMPLES,
+};
+use callbacks::CallbacksRegistry;
+use lc3b_c_compiler::{
+    compile as compile_c, available_headers, line_markers, CompileError, CompileOptions,
+};
+use lc3b_assembler::{AssembledProgram, SourceError};
+use lc3b_isa::Instruction;
+
+/// Instructions `run_for` executes between clock checks
+const RUN_SLICE: usize = 4096;
 
 #[wasm_bindgen]
 extern "C" {
@@ -9,11 +25,12 @@ extern "C" {
     pub fn log(s: &str);
 }
 
-/// Compile C source code to LC-3b assembly
+/// Compile C source code to LC-3b assembly. Throws an error object as
+/// `load_assembly` does; syntax errors have a location.
 #[wasm_bindgen]
-pub fn compile_c_to_assembly(source: &str) -> Result<String, String> {
+pub fn compile_c_to_assembly(source: &str) -> Result<String, JsValue> {
     let options = CompileOptions::default();
-    compile_c(source, &options).map_err(|e| e.to_string())
+    compile_c(source, &options).map_err(|e| compile_error(source, &e))
 }
 
 /// Get the list of available C header file names
@@ -31,6 +48,52 @@ pub fn get_header_contents(name: &str) -> Option<String> {
         .map(|h| h.contents.to_string())
 }
 
+/// Assemble without a machine, returning `load_assembly`'s `{origin,
+/// labels, lines}` plus `words` (a Uint16Array) and `listing`, a text
+/// table of every word's address, encoding and source line. Throws the
+/// same error objects as `load_assembly`.
+#[wasm_bindgen]
+pub fn assemble_source(source: &str) -> Result<JsValue, JsValue> {
+    let program = lc3b_assembler::assemble(source)
+        .map_err(|e| assembly_error(source, e.downcast_ref(), e.to_string()))?;
+    let metadata = load_metadata(&program);
+    let words: js_sys::Uint16Array = program.words.as_slice().into();
+    let _ = js_sys::Reflect::set(&metadata, &"words".into(), &words);
+    let listing = program.listing(source);
+    let _ = js_sys::Reflect::set(&metadata, &"listing".into(), &listing.into());
+    Ok(metadata)
+}
+
+/// `source` in the assembler's standard layout (see
+/// `lc3b_assembler::format`); throws a located error if it does not parse
+#[wasm_bindgen]
+pub fn format_assembly(source: &str) -> Result<String, JsValue> {
+    lc3b_assembler::format(source).map_err(|e| {
+        error_object(source, &e.message, Some((e.line, e.column, e.span.clone())))
+    })
+}
+
+/// The bundled example programs as `[{title, description, language,
+/// code}]`, `language` being `assembly` or `c`
+#[wasm_bindgen]
+pub fn get_samples() -> JsValue {
+    let samples = js_sys::Array::new();
+    for sample in SAMPLES {
+        let object = js_sys::Object::new();
+        let fields = [
+            ("title", sample.title),
+            ("description", sample.description),
+            ("language", sample.language.name()),
+            ("code", sample.source.trim_end()),
+        ];
+        for (name, value) in fields {
+            let _ = js_sys::Reflect::set(&object, &name.into(), &value.into());
+        }
+        samples.push(&object);
+    }
+    samples.into()
+}
+
 /// Returns the WASM linear memory size in bytes
 #[wasm_bindgen]
 pub fn wasm_memory_size() -> usize {
@@ -52,35 +115,383 @@ pub fn parse_program(program: &str) {
     }
 }
 
-/// WASM-exposed computer wrapping Computer<BufferedIO, UIObserver>
+/// A JS `Error` with `message`, and where known `line`, `column` (both
+/// 1-based) and `span: {start, end}` in UTF-16 code units, the offsets
+/// editors use
+fn error_object(
+    source: &str,
+    message: &str,
+    location: Option<(usize, usize, std::ops::Range<usize>)>,
+) -> JsValue {
+    let error = js_sys::Error::new(message);
+    if let Some((line, column, span)) = location {
+        let utf16 = |offset: usize| source.get(..offset).map_or(0, |s| s.encode_utf16().count());
+        let range = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&range, &"start".into(), &(utf16(span.start) as u32).into());
+        let _ = js_sys::Reflect::set(&range, &"end".into(), &(utf16(span.end) as u32).into());
+        let _ = js_sys::Reflect::set(&error, &"line".into(), &(line as u32).into());
+        let _ = js_sys::Reflect::set(&error, &"column".into(), &(column as u32).into());
+        let _ = js_sys::Reflect::set(&error, &"span".into(), &range);
+    }
+    error.into()
+}
+
+/// `message` is the whole error, for when it has no `SourceError`
+fn assembly_error(source: &str, error: Option<&SourceError>, message: String) -> JsValue {
+    match error {
+        Some(e) => error_object(source, &e.message, Some((e.line, e.column, e.span.clone()))),
+        None => error_object(source, &message, None),
+    }
+}
+
+fn compile_error(source: &str, error: &CompileError) -> JsValue {
+    let location = error.location.as_ref().map(|l| (l.line, l.column, l.span.clone()));
+    error_object(source, &error.message, location)
+}
+
+/// The `{origin, labels, lines}` object `load_assembly` returns
+fn load_metadata(program: &AssembledProgram) -> JsValue {
+    let labels = js_sys::Object::new();
+    for (name, addr) in program.symbols.iter() {
+        let _ = js_sys::Reflect::set(&labels, &name.into(), &addr.into());
+    }
+    let lines = js_sys::Object::new();
+    // Backwards, so a line's first address is the one left set
+    let mapped: Vec<(u16, usize)> = program.source_map.iter().collect();
+    for (addr, line) in mapped.into_iter().rev() {
+        let _ = js_sys::Reflect::set(&lines, &(line as u32).into(), &addr.into());
+    }
+    let metadata = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&metadata, &"origin".into(), &program.origin.into());
+    let _ = js_sys::Reflect::set(&metadata, &"labels".into(), &labels);
+    let _ = js_sys::Reflect::set(&metadata, &"lines".into(), &lines);
+    metadata.into()
+}
+
+/// How a `WasmComputer::run` ended
+#[wasm_bindgen]
+pub struct RunResult {
+    reason: StopReason,
+    instructions: u64,
+}
+
+#[wasm_bindgen]
+impl RunResult {
+    /// `halted`, `max_instructions`, `out_of_fuel`, `stop_requested`,
+    /// `breakpoint`, `watchpoint`, `reached`, `cycle_limit`,
+    /// `infinite_loop`, `no_progress` or `error`
+    #[wasm_bindgen(getter)]
+    pub fn reason(&self) -> String {
+        match self.reason {
+            StopReason::Halted => "halted",
+            StopReason::MaxInstructions => "max_instructions",
+            StopReason::OutOfFuel => "out_of_fuel",
+            StopReason::StopRequested => "stop_requested",
+            StopReason::Breakpoint(_) => "breakpoint",
+            StopReason::Watchpoint(_) => "watchpoint",
+            StopReason::Reached(_) => "reached",
+            StopReason::CycleLimit => "cycle_limit",
+            StopReason::InfiniteLoop(_) => "infinite_loop",
+            StopReason::NoProgress(_) => "no_progress",
+            StopReason::Error(_) => "error",
+        }
+        .to_string()
+    }
+
+    /// Instructions executed by the run
+    #[wasm_bindgen(getter)]
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// The breakpoint, watched address or PC the reason refers to
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> Option<u16> {
+        match self.reason {
+            StopReason::Breakpoint(addr)
+            | StopReason::Watchpoint(addr)
+            | StopReason::Reached(addr)
+            | StopReason::InfiniteLoop(addr)
+            | StopReason::NoProgress(addr) => Some(addr),
+            _ => None,
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> Option<String> {
+        match &self.reason {
+            StopReason::Error(e) => Some(e.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// WASM-exposed computer wrapping Computer<BufferedIO, UIObserver> with
+/// run statistics, a profile and JS callbacks alongside, recording
+/// console interaction for bug reports
 #[wasm_bindgen]
 pub struct WasmComputer {
-    inner: Computer<BufferedIO, UIObserver>,
+    inner: Computer<RecordingIO<BufferedIO>, WebObservers>,
+    /// C source line of each instruction, when the program came from `load_c`
+    c_lines: BTreeMap<u16, usize>,
+    /// Address of the first instruction of each C function and statement
+    c_statements: Rc<BTreeSet<u16>>,
+    /// The last program loaded, for `reset` and `reload`
+    loaded: Option<LoadedProgram>,
 }
 
+/// A program image as `reset` puts it back
+struct LoadedProgram {
+    /// What it was built from, for `reload`
+    source: Option<ProgramSource>,
+    segments: Vec<(u16, Vec<u16>)>,
+    entry: u16,
+}
+
+#[derive(Clone)]
+enum ProgramSource {
+    Assembly(String),
+    C(String),
+}
+
+impl LoadedProgram {
+    fn words(origin: u16, words: &[u16]) -> Self {
+        LoadedProgram {
+            source: None,
+            segments: vec![(origin, words.to_vec())],
+            entry: origin,
+        }
+    }
+}
+
+type WebObservers = (UIObserver, StatisticsObserver, CallbacksRegistry);
+
+/// Busiest addresses `execution_stats` lists
+const HOTSPOTS: usize = 32;
+
 #[wasm_bindgen]
 impl WasmComputer {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
         Self {
-            inner: Computer::with_observer(BufferedIO::new(), UIObserver::new()),
+            inner: Computer::with_observer(
+                RecordingIO::new(BufferedIO::new()),
+                (
+                    UIObserver::new(),
+                    StatisticsObserver::new(),
+                    CallbacksRegistry::default(),
+                ),
+            ),
+            c_lines: BTreeMap::new(),
+            c_statements: Rc::default(),
+            loaded: None,
         }
     }
 
-    pub fn load_assembly(&mut self, program: &str) -> Result<(), String> {
-        let program = Program::from_assembly(program).map_err(|e| format!("{:?}", e))?;
-        let words = program.to_words();
-        self.inner.load_program(&words, USER_PROGRAM_START);
-        Ok(())
+    /// Assemble and load `source`, returning `{origin, labels, lines}`:
+    /// `labels` maps each label to its address and `lines` each source
+    /// line number (1-based) holding an instruction to its address. On
+    /// failure throws an error object with the location of the problem.
+    pub fn load_assembly(&mut self, source: &str) -> Result<JsValue, JsValue> {
+        self.assemble_and_load(source).map(|program| load_metadata(&program))
+    }
+
+    /// Load raw machine code at `origin` and point the PC there
+    pub fn load_words(&mut self, origin: u16, words: &[u16]) {
+        self.clear_c_lines();
+        self.inner.load_program(words, origin);
+        self.loaded = Some(LoadedProgram::words(origin, words));
+    }
+
+    /// Load a `.obj` image from another LC-3b assembler (big-endian words,
+    /// origin first) and return its origin
+    pub fn load_obj(&mut self, bytes: &[u8]) -> Result<u16, String> {
+        self.clear_c_lines();
+        let origin = self.inner.load_object(bytes).map_err(|e| e.to_string())?;
+        let words = lc3b_isa::words_from_bytes(bytes, lc3b_isa::Endianness::Big);
+        self.loaded = Some(LoadedProgram::words(origin, &words[1..]));
+        Ok(origin)
+    }
+
+    /// Compile C `source`, assemble it and load the result. Returns the
+    /// `load_assembly` metadata with the generated text as `assembly`. A
+    /// failure throws the error either step would, with `stage` set to
+    /// `compile` (located in the C source) or `assemble` (located in the
+    /// generated assembly, a compiler bug).
+    pub fn load_c(&mut self, source: &str) -> Result<JsValue, JsValue> {
+        let with_stage = |error: JsValue, stage: &str| {
+            let _ = js_sys::Reflect::set(&error, &"stage".into(), &stage.into());
+            error
+        };
+        let assembly = compile_c(source, &CompileOptions::default())
+            .map_err(|e| with_stage(compile_error(source, &e), "compile"))?;
+        let program = self
+            .assemble_and_load(&assembly)
+            .map_err(|e| with_stage(e, "assemble"))?;
+        self.map_c_lines(&program, &assembly);
+        if let Some(loaded) = &mut self.loaded {
+            loaded.source = Some(ProgramSource::C(source.to_string()));
+        }
+        let metadata = load_metadata(&program);
+        let _ = js_sys::Reflect::set(&metadata, &"assembly".into(), &assembly.into());
+        Ok(metadata)
+    }
+
+    /// Start the loaded program over: the machine is reset as
+    /// `Computer::reset` does, memory is cleared and the program image
+    /// loaded again (undoing anything it wrote), and the console, pending
+    /// input, I/O log and statistics are cleared. Breakpoints, watches and
+    /// callbacks are kept.
+    pub fn reset(&mut self) {
+        self.inner.reset();
+        self.inner.reset_memory();
+        if let Some(loaded) = &self.loaded {
+            for (origin, words) in &loaded.segments {
+                self.inner.load_program(words, *origin);
+            }
+            self.inner.set_pc(loaded.entry);
+        }
+        self.inner.io_mut().inner_mut().reset();
+        self.inner.io_mut().clear_log();
+        self.inner.observer_mut().0.reset_instruction_state();
+        self.reset_statistics();
+    }
+
+    /// Assemble (or compile) the last loaded source again and `reset`,
+    /// returning the metadata its `load_assembly` or `load_c` does.
+    /// Programs loaded as words or an object file are just reset, and
+    /// undefined is returned. A failure throws as loading did and leaves
+    /// the machine as it was.
+    pub fn reload(&mut self) -> Result<JsValue, JsValue> {
+        let source = self.loaded.as_ref().and_then(|loaded| loaded.source.clone());
+        let metadata = match source {
+            Some(ProgramSource::Assembly(text)) => self.load_assembly(&text)?,
+            Some(ProgramSource::C(text)) => self.load_c(&text)?,
+            None => JsValue::UNDEFINED,
+        };
+        self.reset();
+        Ok(metadata)
+    }
+
+    /// The C source line (1-based) the instruction at the PC was compiled
+    /// from, if the program was loaded with `load_c`
+    pub fn current_c_line(&self) -> Option<usize> {
+        self.c_lines.get(&self.inner.program_counter()).copied()
+    }
+
+    /// Run until the PC reaches the start of a C statement or function
+    /// (the next one, or the same one again in a loop), or as `run` stops
+    /// first. Calls are stepped into. Without C line information this
+    /// runs like `run`.
+    pub fn step_c_line(&mut self, max_instructions: usize) -> RunResult {
+        let statements = Rc::clone(&self.c_statements);
+        self.inner
+            .set_stop_predicate(move |computer| statements.contains(&computer.program_counter()));
+        let result = self.run(max_instructions);
+        self.inner.clear_stop_predicate();
+        result
     }
 
     pub fn next_instruction(&mut self) -> Result<(), String> {
-        self.inner.observer_mut().reset_instruction_state();
+        self.inner.observer_mut().0.reset_instruction_state();
         self.inner.next_instruction().map_err(|e| e.to_string())
     }
 
-    pub fn run(&mut self, max_instructions: usize) -> Result<usize, String> {
-        self.inner.run(max_instructions).map_err(|e| e.to_string())
+    /// Run until halted, a breakpoint, an error or `max_instructions`.
+    /// Continuing from a breakpoint executes the instruction there first.
+    pub fn run(&mut self, max_instructions: usize) -> RunResult {
+        let start = self.inner.instructions_executed();
+        let reason = self.inner.run(max_instructions);
+        RunResult {
+            reason,
+            instructions: self.inner.instructions_executed() - start,
+        }
+    }
+
+    /// Run until halted, a breakpoint, an error or `ms` milliseconds of
+    /// wall-clock time have passed, which stops with `max_instructions`.
+    /// Calling this once per animation frame keeps the page responsive
+    /// however fast the machine is.
+    pub fn run_for(&mut self, ms: f64) -> RunResult {
+        let start = self.inner.instructions_executed();
+        let deadline = js_sys::Date::now() + ms;
+        let reason = loop {
+            let reason = self.inner.run(RUN_SLICE);
+            if !matches!(reason, StopReason::MaxInstructions) || js_sys::Date::now() >= deadline {
+                break reason;
+            }
+        };
+        RunResult {
+            reason,
+            instructions: self.inner.instructions_executed() - start,
+        }
+    }
+
+    /// Overall instruction budget shared by every `run` call; undefined
+    /// removes it. A run that uses it up stops early without an error.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.inner.set_fuel(fuel);
+    }
+
+    pub fn fuel(&self) -> Option<u64> {
+        self.inner.fuel()
+    }
+
+    pub fn add_fuel(&mut self, amount: u64) {
+        self.inner.add_fuel(amount);
+    }
+
+    pub fn instructions_executed(&self) -> u64 {
+        self.inner.instructions_executed()
+    }
+
+    // --- Callbacks ---
+
+    /// Call `f(pc)` after each instruction; undefined removes it. Like
+    /// the other callbacks it runs inside `run`, so keep it cheap.
+    pub fn set_on_instruction(&mut self, f: Option<js_sys::Function>) {
+        self.inner.observer_mut().2.on_instruction = f;
+    }
+
+    /// Call `f(text)` for each character printed
+    pub fn set_on_output(&mut self, f: Option<js_sys::Function>) {
+        self.inner.observer_mut().2.on_output = f;
+    }
+
+    /// Call `f()` when the machine halts
+    pub fn set_on_halt(&mut self, f: Option<js_sys::Function>) {
+        self.inner.observer_mut().2.on_halt = f;
+    }
+
+    /// Call `f(addr, old, new)` for each memory write
+    pub fn set_on_memory_write(&mut self, f: Option<js_sys::Function>) {
+        self.inner.observer_mut().2.on_memory_write = f;
+    }
+
+    /// The first exception a callback threw since the last call. A throw
+    /// stops the run with `stop_requested`.
+    pub fn take_callback_error(&mut self) -> JsValue {
+        self.inner.observer_mut().2.take_error().unwrap_or(JsValue::UNDEFINED)
+    }
+
+    // --- Breakpoints ---
+
+    /// False if there already was one at `addr`
+    pub fn add_breakpoint(&mut self, addr: u16) -> bool {
+        self.inner.add_breakpoint(addr)
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.inner.remove_breakpoint(addr)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.inner.clear_breakpoints();
+    }
+
+    /// Breakpoint addresses in ascending order
+    pub fn breakpoints(&self) -> Vec<u16> {
+        self.inner.list_breakpoints()
     }
 
     // --- State accessors ---
@@ -93,6 +504,41 @@ impl WasmComputer {
         self.inner.register(index)
     }
 
+    /// Overwrite R`index` (0-7), for an editable register pane
+    pub fn set_register(&mut self, index: u8, value: u16) -> Result<(), String> {
+        if index > 7 {
+            return Err(format!("no register R{}", index));
+        }
+        self.inner.set_register(index, value);
+        Ok(())
+    }
+
+    /// R0 through R7
+    pub fn registers(&self) -> Vec<u16> {
+        self.inner.registers().to_vec()
+    }
+
+    /// Everything the register pane shows, in one call: `{pc, registers,
+    /// n, z, p, halted, cycles, instructions}`
+    pub fn cpu_state(&self) -> JsValue {
+        let registers: js_sys::Uint16Array = self.inner.registers().as_slice().into();
+        let state = js_sys::Object::new();
+        let fields: [(&str, JsValue); 8] = [
+            ("pc", self.inner.program_counter().into()),
+            ("registers", registers.into()),
+            ("n", self.inner.condition_n().into()),
+            ("z", self.inner.condition_z().into()),
+            ("p", self.inner.condition_p().into()),
+            ("halted", self.inner.is_halted().into()),
+            ("cycles", (self.inner.cycles() as f64).into()),
+            ("instructions", (self.inner.instructions_executed() as f64).into()),
+        ];
+        for (name, value) in fields {
+            let _ = js_sys::Reflect::set(&state, &name.into(), &value);
+        }
+        state.into()
+    }
+
     pub fn condition_n(&self) -> bool {
         self.inner.condition_n()
     }
@@ -109,36 +555,328 @@ impl WasmComputer {
         self.inner.read_memory(addr)
     }
 
+    /// Overwrite the word at `addr`, for an editable memory pane. Device
+    /// registers are written as plain memory, without side effects.
+    pub fn write_memory(&mut self, addr: u16, value: u16) {
+        self.inner.write_memory(addr, value);
+    }
+
+    /// Up to `len` words from `start` in one call (a Uint16Array in JS)
+    pub fn read_memory_range(&self, start: u16, len: usize) -> Vec<u16> {
+        self.inner.read_memory_range(start, len).to_vec()
+    }
+
+    pub fn hexdump(&self, start: u16, len: usize) -> String {
+        self.inner.hexdump(start, len)
+    }
+
+    /// `count` words from `start` as an array of
+    /// `{addr, word, text, is_current}`, `text` being the disassembly and
+    /// `is_current` marking the PC
+    pub fn disassemble(&self, start: u16, count: u16) -> JsValue {
+        let rows = js_sys::Array::new();
+        let pc = self.inner.program_counter();
+        for i in 0..count {
+            let addr = start.wrapping_add(i);
+            let word = self.inner.read_memory(addr);
+            let text = match Instruction::try_from(word) {
+                Ok(inst) => inst.to_string(),
+                Err(_) => format!(".FILL x{:04X}", word),
+            };
+            let row = js_sys::Object::new();
+            let set = |key: &str, value: JsValue| js_sys::Reflect::set(&row, &key.into(), &value);
+            let _ = set("addr", addr.into());
+            let _ = set("word", word.into());
+            let _ = set("text", text.into());
+            let _ = set("is_current", (addr == pc).into());
+            rows.push(&row);
+        }
+        rows.into()
+    }
+
+    /// Value of an expression such as `R3 + 2`, `[x4000]` or `label+1`
+    pub fn evaluate(&self, expression: &str) -> Result<u16, String> {
+        self.inner.evaluate(expression).map_err(|e| e.to_string())
+    }
+
+    /// Track an expression after every instruction; returns its id
+    pub fn add_watch(&mut self, expression: &str) -> Result<usize, String> {
+        self.inner.add_watch(expression).map_err(|e| e.to_string())
+    }
+
+    pub fn remove_watch(&mut self, id: usize) -> bool {
+        self.inner.remove_watch(id)
+    }
+
+    /// Current value of watch `id`, or undefined if it has none
+    pub fn watch_value(&self, id: usize) -> Option<u16> {
+        let watches = self.inner.watches();
+        watches.iter().find(|watch| watch.0 == id).and_then(|watch| watch.2)
+    }
+
+    /// Active calls, innermost first, as `{kind, vector, call_site,
+    /// target, return_address, symbol, frame_pointer}`. `kind` is
+    /// `subroutine`, `trap` or `interrupt` (`vector` is set for the last
+    /// two) and `symbol` names the target. `frame_pointer` follows the C
+    /// compiler's layout from R5 (the caller's R5 is saved one slot above
+    /// it); it is only meaningful for compiled C, and for a call whose
+    /// prologue has not run yet it still shows the caller's frame.
+    pub fn call_stack(&self) -> JsValue {
+        let frames = js_sys::Array::new();
+        let mut frame_pointer = self.inner.register(5);
+        for frame in self.inner.call_stack().iter().rev() {
+            let (kind, vector) = match frame.kind {
+                FrameKind::Subroutine => ("subroutine", None),
+                FrameKind::Trap(vector) => ("trap", Some(vector)),
+                FrameKind::Interrupt(vector) => ("interrupt", Some(vector)),
+            };
+            let symbol = self.inner.describe_address(frame.target);
+            let object = js_sys::Object::new();
+            let fields: [(&str, JsValue); 7] = [
+                ("kind", kind.into()),
+                ("vector", vector.map_or(JsValue::UNDEFINED, JsValue::from)),
+                ("call_site", frame.call_site.into()),
+                ("target", frame.target.into()),
+                ("return_address", frame.return_address.into()),
+                ("symbol", symbol.into()),
+                ("frame_pointer", frame_pointer.into()),
+            ];
+            for (name, value) in fields {
+                let _ = js_sys::Reflect::set(&object, &name.into(), &value);
+            }
+            frames.push(&object);
+            if frame.kind == FrameKind::Subroutine {
+                frame_pointer = self.inner.read_memory(frame_pointer.wrapping_add(2));
+            }
+        }
+        frames.into()
+    }
+
     // --- Observer state ---
 
     pub fn last_modified_register(&self) -> i8 {
         self.inner
             .observer()
+            .0
             .last_modified_register()
             .map(|r| r as i8)
             .unwrap_or(-1)
     }
 
+    /// Registers the last instruction wrote, in order (may repeat)
+    pub fn modified_registers(&self) -> Vec<u8> {
+        let changes = self.inner.observer().0.register_changes();
+        changes.iter().map(|delta| delta.register).collect()
+    }
+
+    /// Memory addresses the last instruction wrote, in order
+    pub fn modified_memory(&self) -> Vec<u16> {
+        let changes = self.inner.observer().0.memory_changes();
+        changes.iter().map(|delta| delta.addr).collect()
+    }
+
+    pub fn last_modified_memory(&self) -> Option<u16> {
+        self.inner.observer().0.last_modified_memory()
+    }
+
+    /// Whether the last instruction changed N, Z or P
+    pub fn condition_changed(&self) -> bool {
+        self.inner.observer().0.condition_changed()
+    }
+
+    /// The last instruction's whole change set: `{registers: [{register,
+    /// old, new}], memory: [{addr, old, new}], condition_changed}`
+    pub fn changes(&self) -> JsValue {
+        let ui = &self.inner.observer().0;
+        let delta = |key: &str, at: u16, old: u16, new: u16| {
+            let object = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&object, &key.into(), &at.into());
+            let _ = js_sys::Reflect::set(&object, &"old".into(), &old.into());
+            let _ = js_sys::Reflect::set(&object, &"new".into(), &new.into());
+            object
+        };
+        let registers: js_sys::Array = ui
+            .register_changes()
+            .iter()
+            .map(|d| delta("register", d.register as u16, d.old, d.new))
+            .collect();
+        let memory: js_sys::Array = ui
+            .memory_changes()
+            .iter()
+            .map(|d| delta("addr", d.addr, d.old, d.new))
+            .collect();
+        let changes = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&changes, &"registers".into(), &registers);
+        let _ = js_sys::Reflect::set(&changes, &"memory".into(), &memory);
+        let condition_changed = ui.condition_changed().into();
+        let _ = js_sys::Reflect::set(&changes, &"condition_changed".into(), &condition_changed);
+        changes.into()
+    }
+
+    /// Instruction, memory, stack and branch totals since the last
+    /// `reset_statistics`, as text
+    pub fn statistics_report(&self) -> String {
+        self.inner.observer().1.report().to_string()
+    }
+
+    /// Statistics and profile since the last `reset_statistics`, for a
+    /// profiling panel: `{instructions, cycles, opcodes: [{opcode,
+    /// count}], hotspots: [{addr, count, symbol}], memory_reads,
+    /// memory_writes, max_stack_depth, branches_taken,
+    /// branches_not_taken}`. Opcodes are busiest first, and `hotspots`
+    /// holds the 32 busiest addresses. `cycles` counts from the start of
+    /// the machine, not the reset.
+    pub fn execution_stats(&self) -> JsValue {
+        let statistics = &self.inner.observer().1;
+        let report = statistics.report();
+        let entry = |fields: [(&str, JsValue); 2]| {
+            let object = js_sys::Object::new();
+            for (name, value) in fields {
+                let _ = js_sys::Reflect::set(&object, &name.into(), &value);
+            }
+            object
+        };
+        let opcodes: js_sys::Array = report
+            .opcodes
+            .iter()
+            .map(|(op, count)| {
+                entry([("opcode", format!("{:?}", op).into()), ("count", (*count as f64).into())])
+            })
+            .collect();
+        let hotspots: js_sys::Array = statistics
+            .profile()
+            .report()
+            .hotspots
+            .iter()
+            .take(HOTSPOTS)
+            .map(|&(addr, count)| {
+                let object = entry([("addr", addr.into()), ("count", (count as f64).into())]);
+                let symbol = self.inner.describe_address(addr);
+                let _ = js_sys::Reflect::set(&object, &"symbol".into(), &symbol.into());
+                object
+            })
+            .collect();
+        let stats = js_sys::Object::new();
+        let fields: [(&str, JsValue); 9] = [
+            ("instructions", (report.instructions as f64).into()),
+            ("cycles", (self.inner.cycles() as f64).into()),
+            ("opcodes", opcodes.into()),
+            ("hotspots", hotspots.into()),
+            ("memory_reads", (report.memory_reads as f64).into()),
+            ("memory_writes", (report.memory_writes as f64).into()),
+            ("max_stack_depth", report.max_stack_depth.into()),
+            ("branches_taken", (report.branches_taken as f64).into()),
+            ("branches_not_taken", (report.branches_not_taken as f64).into()),
+        ];
+        for (name, value) in fields {
+            let _ = js_sys::Reflect::set(&stats, &name.into(), &value);
+        }
+        stats.into()
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.inner.observer_mut().1.reset();
+    }
+
     // --- I/O state ---
 
     pub fn console_output(&self) -> String {
-        self.inner.io().output().to_string()
+        self.inner.io().inner().output().to_string()
     }
 
     pub fn clear_console(&mut self) {
-        self.inner.io_mut().clear_output();
+        self.inner.io_mut().inner_mut().clear_output();
+    }
+
+    /// Console output from `position` on, dropping what came before; pass
+    /// the last `console_position()` to fetch only new output
+    pub fn take_console_since(&mut self, position: usize) -> String {
+        self.inner.io_mut().inner_mut().take_output_since(position)
+    }
+
+    /// Characters printed so far, counting any no longer held
+    pub fn console_position(&self) -> usize {
+        self.inner.io().inner().output_position()
+    }
+
+    pub fn drain_console(&mut self) -> String {
+        self.inner.io_mut().inner_mut().drain_output()
+    }
+
+    /// Keep only the most recent `limit` characters of console output;
+    /// undefined keeps everything
+    pub fn set_console_limit(&mut self, limit: Option<usize>) {
+        self.inner.io_mut().inner_mut().set_output_limit(limit);
     }
 
     pub fn is_halted(&self) -> bool {
-        self.inner.io().is_halted()
+        self.inner.is_halted()
+    }
+
+    /// What halted the machine, if it is halted
+    pub fn halt_reason(&self) -> Option<String> {
+        self.inner.halt_reason().map(|reason| reason.to_string())
     }
 
     pub fn push_input(&mut self, ch: char) {
-        self.inner.io_mut().push_input(ch);
+        self.inner.io_mut().inner_mut().push_input(ch);
     }
 
     pub fn push_input_str(&mut self, s: &str) {
-        self.inner.io_mut().push_input_str(s);
+        self.inner.io_mut().inner_mut().push_input_str(s);
+    }
+
+    /// Queue `ch` and raise the keyboard interrupt (vector x80, priority
+    /// 4) for it, whether or not the program set KBSR's IE bit. The
+    /// handler reads the character from KBDR as usual. Programs that poll
+    /// should get `push_input` instead.
+    pub fn raise_keyboard_interrupt(&mut self, ch: char) {
+        self.push_input(ch);
+        self.inner.raise_interrupt(Interrupt::KEYBOARD);
+    }
+
+    /// Console interaction so far in `IoLog` text form, to attach to a bug
+    /// report along with the program
+    pub fn io_log(&self) -> String {
+        self.inner.io().log().to_string()
+    }
+
+    pub fn clear_io_log(&mut self) {
+        self.inner.io_mut().clear_log();
+    }
+
+    /// Feed a log from `io_log` back as keyboard input. Load the program
+    /// first, so the rerun starts where the recording did.
+    pub fn replay_io_log(&mut self, log: &str) -> Result<(), String> {
+        let log: IoLog = log.parse().map_err(|e: crate::Error| e.to_string())?;
+        self.inner.io_mut().replay(&log);
+        Ok(())
+    }
+
+    /// Instruction count where a replay's output first differed from the
+    /// recording, if it has
+    pub fn replay_divergence(&self) -> Option<u64> {
+        self.inner.io().divergence()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[wasm_bindgen]
+impl WasmComputer {
+    /// The machine state (see `Snapshot`) as bytes to save or share.
+    /// Breakpoints, watches and statistics are not included.
+    pub fn snapshot(&self) -> Vec<u8> {
+        serde_json::to_vec(&self.inner.snapshot()).expect("snapshots serialize")
+    }
+
+    /// Return to a state saved by `snapshot`. Bytes that are not a valid
+    /// snapshot leave the machine as it was.
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let snapshot: crate::Snapshot<RecordingIO<BufferedIO>> =
+            serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+        snapshot.validate().map_err(|e| e.to_string())?;
+        self.inner.restore(&snapshot);
+        Ok(())
     }
 }
 
@@ -147,3 +885,43 @@ impl Default for WasmComputer {
         Self::new()
     }
 }
+
+impl WasmComputer {
+    fn assemble_and_load(&mut self, source: &str) -> Result<AssembledProgram, JsValue> {
+        let program = lc3b_assembler::assemble(source)
+            .map_err(|e| assembly_error(source, e.downcast_ref(), e.to_string()))?;
+        self.clear_c_lines();
+        self.inner.load_assembled(&program, true);
+        self.loaded = Some(LoadedProgram {
+            source: Some(ProgramSource::Assembly(source.to_string())),
+            segments: program.segments().map(|(origin, words)| (origin, words.to_vec())).collect(),
+            entry: program.origin,
+        });
+        Ok(program)
+    }
+
+    fn clear_c_lines(&mut self) {
+        self.c_lines.clear();
+        self.c_statements = Rc::default();
+    }
+
+    /// Give each instruction of `program`, compiled C, the line of the
+    /// last line marker before it in `assembly`
+    fn map_c_lines(&mut self, program: &AssembledProgram, assembly: &str) {
+        let markers = line_markers(assembly);
+        let mut statements = BTreeSet::new();
+        let mut previous = None;
+        for (addr, asm_line) in program.source_map.iter() {
+            let index = markers.partition_point(|&(marker, _)| marker < asm_line);
+            let Some(&(marker, c_line)) = index.checked_sub(1).map(|i| &markers[i]) else {
+                continue;
+            };
+            if previous != Some(marker) {
+                statements.insert(addr);
+                previous = Some(marker);
+            }
+            self.c_lines.insert(addr, c_line);
+        }
+        self.c_statements = Rc::new(statements);
+    }
+}
@@ -0,0 +1,698 @@
+//! C programs compiled, assembled and run to completion
+
+use lc3b::{BufferedIO, Computer};
+use lc3b_c_compiler::CompileOptions;
+
+/// Compile, assemble and run `source`, returning `main`'s return value.
+/// Every optimization level has to give the same one, and compiling
+/// straight to words the same program as assembling.
+fn run_c(source: &str) -> i16 {
+    let result = run_c_with(source, &CompileOptions::default());
+    for opt_level in 1..=2 {
+        let options = CompileOptions {
+            opt_level,
+            ..CompileOptions::default()
+        };
+        assert_eq!(run_c_with(source, &options), result, "opt_level {}\n{}", opt_level, source);
+    }
+    result
+}
+
+fn run_c_with(source: &str, options: &CompileOptions) -> i16 {
+    let assembly = lc3b_c_compiler::compile(source, options).unwrap();
+    let program = lc3b_assembler::assemble(&assembly)
+        .unwrap_or_else(|e| panic!("{}\n\n{}", e, assembly));
+    let compiled = lc3b_c_compiler::compile_to_words(source, options).unwrap();
+    let assembled = (program.origin, &program.words);
+    assert_eq!((compiled.origin, &compiled.words), assembled, "{}", assembly);
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_assembled(&program, true);
+    let reason = computer.run(100_000);
+    assert!(computer.is_halted(), "{:?}\n\n{}", reason, assembly);
+    computer.register(0) as i16
+}
+
+#[test]
+fn test_expressions_use_the_stack() {
+    // Binary operators push the left operand while the right is evaluated
+    assert_eq!(run_c("int main() { return (1 + 2) - (4 - 8); }"), 7);
+    assert_eq!(run_c("int main() { return (9 - 2) - (1 + (3 - 5)); }"), 8);
+}
+
+#[test]
+fn test_multiply_divide_modulo() {
+    let values = [-15, -7, -1, 0, 1, 3, 13, 15];
+    for a in values {
+        for b in values {
+            let product = run_c(&format!("int main() {{ return ({}) * ({}); }}", a, b));
+            assert_eq!(product, a * b, "{} * {}", a, b);
+            if b == 0 {
+                continue;
+            }
+            let quotient = run_c(&format!("int main() {{ return ({}) / ({}); }}", a, b));
+            assert_eq!(quotient, a / b, "{} / {}", a, b);
+            let remainder = run_c(&format!("int main() {{ return ({}) % ({}); }}", a, b));
+            assert_eq!(remainder, a % b, "{} % {}", a, b);
+        }
+    }
+    assert_eq!(run_c("int main() { return 300 * 100; }"), 30000);
+    assert_eq!(run_c("int main() { return 30000 / 7; }"), 4285);
+    assert_eq!(run_c("int main() { return -32768 / 1; }"), -32768);
+    assert_eq!(run_c("int main() { return 9 * 16; }"), 144);
+}
+
+#[test]
+fn test_local_arrays() {
+    let source = "
+        int main() {
+            int a[10];
+            int i;
+            int sum = 0;
+            for (i = 0; i < 10; i++) {
+                a[i] = i + i + i;
+            }
+            a[2] += 100;
+            for (i = 0; i < 10; i++) {
+                sum += a[i];
+            }
+            return sum;
+        }";
+    assert_eq!(run_c(source), 235);
+
+    let source = "
+        int main() {
+            int x = 7;
+            int a[4] = {5, -2};
+            int y = 9;
+            return a[0] - a[1] + a[2] + a[3] + x + y;
+        }";
+    assert_eq!(run_c(source), 23);
+}
+
+#[test]
+fn test_global_arrays() {
+    let source = "
+        int primes[6] = {2, 3, 5, 7, 11};
+        int squares[20];
+        int i;
+        int main() {
+            for (i = 0; i < 20; i++) {
+                squares[i] = i + i;
+            }
+            primes[5] = 13;
+            return squares[19] + primes[5] - primes[0] + primes[4];
+        }";
+    assert_eq!(run_c(source), 38 + 13 - 2 + 11);
+}
+
+#[test]
+fn test_pointers() {
+    let source = "
+        int main() {
+            int x = 5;
+            int *p = &x;
+            *p = 9;
+            *p += 1;
+            return x;
+        }";
+    assert_eq!(run_c(source), 10);
+
+    let source = "
+        int g;
+        int main() {
+            int *p = &g;
+            *p = 7;
+            return g + *p;
+        }";
+    assert_eq!(run_c(source), 14);
+
+    let source = "
+        int main() {
+            int a[3] = {1, 2, 3};
+            int *p = &a[1];
+            *p = 4;
+            p[1] = 6;
+            *(p - 1) = 10;
+            return a[0] + a[1] + a[2];
+        }";
+    assert_eq!(run_c(source), 20);
+}
+
+#[test]
+fn test_structs() {
+    let source = "
+        struct point {
+            int x;
+            int y;
+        };
+        struct point origin = {3, 4};
+        int main() {
+            struct point p;
+            struct point *q = &p;
+            p.x = 10;
+            q->y = 20;
+            q->x += origin.y;
+            return p.x + p.y + origin.x;
+        }";
+    assert_eq!(run_c(source), 37);
+
+    let source = "
+        struct buffer {
+            int len;
+            int data[4];
+        };
+        int main() {
+            struct buffer b = {2, 5, 6};
+            struct buffer *p = &b;
+            p->data[p->len] = 7;
+            return b.data[0] + b.data[1] + b.data[2] + b.data[3] + b.len;
+        }";
+    assert_eq!(run_c(source), 20);
+}
+
+#[test]
+fn test_linked_list() {
+    let source = "
+        struct node {
+            int value;
+            struct node *next;
+        };
+        struct node nodes[4];
+        struct node *head;
+        int sum;
+        int main() {
+            struct node *n;
+            nodes[0].value = 1;
+            nodes[1].value = 20;
+            nodes[2].value = 300;
+            nodes[0].next = &nodes[1];
+            nodes[1].next = &nodes[2];
+            nodes[2].next = 0;
+            head = &nodes[0];
+            sum = 0;
+            n = head;
+            while (n) {
+                sum += n->value;
+                n = n->next;
+            }
+            return sum + head->next->next->value;
+        }";
+    assert_eq!(run_c(source), 621);
+}
+
+#[test]
+fn test_break_and_continue() {
+    let source = "
+        int i;
+        int j;
+        int sum;
+        int main() {
+            sum = 0;
+            for (i = 0; i < 10; i++) {
+                if (i == 2) {
+                    continue;
+                }
+                if (i == 6) {
+                    break;
+                }
+                j = 0;
+                while (1) {
+                    j++;
+                    if (j > i) {
+                        break;
+                    }
+                    if (j == 2) {
+                        continue;
+                    }
+                    sum += 10;
+                }
+                sum += i;
+            }
+            return sum;
+        }";
+    // i = 0, 1, 3, 4, 5 add themselves (13) and 10 for each j in 1..=i but 2
+    assert_eq!(run_c(source), 13 + 10 * (1 + 2 + 3 + 4));
+}
+
+#[test]
+fn test_do_while() {
+    let source = "
+        int n;
+        int runs;
+        int main() {
+            n = 100;
+            runs = 0;
+            do {
+                runs++;
+            } while (n < 10);
+            do {
+                n -= 7;
+                if (n > 50) {
+                    continue;
+                }
+                runs += 10;
+                if (n < 30) {
+                    break;
+                }
+            } while (n > 0);
+            return runs * 128 + n;
+        }";
+    // The first body runs once however false the condition is; the second
+    // counts 93, 86, ..., 51 without adding, then 44, 37, 30, 23
+    assert_eq!(run_c(source), 41 * 128 + 23);
+}
+
+#[test]
+fn test_conditional_operator() {
+    let source = "
+        int a;
+        int b;
+        int main() {
+            a = 3 > 2 ? 10 : 20;
+            b = 0 ? 1 : 2 ? 30 : 40;
+            return (a < b ? a : b) + (a == 10 ? 1 : 0);
+        }";
+    assert_eq!(run_c(source), 11);
+
+    // Only the value chosen is evaluated
+    let source = "
+        int g;
+        int h;
+        int main() {
+            g = 1 ? 5 : (h = 9);
+            g += 0 ? (h = 20) : 2;
+            return g ? g + h : -1;
+        }";
+    assert_eq!(run_c(source), 7);
+}
+
+#[test]
+fn test_short_circuit() {
+    let source = "
+        int n;
+        int main() {
+            int r;
+            n = 0;
+            r = (n++ || n++) + (n && n++) * 2 + (0 && n++) * 4 + (n - n || 0) * 8;
+            return r * 16 + n;
+        }";
+    // n++ || ...: 0, so n++ runs too (1), n = 2; n && n++: 1, n = 3;
+    // 0 && ...: 0, n++ skipped; 0 || 0: 0
+    assert_eq!(run_c(source), 3 * 16 + 3);
+}
+
+#[test]
+fn test_recursion() {
+    let source = "
+        int factorial(int n) {
+            if (n <= 1) {
+                return 1;
+            }
+            return n * factorial(n - 1);
+        }
+        int main() {
+            return factorial(7);
+        }";
+    assert_eq!(run_c(source), 5040);
+
+    let source = "
+        int fib(int n) {
+            if (n < 2) {
+                return n;
+            }
+            return fib(n - 1) + fib(n - 2);
+        }
+        int main() {
+            return fib(12);
+        }";
+    assert_eq!(run_c(source), 144);
+
+    // Locals keep their values across the recursive calls
+    let source = "
+        int sum_to(int n) {
+            int rest;
+            int here = n;
+            if (n == 0) {
+                return 0;
+            }
+            rest = sum_to(n - 1);
+            return here + rest;
+        }
+        int main() {
+            return sum_to(20);
+        }";
+    assert_eq!(run_c(source), 210);
+
+    // Calls may name functions defined further down
+    let source = "
+        int is_even(int n) {
+            if (n == 0) {
+                return 1;
+            }
+            return is_odd(n - 1);
+        }
+        int is_odd(int n) {
+            if (n == 0) {
+                return 0;
+            }
+            return is_even(n - 1);
+        }
+        int main() {
+            return is_even(10) * 10 + is_odd(7);
+        }";
+    assert_eq!(run_c(source), 11);
+}
+
+#[test]
+fn test_parameters() {
+    let source = "
+        int gcd(int a, int b) {
+            if (b == 0) {
+                return a;
+            }
+            return gcd(b, a % b);
+        }
+        int main() {
+            return gcd(1071, 462);
+        }";
+    assert_eq!(run_c(source), 21);
+
+    // Arguments that are themselves calls, in order
+    let source = "
+        int combine(int a, int b, int c) {
+            return a * 100 + b * 10 + c;
+        }
+        int twice(int x) {
+            return x + x;
+        }
+        int main() {
+            return combine(twice(1), 3, twice(twice(1)));
+        }";
+    assert_eq!(run_c(source), 234);
+
+    let source = "
+        int ackermann(int m, int n) {
+            if (m == 0) {
+                return n + 1;
+            }
+            if (n == 0) {
+                return ackermann(m - 1, 1);
+            }
+            return ackermann(m - 1, ackermann(m, n - 1));
+        }
+        int main() {
+            return ackermann(2, 3);
+        }";
+    assert_eq!(run_c(source), 9);
+}
+
+#[test]
+fn test_register_arguments() {
+    let programs = [
+        ("
+        int gcd(int a, int b) {
+            if (b == 0) {
+                return a;
+            }
+            return gcd(b, a % b);
+        }
+        int main() {
+            return gcd(1071, 462);
+        }", 21),
+        ("
+        int combine(int a, int b, int c, int d) {
+            return a * 1000 + b * 100 + c * 10 + d;
+        }
+        int twice(int x) {
+            return x + x;
+        }
+        int main() {
+            return combine(twice(1), 3, twice(twice(1)), twice(3) - 1);
+        }", 2345),
+        ("
+        int fib(int n) {
+            if (n < 2) {
+                return n;
+            }
+            return fib(n - 1) + fib(n - 2);
+        }
+        int main() {
+            return fib(10);
+        }", 55),
+        ("
+        int max(int a, int b) {
+            if (a > b) {
+                return a;
+            }
+            return b;
+        }
+        int main() {
+            return max(3, 8) * 10 + max(4, -2);
+        }", 84),
+        // A parameter whose address is taken is spilled
+        ("
+        void bump(int *p) {
+            *p = *p + 1;
+        }
+        int twice_bumped(int x) {
+            bump(&x);
+            return x + x;
+        }
+        int main() {
+            return twice_bumped(20);
+        }", 42),
+    ];
+    for register_args in 0..=3 {
+        let options = CompileOptions {
+            register_args,
+            ..CompileOptions::default()
+        };
+        for (source, expected) in programs {
+            assert_eq!(run_c_with(source, &options), expected, "{}\n{}", register_args, source);
+        }
+    }
+}
+
+#[test]
+fn test_register_allocation() {
+    // Locals compared and combined in registers keep their values
+    let source = "
+        int main() {
+            int i;
+            int sum = 0;
+            int count = 0;
+            for (i = 0; i < 10; i++) {
+                if (i != 3 && i <= 8) {
+                    sum = sum + i * 3;
+                    count++;
+                }
+            }
+            return sum - count;
+        }";
+    assert_eq!(run_c(source), 99 - 8);
+
+    // More locals live at once than there are registers
+    let source = "
+        int main() {
+            int a = 1;
+            int b = 2;
+            int c = 3;
+            int d = 4;
+            int e = 5;
+            int f = 6;
+            int i;
+            for (i = 0; i < 4; i++) {
+                a = a + b;
+                b = b + c;
+                c = c + d;
+                d = d + e;
+                e = e + f;
+            }
+            return a + b + c + d + e + f;
+        }";
+    assert_eq!(run_c(source), 48 + 64 + 73 + 60 + 29 + 6);
+
+    // Locals live across calls, which use the same registers themselves
+    let source = "
+        int square(int x) {
+            int y = x * x;
+            return y;
+        }
+        int main() {
+            int total = 0;
+            int i;
+            for (i = 1; i <= 5; i++) {
+                total = total + square(i) - i;
+            }
+            return total;
+        }";
+    assert_eq!(run_c(source), 55 - 15);
+
+    // Parameters arriving in registers that their callee wants swapped
+    let source = "
+        int pick(int a, int b, int c) {
+            int x = c * 100;
+            int y = b * 10;
+            return x + y + a;
+        }
+        int main() {
+            return pick(1, 2, 3) + pick(4, 5, 6);
+        }";
+    for register_args in 0..=3 {
+        let options = CompileOptions {
+            register_args,
+            ..CompileOptions::default()
+        };
+        assert_eq!(run_c_with(source, &options), 321 + 654);
+    }
+}
+
+#[test]
+fn test_constant_folding() {
+    let folded = CompileOptions {
+        opt_level: 1,
+        ..CompileOptions::default()
+    };
+    let ops = [
+        "+", "-", "*", "/", "%", "&", "|", "^", "<<", ">>", "==", "!=", "<", "<=", ">", ">=", "&&",
+        "||",
+    ];
+    let values = [-32768, -7, -1, 0, 1, 3, 30000];
+    // Folded operations give what the unfolded code computes, wrapping
+    // and division by zero included
+    for op in ops {
+        for a in values {
+            for b in values {
+                if (op == "<<" || op == ">>") && !(0..20).contains(&b) {
+                    continue;
+                }
+                let source = format!("int main() {{ return ({}) {} ({}); }}", a, op, b);
+                let expected = run_c(&source);
+                assert_eq!(run_c_with(&source, &folded), expected, "{}", source);
+            }
+        }
+    }
+    for op in ["-", "~", "!"] {
+        for a in values {
+            let source = format!("int main() {{ return {}({}); }}", op, a);
+            assert_eq!(run_c_with(&source, &folded), run_c(&source), "{}", source);
+        }
+    }
+}
+
+#[test]
+fn test_constant_propagation() {
+    let folded = CompileOptions {
+        opt_level: 1,
+        ..CompileOptions::default()
+    };
+    let programs = [
+        // Straight-line code, and identities around unknown values
+        ("int f(int n) { return n; }
+          int main() {
+              int a = 6;
+              int b = a * 7;
+              int c = f(b) * 1 + 0;
+              return (c - 0) * (b / a) + (0 * f(1)) + (c & -1);
+          }", 336),
+        // Branches that agree, disagree, or can't run
+        ("int f(int n) { return n; }
+          int main() {
+              int a = 1;
+              int b = 2;
+              if (f(0)) { a = 5; b = 3; } else { a = 5; }
+              if (b - 2) { return 99; }
+              if (0) { a = 100; }
+              return a * 10 + b;
+          }", 52),
+        // A loop changes what was constant before it
+        ("int main() {
+              int i = 0;
+              int total = 0;
+              int step = 2;
+              while (i < 10) {
+                  total = total + step;
+                  if (i == 4) { step = 3; }
+                  i++;
+              }
+              return total * 100 + i;
+          }", 2510),
+        ("int main() {
+              int x = 1;
+              int n = 0;
+              do { x = x * 2; n++; } while (x < 100);
+              for (x = 0; 0; x++) { n = 50; }
+              for (;1;) { if (n > 9) break; n++; }
+              return x * 100 + n;
+          }", 10),
+        // A local whose address is taken can change behind its back
+        ("void set(int *p) { *p = 9; }
+          int main() {
+              int a = 1;
+              set(&a);
+              return a;
+          }", 9),
+        // Short-circuiting keeps side effects that would happen
+        ("int calls = 0;
+          int f(int n) { calls++; return n; }
+          int main() {
+              int zero = 0;
+              int r = (zero && f(1)) + (1 || f(1)) * 10 + (f(5) && 1) * 100;
+              r = r + (zero ? f(1) : 4) * 1000;
+              return r + calls * 10000;
+          }", 14110),
+    ];
+    for (source, expected) in programs {
+        assert_eq!(run_c(source), expected, "{}", source);
+        assert_eq!(run_c_with(source, &folded), expected, "{}", source);
+    }
+}
+
+#[test]
+fn test_strength_reduction() {
+    let factors = [-32768, -9, -8, -3, -1, 0, 1, 2, 3, 10, 16, 255, 1000, 30000];
+    for x in [-32768i16, -100, -9, -1, 0, 1, 7, 100, 32767] {
+        for c in factors {
+            // x comes from a call so that it isn't folded
+            let program = |op: &str| {
+                format!(
+                    "int id(int n) {{ return n; }}
+                     int main() {{ int x = id({}); return x {} ({}); }}",
+                    x, op, c
+                )
+            };
+            assert_eq!(run_c(&program("*")), x.wrapping_mul(c), "{} * {}", x, c);
+            if c != 0 {
+                assert_eq!(run_c(&program("/")), x.wrapping_div(c), "{} / {}", x, c);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_compile_to_words() {
+    // Data, calls and runtime routines, loaded and run without assembling
+    let source = r#"
+        char message[6] = "words";
+        int scale = 7;
+
+        int length(char *s) {
+            int n = 0;
+            while (s[n]) {
+                n++;
+            }
+            return n;
+        }
+
+        int main() {
+            return length(message) * scale / 3 + length("ab");
+        }
+    "#;
+    let compiled = lc3b_c_compiler::compile_to_words(source, &CompileOptions::default()).unwrap();
+    assert_eq!(compiled.origin, 0x3000);
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&compiled.words, compiled.origin);
+    let reason = computer.run(100_000);
+    assert!(computer.is_halted(), "{:?}", reason);
+    assert_eq!(computer.register(0) as i16, 13);
+}
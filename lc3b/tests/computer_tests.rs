@@ -1,4 +1,8 @@
-use lc3b::{BufferedIO, Computer, IO};
+use lc3b::{
+    BufferedIO, Computer, HaltReason, Interrupt, DDR, DSR, INTERRUPT_VECTOR_TABLE, IO, KBDR, KBSR,
+    MCR, PRIVILEGE_MODE_EXCEPTION, StopReason, TrapMode, USER_STACK_START,
+};
+use lc3b_isa::Privilege;
 
 #[test]
 fn test_trap_out() {
@@ -16,10 +20,10 @@ fn test_trap_out() {
     ];
     computer.load_program(&program, 0x3000);
 
-    computer.run(100).unwrap();
+    computer.run_count(100).unwrap();
 
     assert_eq!(computer.io().output(), "A");
-    assert!(computer.io().is_halted());
+    assert!(computer.is_halted());
 }
 
 #[test]
@@ -60,10 +64,10 @@ fn test_trap_puts() {
     ];
     computer.load_program(&program, 0x3000);
 
-    computer.run(100).unwrap();
+    computer.run_count(100).unwrap();
 
     assert_eq!(computer.io().output(), "Hi");
-    assert!(computer.io().is_halted());
+    assert!(computer.is_halted());
 }
 
 #[test]
@@ -78,26 +82,26 @@ fn test_trap_getc() {
     ];
     computer.load_program(&program, 0x3000);
 
-    computer.run(100).unwrap();
+    computer.run_count(100).unwrap();
 
     assert_eq!(computer.register(0), 'X' as u16);
-    assert!(computer.io().is_halted());
+    assert!(computer.is_halted());
 }
 
 #[test]
 fn test_trap_halt() {
     let mut computer = Computer::new(BufferedIO::new());
 
-    assert!(!computer.io().is_halted());
+    assert!(!computer.is_halted());
 
     // Program: just HALT
     let program = vec![0xF025]; // TRAP x25 (HALT)
     computer.load_program(&program, 0x3000);
 
-    let count = computer.run(100).unwrap();
+    let count = computer.run_count(100).unwrap();
 
     assert_eq!(count, 1);
-    assert!(computer.io().is_halted());
+    assert!(computer.is_halted());
 }
 
 #[test]
@@ -113,11 +117,11 @@ fn test_run_stops_at_halt() {
     ];
     computer.load_program(&program, 0x3000);
 
-    let count = computer.run(100).unwrap();
+    let count = computer.run_count(100).unwrap();
 
     assert_eq!(count, 4);
     assert_eq!(computer.register(1), 3);
-    assert!(computer.io().is_halted());
+    assert!(computer.is_halted());
 }
 
 #[test]
@@ -154,15 +158,15 @@ fn test_lea_with_puts() {
     ];
     computer.load_program(&program, 0x3000);
 
-    computer.run(100).unwrap();
+    computer.run_count(100).unwrap();
 
     assert_eq!(computer.io().output(), "Hi");
-    assert!(computer.io().is_halted());
+    assert!(computer.is_halted());
 }
 
 #[test]
 fn test_hello_world_sample() {
-    // Test that assembles and runs the Hello World sample from samples.ts
+    // Test that assembles and runs the Hello World sample among the bundled samples
     use lc3b_assembler::assemble;
 
     let code = r#"
@@ -187,8 +191,1516 @@ hello:
     let mut computer = Computer::new(BufferedIO::new());
     computer.load_program(&assembled.words, assembled.origin);
     
-    computer.run(100).unwrap();
+    computer.run_count(100).unwrap();
     
     assert_eq!(computer.io().output(), "Hi");
-    assert!(computer.io().is_halted());
+    assert!(computer.is_halted());
+}
+
+#[test]
+fn test_shift_instructions_execute_per_encoding() {
+    let mut computer = Computer::new(BufferedIO::new());
+
+    let program = vec![
+        0x1268,                  // ADD R1, R1, #8
+        0x1B7F,                  // ADD R5, R5, #-1 -> R5 = 0xFFFF
+        0b1101_010_001_0_0_0001, // LSHF  R2, R1, #1 -> 16
+        0b1101_011_001_1_0_0001, // RSHFL R3, R1, #1 -> 4
+        0b1101_100_101_1_1_0100, // RSHFA R4, R5, #4 -> 0xFFFF
+        0b1101_110_101_1_0_0100, // RSHFL R6, R5, #4 -> 0x0FFF
+        0xF025,                  // HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run_count(100).unwrap();
+
+    assert_eq!(computer.register(2), 16);
+    assert_eq!(computer.register(3), 4);
+    assert_eq!(computer.register(4), 0xFFFF);
+    assert_eq!(computer.register(6), 0x0FFF);
+}
+
+#[test]
+fn test_memory_mapped_keyboard_and_display() {
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.io_mut().push_input('k');
+
+    // Pointers to the device registers, reached through R1 = 0
+    computer.write_memory(0x0000, KBSR);
+    computer.write_memory(0x0002, KBDR);
+    computer.write_memory(0x0004, DDR);
+    computer.write_memory(0x0006, DSR);
+    computer.write_memory(0x0010, 0x4000);
+
+    let program = vec![
+        0b1010_010_001_000000, // LDI R2, R1, #0 -> KBSR
+        0b1010_011_001_000001, // LDI R3, R1, #1 -> KBDR
+        0b1011_011_001_000010, // STI R3, R1, #2 -> DDR
+        0b1010_100_001_000000, // LDI R4, R1, #0 -> KBSR, now empty
+        0b1010_101_001_000011, // LDI R5, R1, #3 -> DSR
+        0b0110_110_001_001000, // LDR R6, R1, #8 -> x4000
+        0b1011_110_001_000000, // STI R6, R1, #0 -> enable keyboard interrupts
+        0b1010_111_001_000000, // LDI R7, R1, #0 -> KBSR
+        0xF025,                // HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run_count(100).unwrap();
+
+    assert_eq!(computer.register(2), 0x8000);
+    assert_eq!(computer.register(3), 'k' as u16);
+    assert_eq!(computer.io().output(), "k");
+    assert_eq!(computer.register(4), 0x0000);
+    assert_eq!(computer.register(5), 0x8000);
+    assert_eq!(computer.register(7), 0x4000);
+    // Device registers are not backed by memory
+    assert_eq!(computer.read_memory(KBSR), 0);
+}
+
+#[test]
+fn test_keyboard_interrupt_switches_to_supervisor_stack() {
+    let mut computer = Computer::new(BufferedIO::new());
+
+    computer.write_memory(0x0000, KBSR);
+    computer.write_memory(0x0010, 0x4000);
+    // Keyboard vector x80 -> handler at x5000, which just halts
+    computer.write_memory(Interrupt::KEYBOARD.table_entry(), 0x5000);
+    computer.write_memory(0x5000, 0xF025);
+
+    let program = vec![
+        0b0110_101_001_001000, // LDR R5, R1, #8 -> x4000
+        0b1011_101_001_000000, // STI R5, R1, #0 -> enable keyboard interrupts
+        0b0000_111_111111111,  // BRnzp #-1 (spin)
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.io_mut().push_input('a');
+    computer.run_count(100).unwrap();
+
+    assert!(computer.is_halted());
+    assert_eq!(computer.psr().privilege(), Privilege::Supervisor);
+    assert_eq!(computer.psr().priority(), 4);
+    assert_eq!(computer.register(6), 0x2FFC);
+    assert_eq!(computer.read_memory(0x2FFE), 0x8001); // user mode, P set
+    assert_eq!(computer.read_memory(0x2FFC), 0x3002);
+}
+
+#[test]
+fn test_raised_interrupts_respect_priority() {
+    let mut computer = Computer::new(BufferedIO::new());
+    assert!(Interrupt::new(0x01, 0).is_err());
+
+    let low = Interrupt::new(0x01, 2).unwrap();
+    let high = Interrupt::new(0x02, 5).unwrap();
+    computer.write_memory(low.table_entry(), 0x5000);
+    computer.write_memory(high.table_entry(), 0x6000);
+    computer.write_memory(0x6000, 0xF025); // HALT
+    computer.load_program(&[0x0000], 0x3000);
+
+    computer.raise_interrupt(low);
+    computer.raise_interrupt(high);
+    computer.run_count(10).unwrap();
+
+    assert!(computer.is_halted());
+    assert_eq!(computer.psr().priority(), 5);
+    // The lower-priority request cannot preempt the running handler
+    assert_eq!(computer.pending_interrupts(), &[low]);
+}
+
+#[test]
+fn test_rti_returns_from_keyboard_interrupt() {
+    let mut computer = Computer::new(BufferedIO::new());
+
+    computer.write_memory(0x0000, KBSR);
+    computer.write_memory(0x0002, KBDR);
+    computer.write_memory(0x0010, 0x4000);
+    computer.write_memory(Interrupt::KEYBOARD.table_entry(), 0x5000);
+    computer.load_program(
+        &[
+            0b1010_000_001_000001, // LDI R0, R1, #1 -> read KBDR
+            0x8000,                // RTI
+        ],
+        0x5000,
+    );
+
+    let program = vec![
+        0b0110_101_001_001000, // LDR R5, R1, #8 -> x4000
+        0b1011_101_001_000000, // STI R5, R1, #0 -> enable keyboard interrupts
+        0b0001_010_010_1_00001, // ADD R2, R2, #1
+        0xF025,                // HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.io_mut().push_input('a');
+    computer.run_count(100).unwrap();
+
+    assert!(computer.is_halted());
+    assert_eq!(computer.register(0), 'a' as u16);
+    assert_eq!(computer.register(2), 1);
+    assert!(computer.psr().is_user());
+    assert_eq!(computer.psr().priority(), 0);
+    assert_eq!(computer.register(6), 0);
+    assert_eq!(computer.supervisor_stack_pointer(), 0x3000);
+}
+
+#[test]
+fn test_user_mode_rti_raises_privilege_exception() {
+    let mut computer = Computer::new(BufferedIO::new());
+    let entry = INTERRUPT_VECTOR_TABLE + ((PRIVILEGE_MODE_EXCEPTION as u16) << 1);
+    computer.write_memory(entry, 0x5000);
+    computer.write_memory(0x5000, 0xF025); // HALT
+
+    computer.load_program(&[0x8000], 0x3000); // RTI
+    computer.run_count(10).unwrap();
+
+    assert!(computer.is_halted());
+    assert!(computer.psr().is_supervisor());
+    assert_eq!(computer.read_memory(0x2FFC), 0x3001);
+    assert_eq!(computer.user_stack_pointer(), 0);
+}
+
+#[test]
+fn test_exceptions_vector_faults_when_enabled() {
+    use lc3b::{
+        Error, Exceptions, Protection, ACCESS_CONTROL_VIOLATION_EXCEPTION,
+        ILLEGAL_OPCODE_EXCEPTION,
+    };
+
+    let setup = || {
+        let mut computer = Computer::new(BufferedIO::new());
+        let acv = INTERRUPT_VECTOR_TABLE + ((ACCESS_CONTROL_VIOLATION_EXCEPTION as u16) << 1);
+        let illegal = INTERRUPT_VECTOR_TABLE + ((ILLEGAL_OPCODE_EXCEPTION as u16) << 1);
+        computer.write_memory(acv, 0x5000);
+        computer.write_memory(illegal, 0x5100);
+        computer.write_memory(0x5000, 0xF025); // HALT
+        computer.write_memory(0x5100, 0xF025); // HALT
+        computer.protect(0x4000..=0x40FF, Protection::READ_ONLY);
+        computer.set_register(1, 0x4000);
+        computer
+    };
+
+    // STW R0, R1, #0 into read-only memory: an error by default
+    let mut computer = setup();
+    computer.load_program(&[0b0111_000_001_000000], 0x3000);
+    assert_eq!(
+        computer.run(10),
+        StopReason::Error(Error::WriteProtected {
+            pc: 0x3000,
+            addr: 0x4000
+        })
+    );
+
+    // ...and an access control violation the program handles when enabled
+    let mut computer = setup();
+    computer.set_exceptions(Exceptions {
+        access_violation: true,
+        ..Exceptions::default()
+    });
+    computer.load_program(&[0b0111_000_001_000000], 0x3000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert!(computer.psr().is_supervisor());
+    assert_eq!(computer.read_memory(0x2FFC), 0x3001);
+    assert_eq!(computer.read_memory(0x4000), 0);
+
+    // With the Appendix C opcode set, LDI is an illegal opcode
+    let mut computer = setup();
+    computer.set_exceptions(Exceptions {
+        strict_opcodes: true,
+        ..Exceptions::default()
+    });
+    computer.load_program(&[0b1010_000_001_000000], 0x3000);
+    assert!(matches!(
+        computer.run(10),
+        StopReason::Error(Error::InstructionDecode { address: 0x3000, .. })
+    ));
+
+    let mut computer = setup();
+    computer.set_exceptions(Exceptions::all());
+    computer.load_program(&[0b1010_000_001_000000], 0x3000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.read_memory(0x2FFC), 0x3001);
+    assert_eq!(computer.register(0), 0);
+}
+
+#[test]
+fn test_os_image_services_traps_through_vector_table() {
+    use lc3b_assembler::assemble;
+
+    let code = r#"
+        .ORIG x3000
+        LEA R0, MSG
+        PUTS
+        GETC
+        OUT
+        IN
+        HALT
+        .BLKW #1                ; keep MSG an even distance from the LEA
+MSG:    .STRINGZ "Hi "
+        .END
+"#;
+    let assembled = assemble(code).unwrap();
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_os().unwrap();
+    computer.load_program(&assembled.words, assembled.origin);
+    computer.io_mut().push_input_str("zq");
+    computer.run_count(10_000).unwrap();
+
+    assert!(computer.is_halted());
+    assert_eq!(computer.io().output(), "Hi zInput a character> q");
+    assert_eq!(computer.register(0), 'q' as u16);
+    // Service routines restore the stack they borrowed
+    assert_eq!(computer.register(6), USER_STACK_START);
+}
+
+#[test]
+fn test_vector_table_trap_saves_return_address() {
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_os().unwrap();
+    assert_eq!(computer.trap_mode(), TrapMode::VectorTable);
+
+    // Custom service routine at an unused vector
+    computer.write_memory(0x40 << 1, 0x5000);
+    computer.load_program(
+        &[
+            0b0001_001_001_1_00101, // ADD R1, R1, #5
+            0xC1C0,                 // RET
+        ],
+        0x5000,
+    );
+    computer.load_program(
+        &[
+            0xF040,                 // TRAP x40
+            0b0001_010_111_1_00000, // ADD R2, R7, #0
+            0xF025,                 // HALT
+        ],
+        0x3000,
+    );
+    computer.run_count(100).unwrap();
+
+    assert!(computer.is_halted());
+    assert_eq!(computer.register(1), 5);
+    assert_eq!(computer.register(2), 0x3001);
+}
+
+#[test]
+fn test_custom_trap_handler_runs_on_host() {
+    use std::{cell::Cell, rc::Rc};
+
+    let mut computer = Computer::new(BufferedIO::new());
+    let calls = Rc::new(Cell::new(0));
+    let seen = calls.clone();
+    // x30: R0 <- R0 * 2, a host-side "system call"
+    computer.register_trap_handler(0x30, move |computer| {
+        seen.set(seen.get() + 1);
+        let value = computer.register(0);
+        computer.set_register(0, value * 2);
+    });
+
+    let program = vec![
+        0b0001_000_000_1_00011, // ADD R0, R0, #3
+        0xF030,                 // TRAP x30
+        0xF030,                 // TRAP x30
+        0xF025,                 // HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run_count(100).unwrap();
+
+    assert!(computer.is_halted());
+    assert_eq!(computer.register(0), 12);
+    assert_eq!(calls.get(), 2);
+    assert!(computer.unregister_trap_handler(0x30));
+    assert!(!computer.unregister_trap_handler(0x30));
+}
+
+#[test]
+fn test_custom_trap_handler_overrides_builtin_vector() {
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.register_trap_handler(0x21, |computer| computer.io_mut().write_str("<out>"));
+    computer.load_program(&[0xF021, 0xF025], 0x3000);
+    computer.run_count(10).unwrap();
+
+    assert_eq!(computer.io().output(), "<out>");
+}
+
+#[test]
+fn test_access_checks_reject_odd_and_system_addresses() {
+    use lc3b::{AccessChecks, Error};
+
+    // LDW R0, R1, #0 with R1 = 1
+    let program = vec![0b0001_001_001_1_00001, 0b0110_000_001_000000, 0xF025];
+
+    let mut permissive = Computer::new(BufferedIO::new());
+    permissive.load_program(&program, 0x3000);
+    permissive.run_count(10).unwrap();
+    assert!(permissive.is_halted());
+
+    let mut aligned = Computer::new(BufferedIO::new());
+    aligned.set_access_checks(AccessChecks {
+        alignment: true,
+        ..AccessChecks::default()
+    });
+    aligned.load_program(&program, 0x3000);
+    assert!(matches!(aligned.run(10), StopReason::Error(Error::AlignmentError(_))));
+    // The faulting instruction did not retire
+    assert_eq!(aligned.program_counter(), 0x3001);
+
+    // User-mode LDW from x0000 touches system space
+    let mut protected = Computer::new(BufferedIO::new());
+    protected.set_access_checks(AccessChecks::strict());
+    protected.load_program(&[0b0110_000_001_000000], 0x3000);
+    assert!(matches!(protected.run(10), StopReason::Error(Error::InvalidMemoryAccess(0x0000))));
+}
+
+#[test]
+fn test_access_checks_apply_to_byte_accesses_by_word() {
+    use lc3b::{AccessChecks, Error};
+
+    let checks = AccessChecks {
+        system_space: true,
+        ..AccessChecks::default()
+    };
+    // LDB R0, R1, #0 and STB R0, R1, #0
+    for instruction in [0b0010_000_001_000000, 0b0011_000_001_000000] {
+        // Byte x3000 is in word x1800, which is system space
+        let mut computer = Computer::new(BufferedIO::new());
+        computer.set_access_checks(checks);
+        computer.load_program(&[instruction, 0xF025], 0x3000);
+        computer.set_register(1, 0x3000);
+        assert!(matches!(
+            computer.run(10),
+            StopReason::Error(Error::InvalidMemoryAccess(0x1800))
+        ));
+
+        // Byte x6001 is in word x3000, the program's own
+        let mut computer = Computer::new(BufferedIO::new());
+        computer.set_access_checks(checks);
+        computer.load_program(&[instruction, 0xF025], 0x3000);
+        computer.set_register(1, 0x6001);
+        computer.run_count(10).unwrap();
+        assert!(computer.is_halted());
+    }
+}
+
+#[test]
+fn test_uninitialized_reads() {
+    use lc3b::{AccessChecks, Error, Observer, UninitializedRead};
+
+    #[derive(Default)]
+    struct Reads(Vec<(u16, UninitializedRead)>);
+    impl Observer for Reads {
+        fn on_uninitialized_read(&mut self, pc: u16, read: UninitializedRead) {
+            self.0.push((pc, read));
+        }
+    }
+
+    let program = vec![
+        0b0001_000_001_1_00001, // x3000 ADD R0, R1, #1
+        0b0110_010_011_000000,  // x3001 LDW R2, R3, #0
+        0xF025,                 // x3002 HALT
+    ];
+
+    // Reported but allowed by default
+    let mut computer = Computer::with_observer(BufferedIO::new(), Reads::default());
+    computer.load_program(&program, 0x3000);
+    computer.set_register(3, 0x4000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(
+        computer.observer().0,
+        vec![
+            (0x3000, UninitializedRead::Register(1)),
+            (0x3001, UninitializedRead::Memory(0x4000)),
+        ]
+    );
+
+    let checks = AccessChecks {
+        uninitialized: true,
+        ..AccessChecks::default()
+    };
+    let mut strict = Computer::new(BufferedIO::new());
+    strict.set_access_checks(checks);
+    strict.load_program(&program, 0x3000);
+    strict.set_register(3, 0x4000);
+    assert_eq!(
+        strict.run(10),
+        StopReason::Error(Error::UninitializedRead {
+            pc: 0x3000,
+            read: UninitializedRead::Register(1),
+        })
+    );
+
+    strict.set_register(1, 0);
+    strict.write_memory(0x4000, 7);
+    assert_eq!(strict.run(10), StopReason::Halted);
+    assert_eq!(strict.register(2), 7);
+}
+
+#[test]
+fn test_uninitialized_read_retires_the_instruction() {
+    use lc3b::{AccessChecks, Error, UninitializedRead};
+
+    for fast in [false, true] {
+        let mut computer = Computer::new(BufferedIO::new());
+        computer.set_access_checks(AccessChecks {
+            uninitialized: true,
+            ..AccessChecks::default()
+        });
+        computer.load_program(&[0b0100_0_00_011_000000], 0x3000); // JSRR R3
+        computer.write_memory(0x0000, 0xF025); // HALT
+        let run = |computer: &mut Computer<BufferedIO>| match fast {
+            true => computer.run_fast(10),
+            false => computer.run(10),
+        };
+        assert_eq!(
+            run(&mut computer),
+            StopReason::Error(Error::UninitializedRead {
+                pc: 0x3000,
+                read: UninitializedRead::Register(3),
+            })
+        );
+        // The call went through to R3's value, x0000
+        assert_eq!(computer.program_counter(), 0x0000);
+        assert_eq!(computer.register(7), 0x3001);
+        assert_eq!(computer.instructions_retired(), 1);
+
+        // Resuming carries on at the call target
+        assert_eq!(run(&mut computer), StopReason::Halted);
+        assert_eq!(computer.register(7), 0x3001);
+    }
+}
+
+#[test]
+fn test_restore_keeps_uninitialized_tracking() {
+    use lc3b::{AccessChecks, Error, UninitializedRead};
+
+    let checks = AccessChecks {
+        uninitialized: true,
+        ..AccessChecks::default()
+    };
+    let program = vec![
+        0b0001_100_001_1_00000, // x3000 ADD R4, R1, #0
+        0b0111_001_001_000001,  // x3001 STW R1, R1, #1 -> mem[x4002]
+        0xF025,                 // x3002 HALT
+        0b0110_011_001_000001,  // x3003 LDW R3, R1, #1
+        0b0001_000_100_1_00000, // x3004 ADD R0, R4, #0
+        0xF025,                 // x3005 HALT
+    ];
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.set_access_checks(checks);
+    computer.load_program(&program, 0x3000);
+    computer.set_register(1, 0x4000);
+    computer.write_memory(0x4000, 5);
+
+    // R1 was written; x4002 shares a stored page with x4000 but was not
+    let mut fresh = Computer::new(BufferedIO::new());
+    fresh.set_access_checks(checks);
+    fresh.restore(&computer.snapshot());
+    fresh.set_pc(0x3003);
+    assert_eq!(
+        fresh.run(10),
+        StopReason::Error(Error::UninitializedRead {
+            pc: 0x3003,
+            read: UninitializedRead::Memory(0x4002),
+        })
+    );
+
+    // Undoing the first writes forgets them again
+    computer.enable_journal(10);
+    assert_eq!(computer.run(2), StopReason::MaxInstructions);
+    assert_eq!(computer.step_back(2), 2);
+    computer.set_pc(0x3003);
+    assert_eq!(
+        computer.run(10),
+        StopReason::Error(Error::UninitializedRead {
+            pc: 0x3003,
+            read: UninitializedRead::Memory(0x4002),
+        })
+    );
+    assert_eq!(
+        computer.run(10),
+        StopReason::Error(Error::UninitializedRead {
+            pc: 0x3004,
+            read: UninitializedRead::Register(4),
+        })
+    );
+    assert_eq!(computer.run(10), StopReason::Halted);
+}
+
+#[test]
+fn test_memory_protection() {
+    use lc3b::{Error, Protection};
+
+    let program = vec![
+        0b0001_001_001_1_00001, // x3000 ADD R1, R1, #1
+        0b0111_001_010_000000,  // x3001 STW R1, R2, #0 -> mem[R2]
+        0xF025,                 // x3002 HALT
+    ];
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program, 0x3000);
+    computer.protect(0x3000..=0x3002, Protection::READ_ONLY);
+    computer.set_register(2, 0x3000);
+    assert_eq!(
+        computer.run(10),
+        StopReason::Error(Error::WriteProtected {
+            pc: 0x3001,
+            addr: 0x3000
+        })
+    );
+    assert_eq!(computer.read_memory(0x3000), program[0]);
+
+    // Data may be written but not executed
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program, 0x3000);
+    computer.protect(0x3000..=0x3002, Protection::READ_ONLY);
+    computer.protect(0x4000..=0x4FFF, Protection::NO_EXECUTE);
+    computer.set_register(2, 0x4000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.read_memory(0x4000), 1);
+    assert_eq!(
+        computer.protection_at(0x3001),
+        Protection {
+            read_only: true,
+            no_execute: false
+        }
+    );
+
+    let mut fetching = Computer::new(BufferedIO::new());
+    fetching.protect(0x4000..=0x4FFF, Protection::NO_EXECUTE);
+    fetching.load_program(&[0x0000], 0x4000);
+    assert_eq!(fetching.run(10), StopReason::Error(Error::ExecuteProtected(0x4000)));
+
+    fetching.clear_protections();
+    assert!(fetching.protections().is_empty());
+}
+
+#[test]
+fn test_loop_detection() {
+    let spin = vec![
+        0b0001_000_000_1_00001, // x3000 ADD R0, R0, #1
+        0b0000_111_111111111,   // x3001 BRnzp x3001
+    ];
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&spin, 0x3000);
+    assert_eq!(computer.run(100), StopReason::MaxInstructions);
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.enable_loop_detection(0);
+    computer.load_program(&spin, 0x3000);
+    assert_eq!(computer.run(100), StopReason::InfiniteLoop(0x3001));
+    assert_eq!(computer.instructions_retired(), 1);
+
+    // A keyboard interrupt could still break the loop
+    computer.set_register(1, 0xFE00);
+    computer.set_register(2, 0x4000);
+    computer.load_program(
+        &[
+            0b0111_010_001_000000, // x3000 STW R2, R1, #0 -> KBSR IE
+            0b0000_111_111111111,  // x3001 BRnzp x3001
+        ],
+        0x3000,
+    );
+    assert_eq!(computer.run(100), StopReason::MaxInstructions);
+
+    // Busy work that changes nothing
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.enable_loop_detection(20);
+    computer.load_program(
+        &[
+            0b0001_000_000_1_00000, // x3000 ADD R0, R0, #0
+            0b0000_111_111111110,   // x3001 BRnzp x3000
+        ],
+        0x3000,
+    );
+    assert!(matches!(computer.run(1000), StopReason::NoProgress(_)));
+    assert!(computer.instructions_retired() < 30);
+
+    computer.disable_loop_detection();
+    assert_eq!(computer.run(100), StopReason::MaxInstructions);
+}
+
+#[test]
+fn test_run_fast_matches_run() {
+    let code = r#"
+.ORIG x3000
+        AND R1, R1, #0
+        ADD R1, R1, #15
+loop:   ADD R2, R2, R1
+        ADD R1, R1, #-1
+        BRp loop
+        LEA R0, msg
+        PUTS
+        HALT
+msg:
+.STRINGZ "done"
+.END
+"#;
+    let program = lc3b_assembler::assemble(code).unwrap();
+    let machine = || {
+        let mut computer = Computer::new(BufferedIO::new());
+        computer.load_os().unwrap();
+        computer.set_register(2, 0);
+        computer.load_program(&program.words, program.origin);
+        computer
+    };
+
+    let mut slow = machine();
+    let mut fast = machine();
+    assert_eq!(slow.run(10_000), StopReason::Halted);
+    assert_eq!(fast.run_fast(10_000), StopReason::Halted);
+    assert_eq!(fast.io().output(), "done");
+    assert_eq!(fast.registers(), slow.registers());
+    assert_eq!(fast.cycles(), slow.cycles());
+    assert_eq!(fast.instructions_retired(), slow.instructions_retired());
+
+    let mut limited = machine();
+    assert_eq!(limited.run_fast(10), StopReason::MaxInstructions);
+    assert_eq!(limited.instructions_retired(), 10);
+
+    // Breakpoints still stop it
+    let mut stopped = machine();
+    stopped.add_breakpoint(program.origin + 4);
+    assert_eq!(stopped.run_fast(10_000), StopReason::Breakpoint(program.origin + 4));
+}
+
+#[test]
+fn test_fuel_limits_runs_across_calls() {
+    let mut computer = Computer::new(BufferedIO::new());
+    // ADD R1, R1, #1; BRnzp back to it
+    computer.load_program(&[0b0001_001_001_1_00001, 0b0000_111_111111110], 0x3000);
+    computer.set_fuel(Some(25));
+
+    // Time slices of 10 share the overall budget of 25
+    assert_eq!(computer.run(10), StopReason::MaxInstructions);
+    assert_eq!(computer.run(10), StopReason::MaxInstructions);
+    assert_eq!(computer.fuel(), Some(5));
+    assert_eq!(computer.run(10), StopReason::OutOfFuel);
+    assert_eq!(computer.run(10), StopReason::OutOfFuel);
+    assert_eq!(computer.instructions_executed(), 25);
+
+    computer.add_fuel(4);
+    assert_eq!(computer.run_fast(10), StopReason::OutOfFuel);
+    assert_eq!(computer.instructions_executed(), 29);
+
+    // step_back rewinds the machine but not the executed count
+    computer.enable_journal(8);
+    computer.set_fuel(None);
+    assert_eq!(computer.run(3), StopReason::MaxInstructions);
+    assert_eq!(computer.step_back(3), 3);
+    assert_eq!(computer.instructions_retired(), 29);
+    assert_eq!(computer.instructions_executed(), 32);
+}
+
+#[test]
+fn test_reset_reruns_program() {
+    let program = vec![
+        0b0001_000_000_1_00011, // x3000 ADD R0, R0, #3
+        0b0111_000_001_000000,  // x3001 STW R0, R1, #0 -> mem[x4000]
+        0xF025,                 // x3002 HALT
+    ];
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program, 0x3000);
+    computer.set_register(1, 0x4000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.read_memory(0x4000), 3);
+
+    computer.reset();
+    assert!(!computer.is_halted());
+    assert_eq!(computer.program_counter(), 0x3000);
+    assert_eq!(computer.registers(), &[0; 8]);
+    assert_eq!(computer.condition(), lc3b_isa::Condition::default());
+    assert_eq!(computer.instructions_retired(), 0);
+
+    computer.set_register(1, 0x4000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.register(0), 3);
+
+    computer.reset_memory();
+    assert_eq!(computer.read_memory(0x3000), 0);
+    assert_eq!(computer.read_memory(0x4000), 0);
+}
+
+#[test]
+fn test_load_assembled_uses_origin() {
+    let program = lc3b_assembler::assemble(
+        r#"
+.ORIG x4000
+start:  ADD R0, R0, #2
+        HALT
+.END
+"#,
+    )
+    .unwrap();
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_assembled(&program, true);
+    assert_eq!(computer.program_counter(), 0x4000);
+    assert_eq!(computer.program_ranges(), &[0x4000..=0x4001]);
+    assert!(computer.is_program_address(0x4001));
+    assert!(!computer.is_program_address(0x3000));
+    assert_eq!(computer.address_of("start"), Some(0x4000));
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.register(0), 2);
+
+    let mut bare = Computer::new(BufferedIO::new());
+    bare.load_assembled(&program, false);
+    assert_eq!(bare.address_of("start"), None);
+    bare.reset_memory();
+    assert!(bare.program_ranges().is_empty());
+}
+
+#[test]
+fn test_memory_inspection() {
+    let program = vec![
+        0b0001_000_000_1_00101, // x3000 ADD R0, R0, #5
+        0b0111_000_001_000000,  // x3001 STW R0, R1, #0 -> mem[x4000]
+        0xF025,                 // x3002 HALT
+    ];
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program, 0x3000);
+    computer.set_register(1, 0x4000);
+
+    assert_eq!(computer.read_memory_range(0x3000, 3), &program[..]);
+    assert_eq!(computer.read_memory_range(0xFFFE, 10).len(), 2);
+    assert_eq!(
+        computer.hexdump(0x3000, 3),
+        "x3000: 1025 7040 F025                           %@%\n"
+    );
+
+    let before = computer.snapshot();
+    computer.run(10);
+    computer.write_memory(0x0100, 1);
+    assert_eq!(computer.diff_memory(&before), vec![0x0100, 0x4000]);
+}
+
+#[test]
+fn test_stack_view() {
+    use lc3b::StackEntry;
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.write_memory(0x3FFA, 7); // local
+    computer.write_memory(0x3FFC, 0x3003); // saved R7
+    computer.write_memory(0x3FFE, 0x4000); // caller's R5
+    computer.write_memory(0x4000, 9); // caller's frame
+    computer.set_register(6, 0x3FFA);
+    computer.set_register(5, 0x3FFC);
+
+    let entry = |addr, value, in_frame| StackEntry {
+        addr,
+        value,
+        in_frame,
+    };
+    assert_eq!(
+        computer.stack_view(4),
+        vec![
+            entry(0x3FFA, 7, true),
+            entry(0x3FFC, 0x3003, true),
+            entry(0x3FFE, 0x4000, true),
+            entry(0x4000, 9, false),
+        ]
+    );
+
+    computer.set_register(5, 0);
+    assert!(computer.stack_view(2).iter().all(|e| !e.in_frame));
+
+    computer.set_register(6, 0xFFFC);
+    assert_eq!(computer.stack_view(5).len(), 2);
+}
+
+#[test]
+fn test_state_setters() {
+    use lc3b_isa::{Condition, Psr};
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(
+        &[
+            0b0000_010_000000001,   // x3000 BRz x3002
+            0b0001_000_000_1_00001, // x3001 ADD R0, R0, #1
+            0xF025,                 // x3002 HALT
+        ],
+        0x3000,
+    );
+
+    let zero = Condition {
+        n: false,
+        z: true,
+        p: false,
+    };
+    computer.set_condition(zero);
+    assert_eq!(computer.condition(), zero);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.register(0), 0);
+
+    computer.reset();
+    computer.set_pc(0x3001);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.register(0), 1);
+
+    // Entering supervisor mode swaps in the supervisor stack
+    computer.set_register(6, 0xFE00 - 2);
+    computer.set_supervisor_stack_pointer(0x2000);
+    computer.set_psr(Psr::new(Privilege::Supervisor, 3, zero).unwrap());
+    assert_eq!(computer.register(6), 0x2000);
+    assert_eq!(computer.psr().priority(), 3);
+    computer.set_psr(Psr::user());
+    assert_eq!(computer.register(6), 0xFE00 - 2);
+    assert_eq!(computer.supervisor_stack_pointer(), 0x2000);
+}
+
+#[test]
+fn test_watch_expressions_log_changes() {
+    use lc3b::{Error, WatchChange};
+
+    let code = r#"
+.ORIG x3000
+        LEA R1, count
+        ADD R2, R2, #2
+loop:   LDW R3, R1, #0
+        ADD R3, R3, #1
+        STW R3, R1, #0
+        ADD R2, R2, #-1
+        BRp loop
+        HALT
+        .FILL #0        ; LEA targets must be an even distance away
+count:  .FILL #0
+.END
+"#;
+    let program = lc3b_assembler::assemble(code).unwrap();
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_assembled(&program, true);
+
+    let count = program.symbols.address_of("count").unwrap();
+    assert_eq!(computer.evaluate("[count]"), Ok(0));
+    assert_eq!(computer.evaluate("count + 1"), Ok(count + 1));
+    assert_eq!(computer.evaluate("R2 - #1"), Ok(0xFFFF));
+    assert!(matches!(computer.evaluate("R2 +"), Err(Error::InvalidExpression(_))));
+    assert_eq!(computer.evaluate("missing"), Err(Error::UndefinedLabel("missing".into())));
+
+    let memory = computer.add_watch("[count]").unwrap();
+    let register = computer.add_watch("R2 + 2").unwrap();
+    assert_eq!(computer.run(100), StopReason::Halted);
+
+    let changes = computer.take_watch_changes();
+    let memory_changes: Vec<&WatchChange> =
+        changes.iter().filter(|change| change.id == memory).collect();
+    assert_eq!(memory_changes.len(), 2);
+    assert_eq!(memory_changes[1].pc, 0x3004);
+    assert_eq!((memory_changes[1].old, memory_changes[1].new), (Some(1), Some(2)));
+    assert_eq!(changes.iter().filter(|change| change.id == register).count(), 3);
+    assert_eq!(computer.watches()[1], (register, "R2 + 2", Some(2)));
+
+    assert!(computer.remove_watch(memory));
+    assert!(!computer.remove_watch(memory));
+}
+
+#[test]
+fn test_stop_predicate_and_observer_requests() {
+    use lc3b::Observer;
+
+    // Print '<' forever
+    let words = vec![
+        0b0101_000_000_1_00000, // x3000 AND R0, R0, #0
+        0b0001_000_000_1_01111, // x3001 ADD R0, R0, #15
+        0b0001_000_000_1_01111, // x3002 ADD R0, R0, #15
+        0b0001_000_000_0_00000, // x3003 ADD R0, R0, R0 -> 60
+        0xF021,                 // x3004 OUT
+        0b0000_111_111111010,   // x3005 BRnzp x3000
+    ];
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&words, 0x3000);
+    computer.set_stop_predicate(|c| c.io().output().len() >= 3);
+    assert_eq!(computer.run(1000), StopReason::StopRequested);
+    assert_eq!(computer.io().output(), "<<<");
+    assert_eq!(computer.program_counter(), 0x3005);
+
+    computer.clear_stop_predicate();
+    assert_eq!(computer.run(10), StopReason::MaxInstructions);
+
+    struct StopAfterOutput(usize);
+    impl Observer for StopAfterOutput {
+        fn on_io_output(&mut self, _ch: char) {
+            self.0 += 1;
+        }
+        fn stop_requested(&mut self) -> bool {
+            self.0 == 2
+        }
+    }
+    let mut computer = Computer::with_observer(BufferedIO::new(), StopAfterOutput(0));
+    computer.load_program(&words, 0x3000);
+    assert_eq!(computer.run(1000), StopReason::StopRequested);
+    assert_eq!(computer.io().output(), "<<");
+}
+
+#[test]
+fn test_halt_reasons() {
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&[0xF025], 0x3000); // HALT
+    assert_eq!(computer.halt_reason(), None);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.halt_reason(), Some(HaltReason::Trap));
+
+    // Stepping back over HALT undoes it
+    computer.reset();
+    computer.enable_journal(4);
+    computer.run(10);
+    assert_eq!(computer.step_back(1), 1);
+    assert!(!computer.is_halted());
+
+    // Clearing the MCR clock enable bit, as the OS HALT routine does
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.write_memory(0x0000, MCR);
+    let program = vec![
+        0b0101_000_000_1_00000, // AND R0, R0, #0
+        0b1011_000_001_000000,  // STI R0, R1, #0 -> MCR
+        0b0001_000_000_1_00001, // ADD R0, R0, #1
+    ];
+    computer.load_program(&program, 0x3000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.halt_reason(), Some(HaltReason::MachineControl));
+    assert_eq!(computer.program_counter(), 0x3002);
+
+    // The host can halt and resume
+    computer.clear_halt();
+    computer.next_instruction().unwrap();
+    assert_eq!(computer.register(0), 1);
+    computer.halt();
+    assert_eq!(computer.halt_reason(), Some(HaltReason::Host));
+    assert_eq!(computer.run(10), StopReason::Halted);
+}
+
+#[test]
+fn test_load_object() {
+    let mut computer = Computer::new(BufferedIO::new());
+    // Origin x3000, then ADD R1, R1, #5 and HALT
+    let image = [0x30, 0x00, 0x12, 0x65, 0xF0, 0x25];
+    assert_eq!(computer.load_object(&image).unwrap(), 0x3000);
+    assert_eq!(computer.program_counter(), 0x3000);
+    assert_eq!(computer.read_memory(0x3000), 0x1265);
+    computer.run(10);
+    assert_eq!(computer.registers()[1], 5);
+
+    assert!(computer.load_object(&[0x30, 0x00, 0x12]).is_err());
+    assert!(computer.load_object(&[]).is_err());
+}
+
+#[test]
+fn test_samples_build() {
+    use lc3b::{SampleLanguage, SAMPLES};
+
+    for sample in SAMPLES {
+        match sample.language {
+            SampleLanguage::Assembly => {
+                let program = lc3b_assembler::assemble(sample.source)
+                    .unwrap_or_else(|e| panic!("{}: {}", sample.title, e));
+                let mut computer = Computer::new(BufferedIO::new());
+                computer.load_assembled(&program, true);
+                let reason = computer.run(10_000);
+                assert!(!matches!(reason, StopReason::Error(_)), "{}", sample.title);
+            }
+            SampleLanguage::C => {
+                let options = lc3b_c_compiler::CompileOptions::default();
+                let assembly = lc3b_c_compiler::compile(sample.source, &options)
+                    .unwrap_or_else(|e| panic!("{}: {}", sample.title, e));
+                lc3b_assembler::assemble(&assembly)
+                    .unwrap_or_else(|e| panic!("{}: {}", sample.title, e));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_breakpoints_stop_and_resume() {
+    let mut computer = Computer::new(BufferedIO::new());
+    let program = vec![
+        0b0001_000_000_1_00001, // x3000 ADD R0, R0, #1
+        0b0001_000_000_1_00001, // x3001 ADD R0, R0, #1
+        0b0000_111_111111101,   // x3002 BRnzp x3000
+    ];
+    computer.load_program(&program, 0x3000);
+
+    assert!(computer.add_breakpoint(0x3002));
+    assert!(!computer.add_breakpoint(0x3002));
+    assert_eq!(computer.run(100), StopReason::Breakpoint(0x3002));
+    assert_eq!(computer.register(0), 2);
+
+    // Resuming steps past the breakpoint we are sitting on
+    assert_eq!(computer.run(100), StopReason::Breakpoint(0x3002));
+    assert_eq!(computer.register(0), 4);
+
+    assert!(computer.remove_breakpoint(0x3002));
+    computer.add_conditional_breakpoint(0x3001, |c| c.register(0) == 9);
+    assert_eq!(computer.list_breakpoints(), vec![0x3001]);
+    assert_eq!(computer.run(100), StopReason::Breakpoint(0x3001));
+    assert_eq!(computer.register(0), 9);
+
+    computer.clear_breakpoints();
+    assert_eq!(computer.run(5), StopReason::MaxInstructions);
+}
+
+#[test]
+fn test_run_reports_stop_reason() {
+    let mut computer = Computer::new(BufferedIO::new());
+    let program = vec![
+        0b0101_001_001_1_00000, // x3000 AND R1, R1, #0
+        0b0001_000_000_1_00101, // x3001 ADD R0, R0, #5
+        0b0111_000_001_001000,  // x3002 STW R0, R1, #8 -> mem[x0010]
+        0b0111_000_001_001001,  // x3003 STW R0, R1, #9 -> mem[x0012]
+        0xF025,                 // x3004 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+
+    assert!(computer.add_watchpoint(0x0012));
+    assert_eq!(computer.list_watchpoints(), vec![0x0012]);
+    assert_eq!(computer.run(100), StopReason::Watchpoint(0x0012));
+    // The writing instruction has retired
+    assert_eq!(computer.program_counter(), 0x3004);
+    assert_eq!(computer.read_memory(0x0012), 5);
+
+    assert_eq!(computer.run(100), StopReason::Halted);
+    assert_eq!(computer.run(100), StopReason::Halted);
+
+    // A failed access stops with the error
+    let mut faulting = Computer::new(BufferedIO::new());
+    faulting.set_access_checks(lc3b::AccessChecks::strict());
+    faulting.load_program(&[0b0110_000_001_000000], 0x3000); // LDW R0, R1, #0
+    assert!(matches!(
+        faulting.run(10),
+        StopReason::Error(lc3b::Error::InvalidMemoryAccess(0x0000))
+    ));
+    assert!(faulting.run_count(10).is_err());
+}
+
+#[test]
+fn test_step_over_step_out_and_run_to() {
+    let program = vec![
+        0b0001_000_000_1_00001, // x3000 ADD R0, R0, #1
+        0x4802,                 // x3001 JSR x3006
+        0b0001_000_000_1_00001, // x3002 ADD R0, R0, #1
+        0xF025,                 // x3003 HALT
+        0x0000,                 // x3004
+        0x0000,                 // x3005
+        0b0001_001_001_1_00001, // x3006 ADD R1, R1, #1
+        0b0001_001_001_1_00001, // x3007 ADD R1, R1, #1
+        0xC1C0,                 // x3008 RET
+    ];
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program, 0x3000);
+    assert_eq!(computer.step_over(100), StopReason::Reached(0x3001));
+    assert_eq!(computer.step_over(100), StopReason::Reached(0x3002));
+    assert_eq!(computer.register(1), 2);
+    assert_eq!(computer.call_depth(), 0);
+    assert_eq!(computer.step_over(100), StopReason::Reached(0x3003));
+    assert_eq!(computer.step_over(100), StopReason::Halted);
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program, 0x3000);
+    assert_eq!(computer.run_to(0x3007, 100), StopReason::Reached(0x3007));
+    assert_eq!(computer.call_depth(), 1);
+    assert_eq!(computer.step_out(100), StopReason::Reached(0x3002));
+    assert_eq!(computer.register(1), 2);
+    assert_eq!(computer.call_depth(), 0);
+
+    // Breakpoints inside the callee still stop a step over
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program, 0x3000);
+    computer.add_breakpoint(0x3007);
+    computer.step_over(100);
+    assert_eq!(computer.step_over(100), StopReason::Breakpoint(0x3007));
+}
+
+#[test]
+fn test_step_back_rewinds_registers_memory_and_pc() {
+    let mut computer = Computer::new(BufferedIO::new());
+    let program = vec![
+        0b0001_000_000_1_00111, // x3000 ADD R0, R0, #7
+        0b0101_001_001_1_00000, // x3001 AND R1, R1, #0
+        0b0111_000_001_001000,  // x3002 STW R0, R1, #8 -> mem[x0010]
+        0b0001_000_000_1_11101, // x3003 ADD R0, R0, #-3
+        0xF025,                 // x3004 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.write_memory(0x0010, 0xBEEF);
+    computer.enable_journal(3);
+
+    assert_eq!(computer.run(4), StopReason::MaxInstructions);
+    assert_eq!(computer.register(0), 4);
+    assert_eq!(computer.read_memory(0x0010), 7);
+    // Only the last three instructions fit
+    assert_eq!(computer.journal_len(), 3);
+
+    assert_eq!(computer.step_back(1), 1);
+    assert_eq!(computer.program_counter(), 0x3003);
+    assert_eq!(computer.register(0), 7);
+
+    assert_eq!(computer.step_back(1), 1);
+    assert_eq!(computer.program_counter(), 0x3002);
+    assert_eq!(computer.read_memory(0x0010), 0xBEEF);
+
+    assert_eq!(computer.step_back(5), 1);
+    assert_eq!(computer.program_counter(), 0x3001);
+    assert_eq!(computer.journal_len(), 0);
+
+    // Replaying reaches the same state
+    assert_eq!(computer.run(3), StopReason::MaxInstructions);
+    assert_eq!(computer.register(0), 4);
+    assert_eq!(computer.read_memory(0x0010), 7);
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut computer = Computer::new(BufferedIO::new());
+    let program = vec![
+        0b0001_000_000_1_00001, // x3000 ADD R0, R0, #1
+        0b0101_001_001_1_00000, // x3001 AND R1, R1, #0
+        0b0111_000_001_001000,  // x3002 STW R0, R1, #8 -> mem[x0010]
+        0xF021,                 // x3003 OUT
+        0b0000_111_111111011,   // x3004 BRnzp x3000
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run(5);
+
+    let snapshot = computer.snapshot();
+    assert_eq!(snapshot.program_counter(), 0x3000);
+    assert_eq!(snapshot.read_memory(0x0010), 1);
+    // Page x00 (x0010) and page x30 (the program)
+    assert_eq!(snapshot.page_count(), 2);
+
+    computer.run(10);
+    assert_eq!(computer.read_memory(0x0010), 3);
+    assert_eq!(computer.io().output(), "\u{1}\u{2}\u{3}");
+
+    computer.restore(&snapshot);
+    assert_eq!(computer.program_counter(), 0x3000);
+    assert_eq!(computer.register(0), 1);
+    assert_eq!(computer.read_memory(0x0010), 1);
+    assert_eq!(computer.io().output(), "\u{1}");
+    assert_eq!(computer.snapshot(), snapshot);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_snapshot_serde_round_trip() {
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&[0b0001_000_000_1_00101, 0xF025], 0x3000);
+    computer.run(10);
+
+    let snapshot = computer.snapshot();
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let decoded: lc3b::Snapshot<BufferedIO> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, snapshot);
+
+    let mut fresh = Computer::new(BufferedIO::new());
+    fresh.restore(&decoded);
+    assert_eq!(fresh.register(0), 5);
+    assert!(fresh.is_halted());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_snapshot_validate_rejects_bad_pages() {
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&[0b0001_000_000_1_00101, 0xF025], 0x3000);
+    let json = serde_json::to_string(&computer.snapshot()).unwrap();
+    let snapshot: lc3b::Snapshot<BufferedIO> = serde_json::from_str(&json).unwrap();
+    assert_eq!(snapshot.validate(), Ok(()));
+
+    // An interrupt the processor could never dispatch
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["pending_interrupts"] = serde_json::json!([{"vector": 1, "priority": 9}]);
+    assert!(serde_json::from_value::<lc3b::Snapshot<BufferedIO>>(value).is_err());
+
+    // Page x30 cut down to its first two words
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["pages"]["48"] = serde_json::json!([0b0001_000_000_1_00101, 0xF025]);
+    let short: lc3b::Snapshot<BufferedIO> = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(short.read_memory(0x3001), 0xF025);
+    assert_eq!(short.read_memory(0x3002), 0);
+    assert!(matches!(short.validate(), Err(lc3b::Error::InvalidSnapshot(_))));
+
+    // A full page, but past the end of memory
+    let pages = value["pages"].as_object_mut().unwrap();
+    let page = pages.remove("48").unwrap();
+    pages.insert("256".into(), serde_json::json!(vec![page[0].clone(); 256]));
+    let out_of_range: lc3b::Snapshot<BufferedIO> = serde_json::from_value(value).unwrap();
+    assert!(out_of_range.validate().is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_interrupt_priority_checked_when_deserializing() {
+    use lc3b::Interrupt;
+
+    let json = serde_json::to_string(&Interrupt::KEYBOARD).unwrap();
+    assert_eq!(serde_json::from_str::<Interrupt>(&json).unwrap(), Interrupt::KEYBOARD);
+    for priority in [0, 8] {
+        let json = format!(r#"{{"vector":1,"priority":{}}}"#, priority);
+        assert!(serde_json::from_str::<Interrupt>(&json).is_err(), "{}", json);
+    }
+}
+
+#[test]
+fn test_cycle_counting_and_run_for_cycles() {
+    use lc3b_isa::StateMachineTiming;
+
+    let mut computer = Computer::new(BufferedIO::new());
+    let program = vec![
+        0b0001_000_000_1_00011, // x3000 ADD R0, R0, #3
+        0b0001_000_000_1_11111, // x3001 ADD R0, R0, #-1
+        0b0000_001_111111110,   // x3002 BRp x3001
+        0xF025,                 // x3003 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.set_timing_model(StateMachineTiming::new(1));
+    // Fetch/decode is 4 cycles with one-cycle memory; ADD executes in 1
+    assert_eq!(computer.run_for_cycles(5), StopReason::CycleLimit);
+    assert_eq!(computer.cycles(), 5);
+    assert_eq!(computer.instructions_retired(), 1);
+
+    // ADD (5), BRp taken (6), ADD (5): stops once 12 cycles have passed
+    assert_eq!(computer.run_for_cycles(12), StopReason::CycleLimit);
+    assert_eq!(computer.cycles(), 21);
+    assert_eq!(computer.instructions_retired(), 4);
+
+    assert_eq!(computer.run(100), StopReason::Halted);
+    // Remaining: BRp taken, ADD, BRp not taken (5), HALT (7)
+    assert_eq!(computer.cycles(), 21 + 6 + 5 + 5 + 7);
+    assert_eq!(computer.instructions_retired(), 8);
+    assert_eq!(computer.cycles_per_instruction(), 44.0 / 8.0);
+}
+
+#[test]
+fn test_rng_is_reproducible() {
+    use lc3b::RNG;
+
+    // R1 = xFE0C; read RNG three times
+    let program = vec![
+        0b0110_000_001_000000, // x3000 LDW R0, R1, #0
+        0b0110_010_001_000000, // x3001 LDW R2, R1, #0
+        0b0110_011_001_000000, // x3002 LDW R3, R1, #0
+        0xF025,                // x3003 HALT
+    ];
+    let draws = |seed: Option<u64>| {
+        let mut computer = Computer::new(BufferedIO::new());
+        if let Some(seed) = seed {
+            computer = computer.with_seed(seed);
+        }
+        computer.load_program(&program, 0x3000);
+        computer.set_register(1, RNG);
+        computer.run(10);
+        [computer.register(0), computer.register(2), computer.register(3)]
+    };
+
+    assert_eq!(draws(None), draws(None));
+    assert_eq!(draws(Some(7)), draws(Some(7)));
+    assert_ne!(draws(Some(7)), draws(Some(8)));
+    let values = draws(Some(7));
+    assert!(values[0] != values[1] || values[1] != values[2]);
+
+    // reset restarts the sequence from the construction seed
+    let mut computer = Computer::new(BufferedIO::new()).with_seed(7);
+    computer.load_program(&program, 0x3000);
+    computer.set_register(1, RNG);
+    computer.run(10);
+    computer.reset();
+    computer.set_register(1, RNG);
+    computer.run(10);
+    assert_eq!([computer.register(0), computer.register(2), computer.register(3)], values);
+}
+
+#[test]
+fn test_timer_interrupts_every_interval() {
+    use lc3b::{TIMER_INTERRUPT_VECTOR, TMI, TMR};
+
+    let mut computer = Computer::new(BufferedIO::new());
+    let handler = vec![
+        0b0001_100_100_1_00001, // ADD R4, R4, #1
+        0b0111_011_001_000000,  // STW R3, R1, #0 ; acknowledge, keep IE
+        0x8000,                 // RTI
+    ];
+    computer.load_program(&handler, 0x1000);
+    let program = vec![
+        0b0111_010_001_000001,  // x3000 STW R2, R1, #1 ; TMI = 5
+        0b0111_011_001_000000,  // x3001 STW R3, R1, #0 ; TMR = IE, priority 2
+        0b0001_000_000_1_00001, // x3002 ADD R0, R0, #1
+        0b0000_111_111111110,   // x3003 BRnzp x3002
+    ];
+    computer.load_program(&program, 0x3000);
+    let entry = Interrupt::new(TIMER_INTERRUPT_VECTOR, 2).unwrap().table_entry();
+    computer.write_memory(entry, 0x1000);
+    computer.set_register(1, TMR);
+    computer.set_register(2, 5);
+    computer.set_register(3, 0x4200);
+
+    // The count starts with the STW to TMI and includes handler code;
+    // the interrupt is taken before the sixth instruction
+    computer.run(5);
+    assert_eq!(computer.register(4), 0);
+    computer.run(1);
+    assert_eq!(computer.register(4), 1);
+    assert_eq!(computer.psr().priority(), 2);
+    computer.run(2);
+    assert_eq!(computer.psr().priority(), 0);
+
+    computer.run(50);
+    assert!(computer.register(4) >= 10);
+    assert_eq!(computer.read_memory(TMI), 0, "device registers are not RAM");
+}
+
+#[test]
+fn test_attached_device_handles_loads_and_stores() {
+    use std::ops::RangeInclusive;
+
+    use lc3b::{Device, Error, KBSR};
+
+    /// Latches the last value stored and counts ticks
+    #[derive(Default)]
+    struct Latch {
+        value: u16,
+        ticks: u32,
+    }
+
+    impl Device for Latch {
+        fn range(&self) -> RangeInclusive<u16> {
+            0x4000..=0x4001
+        }
+
+        fn read_word(&mut self, addr: u16, _io: &mut dyn IO) -> u16 {
+            if addr == 0x4000 {
+                self.value
+            } else {
+                self.ticks as u16
+            }
+        }
+
+        fn write_word(&mut self, _addr: u16, value: u16, _io: &mut dyn IO) {
+            self.value = value.wrapping_mul(2);
+        }
+
+        fn tick(&mut self, _cycles: u32) {
+            self.ticks += 1;
+        }
+    }
+
+    struct Overlapping;
+
+    impl Device for Overlapping {
+        fn range(&self) -> RangeInclusive<u16> {
+            0xFD00..=KBSR
+        }
+
+        fn read_word(&mut self, _addr: u16, _io: &mut dyn IO) -> u16 {
+            0
+        }
+
+        fn write_word(&mut self, _addr: u16, _value: u16, _io: &mut dyn IO) {}
+    }
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.attach_device(Latch::default()).unwrap();
+    assert!(matches!(computer.attach_device(Latch::default()), Err(Error::DeviceOverlap(0x4000))));
+    assert!(matches!(computer.attach_device(Overlapping), Err(Error::DeviceOverlap(KBSR))));
+
+    let program = vec![
+        0b0111_000_001_000000, // x3000 STW R0, R1, #0 ; latch 21
+        0b0110_010_001_000000, // x3001 LDW R2, R1, #0
+        0xF025,                // x3002 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.set_register(0, 21);
+    computer.set_register(1, 0x4000);
+    computer.run(10);
+
+    assert_eq!(computer.register(2), 42);
+    assert_eq!(computer.read_memory(0x4000), 0, "device stores bypass RAM");
+    let latch = computer.device::<Latch>().unwrap();
+    assert_eq!(latch.ticks, 3);
+}
+
+#[test]
+fn test_framebuffer_device() {
+    use lc3b::{Framebuffer, FRAMEBUFFER_START, FRAMEBUFFER_WIDTH};
+
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.attach_device(Framebuffer::new()).unwrap();
+
+    // Pixel (2, 1) is one row plus two words in
+    let program = vec![
+        0b0111_000_001_000001, // x3000 STW R0, R1, #1
+        0xF025,                // x3001 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.set_register(0, 0x7C00); // pure red
+    computer.set_register(1, FRAMEBUFFER_START + FRAMEBUFFER_WIDTH as u16);
+    computer.run(10);
+
+    let framebuffer = computer.device::<Framebuffer>().unwrap();
+    assert_eq!(framebuffer.pixel(2, 1), Some(0x7C00));
+    assert_eq!(framebuffer.pixel(128, 0), None);
+    let offset = (FRAMEBUFFER_WIDTH + 2) * 4;
+    assert_eq!(&framebuffer.to_rgba()[offset..offset + 4], &[0xFF, 0, 0, 0xFF]);
+    assert_eq!(framebuffer.pixels().iter().filter(|&&p| p != 0).count(), 1);
+}
+
+#[test]
+fn test_call_stack_frames() {
+    use lc3b::{Frame, FrameKind};
+
+    let program = vec![
+        0x4802,                 // x3000 JSR x3005
+        0xF021,                 // x3001 OUT
+        0xF025,                 // x3002 HALT
+        0x0000,                 // x3003
+        0x0000,                 // x3004
+        0b0001_001_001_1_00001, // x3005 ADD R1, R1, #1
+        0xC1C0,                 // x3006 RET
+    ];
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_os().unwrap();
+    computer.load_program(&program, 0x3000);
+
+    assert_eq!(computer.run_to(0x3006, 100), StopReason::Reached(0x3006));
+    let subroutine = Frame {
+        kind: FrameKind::Subroutine,
+        call_site: 0x3000,
+        target: 0x3005,
+        return_address: 0x3001,
+    };
+    assert_eq!(computer.call_stack(), vec![subroutine]);
+
+    computer.step_over(100);
+    assert!(computer.call_stack().is_empty());
+
+    // The OS OUT routine runs as a vector-table TRAP frame
+    computer.step_over(1);
+    assert_eq!(computer.call_stack().len(), 1);
+    assert_eq!(computer.call_stack()[0].kind, FrameKind::Trap(0x21));
+    assert_eq!(computer.call_stack()[0].return_address, 0x3002);
+    assert_eq!(computer.step_out(1000), StopReason::Reached(0x3002));
+    assert!(computer.call_stack().is_empty());
+}
+
+#[test]
+fn test_symbols_breakpoints_and_backtrace() {
+    let source = r#"
+.ORIG x3000
+MAIN:   LEA R1, SUB
+        JSRR R1
+        HALT
+SUB:    ADD R0, R0, #1
+        RET
+"#;
+    let program = lc3b_assembler::assemble(source).unwrap();
+    let mut computer = Computer::new(BufferedIO::new());
+    computer.load_program(&program.words, program.origin);
+    computer.load_symbols(&program.symbols);
+
+    assert_eq!(computer.address_of("SUB"), Some(0x3003));
+    assert_eq!(computer.symbol_at(0x3000), Some("MAIN"));
+    assert_eq!(computer.describe_address(0x3004), "SUB+1");
+    assert_eq!(computer.describe_address(0x2000), "x2000");
+    assert!(matches!(
+        computer.add_symbol_breakpoint("NOPE"),
+        Err(lc3b::Error::UndefinedLabel(_))
+    ));
+
+    assert!(computer.add_symbol_breakpoint("SUB").unwrap());
+    assert_eq!(computer.run(100), StopReason::Breakpoint(0x3003));
+    assert_eq!(computer.backtrace(), vec!["#0 SUB called from MAIN+1".to_string()]);
 }
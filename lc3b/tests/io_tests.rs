@@ -0,0 +1,348 @@
+use lc3b::{
+    BufferedIO, CallbackIO, ChannelIO, Computer, Encoding, Error, HaltReason, IoLog, RecordingIO,
+    ScriptedIO, StopReason, TeeIO, DDR, IO, KBDR, KBSR,
+};
+
+/// GETC then OUT twice, then HALT
+const ECHO_TWICE: [u16; 5] = [0xF020, 0xF021, 0xF020, 0xF021, 0xF025];
+
+#[test]
+fn test_channel_io_across_threads() {
+    let (io, keyboard, console) = ChannelIO::channels();
+    let machine = std::thread::spawn(move || {
+        let mut computer = Computer::new(io);
+        computer.load_program(&ECHO_TWICE, 0x3000);
+        computer.run(100)
+    });
+
+    keyboard.send('h').unwrap();
+    assert_eq!(console.recv().unwrap(), 'h');
+    keyboard.send('i').unwrap();
+    assert_eq!(console.recv().unwrap(), 'i');
+    assert_eq!(machine.join().unwrap(), StopReason::Halted);
+}
+
+#[test]
+fn test_channel_io_polling() {
+    let (mut io, keyboard, _console) = ChannelIO::channels();
+    assert!(!io.has_input());
+    keyboard.send('k').unwrap();
+    assert!(io.has_input());
+    assert!(io.has_input());
+    assert_eq!(io.read_char(), Some('k'));
+
+    drop(keyboard);
+    assert!(!io.has_input());
+    assert_eq!(io.read_char(), None);
+}
+
+#[test]
+fn test_callback_io() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let output = Rc::new(RefCell::new(String::new()));
+    let halts = Rc::new(RefCell::new(0));
+    let mut input = "ok".chars();
+    let io = CallbackIO::new(
+        {
+            let output = output.clone();
+            move |ch| output.borrow_mut().push(ch)
+        },
+        move || input.next(),
+    )
+    .with_halt({
+        let halts = halts.clone();
+        move || *halts.borrow_mut() += 1
+    });
+
+    let mut computer = Computer::new(io);
+    computer.load_program(&ECHO_TWICE, 0x3000);
+    assert_eq!(computer.run(100), StopReason::Halted);
+    assert_eq!(*output.borrow(), "ok");
+    assert_eq!(*halts.borrow(), 1);
+    assert!(!computer.io_mut().has_input());
+}
+
+/// Prompts with PUTS, reads a character with GETC and prints it back
+/// between brackets
+fn prompt_program() -> lc3b_assembler::AssembledProgram {
+    lc3b_assembler::assemble(
+        r#"
+.ORIG x3000
+        LEA R0, prompt
+        PUTS
+        GETC
+        ADD R1, R0, #0
+        LEA R0, open
+        PUTS
+        ADD R0, R1, #0
+        OUT
+        LEA R0, close
+        PUTS
+        HALT
+prompt: .STRINGZ "key? "
+open:   .STRINGZ "["
+close:  .STRINGZ "]"
+.END
+"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_tee_io_copies_output() {
+    let mut input = BufferedIO::new();
+    input.push_input('é');
+    let mut computer = Computer::new(TeeIO::new(input, Vec::new()));
+    computer.load_assembled(&prompt_program(), false);
+    assert_eq!(computer.run(100), StopReason::Halted);
+
+    assert!(computer.io_mut().take_error().is_none());
+    assert_eq!(computer.io().inner().output(), "key? [é]");
+    assert_eq!(String::from_utf8_lossy(computer.io().sink()), "key? [é]");
+}
+
+#[test]
+fn test_scripted_io_follows_script() {
+    let io = ScriptedIO::new().expect("key? ").input("z").expect("[z]");
+    let mut computer = Computer::new(io);
+    computer.load_assembled(&prompt_program(), false);
+    assert_eq!(computer.run(100), StopReason::Halted);
+    assert_eq!(computer.io().finish(), Ok(()));
+}
+
+#[test]
+fn test_scripted_io_reports_divergence() {
+    let io = ScriptedIO::new().expect("key? ").input("z").expect("[y]");
+    let mut computer = Computer::new(io);
+    computer.load_assembled(&prompt_program(), false);
+    assert_eq!(computer.run(100), StopReason::Halted);
+    assert_eq!(computer.halt_reason(), Some(HaltReason::Io));
+
+    let mismatch = computer.io().finish().unwrap_err();
+    assert_eq!(mismatch.step, 2);
+    assert_eq!(mismatch.actual, "[z");
+    assert_eq!(
+        mismatch.to_string(),
+        "step 2: console output diverged\n\
+         expected: \"[y]\"\n\
+         actual:   \"[z\"\n\
+         \x20           ^"
+    );
+
+    // Reading before the expected prompt was printed
+    let io = ScriptedIO::new().expect("name? ").input("z");
+    let mut computer = Computer::new(io);
+    computer.load_program(&[0xF020, 0xF025], 0x3000); // GETC, HALT
+    computer.run(100);
+    assert!(computer.io().finish().unwrap_err().unexpected_read);
+}
+
+/// Polls KBSR until a key arrives, then echoes it and halts. Expects
+/// pointers to KBSR and KBDR at x0000 and x0002.
+const POLL_ECHO: [u16; 5] = [
+    0b1010_010_001_000000, // x3000 LDI R2, R1, #0 -> KBSR
+    0b0000_011_111111110,  // x3001 BRzp x3000
+    0b1010_000_001_000001, // x3002 LDI R0, R1, #1 -> KBDR
+    0xF021,                // x3003 OUT
+    0xF025,                // x3004 HALT
+];
+
+fn poll_echo_computer() -> Computer<RecordingIO<BufferedIO>> {
+    let mut computer = Computer::new(RecordingIO::new(BufferedIO::new()));
+    computer.write_memory(0x0000, KBSR);
+    computer.write_memory(0x0002, KBDR);
+    computer.load_program(&POLL_ECHO, 0x3000);
+    computer
+}
+
+#[test]
+fn test_recording_io_replays_input_timing() {
+    let mut computer = poll_echo_computer();
+    assert_eq!(computer.run(20), StopReason::MaxInstructions);
+    computer.io_mut().inner_mut().push_input('q');
+    assert_eq!(computer.run(100), StopReason::Halted);
+
+    // The key counts as arriving at the poll that first saw it
+    let log = computer.io().log().clone();
+    assert_eq!(log.to_string(), "20 in 113\n23 out 113\n24 halt\n");
+    assert_eq!(log.to_string().parse::<IoLog>().unwrap(), log);
+
+    let mut replay = poll_echo_computer();
+    replay.io_mut().replay(&log);
+    assert_eq!(replay.run(100), StopReason::Halted);
+    assert_eq!(replay.io().inner().output(), "q");
+    assert_eq!(replay.io().log(), &log);
+    assert_eq!(replay.io().divergence(), None);
+    assert!(!replay.io().is_replaying());
+}
+
+#[test]
+fn test_recording_io_reports_divergence() {
+    let mut computer = Computer::new(RecordingIO::new(BufferedIO::new()));
+    computer.load_program(&ECHO_TWICE, 0x3000);
+    computer.io_mut().replay(&"0 in 104\n1 out 104\n2 in 105\n3 out 106\n".parse().unwrap());
+    assert_eq!(computer.run(100), StopReason::Halted);
+    assert_eq!(computer.io().inner().output(), "hi");
+    assert_eq!(computer.io().divergence(), Some(3));
+
+    // Printing more than the recording did
+    let mut computer = Computer::new(RecordingIO::new(BufferedIO::new()));
+    computer.load_program(&ECHO_TWICE, 0x3000);
+    computer.io_mut().replay(&"0 in 104\n1 out 104\n2 in 105\n".parse().unwrap());
+    assert_eq!(computer.run(100), StopReason::Halted);
+    assert_eq!(computer.io().divergence(), Some(3));
+
+    // Halting before printing all the recording did
+    let mut computer = Computer::new(RecordingIO::new(BufferedIO::new()));
+    computer.load_program(&ECHO_TWICE, 0x3000);
+    let log = "0 in 104\n1 out 104\n2 in 105\n3 out 105\n4 out 33\n".parse().unwrap();
+    computer.io_mut().replay(&log);
+    assert_eq!(computer.run(100), StopReason::Halted);
+    assert_eq!(computer.io().divergence(), Some(4));
+
+    let err = "0 in 104\n1 typed 104\n".parse::<IoLog>().unwrap_err();
+    assert_eq!(err.to_string(), "invalid I/O log at line 2: expected `in`, `out` or `halt`");
+}
+
+#[test]
+fn test_buffered_io_incremental_output() {
+    let mut io = BufferedIO::new();
+    io.write_str("hello");
+    let cursor = io.output_position();
+    assert_eq!(cursor, 5);
+    io.write_str(", wörld");
+    assert_eq!(io.take_output_since(cursor), ", wörld");
+    assert_eq!(io.output(), ", wörld");
+    assert_eq!(io.output_start(), 5);
+
+    assert_eq!(io.drain_output(), ", wörld");
+    assert_eq!(io.output(), "");
+    assert_eq!(io.output_position(), 12);
+    io.write_char('!');
+    assert_eq!(io.take_output_since(12), "!");
+}
+
+#[test]
+fn test_buffered_io_output_limit() {
+    let mut io = BufferedIO::new();
+    io.write_str("0123456789");
+    io.set_output_limit(Some(4));
+    assert_eq!(io.output(), "6789");
+    assert_eq!(io.output_start(), 6);
+
+    // Overshoots by up to a quarter of the limit before trimming
+    io.write_char('a');
+    assert_eq!(io.output(), "6789a");
+    io.write_char('b');
+    assert_eq!(io.output(), "89ab");
+    assert_eq!(io.output_position(), 12);
+
+    // Output dropped by the limit is skipped
+    assert_eq!(io.take_output_since(2), "89ab");
+}
+
+/// OUT of R0, then GETC, then HALT
+const OUT_THEN_GETC: [u16; 3] = [0xF021, 0xF020, 0xF025];
+
+fn run_encoding(
+    encoding: Encoding,
+    r0: u16,
+    input: char,
+) -> (StopReason, Computer<BufferedIO>) {
+    let mut io = BufferedIO::new().with_encoding(encoding);
+    io.push_input(input);
+    let mut computer = Computer::new(io);
+    computer.load_program(&OUT_THEN_GETC, 0x3000);
+    computer.set_register(0, r0);
+    (computer.run(10), computer)
+}
+
+#[test]
+fn test_latin1_encoding() {
+    // The high byte is ignored; input past U+00FF becomes `?`
+    let (reason, computer) = run_encoding(Encoding::Latin1, 0x41E9, '€');
+    assert_eq!(reason, StopReason::Halted);
+    assert_eq!(computer.io().output(), "é");
+    assert_eq!(computer.register(0), '?' as u16);
+
+    let (_, computer) = run_encoding(Encoding::Latin1, 'A' as u16, 'ü');
+    assert_eq!(computer.register(0), 0xFC);
+}
+
+#[test]
+fn test_ascii_encoding_rejects_other_characters() {
+    let (reason, computer) = run_encoding(Encoding::Ascii, 'A' as u16, 'z');
+    assert_eq!(reason, StopReason::Halted);
+    assert_eq!(computer.io().output(), "A");
+    assert_eq!(computer.register(0), 'z' as u16);
+
+    let (reason, computer) = run_encoding(Encoding::Ascii, 0x00E9, 'z');
+    assert_eq!(reason, StopReason::Error(Error::NotAscii { pc: 0x3000, value: 0xE9 }));
+    assert_eq!(computer.io().output(), "");
+
+    let (reason, _) = run_encoding(Encoding::Ascii, 'A' as u16, 'é');
+    assert_eq!(reason, StopReason::Error(Error::NotAscii { pc: 0x3001, value: 0xE9 }));
+
+    // Through DDR as well
+    let mut computer = Computer::new(BufferedIO::new().with_encoding(Encoding::Ascii));
+    computer.write_memory(0x0000, DDR);
+    computer.load_program(&[0b1011_000_001_000000], 0x3000); // STI R0, R1, #0
+    computer.set_register(0, 0x0080);
+    assert_eq!(computer.next_instruction(), Err(Error::NotAscii { pc: 0x3000, value: 0x80 }));
+}
+
+#[test]
+fn test_ascii_rejection_retires_the_instruction() {
+    // Resuming continues after the rejected PUTS and GETC rather than
+    // printing the string again or reading another key
+    for fast in [false, true] {
+        let mut io = BufferedIO::new().with_encoding(Encoding::Ascii);
+        io.push_input('é');
+        io.push_input('z');
+        let mut computer = Computer::new(io);
+        let text: Vec<u16> = "AB\u{e9}C".encode_utf16().chain([0]).collect();
+        computer.load_program(&text, 0x4000);
+        computer.load_program(&[0xF022, 0xF020, 0xF020, 0xF025], 0x3000); // PUTS, GETC, GETC, HALT
+        computer.set_register(0, 0x4000);
+        let run = |computer: &mut Computer<BufferedIO>| match fast {
+            true => computer.run_fast(10),
+            false => computer.run(10),
+        };
+
+        let error = Error::NotAscii { pc: 0x3000, value: 0xE9 };
+        assert_eq!(run(&mut computer), StopReason::Error(error));
+        assert_eq!(computer.program_counter(), 0x3001);
+        assert_eq!(computer.io().output(), "AB");
+
+        let error = Error::NotAscii { pc: 0x3001, value: 0xE9 };
+        assert_eq!(run(&mut computer), StopReason::Error(error));
+        assert_eq!(computer.program_counter(), 0x3002);
+        assert_eq!(computer.register(0), 0x4000);
+
+        assert_eq!(run(&mut computer), StopReason::Halted);
+        assert_eq!(computer.io().output(), "AB");
+        assert_eq!(computer.register(0), 'z' as u16);
+    }
+}
+
+#[test]
+fn test_utf16_encoding() {
+    let (reason, computer) = run_encoding(Encoding::Utf16, 0x263A, 'Ω');
+    assert_eq!(reason, StopReason::Halted);
+    assert_eq!(computer.io().output(), "☺");
+    assert_eq!(computer.register(0), 0x03A9);
+
+    // Surrogates and characters beyond the BMP don't fit one word
+    let (_, computer) = run_encoding(Encoding::Utf16, 0xD800, '😀');
+    assert_eq!(computer.io().output(), "\u{FFFD}");
+    assert_eq!(computer.register(0), 0xFFFD);
+
+    let mut computer = Computer::new(BufferedIO::new().with_encoding(Encoding::Utf16));
+    let text: Vec<u16> = "¡Hola, 世界!".encode_utf16().chain([0]).collect();
+    computer.load_program(&text, 0x4000);
+    computer.load_program(&[0xF022, 0xF025], 0x3000); // PUTS, HALT
+    computer.set_register(0, 0x4000);
+    assert_eq!(computer.run(10), StopReason::Halted);
+    assert_eq!(computer.io().output(), "¡Hola, 世界!");
+}
@@ -0,0 +1,308 @@
+use lc3b::{
+    BranchStats, BufferedIO, Computer, MemoryDelta, ProfileObserver, RegisterDelta, TraceObserver,
+};
+use lc3b_isa::{Condition, OpCode};
+
+fn traced_program() -> Computer<BufferedIO, TraceObserver> {
+    let mut computer = Computer::with_observer(BufferedIO::new(), TraceObserver::new());
+    let program = vec![
+        0b0001_000_000_1_00101, // x3000 ADD R0, R0, #5
+        0b0101_001_001_1_00000, // x3001 AND R1, R1, #0
+        0b0111_000_001_001000,  // x3002 STW R0, R1, #8 -> mem[x0010]
+        0xF025,                 // x3003 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run(10);
+    computer
+}
+
+#[test]
+fn test_trace_records_deltas() {
+    let computer = traced_program();
+    let steps = computer.observer().steps();
+    assert_eq!(steps.len(), 4);
+
+    assert_eq!(steps[0].pc, 0x3000);
+    assert_eq!(
+        steps[0].registers,
+        vec![RegisterDelta {
+            register: 0,
+            old: 0,
+            new: 5
+        }]
+    );
+    assert_eq!(steps[0].condition, Some(Condition { n: false, z: false, p: true }));
+
+    assert_eq!(steps[2].registers, vec![]);
+    assert_eq!(
+        steps[2].memory,
+        vec![MemoryDelta {
+            addr: 0x0010,
+            old: 0,
+            new: 5
+        }]
+    );
+    assert_eq!(steps[2].condition, None);
+}
+
+#[test]
+fn test_trace_exports() {
+    let computer = traced_program();
+    let trace = computer.observer();
+
+    let mut json = Vec::new();
+    trace.write_json_lines(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    let lines: Vec<&str> = json.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[0],
+        r#"{"pc":12288,"word":4133,"registers":[[0,0,5]],"memory":[],"condition":"p"}"#
+    );
+    assert_eq!(
+        lines[2],
+        r#"{"pc":12290,"word":28744,"registers":[],"memory":[[16,0,5]],"condition":null}"#
+    );
+
+    let mut binary = Vec::new();
+    trace.write_binary(&mut binary).unwrap();
+    assert_eq!(TraceObserver::read_binary(binary.as_slice()).unwrap(), trace.steps());
+    assert!(TraceObserver::read_binary(&b"nope!"[..]).is_err());
+}
+
+#[test]
+fn test_profile_counts_opcodes_hotspots_and_branches() {
+    let mut computer = Computer::with_observer(BufferedIO::new(), ProfileObserver::new());
+    let program = vec![
+        0b0001_000_000_1_00011, // x3000 ADD R0, R0, #3
+        0b0001_000_000_1_11111, // x3001 ADD R0, R0, #-1
+        0b0000_001_111111110,   // x3002 BRp x3001
+        0xF025,                 // x3003 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run(100);
+
+    let report = computer.observer().report();
+    assert_eq!(report.total_instructions, 8);
+    assert_eq!(report.opcodes[0], (OpCode::ADD, 4));
+    assert_eq!(report.opcodes[1], (OpCode::BR, 3));
+    assert_eq!(report.hotspots[0], (0x3001, 3));
+    assert_eq!(report.hotspots[1], (0x3002, 3));
+    assert_eq!(
+        report.branches,
+        vec![BranchStats {
+            addr: 0x3002,
+            taken: 2,
+            not_taken: 1
+        }]
+    );
+    assert_eq!(computer.observer().count_at(0x3003), 1);
+}
+
+#[test]
+fn test_observers_combine() {
+    use std::{cell::Cell, rc::Rc};
+
+    use lc3b::{CompositeObserver, Observer, UIObserver};
+
+    let program = vec![
+        0b0001_000_000_1_00101, // ADD R0, R0, #5
+        0xF025,                 // HALT
+    ];
+
+    let observers = (ProfileObserver::new(), TraceObserver::new(), UIObserver::new());
+    let mut computer = Computer::with_observer(BufferedIO::new(), observers);
+    computer.load_program(&program, 0x3000);
+    computer.run(10);
+    let (profile, trace, ui) = computer.observer();
+    assert_eq!(profile.count_at(0x3000), 1);
+    assert_eq!(trace.steps().len(), 2);
+    assert_eq!(ui.last_condition(), Condition { n: false, z: false, p: true });
+
+    struct Counter(Rc<Cell<u32>>);
+    impl Observer for Counter {
+        fn on_instruction_end(&mut self, _pc: u16, _inst: &lc3b_isa::Instruction) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    let first = Rc::new(Cell::new(0));
+    let second = Rc::new(Cell::new(0));
+    let composite = CompositeObserver::new()
+        .with(Counter(first.clone()))
+        .with(Box::new(Counter(second.clone())) as Box<dyn Observer>);
+    let mut computer = Computer::with_observer(BufferedIO::new(), composite);
+    computer.load_program(&program, 0x3000);
+    computer.run(10);
+    assert_eq!(computer.observer().len(), 2);
+    assert_eq!((first.get(), second.get()), (2, 2));
+}
+
+#[test]
+fn test_observer_sees_reads_traps_output_and_halt() {
+    use lc3b::Observer;
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+    impl Observer for Events {
+        fn on_memory_read(&mut self, addr: u16, value: u16) {
+            self.0.push(format!("read {:#06x} {}", addr, value));
+        }
+        fn on_trap(&mut self, vector: u8) {
+            self.0.push(format!("trap {:#04x}", vector));
+        }
+        fn on_io_output(&mut self, ch: char) {
+            self.0.push(format!("output {}", ch));
+        }
+        fn on_halt(&mut self) {
+            self.0.push("halt".to_string());
+        }
+    }
+
+    let mut computer = Computer::with_observer(BufferedIO::new(), Events::default());
+    computer.write_memory(0x0010, 'A' as u16);
+    let program = vec![
+        0b0101_001_001_1_00000, // AND R1, R1, #0
+        0b0110_000_001_001000,  // LDW R0, R1, #8 -> mem[x0010]
+        0xF021,                 // OUT
+        0xF025,                 // HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run(10);
+
+    assert_eq!(
+        computer.observer().0,
+        vec!["read 0x0010 65", "trap 0x21", "output A", "trap 0x25", "halt"]
+    );
+}
+
+#[test]
+fn test_statistics_totals() {
+    use lc3b::StatisticsObserver;
+
+    let mut computer = Computer::with_observer(BufferedIO::new(), StatisticsObserver::new());
+    computer.set_register(6, 0x4000);
+    let program = vec![
+        0b0001_110_110_1_11110, // x3000 ADD R6, R6, #-2
+        0b0111_000_110_000000,  // x3001 STW R0, R6, #0
+        0b0001_110_110_1_11110, // x3002 ADD R6, R6, #-2
+        0b0110_001_110_000000,  // x3003 LDW R1, R6, #0
+        0b0001_110_110_1_00100, // x3004 ADD R6, R6, #4
+        0b0000_010_000000001,   // x3005 BRz x3007 (not taken)
+        0b0000_111_000000001,   // x3006 BRnzp x3008
+        0xF025,                 // x3007 HALT (skipped)
+        0xF025,                 // x3008 HALT
+    ];
+    computer.load_program(&program, 0x3000);
+    computer.run(100);
+
+    let report = computer.observer().report();
+    assert_eq!(report.instructions, 8);
+    assert_eq!(report.opcodes[0], (OpCode::ADD, 3));
+    assert_eq!(report.memory_reads, 1);
+    assert_eq!(report.memory_writes, 1);
+    assert_eq!(report.max_stack_depth, 2);
+    assert_eq!((report.branches_taken, report.branches_not_taken), (1, 1));
+    assert!(report.to_string().contains("max stack depth: 2"));
+
+    // The per-address breakdown behind the totals
+    let profile = computer.observer().profile();
+    assert_eq!(profile.total_instructions(), report.instructions);
+    assert_eq!(profile.count_at(0x3007), 0);
+}
+
+#[test]
+fn test_channel_observer_streams_events_across_threads() {
+    use lc3b::{ChannelObserver, ExecutionEvent};
+
+    let (observer, events) = ChannelObserver::channel();
+    let worker = std::thread::spawn(move || {
+        let mut computer = Computer::with_observer(BufferedIO::new(), observer);
+        let program = vec![
+            0b0001_000_000_1_00101, // ADD R0, R0, #5
+            0xF025,                 // HALT
+        ];
+        computer.load_program(&program, 0x3000);
+        computer.run(10);
+    });
+
+    let events: Vec<ExecutionEvent> = events.iter().collect();
+    worker.join().unwrap();
+
+    assert!(events.contains(&ExecutionEvent::RegisterWrite {
+        reg: 0,
+        old: 0,
+        new: 5
+    }));
+    assert!(events.contains(&ExecutionEvent::Trap(0x25)));
+    assert_eq!(events.last(), Some(&ExecutionEvent::PcChange { old: 0x3001, new: 0x3002 }));
+    assert!(events.contains(&ExecutionEvent::Halt));
+}
+
+#[test]
+fn test_coverage_maps_back_to_source_lines() {
+    use lc3b::CoverageObserver;
+
+    let source = r#".ORIG x3000
+        ADD R0, R0, #2
+loop:   ADD R0, R0, #-1
+        BRp loop
+        BRz done
+        ADD R1, R1, #1
+done:   HALT
+.END"#;
+    let program = lc3b_assembler::assemble(source).unwrap();
+    let mut computer = Computer::with_observer(BufferedIO::new(), CoverageObserver::new());
+    computer.load_assembled(&program, true);
+    computer.run(100);
+
+    let report = computer.observer().report(&program.source_map);
+    assert_eq!(report.total(), 6);
+    assert_eq!(report.covered(), 5);
+    assert_eq!(report.uncovered_lines(), vec![6]);
+    assert_eq!(report.lines[1].hits, 2);
+
+    let annotated = report.annotate(source);
+    let lines: Vec<&str> = annotated.lines().collect();
+    assert_eq!(lines[0], "        -:    1:.ORIG x3000");
+    assert_eq!(lines[2], "        2:    3:loop:   ADD R0, R0, #-1");
+    assert_eq!(lines[5], "    #####:    6:        ADD R1, R1, #1");
+}
+
+#[test]
+fn test_ui_observer_keeps_every_change_of_an_instruction() {
+    use lc3b::UIObserver;
+
+    let mut computer = Computer::with_observer(BufferedIO::new(), UIObserver::new());
+    computer.set_register(6, 0x4000);
+    computer.write_memory(0x0200, 0x5000); // privilege exception handler
+    computer.write_memory(0x5000, 0xF025); // HALT
+    let program = vec![
+        0b0001_000_000_1_00101, // ADD R0, R0, #5
+        0x8000,                 // RTI in user mode: exception
+    ];
+    computer.load_program(&program, 0x3000);
+
+    computer.next_instruction().unwrap();
+    let ui = computer.observer();
+    assert_eq!(
+        ui.register_changes(),
+        &[RegisterDelta {
+            register: 0,
+            old: 0,
+            new: 5
+        }]
+    );
+    assert!(ui.memory_changes().is_empty());
+
+    // The exception swaps R6 to the supervisor stack and pushes PSR and PC
+    computer.next_instruction().unwrap();
+    let ui = computer.observer();
+    assert_eq!(ui.register_changes().len(), 3);
+    assert_eq!(ui.last_modified_register(), Some(6));
+    assert_eq!(
+        ui.memory_changes().iter().map(|delta| delta.addr).collect::<Vec<_>>(),
+        vec![0x2FFE, 0x2FFC]
+    );
+    assert_eq!(ui.last_modified_memory(), Some(0x2FFC));
+}